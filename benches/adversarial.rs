@@ -0,0 +1,111 @@
+//! Worst cases for the filter: keys that all set the same filter bit, so it can never reject a
+//! miss that shares it, and sets of random keys large enough to saturate it. Each data set's
+//! saturation is printed before it is measured, so a hasher change can be judged by both.
+
+mod common;
+
+use bloomset::{BloomSet, FILTER_BITS};
+use criterion::{
+    black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput,
+};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+const SIZES: [usize; 2] = [16, 255];
+const QUERIES: usize = 256;
+
+/// Measures filling a set with `keys` and looking up `misses` in it.
+fn worst_case<K: Hash + Eq + Clone + Debug>(
+    c: &mut Criterion,
+    name: &str,
+    data: &str,
+    keys: &[K],
+    misses: &[K],
+) {
+    let mut group = c.benchmark_group(name);
+    for size in SIZES {
+        let keys = &keys[..size];
+        let set: BloomSet<K> = keys.iter().cloned().collect();
+        let parameter = format!("{} keys", size);
+        println!(
+            "{} {}, {}: saturation {:.3}",
+            name,
+            data,
+            parameter,
+            set.saturation()
+        );
+
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(
+            BenchmarkId::new(format!("{} insert", data), &parameter),
+            keys,
+            |b, keys| {
+                b.iter_batched(
+                    || keys.to_vec(),
+                    |keys| {
+                        let mut set = BloomSet::<K>::new();
+                        for key in keys {
+                            set.insert(key);
+                        }
+                        set
+                    },
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+
+        group.throughput(Throughput::Elements(QUERIES as u64));
+        group.bench_with_input(
+            BenchmarkId::new(format!("{} contains miss", data), &parameter),
+            &misses[..QUERIES],
+            |b, misses| {
+                b.iter(|| {
+                    for miss in misses {
+                        black_box(set.contains(miss));
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+/// Runs [`worst_case`] on keys that collide on one filter bit, with misses that collide on it
+/// too, and on as many random keys, with random misses.
+fn sweep<K: Hash + Eq + Clone + Debug>(
+    c: &mut Criterion,
+    name: &str,
+    candidates: impl Fn() -> Box<dyn Iterator<Item = K>>,
+) {
+    let largest = SIZES[SIZES.len() - 1];
+    let (colliding, tried) = common::colliding(candidates(), largest + QUERIES);
+    println!(
+        "{}: {} keys on one filter bit out of {} candidates, {:.0} per key ({} filter bits)",
+        name,
+        colliding.len(),
+        tried,
+        tried as f64 / colliding.len() as f64,
+        FILTER_BITS
+    );
+    let (keys, misses) = colliding.split_at(largest);
+    worst_case(c, name, "colliding", keys, misses);
+
+    let random: Vec<K> = candidates().take(largest + QUERIES).collect();
+    let (keys, misses) = random.split_at(largest);
+    worst_case(c, name, "random", keys, misses);
+}
+
+pub fn u64_keys(c: &mut Criterion) {
+    sweep(c, "adversarial u64 keys", || {
+        Box::new(common::xorshift(0x2545_F491_4F6C_DD1D))
+    });
+}
+
+pub fn string_keys(c: &mut Criterion) {
+    sweep(c, "adversarial String keys", || {
+        Box::new((0_u64..).map(|i| format!("user-{}", i)))
+    });
+}
+
+criterion_group!(adversarial, u64_keys, string_keys);
+criterion_main!(adversarial);
@@ -0,0 +1,65 @@
+//! Lookups in sets of 200 random keys, which saturate the filter, laid out plainly and grouped
+//! by filter bit in a `BucketedBloomSet`. Misses get past the filter either way; the plain set
+//! then compares every element, the bucketed one only those on the miss's bit.
+
+mod common;
+
+use bloomset::{BloomSet, BucketedBloomSet};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::hash::Hash;
+
+const SIZE: usize = 200;
+const QUERIES: usize = 256;
+
+fn lookups<K: Hash + Eq + Clone>(c: &mut Criterion, name: &str, keys: &[K], misses: &[K]) {
+    let plain: BloomSet<K> = keys.iter().cloned().collect();
+    let bucketed: BucketedBloomSet<K> = keys.iter().cloned().collect();
+    println!(
+        "{}, {} keys: saturation {:.3}",
+        name,
+        SIZE,
+        plain.saturation()
+    );
+    let hits = common::queries(keys, misses, 100, QUERIES, 0x2545_F491_4F6C_DD1D);
+
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Elements(QUERIES as u64));
+    for (kind, queries) in [("miss", misses), ("hit", &hits[..])] {
+        group.bench_with_input(BenchmarkId::new("BloomSet", kind), queries, |b, queries| {
+            b.iter(|| {
+                for query in queries {
+                    black_box(plain.contains(query));
+                }
+            })
+        });
+        group.bench_with_input(
+            BenchmarkId::new("BucketedBloomSet", kind),
+            queries,
+            |b, queries| {
+                b.iter(|| {
+                    for query in queries {
+                        black_box(bucketed.contains(query));
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+pub fn u64_keys(c: &mut Criterion) {
+    let random: Vec<u64> = common::xorshift(0x9E37_79B9_7F4A_7C15)
+        .take(SIZE + QUERIES)
+        .collect();
+    let (keys, misses) = random.split_at(SIZE);
+    lookups(c, "bucketed u64 keys", keys, misses);
+}
+
+pub fn string_keys(c: &mut Criterion) {
+    let words = common::words(SIZE + QUERIES, 0x9E37_79B9_7F4A_7C15);
+    let (keys, misses) = words.split_at(SIZE);
+    lookups(c, "bucketed String keys", keys, misses);
+}
+
+criterion_group!(bucketed, u64_keys, string_keys);
+criterion_main!(bucketed);
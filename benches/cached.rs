@@ -0,0 +1,64 @@
+//! Lookups in sets of 64 keys, plain and in a `CachedBloomSet`, with each query repeated three
+//! times in a row, as in code that validates a key and then uses it, and with every query a new
+//! one. The first is where the cache of the last hit should pay off; in the second it never
+//! hits, and should cost no more than the one comparison it adds.
+
+mod common;
+
+use bloomset::{BloomSet, CachedBloomSet};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::hash::Hash;
+
+const SIZE: usize = 64;
+const QUERIES: usize = 255;
+
+fn lookups<K: Hash + Eq + Clone>(c: &mut Criterion, name: &str, keys: &[K], misses: &[K]) {
+    let plain: BloomSet<K> = keys.iter().cloned().collect();
+    let cached: CachedBloomSet<K> = keys.iter().cloned().collect();
+    let distinct = common::queries(keys, misses, 90, QUERIES, 0x2545_F491_4F6C_DD1D);
+    let repeated: Vec<K> = distinct[..QUERIES / 3]
+        .iter()
+        .flat_map(|query| [query.clone(), query.clone(), query.clone()])
+        .collect();
+
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Elements(QUERIES as u64));
+    for (kind, queries) in [("repeated", &repeated), ("distinct", &distinct)] {
+        group.bench_with_input(BenchmarkId::new("BloomSet", kind), queries, |b, queries| {
+            b.iter(|| {
+                for query in queries {
+                    black_box(plain.get(query));
+                }
+            })
+        });
+        group.bench_with_input(
+            BenchmarkId::new("CachedBloomSet", kind),
+            queries,
+            |b, queries| {
+                b.iter(|| {
+                    for query in queries {
+                        black_box(cached.get(query));
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+pub fn u64_keys(c: &mut Criterion) {
+    let random: Vec<u64> = common::xorshift(0x9E37_79B9_7F4A_7C15)
+        .take(SIZE + QUERIES)
+        .collect();
+    let (keys, misses) = random.split_at(SIZE);
+    lookups(c, "cached u64 keys", keys, misses);
+}
+
+pub fn string_keys(c: &mut Criterion) {
+    let words = common::words(SIZE + QUERIES, 0x9E37_79B9_7F4A_7C15);
+    let (keys, misses) = words.split_at(SIZE);
+    lookups(c, "cached String keys", keys, misses);
+}
+
+criterion_group!(cached, u64_keys, string_keys);
+criterion_main!(cached);
@@ -0,0 +1,105 @@
+//! Deterministic data for the benchmarks, so that runs are comparable with each other.
+
+// Each benchmark uses only some of these
+#![allow(dead_code)]
+
+use bloomset::BloomSet;
+use std::collections::HashSet;
+use std::hash::Hash;
+
+/// What the sweeps call `BloomSet`. Built with the `no-filter` feature, the same code runs
+/// without the filter and is reported under its own name, so the two runs land side by side in
+/// criterion's reports: `cargo bench --bench crossover` then again with `--features no-filter`.
+pub const BLOOMSET: &str = if cfg!(feature = "no-filter") {
+    "BloomSet (no filter)"
+} else {
+    "BloomSet"
+};
+
+/// An endless xorshift stream starting from `seed`, which must not be 0.
+pub fn xorshift(mut seed: u64) -> impl Iterator<Item = u64> {
+    std::iter::repeat_with(move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    })
+}
+
+/// `n` distinct lowercase words of 3 to 17 letters, the same ones for the same `seed`.
+#[allow(clippy::cast_possible_truncation)]
+pub fn words(n: usize, seed: u64) -> Vec<String> {
+    let mut bits = xorshift(seed);
+    let mut seen = HashSet::new();
+    let mut words = Vec::with_capacity(n);
+    while words.len() < n {
+        let len = 3 + (bits.next().unwrap() % 15) as usize;
+        let word: String = bits
+            .by_ref()
+            .take(len)
+            .map(|bits| char::from(b'a' + (bits % 26) as u8))
+            .collect();
+        if seen.insert(word.clone()) {
+            words.push(word);
+        }
+    }
+    words
+}
+
+/// `n` queries, `hit_percent` of them drawn from `hits` and the rest from `misses`, in an order
+/// fixed by `seed`.
+#[allow(clippy::cast_possible_truncation)]
+pub fn queries<T: Clone>(
+    hits: &[T],
+    misses: &[T],
+    hit_percent: u64,
+    n: usize,
+    seed: u64,
+) -> Vec<T> {
+    xorshift(seed)
+        .take(n)
+        .map(|bits| {
+            let from = if bits % 100 < hit_percent {
+                hits
+            } else {
+                misses
+            };
+            from[(bits >> 8) as usize % from.len()].clone()
+        })
+        .collect()
+}
+
+/// The first `n` of `candidates` that set the same filter bit as the first candidate, in a
+/// default `BloomSet`, and how many candidates it took to find them.
+///
+/// This only uses the public API, so it keeps working whatever the hasher does: a candidate
+/// collides if a set of it and the first candidate has as few bits set as either alone. A
+/// hasher that spreads keys evenly makes this take about `n` times the number of filter bits;
+/// far fewer means keys pile up on some bits, and it panics if keys never reach the first bit.
+pub fn colliding<K: Hash + Eq + Clone>(
+    candidates: impl IntoIterator<Item = K>,
+    n: usize,
+) -> (Vec<K>, usize) {
+    let mut candidates = candidates.into_iter();
+    let first = candidates.next().expect("no candidates");
+    let one_bit = BloomSet::try_from_iter([first.clone()])
+        .unwrap()
+        .saturation();
+    let mut keys = vec![first.clone()];
+    let mut tried = 1;
+    while keys.len() < n {
+        assert!(
+            tried < n * 10_000,
+            "{} candidates and only {} collide",
+            tried,
+            keys.len()
+        );
+        let candidate = candidates.next().expect("ran out of candidates");
+        tried += 1;
+        let pair = BloomSet::try_from_iter([first.clone(), candidate.clone()]).unwrap();
+        if pair.len() == 2 && pair.saturation() == one_bit {
+            keys.push(candidate);
+        }
+    }
+    (keys, tried)
+}
@@ -0,0 +1,84 @@
+//! The same lookups in a `BloomSet` and the containers it competes with, across set sizes, to
+//! find where each stops being the fastest.
+//!
+//! There is one group per key type and hit rate, with the set size as the numeric parameter, so
+//! criterion's report for a group plots every container's time against size on one chart and
+//! the crossovers are where the lines cross. Keys and queries come from `common`, like the
+//! other benchmarks, so the numbers are directly comparable with theirs.
+
+mod common;
+
+use bloomset::BloomSet;
+use criterion::measurement::WallTime;
+use criterion::{
+    black_box, criterion_group, criterion_main, BenchmarkGroup, BenchmarkId, Criterion, Throughput,
+};
+use fnv::FnvHashSet;
+use std::collections::{BTreeSet, HashSet};
+use std::hash::Hash;
+
+const SIZES: [usize; 9] = [1, 2, 4, 8, 16, 32, 64, 128, 255];
+const HIT_PERCENTS: [u64; 3] = [0, 50, 100];
+const QUERIES: usize = 256;
+
+/// Benchmarks looking up every one of `queries` with `contains`.
+fn lookups<K>(
+    group: &mut BenchmarkGroup<'_, WallTime>,
+    name: &str,
+    size: usize,
+    queries: &[K],
+    contains: impl Fn(&K) -> bool,
+) {
+    group.bench_with_input(BenchmarkId::new(name, size), queries, |b, queries| {
+        b.iter(|| {
+            for query in queries {
+                black_box(contains(query));
+            }
+        })
+    });
+}
+
+/// Benchmarks every container at every size and hit rate, for keys drawn from `keys` and
+/// misses drawn from `misses`.
+fn sweep<K: Hash + Ord + Clone>(c: &mut Criterion, name: &str, keys: &[K], misses: &[K]) {
+    for hit_percent in HIT_PERCENTS {
+        let mut group = c.benchmark_group(format!("crossover {}, {}% hits", name, hit_percent));
+        group.throughput(Throughput::Elements(QUERIES as u64));
+        for size in SIZES {
+            let keys = &keys[..size];
+            let queries = common::queries(keys, misses, hit_percent, QUERIES, 0x5EED + size as u64);
+            let bloom: BloomSet<K> = keys.iter().cloned().collect();
+            let vec = keys.to_vec();
+            let btree: BTreeSet<K> = keys.iter().cloned().collect();
+            let hash: HashSet<K> = keys.iter().cloned().collect();
+            let fnv: FnvHashSet<K> = keys.iter().cloned().collect();
+            lookups(&mut group, common::BLOOMSET, size, &queries, |q| {
+                bloom.contains(q)
+            });
+            lookups(&mut group, "Vec", size, &queries, |q| vec.contains(q));
+            lookups(&mut group, "BTreeSet", size, &queries, |q| {
+                btree.contains(q)
+            });
+            lookups(&mut group, "HashSet", size, &queries, |q| hash.contains(q));
+            lookups(&mut group, "FnvHashSet", size, &queries, |q| {
+                fnv.contains(q)
+            });
+        }
+        group.finish();
+    }
+}
+
+pub fn u64_keys(c: &mut Criterion) {
+    let random: Vec<u64> = common::xorshift(0x2545_F491_4F6C_DD1D).take(512).collect();
+    let (keys, misses) = random.split_at(255);
+    sweep(c, "u64", keys, misses);
+}
+
+pub fn string_keys(c: &mut Criterion) {
+    let words = common::words(512, 0x9E37_79B9_7F4A_7C15);
+    let (keys, misses) = words.split_at(255);
+    sweep(c, "String", keys, misses);
+}
+
+criterion_group!(crossover, u64_keys, string_keys);
+criterion_main!(crossover);
@@ -0,0 +1,209 @@
+//! The same insert and lookup workloads with each hasher a `BloomSet` can be given: the default
+//! `BuildBloomHasher`, FNV, Fx, aHash, and for integers `BuildIdentityHasher`.
+//!
+//! Speed alone says little about a hasher here, since one that puts keys on few filter bits is
+//! fast to run and then scans on most misses. So before the timings, each hasher's filter
+//! quality is printed for each set size: the saturation, and the false-positive rate measured
+//! on misses that aren't in the set. With one bit per element, a hasher that spreads keys
+//! evenly has a false-positive rate close to the saturation. Every input is generated from a
+//! fixed seed, and aHash is seeded too, so runs are comparable.
+
+mod common;
+
+use bloomset::{BloomSet, BuildBloomHasher, BuildIdentityHasher, ElementHasher};
+use criterion::measurement::WallTime;
+use criterion::{
+    black_box, criterion_group, criterion_main, BatchSize, BenchmarkGroup, BenchmarkId, Criterion,
+    Throughput,
+};
+use rustc_hash::FxHasher;
+use std::hash::{BuildHasherDefault, Hash};
+
+// The largest leaves room for the one more element `false_positive_rate` adds
+const SIZES: [usize; 3] = [16, 64, 200];
+const QUERIES: usize = 256;
+
+/// How many of `misses` get past the filter of `set`, out of how many there are.
+///
+/// A miss gets past the filter when its bit is already set, which shows through the public API
+/// as adding it leaving the number of set bits unchanged. This works through any hasher, and
+/// doesn't need the `stats` feature.
+#[allow(clippy::cast_precision_loss)]
+fn false_positive_rate<K: Hash + Eq + Clone, S: ElementHasher<K> + Clone>(
+    set: &BloomSet<K, S>,
+    misses: &[K],
+) -> f64 {
+    let passed = misses
+        .iter()
+        .filter(|&miss| {
+            let mut probe = set.clone();
+            probe.insert(miss.clone());
+            probe.filter_bits_set() == set.filter_bits_set()
+        })
+        .count();
+    passed as f64 / misses.len() as f64
+}
+
+/// Prints the filter quality of `hasher` on `keys`, then measures inserting them and looking up
+/// half hits, half `misses`.
+fn workload<K, S>(
+    group: &mut BenchmarkGroup<'_, WallTime>,
+    data: &str,
+    hasher_name: &str,
+    hasher: &S,
+    keys: &[K],
+    misses: &[K],
+) where
+    K: Hash + Eq + Clone,
+    S: ElementHasher<K> + Clone,
+{
+    for size in SIZES {
+        let keys = &keys[..size];
+        let mut set: BloomSet<K, S> = BloomSet::with_capacity_and_hasher(size, hasher.clone());
+        set.extend(keys.iter().cloned());
+        println!(
+            "{} {}, {} keys: saturation {:.3}, false positives {:.3}",
+            data,
+            hasher_name,
+            size,
+            set.saturation(),
+            false_positive_rate(&set, &misses[..QUERIES])
+        );
+        let parameter = format!("{} keys", size);
+
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(
+            BenchmarkId::new(format!("{} insert", hasher_name), &parameter),
+            keys,
+            |b, keys| {
+                b.iter_batched(
+                    || keys.to_vec(),
+                    |keys| {
+                        let mut set: BloomSet<K, S> =
+                            BloomSet::with_capacity_and_hasher(size, hasher.clone());
+                        for key in keys {
+                            set.insert(key);
+                        }
+                        set
+                    },
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+
+        let queries = common::queries(keys, misses, 50, QUERIES, 0x5EED + size as u64);
+        group.throughput(Throughput::Elements(QUERIES as u64));
+        group.bench_with_input(
+            BenchmarkId::new(format!("{} contains", hasher_name), &parameter),
+            &queries,
+            |b, queries| {
+                b.iter(|| {
+                    for query in queries {
+                        black_box(set.contains(query));
+                    }
+                })
+            },
+        );
+    }
+}
+
+/// Runs [`workload`] with every hasher that can hash `K`.
+fn sweep<K: Hash + Eq + Clone>(c: &mut Criterion, data: &str, keys: &[K], misses: &[K]) {
+    let mut group = c.benchmark_group(format!("hashers {}", data));
+    workload(
+        &mut group,
+        data,
+        "BuildBloomHasher",
+        &BuildBloomHasher,
+        keys,
+        misses,
+    );
+    workload(
+        &mut group,
+        data,
+        "FNV",
+        &fnv::FnvBuildHasher::default(),
+        keys,
+        misses,
+    );
+    let fx = BuildHasherDefault::<FxHasher>::default();
+    workload(&mut group, data, "Fx", &fx, keys, misses);
+    let ahash = ahash::RandomState::with_seeds(1, 2, 3, 4);
+    workload(&mut group, data, "aHash", &ahash, keys, misses);
+    group.finish();
+}
+
+/// Splits `all` into the first `n` as keys, and the rest as misses.
+fn split<K>(all: Vec<K>, n: usize) -> (Vec<K>, Vec<K>) {
+    let mut keys = all;
+    let misses = keys.split_off(n);
+    (keys, misses)
+}
+
+pub fn integer_keys(c: &mut Criterion) {
+    let largest = SIZES[SIZES.len() - 1];
+    let mut seen = std::collections::HashSet::new();
+    let random: Vec<u64> = common::xorshift(0x2545_F491_4F6C_DD1D)
+        .filter(|key| seen.insert(*key))
+        .take(largest + QUERIES)
+        .collect();
+    let (keys, misses) = split(random, largest);
+    sweep(c, "random u64 keys", &keys, &misses);
+    let mut group = c.benchmark_group("hashers random u64 keys");
+    let identity = BuildIdentityHasher;
+    workload(
+        &mut group,
+        "random u64 keys",
+        "BuildIdentityHasher",
+        &identity,
+        &keys,
+        &misses,
+    );
+    group.finish();
+
+    // Ids handed out in order, which is what the identity hasher is for
+    let (keys, misses) = split((0..(largest + QUERIES) as u64).collect(), largest);
+    let mut group = c.benchmark_group("hashers sequential u64 keys");
+    let data = "sequential u64 keys";
+    workload(
+        &mut group,
+        data,
+        "BuildBloomHasher",
+        &BuildBloomHasher,
+        &keys,
+        &misses,
+    );
+    workload(
+        &mut group,
+        data,
+        "BuildIdentityHasher",
+        &identity,
+        &keys,
+        &misses,
+    );
+    group.finish();
+}
+
+pub fn short_string_keys(c: &mut Criterion) {
+    let largest = SIZES[SIZES.len() - 1];
+    let (keys, misses) = split(
+        common::words(largest + QUERIES, 0x9E37_79B9_7F4A_7C15),
+        largest,
+    );
+    sweep(c, "short String keys", &keys, &misses);
+}
+
+pub fn long_string_keys(c: &mut Criterion) {
+    let largest = SIZES[SIZES.len() - 1];
+    // Paths under one long directory, so most of each key is the same
+    let words = common::words(largest + QUERIES, 0xD1B5_4A32_D192_ED03);
+    let paths = words
+        .iter()
+        .map(|word| format!("/srv/build/artifacts/release/x86_64/objects/{}.o", word))
+        .collect();
+    let (keys, misses) = split(paths, largest);
+    sweep(c, "long String keys", &keys, &misses);
+}
+
+criterion_group!(hashers, integer_keys, short_string_keys, long_string_keys);
+criterion_main!(hashers);
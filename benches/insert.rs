@@ -1,20 +1,27 @@
-use bloomset::BloomSet;
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+#[cfg(feature = "bumpalo")]
+use bloomset::BumpBloomSet;
+use bloomset::{
+    BloomSet, BloomSet16, BloomSetPool, BuildBloomHasher, BuildIdentityHasher, ByteSet,
+    IntBloomSet, SmallBloomSet, TaggedBloomSet,
+};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use rustc_hash::FxHasher;
+use std::collections::HashSet;
+use std::hash::BuildHasherDefault;
 
 static TEST_DATA: &[i32] = &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
 static OTHER_DATA: &[i32] = &[
     0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25,
     26, 27, 28, 29, 30, 31, 32, 33,
 ];
-/*
-static TEST_DATA: &[&str] = &["The", "Rust", "Programming", "Language"];
-static OTHER_DATA: &[&str] = &["Is", "pretty", "amazing"];
-*/
+static STR_DATA: &[&str] = &["The", "Rust", "Programming", "Language"];
+static OTHER_STR_DATA: &[&str] = &["Is", "pretty", "amazing", "The", "Rust"];
 
 pub fn bloomset_insert(c: &mut Criterion) {
     c.bench_function("BloomSet::insert", |b| {
-        let mut set = BloomSet::with_capacity(TEST_DATA.len());
+        let mut set = BloomSet::new();
         b.iter(|| {
+            set.reserve(TEST_DATA.len());
             for elem in TEST_DATA {
                 set.insert(elem);
             }
@@ -38,7 +45,125 @@ pub fn hashset_insert(c: &mut Criterion) {
     });
 }
 
-criterion_group!(insert, bloomset_insert, hashset_insert);
+pub fn bloomset_bulk_strings(c: &mut Criterion) {
+    let mut x = 0x9E37_79B9_7F4A_7C15_u64;
+    let strings: Vec<String> = (0..255)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            format!("a/rather/long/shared/path/prefix/{:x}", x)
+        })
+        .collect();
+    c.bench_function("BloomSet collect 255 strings", |b| {
+        b.iter(|| {
+            black_box(
+                strings
+                    .iter()
+                    .map(String::as_str)
+                    .collect::<BloomSet<&str>>(),
+            )
+        })
+    });
+    c.bench_function("BloomSet insert 255 strings", |b| {
+        b.iter(|| {
+            let mut set = BloomSet::new();
+            for string in &strings {
+                set.insert(string.as_str());
+            }
+            black_box(set)
+        })
+    });
+}
+
+/// Collects the same strings in bulk and, by hiding the iterator's length, one at a time, to
+/// show where the bulk path starts to pay off.
+pub fn bloomset_bulk_threshold(c: &mut Criterion) {
+    let strings: Vec<String> = (0..64)
+        .map(|i| format!("a/rather/long/shared/path/prefix/{}", i))
+        .collect();
+    for len in [32, 48, 64] {
+        let strings = || strings[..len].iter().map(String::as_str);
+        c.bench_function(&format!("BloomSet collect {} strings in bulk", len), |b| {
+            b.iter(|| black_box(strings().collect::<BloomSet<&str>>()))
+        });
+        c.bench_function(
+            &format!("BloomSet collect {} strings one at a time", len),
+            |b| b.iter(|| black_box(strings().filter(|_| true).collect::<BloomSet<&str>>())),
+        );
+    }
+    for len in [256, 512, 1024] {
+        c.bench_function(&format!("BloomSet16 collect {} u32s in bulk", len), |b| {
+            b.iter(|| black_box((0..len).collect::<BloomSet16<u32>>()))
+        });
+        c.bench_function(
+            &format!("BloomSet16 collect {} u32s one at a time", len),
+            |b| b.iter(|| black_box((0..len).filter(|_| true).collect::<BloomSet16<u32>>())),
+        );
+    }
+}
+
+/// Builds a set of 255 strings already known to be unique, through `FromIterator` and through
+/// the constructors that skip the duplicate checks.
+pub fn bloomset_from_unique(c: &mut Criterion) {
+    let strings: Vec<String> = (100..355)
+        .map(|i| format!("a/rather/long/shared/path/prefix/{}", i))
+        .collect();
+    let strings: Vec<&str> = strings.iter().map(String::as_str).collect();
+    let mut group = c.benchmark_group("BloomSet from 255 unique strings");
+    group.bench_function("collect", |b| {
+        b.iter(|| black_box(strings.iter().copied().collect::<BloomSet<&str>>()))
+    });
+    group.bench_function("from_unique_iter", |b| {
+        b.iter(|| black_box(BloomSet::<&str>::from_unique_iter(strings.iter().copied())))
+    });
+    group.bench_function("from_sorted_slice", |b| {
+        b.iter(|| black_box(BloomSet::<&str>::from_sorted_slice(&strings)))
+    });
+    group.finish();
+}
+
+pub fn bloomset_retain(c: &mut Criterion) {
+    let strings: Vec<String> = (0..255).map(|i| format!("element {}", i)).collect();
+    // Keeps an unpredictable half, so that neither order of visiting helps the branch predictor
+    let keep = |item: &String| {
+        let digits = item.as_bytes()[8..].iter();
+        let n = digits.fold(0_u32, |n, digit| n * 10 + u32::from(digit - b'0'));
+        n.wrapping_mul(0x9E37_79B9) >> 31 == 0
+    };
+    let mut group = c.benchmark_group("BloomSet retain half of 255 strings");
+    group.bench_function("retain", |b| {
+        b.iter_batched(
+            || strings.iter().cloned().collect::<BloomSet<String>>(),
+            |mut set| {
+                set.retain(keep);
+                set
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("retain_unordered", |b| {
+        b.iter_batched(
+            || strings.iter().cloned().collect::<BloomSet<String>>(),
+            |mut set| {
+                set.retain_unordered(keep);
+                set
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(
+    insert,
+    bloomset_insert,
+    hashset_insert,
+    bloomset_bulk_strings,
+    bloomset_bulk_threshold,
+    bloomset_from_unique,
+    bloomset_retain
+);
 
 pub fn bloomset_contains(c: &mut Criterion) {
     c.bench_function("BloomSet::contains", |b| {
@@ -48,7 +173,7 @@ pub fn bloomset_contains(c: &mut Criterion) {
         }
         b.iter(|| {
             for elem in OTHER_DATA {
-                black_box(set.contains(&elem));
+                black_box(set.contains(elem));
             }
         })
     });
@@ -69,5 +194,456 @@ pub fn hashset_contains(c: &mut Criterion) {
     });
 }
 
-criterion_group!(contains, bloomset_contains, hashset_contains);
-criterion_main!(insert, contains);
+pub fn hashset_bloom_hasher_contains(c: &mut Criterion) {
+    c.bench_function("HashSet<BuildBloomHasher>::contains", |b| {
+        let mut set = HashSet::with_hasher(BuildBloomHasher);
+        set.reserve(TEST_DATA.len());
+        for elem in TEST_DATA {
+            set.insert(elem);
+        }
+        b.iter(|| {
+            for elem in OTHER_DATA {
+                black_box(set.contains(&elem));
+            }
+        })
+    });
+}
+
+pub fn bloomset_saturated_contains(c: &mut Criterion) {
+    c.bench_function("BloomSet::contains saturated misses", |b| {
+        // 220 elements leave almost no filter bit unset
+        let set: BloomSet<u32> = (0..220).collect();
+        b.iter(|| {
+            for elem in 1000..1100_u32 {
+                black_box(set.contains(elem));
+            }
+        })
+    });
+}
+
+pub fn bloomset_random_misses(c: &mut Criterion) {
+    c.bench_function("BloomSet::contains random absent keys", |b| {
+        // Few enough elements that the filter rejects most keys, each on a random bit
+        let set: BloomSet<u64> = (0..20).collect();
+        let mut x = 0x2545_F491_4F6C_DD1D_u64;
+        let keys: Vec<u64> = (0..1000)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                x | 1 << 63
+            })
+            .collect();
+        b.iter(|| {
+            for &key in &keys {
+                black_box(set.contains(key));
+            }
+        })
+    });
+}
+
+pub fn byteset_contains(c: &mut Criterion) {
+    let mut x = 0x2545_F491_4F6C_DD1D_u64;
+    let queries: Vec<u8> = (0..1000)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x as u8
+        })
+        .collect();
+    let bytes = || (0..200_u32).map(|i| (i * 97 % 251) as u8);
+    c.bench_function("BloomSet<u8>::contains 200 elements, random queries", |b| {
+        let set: BloomSet<u8> = bytes().collect();
+        b.iter(|| {
+            for &query in &queries {
+                black_box(set.contains(query));
+            }
+        })
+    });
+    c.bench_function("ByteSet::contains 200 elements, random queries", |b| {
+        let set: ByteSet = bytes().collect();
+        b.iter(|| {
+            for &query in &queries {
+                black_box(set.contains(query));
+            }
+        })
+    });
+}
+
+/// 200 keys in a shuffled order, and 1000 queries of them drawn from a Zipf distribution, so
+/// the hottest few keys make up most lookups wherever they were inserted. Key `r` has rank
+/// `r + 1`.
+#[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+fn zipf_queries() -> (Vec<u64>, Vec<u64>) {
+    let mut x = 0x2545_F491_4F6C_DD1D_u64;
+    let mut random = move || {
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        x
+    };
+    let mut keys: Vec<u64> = (0..200).collect();
+    for i in (1..keys.len()).rev() {
+        keys.swap(i, (random() % (i as u64 + 1)) as usize);
+    }
+    // The key of rank r gets weight 1/r
+    let cumulative: Vec<f64> = (1..=keys.len())
+        .scan(0.0, |total, rank| {
+            *total += 1.0 / rank as f64;
+            Some(*total)
+        })
+        .collect();
+    let total = cumulative[cumulative.len() - 1];
+    let queries = (0..1000)
+        .map(|_| {
+            let target = (random() >> 11) as f64 / (1_u64 << 53) as f64 * total;
+            cumulative.partition_point(|&it| it < target) as u64
+        })
+        .collect();
+    (keys, queries)
+}
+
+/// The average number of elements a scan compares to find each query.
+#[allow(clippy::cast_precision_loss)]
+fn average_scan_length(set: &BloomSet<u64>, queries: &[u64]) -> f64 {
+    let total: usize = queries
+        .iter()
+        .map(|query| set.as_slice().iter().position(|it| it == query).unwrap() + 1)
+        .sum();
+    total as f64 / queries.len() as f64
+}
+
+pub fn bloomset_promote(c: &mut Criterion) {
+    let (keys, queries) = zipf_queries();
+    let set: BloomSet<u64> = keys.iter().copied().collect();
+    let mut moved_to_front: BloomSet<u64> = keys.iter().copied().collect();
+    for &query in &queries {
+        moved_to_front.promote(query);
+    }
+    // Transposing converges slowly, so give it a few passes to settle
+    let mut transposed: BloomSet<u64> = keys.into_iter().collect();
+    for _ in 0..50 {
+        for &query in &queries {
+            transposed.contains_promoting(query);
+        }
+    }
+    println!(
+        "average scan length: {:.1} in insertion order, {:.1} after promote, {:.1} after contains_promoting",
+        average_scan_length(&set, &queries),
+        average_scan_length(&moved_to_front, &queries),
+        average_scan_length(&transposed, &queries),
+    );
+    c.bench_function("BloomSet::contains zipf queries", |b| {
+        b.iter(|| {
+            for &query in &queries {
+                black_box(set.contains(query));
+            }
+        })
+    });
+    c.bench_function("BloomSet::contains zipf queries, promoted", |b| {
+        b.iter(|| {
+            for &query in &queries {
+                black_box(moved_to_front.contains(query));
+            }
+        })
+    });
+    c.bench_function("BloomSet::contains_promoting zipf queries", |b| {
+        b.iter(|| {
+            for &query in &queries {
+                black_box(transposed.contains_promoting(query));
+            }
+        })
+    });
+}
+
+pub fn bloomset_scan_contains(c: &mut Criterion) {
+    for len in [64, 128, 255] {
+        // Even 64 elements leave few filter bits unset, so almost every lookup is a scan, and
+        // nine in ten of them miss
+        let set: BloomSet<u32> = (0..len).map(|i| i * 10).collect();
+        c.bench_function(
+            &format!("BloomSet::contains {} elements, 90% misses", len),
+            |b| {
+                b.iter(|| {
+                    for elem in 0..100_u32 {
+                        // Spread over the elements, and off by one for all but every tenth
+                        let target = elem * len / 100 * 10 + u32::from(elem % 10 != 0);
+                        black_box(set.contains(target));
+                    }
+                })
+            },
+        );
+    }
+}
+
+/// Looks up eight keys, half of them present, against a saturated set of 200 strings, as one
+/// batch and one at a time.
+pub fn bloomset_get_many(c: &mut Criterion) {
+    let strings: Vec<String> = (0..208)
+        .map(|i| format!("a/rather/long/shared/path/prefix/{}", i))
+        .collect();
+    let set: BloomSet<&str> = strings[..200].iter().map(String::as_str).collect();
+    let keys: [&str; 8] = [10, 204, 150, 201, 60, 207, 199, 203].map(|i| strings[i].as_str());
+    let mut group = c.benchmark_group("BloomSet 8 lookups, 200 strings");
+    group.bench_function("get_many", |b| {
+        b.iter(|| black_box(set.get_many(black_box(keys))))
+    });
+    group.bench_function("get", |b| {
+        b.iter(|| black_box(black_box(keys).map(|key| set.get(key))))
+    });
+    group.finish();
+}
+
+pub fn bloomset_sorted_contains(c: &mut Criterion) {
+    // 200 elements saturate the filter, so every miss gets past it
+    let mut set: BloomSet<u32> = (0..200).map(|i| i * 2).collect();
+    set.sort();
+    c.bench_function("BloomSet::contains_sorted saturated, unsorted", |b| {
+        let mut unsorted: BloomSet<u32> = (0..200).map(|i| i * 2).collect();
+        unsorted.insert(1000);
+        b.iter(|| {
+            for elem in (1..200).step_by(2) {
+                black_box(unsorted.contains_sorted(elem));
+            }
+        })
+    });
+    c.bench_function("BloomSet::contains_sorted saturated, sorted", |b| {
+        b.iter(|| {
+            for elem in (1..200).step_by(2) {
+                black_box(set.contains_sorted(elem));
+            }
+        })
+    });
+}
+
+criterion_group!(
+    contains,
+    bloomset_contains,
+    hashset_contains,
+    hashset_bloom_hasher_contains,
+    bloomset_saturated_contains,
+    bloomset_random_misses,
+    bloomset_scan_contains,
+    byteset_contains,
+    bloomset_promote,
+    bloomset_sorted_contains,
+    bloomset_get_many
+);
+pub fn bloomset_str_default_hasher(c: &mut Criterion) {
+    c.bench_function("BloomSet<&str>::contains", |b| {
+        let set: BloomSet<&str> = STR_DATA.iter().copied().collect();
+        b.iter(|| {
+            for elem in OTHER_STR_DATA {
+                black_box(set.contains(elem));
+            }
+        })
+    });
+}
+
+pub fn bloomset_str_fx_hasher(c: &mut Criterion) {
+    c.bench_function("BloomSet<&str, FxHasher>::contains", |b| {
+        let set: BloomSet<&str, BuildHasherDefault<FxHasher>> = STR_DATA.iter().copied().collect();
+        b.iter(|| {
+            for elem in OTHER_STR_DATA {
+                black_box(set.contains(elem));
+            }
+        })
+    });
+}
+
+/// Inserts 64 spread-out `u32` ids, and looks each up again, with the default hasher and as
+/// their own hashes.
+pub fn bloomset_u32_identity_hasher(c: &mut Criterion) {
+    let ids: Vec<u32> = (0..64).map(|i| i * 37).collect();
+    let mut group = c.benchmark_group("BloomSet<u32> insert and find 64 ids");
+    group.bench_function("BuildBloomHasher", |b| {
+        let mut set: BloomSet<u32> = BloomSet::with_capacity(64);
+        b.iter(|| {
+            for &id in black_box(&ids) {
+                set.insert(id);
+            }
+            for &id in &ids {
+                black_box(set.contains(id));
+            }
+            set.clear();
+        })
+    });
+    group.bench_function("BuildIdentityHasher", |b| {
+        let mut set: IntBloomSet<u32> = BloomSet::with_capacity_and_hasher(64, BuildIdentityHasher);
+        b.iter(|| {
+            for &id in black_box(&ids) {
+                set.insert(id);
+            }
+            for &id in &ids {
+                black_box(set.contains(id));
+            }
+            set.clear();
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(
+    hashers,
+    bloomset_str_default_hasher,
+    bloomset_str_fx_hasher,
+    bloomset_u32_identity_hasher
+);
+/// 100 set members and 100 absent probes, sharing a long prefix so comparisons aren't free.
+fn string_sets() -> (Vec<String>, Vec<String>) {
+    let key = |i| format!("a/rather/long/shared/path/prefix/{}", i);
+    ((0..100).map(key).collect(), (100..200).map(key).collect())
+}
+
+pub fn bloomset_string_misses(c: &mut Criterion) {
+    c.bench_function("BloomSet<&str>::contains misses", |b| {
+        let (members, probes) = string_sets();
+        let set: BloomSet<&str> = members.iter().map(String::as_str).collect();
+        b.iter(|| {
+            for probe in &probes {
+                black_box(set.contains(probe.as_str()));
+            }
+        })
+    });
+}
+
+pub fn tagged_string_misses(c: &mut Criterion) {
+    c.bench_function("TaggedBloomSet<&str>::contains misses", |b| {
+        let (members, probes) = string_sets();
+        let set: TaggedBloomSet<&str> = members.iter().map(String::as_str).collect();
+        b.iter(|| {
+            for probe in &probes {
+                black_box(set.contains(&probe.as_str()));
+            }
+        })
+    });
+}
+
+pub fn bloomset_small(c: &mut Criterion) {
+    // Four i32s fill SmallBloomSet's inline slots on 64-bit targets
+    for n in 1..=4 {
+        c.bench_function(&format!("BloomSet build and query {n} elements"), |b| {
+            b.iter(|| {
+                let mut set = BloomSet::new();
+                for elem in &TEST_DATA[..n] {
+                    set.insert(*elem);
+                }
+                for elem in &OTHER_DATA[..2 * n] {
+                    black_box(set.contains(elem));
+                }
+                set
+            })
+        });
+    }
+}
+
+pub fn smallbloomset_small(c: &mut Criterion) {
+    // Four i32s fill SmallBloomSet's inline slots on 64-bit targets
+    for n in 1..=4 {
+        c.bench_function(
+            &format!("SmallBloomSet build and query {n} elements"),
+            |b| {
+                b.iter(|| {
+                    let mut set = SmallBloomSet::new();
+                    for elem in &TEST_DATA[..n] {
+                        set.insert(*elem);
+                    }
+                    for elem in &OTHER_DATA[..2 * n] {
+                        black_box(set.contains(elem));
+                    }
+                    set
+                })
+            },
+        );
+    }
+}
+
+pub fn frozen_misses(c: &mut Criterion) {
+    for &size in &[100_u32, 180, 255] {
+        let set: BloomSet<u32> = (0..size).map(|i| i * 2).collect();
+        c.bench_function(
+            &format!("BloomSet::contains {size} elements, misses"),
+            |b| {
+                b.iter(|| {
+                    for elem in (1..200).step_by(2) {
+                        black_box(set.contains(elem));
+                    }
+                })
+            },
+        );
+        let frozen = set.into_frozen();
+        c.bench_function(
+            &format!("FrozenBloomSet::contains {size} elements, misses"),
+            |b| {
+                b.iter(|| {
+                    for elem in (1..200).step_by(2) {
+                        black_box(frozen.contains(elem));
+                    }
+                })
+            },
+        );
+    }
+}
+
+criterion_group!(tags, bloomset_string_misses, tagged_string_misses);
+criterion_group!(small, bloomset_small, smallbloomset_small);
+criterion_group!(frozen, frozen_misses);
+pub fn bloomset_cycles(c: &mut Criterion) {
+    c.bench_function("BloomSet create-fill-query-drop x100", |b| {
+        b.iter(|| {
+            for _ in 0..100 {
+                let mut set = BloomSet::new();
+                for elem in &TEST_DATA[..8] {
+                    set.insert(*elem);
+                }
+                for elem in &OTHER_DATA[..8] {
+                    black_box(set.contains(elem));
+                }
+            }
+        })
+    });
+}
+
+pub fn pooled_cycles(c: &mut Criterion) {
+    c.bench_function("BloomSetPool create-fill-query-drop x100", |b| {
+        let mut pool = BloomSetPool::new(1);
+        b.iter(|| {
+            for _ in 0..100 {
+                let mut set = pool.get();
+                for elem in &TEST_DATA[..8] {
+                    set.insert(*elem);
+                }
+                for elem in &OTHER_DATA[..8] {
+                    black_box(set.contains(elem));
+                }
+                pool.put(set);
+            }
+        })
+    });
+}
+
+#[cfg(feature = "bumpalo")]
+pub fn bumpbloomset_cycles(c: &mut Criterion) {
+    c.bench_function("BumpBloomSet create-fill-query-drop x100", |b| {
+        let mut arena = bumpalo::Bump::new();
+        b.iter(|| {
+            for _ in 0..100 {
+                let mut set = BumpBloomSet::new_in(&arena);
+                for elem in &TEST_DATA[..8] {
+                    set.insert(*elem);
+                }
+                for elem in &OTHER_DATA[..8] {
+                    black_box(set.contains(elem));
+                }
+            }
+            arena.reset();
+        })
+    });
+}
+
+#[cfg(not(feature = "bumpalo"))]
+criterion_group!(arena, bloomset_cycles, pooled_cycles);
+#[cfg(feature = "bumpalo")]
+criterion_group!(arena, bloomset_cycles, pooled_cycles, bumpbloomset_cycles);
+criterion_main!(insert, contains, hashers, tags, small, frozen, arena);
@@ -0,0 +1,59 @@
+//! Fixed insert/contains/clear scripts for counting instructions and cache accesses under
+//! valgrind, which is deterministic where wall-clock timings are not.
+//!
+//! This binary has no timing harness. It builds the same data on every run, then runs the
+//! one script named on its command line, so the difference between a script's counts and the
+//! `baseline` run's counts is the script itself:
+//!
+//! ```text
+//! cargo bench --bench instructions --no-run
+//! valgrind --tool=cachegrind --cache-sim=yes target/release/deps/instructions-<hash> baseline
+//! valgrind --tool=cachegrind --cache-sim=yes target/release/deps/instructions-<hash> u64
+//! ```
+//!
+//! Running it with no script name lists the scripts.
+
+mod common;
+
+use bloomset::BloomSet;
+use std::hash::Hash;
+use std::hint::black_box;
+
+/// How many elements each script fills its set with.
+const SIZE: usize = 16;
+/// How many times each script fills, queries and clears its set.
+const ROUNDS: usize = 1000;
+
+/// Inserts `keys` into one set, looks up every key and every miss, and clears it, `ROUNDS`
+/// times over. Never inlined, so it shows up by name in callgrind's output too.
+#[inline(never)]
+fn script<K: Hash + Eq + Clone>(keys: &[K], misses: &[K]) {
+    let mut set = BloomSet::<K>::with_capacity(keys.len());
+    for _ in 0..ROUNDS {
+        for key in keys {
+            set.insert(black_box(key.clone()));
+        }
+        for key in keys.iter().chain(misses) {
+            black_box(set.contains(black_box(key)));
+        }
+        set.clear();
+    }
+    black_box(set);
+}
+
+fn main() {
+    let random: Vec<u64> = common::xorshift(0x2545_F491_4F6C_DD1D)
+        .take(2 * SIZE)
+        .collect();
+    let words = common::words(2 * SIZE, 0x9E37_79B9_7F4A_7C15);
+    let (u64_keys, u64_misses) = random.split_at(SIZE);
+    let (string_keys, string_misses) = words.split_at(SIZE);
+
+    match std::env::args().nth(1).as_deref() {
+        Some("baseline") => {}
+        Some("u64") => script(u64_keys, u64_misses),
+        Some("String") => script(string_keys, string_misses),
+        // `cargo bench` passes `--bench`; there is nothing to time
+        _ => println!("scripts: baseline, u64, String"),
+    }
+}
@@ -0,0 +1,81 @@
+//! Lookups of string keys at several set sizes and hit rates, in a `BloomSet`, an
+//! `FnvHashSet` and a plain slice holding the same keys.
+
+mod common;
+
+use bloomset::BloomSet;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use fnv::FnvHashSet;
+use std::hash::Hash;
+
+const SIZES: [usize; 4] = [4, 16, 64, 255];
+const HIT_PERCENTS: [u64; 3] = [0, 50, 100];
+const QUERIES: usize = 256;
+
+/// Benchmarks every size and hit rate for keys drawn from `keys`, with queries that miss drawn
+/// from `misses`.
+fn sweep<K: Hash + Eq + Clone>(c: &mut Criterion, name: &str, keys: &[K], misses: &[K]) {
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Elements(QUERIES as u64));
+    for size in SIZES {
+        let keys = &keys[..size];
+        let bloom: BloomSet<K> = keys.iter().cloned().collect();
+        let fnv: FnvHashSet<K> = keys.iter().cloned().collect();
+        for hit_percent in HIT_PERCENTS {
+            let queries = common::queries(keys, misses, hit_percent, QUERIES, 0x5EED + size as u64);
+            let parameter = format!("{} keys, {}% hits", size, hit_percent);
+            group.bench_with_input(
+                BenchmarkId::new(common::BLOOMSET, &parameter),
+                &queries,
+                |b, queries| {
+                    b.iter(|| {
+                        for query in queries {
+                            black_box(bloom.contains(query));
+                        }
+                    })
+                },
+            );
+            group.bench_with_input(
+                BenchmarkId::new("FnvHashSet", &parameter),
+                &queries,
+                |b, queries| {
+                    b.iter(|| {
+                        for query in queries {
+                            black_box(fnv.contains(query));
+                        }
+                    })
+                },
+            );
+            group.bench_with_input(
+                BenchmarkId::new("slice", &parameter),
+                &queries,
+                |b, queries| {
+                    b.iter(|| {
+                        for query in queries {
+                            black_box(keys.contains(query));
+                        }
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+pub fn str_keys(c: &mut Criterion) {
+    let words: Vec<&'static str> = common::words(512, 0x2545_F491_4F6C_DD1D)
+        .into_iter()
+        .map(|word| &*Box::leak(word.into_boxed_str()))
+        .collect();
+    let (keys, misses) = words.split_at(255);
+    sweep(c, "&'static str keys", keys, misses);
+}
+
+pub fn string_keys(c: &mut Criterion) {
+    let words = common::words(512, 0x9E37_79B9_7F4A_7C15);
+    let (keys, misses) = words.split_at(255);
+    sweep(c, "String keys", keys, misses);
+}
+
+criterion_group!(keys, str_keys, string_keys);
+criterion_main!(keys);
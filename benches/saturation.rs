@@ -0,0 +1,51 @@
+//! What `filter_bits_set` costs. The lookups call it to skip a saturated filter, so it reads a
+//! count kept in the length word rather than counting the filter bits.
+//!
+//! Over 256 sets of 0 to 255 random keys, it is timed against `len`, which reads the other
+//! count in that word, and both against a missing `contains`, which asks for it once. Reading
+//! the count should cost the same as `len`; counting the bits of both words instead took about
+//! eight times as long, a tenth of a miss.
+
+mod common;
+
+use bloomset::BloomSet;
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+const SETS: usize = 256;
+
+pub fn filter_bits_set(c: &mut Criterion) {
+    let mut keys = common::xorshift(0x9E37_79B9_7F4A_7C15);
+    let sets: Vec<BloomSet<u64>> = (0..SETS)
+        .map(|i| keys.by_ref().take(i % 256).collect())
+        .collect();
+    let misses: Vec<u64> = keys.take(SETS).collect();
+
+    let mut group = c.benchmark_group("saturation");
+    group.throughput(Throughput::Elements(SETS as u64));
+    group.bench_function("len", |b| {
+        b.iter(|| {
+            sets.iter()
+                .map(|set| black_box(set).len())
+                .fold(0, usize::wrapping_add)
+        })
+    });
+    group.bench_function("filter_bits_set", |b| {
+        b.iter(|| {
+            sets.iter()
+                .map(|set| black_box(set).filter_bits_set())
+                .fold(0, u32::wrapping_add)
+        })
+    });
+    group.bench_function("contains miss", |b| {
+        b.iter(|| {
+            sets.iter()
+                .zip(&misses)
+                .filter(|(set, miss)| black_box(set).contains(*miss))
+                .count()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(saturation, filter_bits_set);
+criterion_main!(saturation);
@@ -0,0 +1,92 @@
+//! Checks that the fallible half of the API can't panic: every call below runs under a guard
+//! whose destructor refers to a symbol that doesn't exist, so if the compiler leaves any way to
+//! unwind out of one of them, linking fails and names it.
+//!
+//! The check needs optimizations to prove the panics away, and LTO to see into the crate's
+//! out-of-line helpers, so it only applies to release builds:
+//! `CARGO_PROFILE_RELEASE_LTO=fat cargo build --release --example no_panic`, which
+//! `tests/no_panic.rs` runs. Debug builds run the same calls unguarded.
+
+use bloomset::{BloomSet, TryReserveError};
+
+/// Aborts the link if it is still reachable after optimization, which only unwinding can make
+/// it.
+#[cfg(not(debug_assertions))]
+struct NoUnwind;
+
+#[cfg(not(debug_assertions))]
+impl Drop for NoUnwind {
+    #[inline]
+    fn drop(&mut self) {
+        extern "C" {
+            #[link_name = "\n\nerror: a function in examples/no_panic.rs can panic\n\n"]
+            fn unwound() -> !;
+        }
+        unsafe { unwound() }
+    }
+}
+
+/// Runs `f` under a [`NoUnwind`] guard.
+#[cfg(not(debug_assertions))]
+#[inline]
+fn checked<R>(f: impl FnOnce() -> R) -> R {
+    let guard = NoUnwind;
+    let result = f();
+    core::mem::forget(guard);
+    result
+}
+
+#[cfg(debug_assertions)]
+#[inline]
+fn checked<R>(f: impl FnOnce() -> R) -> R {
+    f()
+}
+
+#[inline(never)]
+fn new_set() -> BloomSet<u64> {
+    checked(BloomSet::new)
+}
+
+#[inline(never)]
+fn reserve(set: &mut BloomSet<u64>, additional: usize) -> Result<(), TryReserveError> {
+    checked(|| set.try_reserve(additional))
+}
+
+#[inline(never)]
+fn insert(set: &mut BloomSet<u64>, value: u64) -> Result<(), TryReserveError> {
+    checked(|| set.try_insert(value))
+}
+
+#[inline(never)]
+fn contains(set: &BloomSet<u64>, value: u64) -> bool {
+    checked(|| set.contains(value))
+}
+
+#[inline(never)]
+fn remove(set: &mut BloomSet<u64>, value: u64) -> bool {
+    checked(|| set.remove(value))
+}
+
+#[inline(never)]
+fn clear(set: &mut BloomSet<u64>) {
+    checked(|| set.clear());
+}
+
+#[inline(never)]
+fn free(set: BloomSet<u64>) {
+    checked(|| drop(set));
+}
+
+fn main() {
+    let mut set = new_set();
+    reserve(&mut set, 16).unwrap();
+    for value in 0..300 {
+        let inserted = insert(&mut set, value % 260);
+        assert_eq!(inserted.is_ok(), value % 260 < 255);
+    }
+    assert!(contains(&set, 254) && !contains(&set, 255));
+    assert!(remove(&mut set, 7) && !remove(&mut set, 7));
+    clear(&mut set);
+    assert!(!contains(&set, 0));
+    free(set);
+}
@@ -0,0 +1,278 @@
+//! The harness for the fuzz targets. Fuzzer input is decoded into a sequence of [`Op`]s by
+//! [`ops`], which every target shares:
+//!
+//! - [`differential`] applies them to a `BloomSet` and a `HashSet`, checking they agree after
+//!   every one.
+//! - [`invariants`] applies them to a `BloomSet` alone, checking its internal invariants after
+//!   every one, so corruption is caught before it shows up in an answer.
+//!
+//! Each operation is an opcode byte, then the key it applies to, in whatever encoding [`Key`]
+//! gives the element type, and for a few operations an amount byte. A new element type only
+//! needs a `Key` impl and targets calling these with it.
+
+use bloomset::BloomSet;
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ptr;
+
+/// How many elements a default `BloomSet` can hold. Inserts that would go past it are skipped,
+/// since they panic by design.
+const MAX_CAPACITY: usize = 255;
+
+/// An element type the harness can read from fuzzer input.
+pub trait Key<'a>: Hash + Ord + Clone + Debug {
+    /// Reads a key from the front of `data`, advancing it, or returns `None` if there isn't
+    /// enough left for one.
+    fn take(data: &mut &'a [u8]) -> Option<Self>;
+}
+
+impl<'a> Key<'a> for u16 {
+    fn take(data: &mut &'a [u8]) -> Option<Self> {
+        let (key, rest) = split(data, 2)?;
+        *data = rest;
+        Some(u16::from_le_bytes([key[0], key[1]]))
+    }
+}
+
+/// A length byte, taken modulo 32, and then that many bytes, cut back to their longest valid
+/// UTF-8 prefix. Short keys make collisions between keys likely.
+impl<'a> Key<'a> for &'a str {
+    fn take(data: &mut &'a [u8]) -> Option<Self> {
+        let (&len, rest) = data.split_first()?;
+        let (bytes, rest) = split(rest, usize::from(len % 32))?;
+        *data = rest;
+        Some(match std::str::from_utf8(bytes) {
+            Ok(key) => key,
+            Err(err) => std::str::from_utf8(&bytes[..err.valid_up_to()]).unwrap(),
+        })
+    }
+}
+
+fn split(data: &[u8], len: usize) -> Option<(&[u8], &[u8])> {
+    if data.len() < len {
+        None
+    } else {
+        Some(data.split_at(len))
+    }
+}
+
+/// Reads the amount byte some operations take.
+fn amount(data: &mut &[u8]) -> Option<usize> {
+    let (&amount, rest) = data.split_first()?;
+    *data = rest;
+    Some(usize::from(amount))
+}
+
+/// One operation on a set of `K`.
+#[derive(Debug)]
+pub enum Op<K> {
+    Insert(K),
+    /// Up to three keys, inserted at once.
+    Extend(Vec<K>),
+    Remove(K),
+    Contains(K),
+    Get(K),
+    Promote(K),
+    ContainsPromoting(K),
+    /// Sorts the set, then looks the key up with `contains_sorted`.
+    SortedContains(K),
+    /// Keeps the elements less than the key.
+    Retain { below: K, ordered: bool },
+    /// `try_reserve`, or `try_reserve_exact`, which fail past the capacity limit.
+    Reserve { additional: usize, exact: bool },
+    /// `shrink_to`, which with 0 is `shrink_to_fit`.
+    ShrinkTo(usize),
+    /// Replaces the set with an empty one made by `with_capacity`.
+    WithCapacity(usize),
+    Clear,
+}
+
+/// Decodes `data` into operations, stopping at the first one that is cut short.
+pub fn ops<'a, K: Key<'a>>(mut data: &'a [u8]) -> impl Iterator<Item = Op<K>> + 'a {
+    std::iter::from_fn(move || {
+        let (&op, rest) = data.split_first()?;
+        data = rest;
+        let key = K::take(&mut data)?;
+        let high = op >= 128;
+        Some(match op % 16 {
+            0..=4 => Op::Insert(key),
+            5 => {
+                let mut keys = vec![key];
+                keys.extend((0..op / 16 % 3).map_while(|_| K::take(&mut data)));
+                Op::Extend(keys)
+            }
+            6 | 7 => Op::Remove(key),
+            8 => Op::Get(key),
+            9 => Op::Promote(key),
+            10 => Op::ContainsPromoting(key),
+            11 => Op::SortedContains(key),
+            12 => Op::Retain {
+                below: key,
+                ordered: high,
+            },
+            13 => Op::Reserve {
+                additional: amount(&mut data)?,
+                exact: high,
+            },
+            14 if high => Op::Clear,
+            14 => Op::ShrinkTo(amount(&mut data)?),
+            15 if high => Op::WithCapacity(amount(&mut data)?),
+            _ => Op::Contains(key),
+        })
+    })
+}
+
+/// Applies `op` to `set`, returning its answer if it is a lookup, or whether it succeeded if it
+/// can fail. Inserts that would go past the capacity limit are skipped.
+pub fn apply<'a, K: Key<'a>>(set: &mut BloomSet<K>, op: &Op<K>) -> Option<bool> {
+    match op {
+        Op::Insert(key) => {
+            if set.len() < MAX_CAPACITY || set.contains(key) {
+                set.insert(key.clone());
+            }
+            None
+        }
+        Op::Extend(keys) => {
+            let new: HashSet<_> = keys.iter().filter(|it| !set.contains(*it)).collect();
+            if set.len() + new.len() <= MAX_CAPACITY {
+                set.extend(keys.iter().cloned());
+            }
+            None
+        }
+        Op::Remove(key) => Some(set.remove(key)),
+        Op::Contains(key) => Some(set.contains(key)),
+        Op::Get(key) => {
+            let found = set.get(key);
+            assert!(found.is_none_or(|it| it == key));
+            Some(found.is_some())
+        }
+        Op::Promote(key) => Some(set.promote(key)),
+        Op::ContainsPromoting(key) => Some(set.contains_promoting(key)),
+        Op::SortedContains(key) => {
+            set.sort();
+            assert!(set.is_sorted());
+            Some(set.contains_sorted(key))
+        }
+        Op::Retain { below, ordered } => {
+            if *ordered {
+                set.retain(|it| it < below);
+            } else {
+                set.retain_unordered(|it| it < below);
+            }
+            None
+        }
+        Op::Reserve { additional, exact } => Some(if *exact {
+            set.try_reserve_exact(*additional).is_ok()
+        } else {
+            set.try_reserve(*additional).is_ok()
+        }),
+        Op::ShrinkTo(min) => {
+            set.shrink_to(*min);
+            None
+        }
+        Op::WithCapacity(capacity) => {
+            *set = BloomSet::with_capacity(*capacity);
+            None
+        }
+        Op::Clear => {
+            set.clear();
+            None
+        }
+    }
+}
+
+/// Applies `op` to `model`, returning what [`apply`] should.
+fn apply_model<'a, K: Key<'a>>(model: &mut HashSet<K>, op: &Op<K>) -> Option<bool> {
+    match op {
+        Op::Insert(key) => {
+            if model.len() < MAX_CAPACITY || model.contains(key) {
+                model.insert(key.clone());
+            }
+            None
+        }
+        Op::Extend(keys) => {
+            let new: HashSet<_> = keys.iter().filter(|it| !model.contains(*it)).collect();
+            if model.len() + new.len() <= MAX_CAPACITY {
+                model.extend(keys.iter().cloned());
+            }
+            None
+        }
+        Op::Remove(key) => Some(model.remove(key)),
+        Op::Contains(key)
+        | Op::Get(key)
+        | Op::Promote(key)
+        | Op::ContainsPromoting(key)
+        | Op::SortedContains(key) => Some(model.contains(key)),
+        Op::Retain { below, .. } => {
+            model.retain(|it| it < below);
+            None
+        }
+        Op::Reserve { additional, .. } => Some(model.len() + additional <= MAX_CAPACITY),
+        Op::ShrinkTo(_) => None,
+        Op::WithCapacity(_) | Op::Clear => {
+            model.clear();
+            None
+        }
+    }
+}
+
+/// Runs the operations encoded in `data` on a `BloomSet<K>` and a `HashSet<K>`, panicking as
+/// soon as they disagree on the length, on whether the key just used is present, or on what
+/// an operation returned.
+pub fn differential<'a, K: Key<'a>>(data: &'a [u8]) {
+    let mut set = BloomSet::<K>::new();
+    let mut model = HashSet::new();
+    for op in ops::<K>(data) {
+        assert_eq!(apply(&mut set, &op), apply_model(&mut model, &op), "{:?}", op);
+        assert_eq!(set.len(), model.len());
+        if let Op::Insert(key) | Op::Remove(key) | Op::Contains(key) = &op {
+            assert_eq!(set.contains(key), model.contains(key));
+        }
+        if set.is_sorted() {
+            assert!(set.as_slice().windows(2).all(|pair| pair[0] < pair[1]));
+        }
+    }
+    assert!(set.as_slice().iter().all(|it| model.contains(it)));
+    assert!(model.iter().all(|it| set.contains(it)));
+}
+
+/// Runs the operations encoded in `data` on a `BloomSet<K>`, calling [`check_invariants`] after
+/// every one.
+pub fn invariants<'a, K: Key<'a>>(data: &'a [u8]) {
+    let mut set = BloomSet::<K>::new();
+    for op in ops::<K>(data) {
+        apply(&mut set, &op);
+        check_invariants(&mut set);
+    }
+}
+
+/// Panics unless `set` is internally consistent:
+///
+/// - its length is at most its capacity, which is at most 255;
+/// - every element's filter bits are set, so `contains` finds it;
+/// - no two elements are equal;
+/// - if it is marked sorted, the elements are in ascending order.
+///
+/// It also reads every element and writes every spare slot, so that a buffer smaller than the
+/// capacity claims is an out-of-bounds access that ASAN reports.
+pub fn check_invariants<'a, K: Key<'a>>(set: &mut BloomSet<K>) {
+    assert!(set.len() <= set.capacity() && set.capacity() <= MAX_CAPACITY);
+    let elements = set.as_slice();
+    assert_eq!(elements.len(), set.len());
+    for element in elements {
+        assert!(set.contains(element), "{:?} isn't in the filter", element);
+    }
+    let unique: HashSet<_> = elements.iter().collect();
+    assert_eq!(unique.len(), elements.len(), "duplicate elements");
+    if set.is_sorted() {
+        assert!(elements.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+    let spare = set.capacity() - set.len();
+    let slots = set.spare_capacity_mut();
+    assert_eq!(slots.len(), spare);
+    for slot in slots {
+        // The slots are uninitialized, so anything may be written to them
+        unsafe { ptr::write_bytes(slot.as_mut_ptr(), 0xA5, 1) };
+    }
+}
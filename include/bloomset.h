@@ -0,0 +1,51 @@
+/* C interface to bloomset's sets of u64, built with the `ffi` feature. See src/ffi.rs. */
+
+#ifndef BLOOMSET_H
+#define BLOOMSET_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* An opaque set of up to 255 distinct uint64_t values. */
+typedef struct BloomSetU64 BloomSetU64;
+
+/* bloomset_u64_insert added the value. */
+#define BLOOMSET_INSERTED (1)
+/* bloomset_u64_contains found the value. */
+#define BLOOMSET_PRESENT (1)
+/* The set already holds 255 values, and the inserted one isn't among them. */
+#define BLOOMSET_FULL (-1)
+/* The set pointer was null. */
+#define BLOOMSET_NULL (-2)
+/* The operation panicked. The set is still valid, and holds what it held before. */
+#define BLOOMSET_PANICKED (-3)
+
+/* Creates an empty set, or returns NULL on failure. Free it with bloomset_u64_free. */
+BloomSetU64 *bloomset_u64_new(void);
+
+/* Creates an empty set with room for capacity values, or returns NULL if capacity is more
+ * than 255 or allocating fails. */
+BloomSetU64 *bloomset_u64_with_capacity(size_t capacity);
+
+/* Returns BLOOMSET_INSERTED if value was added, 0 if it was already present, or a negative
+ * error code. */
+int bloomset_u64_insert(BloomSetU64 *set, uint64_t value);
+
+/* Returns BLOOMSET_PRESENT if value is in the set, 0 if it isn't, or a negative error code. */
+int bloomset_u64_contains(const BloomSetU64 *set, uint64_t value);
+
+/* The number of values in the set, or 0 for NULL. */
+size_t bloomset_u64_len(const BloomSetU64 *set);
+
+/* Destroys the set. NULL is ignored. */
+void bloomset_u64_free(BloomSetU64 *set);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* BLOOMSET_H */
@@ -0,0 +1,153 @@
+use crate::{BloomSet, CapacityError, ElementHasher, Packing};
+use alloc::string::ToString;
+use bincode::de::{BorrowDecode, BorrowDecoder, Decode, Decoder};
+use bincode::enc::{Encode, Encoder};
+use bincode::error::{DecodeError, EncodeError};
+use core::convert::TryFrom;
+
+/// Encodes the elements like a slice of them: their number, then each one, in the order
+/// [`as_slice`](BloomSet::as_slice) has them. The filter and seed aren't part of it.
+impl<T: Encode, S, const K: usize, P: Packing> Encode for BloomSet<T, S, K, P> {
+    #[inline]
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        self.as_slice().encode(encoder)
+    }
+}
+
+/// Decodes what a slice or `Vec` of the elements encodes to, rebuilding the filter as it goes.
+/// Unlike deserializing with serde, a duplicate element is an error rather than dropped, as is
+/// a length past the set's capacity, which is caught before any element is decoded.
+impl<Context, T, S, const K: usize, P> Decode<Context> for BloomSet<T, S, K, P>
+where
+    T: Decode<Context> + PartialEq,
+    S: ElementHasher<T> + Default,
+    P: Packing,
+{
+    #[inline]
+    fn decode<D: Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, DecodeError> {
+        decode_set(decoder, T::decode)
+    }
+}
+
+/// Like the `Decode` impl, for elements that borrow from the input, like `&'de str`.
+impl<'de, Context, T, S, const K: usize, P> BorrowDecode<'de, Context> for BloomSet<T, S, K, P>
+where
+    T: BorrowDecode<'de, Context> + PartialEq,
+    S: ElementHasher<T> + Default,
+    P: Packing,
+{
+    #[inline]
+    fn borrow_decode<D: BorrowDecoder<'de, Context = Context>>(
+        decoder: &mut D,
+    ) -> Result<Self, DecodeError> {
+        decode_set(decoder, T::borrow_decode)
+    }
+}
+
+fn decode_set<T, S, const K: usize, P, D>(
+    decoder: &mut D,
+    mut decode: impl FnMut(&mut D) -> Result<T, DecodeError>,
+) -> Result<BloomSet<T, S, K, P>, DecodeError>
+where
+    T: PartialEq,
+    S: ElementHasher<T> + Default,
+    P: Packing,
+    D: Decoder,
+{
+    let len = u64::decode(decoder)?;
+    let len = usize::try_from(len).map_err(|_| DecodeError::OutsideUsizeRange(len))?;
+    if len > P::MAX_CAPACITY {
+        let err = CapacityError::new(len, P::MAX_CAPACITY);
+        return Err(DecodeError::OtherString(err.to_string()));
+    }
+    // Counts against the decoder's limit, if it has one, the way decoding a `Vec` does
+    decoder.claim_container_read::<T>(len)?;
+    let mut set = BloomSet::with_capacity_and_hasher(len, S::default());
+    for _ in 0..len {
+        decoder.unclaim_bytes_read(core::mem::size_of::<T>());
+        set.insert_new(decode(decoder)?)
+            .map_err(|err| DecodeError::OtherString(err.to_string()))?;
+    }
+    Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BloomSet, BloomSet16};
+    use bincode::error::DecodeError;
+
+    fn encode<T: bincode::Encode>(value: T) -> Vec<u8> {
+        bincode::encode_to_vec(value, bincode::config::standard()).unwrap()
+    }
+
+    fn decode<T: bincode::Decode<()>>(bytes: &[u8]) -> Result<T, DecodeError> {
+        bincode::decode_from_slice(bytes, bincode::config::standard()).map(|(value, _)| value)
+    }
+
+    #[test]
+    fn round_trip() {
+        let set: BloomSet<u64> = (0..255).map(|i| i * 7919).collect();
+        let bytes = encode(&set);
+        assert_eq!(bytes, encode(set.as_slice()));
+        let back: BloomSet<u64> = decode(&bytes).unwrap();
+        assert_eq!(back.as_slice(), set.as_slice());
+        let mut rebuilt = BloomSet::with_seed(back.seed());
+        rebuilt.extend(set.as_slice().iter().copied());
+        assert_eq!(back.filter_bits(), rebuilt.filter_bits());
+
+        let names: BloomSet<String> = ["b", "a"].iter().map(ToString::to_string).collect();
+        let back: BloomSet<String> = decode(&encode(&names)).unwrap();
+        assert_eq!(back.as_slice(), ["b", "a"]);
+        assert!(back.contains("a".to_string()));
+
+        let empty: BloomSet<u64> = decode(&encode(Vec::<u64>::new())).unwrap();
+        assert!(empty.is_empty());
+
+        let wide: BloomSet16<u32> = decode(&encode((0..1000).collect::<Vec<u32>>())).unwrap();
+        assert!(wide.as_slice().iter().copied().eq(0..1000));
+    }
+
+    #[test]
+    fn borrowed_from_the_input() {
+        // Arrays encode without their length, unlike slices
+        let bytes = encode(&["read", "write"][..]);
+        let (set, _): (BloomSet<&str>, usize) =
+            bincode::borrow_decode_from_slice(&bytes, bincode::config::standard()).unwrap();
+        assert_eq!(set.as_slice(), ["read", "write"]);
+        assert!(set.as_slice()[0].as_ptr() > bytes.as_ptr());
+    }
+
+    #[test]
+    fn rejects_what_a_set_cant_hold() {
+        let too_many = encode((0..256).collect::<Vec<u64>>());
+        match decode::<BloomSet<u64>>(&too_many) {
+            Err(DecodeError::OtherString(message)) => assert_eq!(
+                message,
+                "a BloomSet cannot hold more than 255 elements (256 requested)"
+            ),
+            other => panic!("{:?}", other.map(|set| set.len())),
+        }
+
+        let duplicates = encode(vec![4_u64, 1, 4]);
+        match decode::<BloomSet<u64>>(&duplicates) {
+            Err(DecodeError::OtherString(message)) => assert_eq!(
+                message,
+                "the BloomSet already holds an equal element, at index 0"
+            ),
+            other => panic!("{:?}", other.map(|set| set.len())),
+        }
+
+        let truncated = &encode(vec![1_u64, 2, 3])[..3];
+        assert!(matches!(
+            decode::<BloomSet<u64>>(truncated),
+            Err(DecodeError::UnexpectedEnd { .. })
+        ));
+
+        let limited = bincode::config::standard().with_limit::<16>();
+        let bytes = encode((0..100).collect::<Vec<u64>>());
+        assert!(matches!(
+            bincode::decode_from_slice::<BloomSet<u64>, _>(&bytes, limited),
+            Err(DecodeError::LimitExceeded)
+        ));
+    }
+}
@@ -0,0 +1,394 @@
+use crate::{
+    bloom_bit, bloom_mask, handle_reserve_error, seeded, BloomSet, BuildBloomHasher, ElementHasher,
+    Packed8, Packing, TryReserveError,
+};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::iter::FromIterator;
+use core::ops::{Deref, Range};
+use core::slice;
+
+/// A [`BloomSet`] that keeps its elements grouped by the filter bit each one sets, so a lookup
+/// the filter lets through compares only the elements that share the item's bit.
+///
+/// A plain `BloomSet` scans every element once its filter says "maybe", which a full filter
+/// says to almost every lookup. Here the elements are stored in order of their filter bit, and
+/// a table of where each bit's group ends says which elements to compare, so a lookup hashes
+/// the item once and compares it with the one or two elements on its bit, rather than with all
+/// of them. The table takes a separate allocation of two bytes per filter bit, 224 for a
+/// [`Packed8`] set, made by the first insert.
+///
+/// The price is the insertion order: the elements come out of [`as_slice`](BloomSet::as_slice)
+/// grouped by bit, and in the order they were inserted only within a group. Inserting moves the
+/// elements after the new one's group up a slot. The set derefs to the `BloomSet` for reading;
+/// changes go through this type, which keeps the grouping.
+///
+/// ```
+/// use bloomset::BucketedBloomSet;
+///
+/// let mut set: BucketedBloomSet<String> = (0..200).map(|i| i.to_string()).collect();
+/// set.insert("200".to_string());
+/// assert!(set.contains("117".to_string()));
+/// assert!(!set.contains("201".to_string()));
+/// assert_eq!(set.len(), 201);
+/// ```
+pub struct BucketedBloomSet<T, S = BuildBloomHasher, P: Packing = Packed8> {
+    set: BloomSet<T, S, 1, P>,
+    /// Where the group of each filter bit ends, so the group of bit `i` is
+    /// `ends[i - 1]..ends[i]`. Empty until the first insert, and after a `clear`, which both
+    /// mean every group is.
+    ends: Vec<u16>,
+}
+
+/// One entry per filter bit.
+#[allow(clippy::cast_possible_truncation)]
+const fn groups<P: Packing>() -> usize {
+    P::FILTER_BITS as usize
+}
+
+/// The group of an element with the seeded hash `hash`, which is its filter bit.
+#[inline]
+#[allow(clippy::cast_possible_truncation)]
+const fn bucket(hash: u64, filter_bits: u64) -> usize {
+    bloom_bit(hash, filter_bits) as usize
+}
+
+impl<T> Default for BucketedBloomSet<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_hasher(BuildBloomHasher)
+    }
+}
+
+impl<T> BucketedBloomSet<T> {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_capacity(cap: usize) -> Self {
+        Self::with_capacity_and_hasher(cap, BuildBloomHasher)
+    }
+}
+
+impl<T, S, P: Packing> BucketedBloomSet<T, S, P> {
+    /// Creates an empty set which will use `hash_builder` to hash its elements.
+    #[inline]
+    #[must_use]
+    pub const fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            set: BloomSet::with_hasher(hash_builder),
+            ends: Vec::new(),
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `cap` is more than the packing's [`MAX_CAPACITY`](Packing::MAX_CAPACITY).
+    #[inline]
+    #[must_use]
+    pub fn with_capacity_and_hasher(cap: usize, hash_builder: S) -> Self {
+        Self {
+            set: BloomSet::with_capacity_and_hasher(cap, hash_builder),
+            ends: Vec::new(),
+        }
+    }
+
+    /// The set, with its elements still grouped by filter bit.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> BloomSet<T, S, 1, P> {
+        self.set
+    }
+
+    /// Removes every element, keeping the allocations for the elements and the table.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.set.clear();
+        self.ends.clear();
+    }
+
+    /// Where the elements on filter bit `bucket` are, which is an empty range at the index
+    /// they would go if there are none.
+    #[inline]
+    fn group(&self, bucket: usize) -> Range<usize> {
+        match self.ends.get(bucket) {
+            Some(&end) => {
+                let start = if bucket == 0 {
+                    0
+                } else {
+                    self.ends[bucket - 1]
+                };
+                usize::from(start)..usize::from(end)
+            }
+            None => 0..0,
+        }
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T>, P: Packing> BucketedBloomSet<T, S, P> {
+    /// The seeded hash of `item`, which gives both its group and its filter bit.
+    #[inline]
+    fn hash_of(&self, item: &T) -> u64 {
+        seeded(self.set.hash_builder.hash_element(item), self.set.seed())
+    }
+
+    /// The filter bit `item` sets, which is the group it belongs in.
+    #[inline]
+    fn bucket_of(&self, item: &T) -> usize {
+        bucket(self.hash_of(item), P::FILTER_BITS)
+    }
+
+    /// Where `item` is, if it is in the set, and its seeded hash either way.
+    #[inline]
+    fn find(&self, item: &T) -> (Option<usize>, u64) {
+        let hash = self.hash_of(item);
+        if !self
+            .set
+            .bloom_contains(bloom_mask::<1>(hash, P::FILTER_BITS))
+        {
+            return (None, hash);
+        }
+        let group = self.group(bucket(hash, P::FILTER_BITS));
+        let index = self.set.as_slice()[group.clone()]
+            .iter()
+            .position(|it| it == item)
+            .map(|offset| group.start + offset);
+        #[cfg(feature = "paranoid")]
+        self.set.check_lookup(item, index.is_some());
+        (index, hash)
+    }
+
+    /// Works out the table again from the elements, which must be grouped already.
+    fn recount(&mut self) {
+        self.ends.clear();
+        self.ends.resize(groups::<P>(), 0);
+        for item in self.set.as_slice() {
+            let bucket = self.bucket_of(item);
+            self.ends[bucket] += 1;
+        }
+        let mut end = 0;
+        for count in &mut self.ends {
+            end += *count;
+            *count = end;
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn contains<B: Borrow<T>>(&self, item: B) -> bool {
+        self.find(item.borrow()).0.is_some()
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the set would need to grow beyond its packing's
+    /// [`MAX_CAPACITY`](Packing::MAX_CAPACITY) (255 by default).
+    #[inline]
+    pub fn insert(&mut self, item: T) {
+        if let Err(err) = self.try_insert(item) {
+            handle_reserve_error(err);
+        }
+    }
+
+    /// Like [`insert`](BucketedBloomSet::insert), but returns an error instead of panicking or
+    /// aborting. On failure the set is left as it was and `item` is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TryReserveError`] if the set is full or the allocator fails, like
+    /// [`BloomSet::try_insert`].
+    #[inline]
+    pub fn try_insert(&mut self, item: T) -> Result<(), TryReserveError> {
+        let (index, hash) = self.find(&item);
+        if index.is_some() {
+            return Ok(());
+        }
+        let bucket = bucket(hash, P::FILTER_BITS);
+        let end = self.group(bucket).end;
+        if self.ends.is_empty() {
+            self.ends.resize(groups::<P>(), 0);
+        }
+        self.set
+            .push_new(item, bloom_mask::<1>(hash, P::FILTER_BITS))?;
+        let len = self.set.len();
+        if end + 1 < len {
+            // The new element is last; rotate it back to the end of its group
+            unsafe { slice::from_raw_parts_mut(self.set.as_mut_ptr().add(end), len - end) }
+                .rotate_right(1);
+            self.set.moved_elements();
+        }
+        for end in &mut self.ends[bucket..] {
+            *end += 1;
+        }
+        Ok(())
+    }
+
+    /// Removes `item` from the set, returning whether it was present. The elements after it
+    /// move down a slot, so the rest stay grouped.
+    #[inline]
+    pub fn remove<B: Borrow<T>>(&mut self, item: B) -> bool {
+        match self.find(item.borrow()) {
+            (Some(index), hash) => {
+                drop(self.set.take(index));
+                for end in &mut self.ends[bucket(hash, P::FILTER_BITS)..] {
+                    *end -= 1;
+                }
+                true
+            }
+            (None, _) => false,
+        }
+    }
+
+    /// Keeps only the elements for which `f` returns `true`, like [`BloomSet::retain`], which
+    /// keeps their order and so their grouping. The table is worked out again afterwards,
+    /// hashing each element left.
+    #[inline]
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, f: F) {
+        self.set.retain(f);
+        self.recount();
+    }
+}
+
+impl<T, S, P: Packing> Deref for BucketedBloomSet<T, S, P> {
+    type Target = BloomSet<T, S, 1, P>;
+
+    #[inline]
+    fn deref(&self) -> &BloomSet<T, S, 1, P> {
+        &self.set
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T>, P: Packing> From<BloomSet<T, S, 1, P>>
+    for BucketedBloomSet<T, S, P>
+{
+    /// Groups the elements of `set` by filter bit, keeping their order within each group. This
+    /// sorts them by bit, which allocates a key per element, and hashes each one twice.
+    #[inline]
+    fn from(mut set: BloomSet<T, S, 1, P>) -> Self {
+        let seed = set.seed();
+        let hash_builder = &set.hash_builder;
+        unsafe { slice::from_raw_parts_mut(set.as_mut_ptr(), set.len()) }.sort_by_cached_key(
+            |it| bloom_bit(seeded(hash_builder.hash_element(it), seed), P::FILTER_BITS),
+        );
+        set.length &= !P::SORTED_FLAG;
+        set.moved_elements();
+        let mut bucketed = Self {
+            set,
+            ends: Vec::new(),
+        };
+        bucketed.recount();
+        bucketed
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T>, P: Packing> Extend<T> for BucketedBloomSet<T, S, P> {
+    /// # Panics
+    ///
+    /// Panics if the set would need to hold more unique elements than it can.
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(item);
+        }
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T> + Default, P: Packing> FromIterator<T>
+    for BucketedBloomSet<T, S, P>
+{
+    /// # Panics
+    ///
+    /// Panics if the iterator yields more unique elements than the set can hold.
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<BloomSet<T, S, 1, P>>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BloomSet16, BuildBloomHasher};
+
+    /// Panics unless the elements are in order of their filter bits, where the table says.
+    fn assert_grouped<T: PartialEq, S: ElementHasher<T>, P: Packing>(
+        set: &BucketedBloomSet<T, S, P>,
+    ) {
+        let buckets: Vec<usize> = set.as_slice().iter().map(|it| set.bucket_of(it)).collect();
+        assert!(
+            buckets.windows(2).all(|pair| pair[0] <= pair[1]),
+            "{:?}",
+            buckets
+        );
+        // And the table agrees on where each group is
+        for (index, &bucket) in buckets.iter().enumerate() {
+            assert!(
+                set.group(bucket).contains(&index),
+                "{} on bit {}",
+                index,
+                bucket
+            );
+        }
+        assert_eq!(
+            set.ends.last().map_or(0, |&end| usize::from(end)),
+            set.len()
+        );
+    }
+
+    #[test]
+    fn inserts_into_its_group() {
+        let mut set = BucketedBloomSet::new();
+        for i in 0..255_u64 {
+            set.insert(i * 7919);
+            set.insert(i * 7919);
+            assert_grouped(&set);
+            assert_eq!(set.len() as u64, i + 1);
+        }
+        assert!((0..255).all(|i| set.contains(i * 7919)));
+        assert!(!set.contains(1));
+        assert!(set.try_insert(1).is_err());
+        assert!(set.try_insert(7919).is_ok());
+
+        // Within a group the elements keep the order they came in
+        let plain: BloomSet<u64> = (0..255).map(|i| i * 7919).collect();
+        let mut expected = plain.as_slice().to_vec();
+        expected.sort_by_key(|it| set.bucket_of(it));
+        assert_eq!(set.as_slice(), expected);
+    }
+
+    #[test]
+    fn removes_keep_the_groups() {
+        let mut set: BucketedBloomSet<String> = (0..100).map(|i| i.to_string()).collect();
+        assert_grouped(&set);
+        for i in (0..100).step_by(3) {
+            assert!(set.remove(i.to_string()));
+            assert!(!set.remove(i.to_string()));
+        }
+        assert_grouped(&set);
+        set.retain(|it| it.len() == 2);
+        assert_grouped(&set);
+        assert_eq!(set.len(), 60);
+        assert!(set.contains("11".to_string()) && !set.contains("12".to_string()));
+        set.extend((0..10).map(|i| i.to_string()));
+        assert_grouped(&set);
+        assert!((0..10).all(|i| set.contains(i.to_string())));
+
+        set.clear();
+        assert!(set.is_empty() && !set.contains("11".to_string()));
+    }
+
+    #[test]
+    fn wide_sets() {
+        let mut set: BucketedBloomSet<u32, BuildBloomHasher, crate::Packed16> =
+            BucketedBloomSet::from(BloomSet16::with_hasher(BuildBloomHasher));
+        set.extend(0..1000);
+        assert_grouped(&set);
+        assert!((0..1000).all(|i| set.contains(i)));
+        assert!(!set.contains(1000));
+        assert_eq!(set.into_inner().len(), 1000);
+    }
+}
@@ -0,0 +1,272 @@
+use crate::{
+    bloom_mask, split_mask, unpack_filter, BuildBloomHasher, ElementHasher, Packed8, FILTER_BITS,
+    MAX_CAPACITY,
+};
+use core::borrow::Borrow;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ptr::{self, NonNull};
+use core::{fmt, slice};
+
+/// A [`BloomSet`](crate::BloomSet) whose elements live in a buffer borrowed from the caller, for
+/// environments without an allocator.
+///
+/// The set packs its filter into its length and capacity words the same way a `BloomSet` does,
+/// but it never reallocates: once the buffer is full, [`try_insert`](BloomSetBuf::try_insert)
+/// hands new elements back. Only the first 255 slots of a longer buffer are used. Dropping the set
+/// drops the elements it holds and leaves the buffer itself to its owner.
+///
+/// ```
+/// use bloomset::BloomSetBuf;
+/// use core::mem::MaybeUninit;
+///
+/// let mut buffer = [MaybeUninit::uninit(); 2];
+/// let mut set = BloomSetBuf::new_in(&mut buffer);
+/// assert_eq!(set.try_insert(1), Ok(()));
+/// assert_eq!(set.try_insert(2), Ok(()));
+/// assert_eq!(set.try_insert(3), Err(3));
+/// assert!(set.contains(2));
+/// ```
+pub struct BloomSetBuf<'a, T, S = BuildBloomHasher> {
+    ptr: NonNull<T>,
+    length: usize,
+    capacity: usize,
+    hash_builder: S,
+    buffer: PhantomData<&'a mut [MaybeUninit<T>]>,
+}
+
+impl<'a, T> BloomSetBuf<'a, T> {
+    #[inline]
+    #[must_use]
+    pub fn new_in(buffer: &'a mut [MaybeUninit<T>]) -> Self {
+        Self::with_hasher_in(BuildBloomHasher, buffer)
+    }
+}
+
+impl<'a, T, S> BloomSetBuf<'a, T, S> {
+    /// Creates an empty set in `buffer` which will use `hash_builder` to hash its elements.
+    #[inline]
+    #[must_use]
+    pub fn with_hasher_in(hash_builder: S, buffer: &'a mut [MaybeUninit<T>]) -> Self {
+        let capacity = buffer.len().min(MAX_CAPACITY);
+        Self {
+            ptr: NonNull::from(buffer).cast(),
+            length: 0,
+            capacity,
+            hash_builder,
+            buffer: PhantomData,
+        }
+    }
+
+    /// Returns a reference to the set's `BuildHasher`.
+    #[inline]
+    #[must_use]
+    pub const fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.length & MAX_CAPACITY
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.capacity & MAX_CAPACITY
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len()) }
+    }
+
+    /// Drops the elements and zeroes the filter, keeping the buffer.
+    #[inline]
+    pub fn clear(&mut self) {
+        let elements = ptr::slice_from_raw_parts_mut(self.ptr.as_ptr(), self.len());
+        // Reset the length first, so a panicking Drop impl can't lead to a double drop
+        self.length = 0;
+        self.capacity &= MAX_CAPACITY;
+        unsafe { ptr::drop_in_place(elements) };
+    }
+
+    const fn filter(&self) -> u128 {
+        unpack_filter::<Packed8>(self.length, self.capacity)
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T>> BloomSetBuf<'_, T, S> {
+    fn bloom_mask_of(&self, item: &T) -> u128 {
+        bloom_mask::<1>(self.hash_builder.hash_element(item), FILTER_BITS)
+    }
+
+    /// Inserts `item`, or hands it back if the buffer is full and doesn't already contain it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `item` if there is no room for it.
+    #[inline]
+    pub fn try_insert(&mut self, item: T) -> Result<(), T> {
+        let mask = self.bloom_mask_of(&item);
+        if self.filter() & mask == mask && self.as_slice().contains(&item) {
+            return Ok(());
+        }
+        if self.is_full() {
+            return Err(item);
+        }
+        unsafe { self.ptr.as_ptr().add(self.len()).write(item) };
+        let (low, high) = split_mask::<Packed8>(mask);
+        self.capacity |= low;
+        self.length = (self.length | high) + 1;
+        Ok(())
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the buffer is full and doesn't already contain `item`.
+    #[inline]
+    pub fn insert(&mut self, item: T) {
+        assert!(
+            self.try_insert(item).is_ok(),
+            "this BloomSetBuf's buffer only has room for {} elements",
+            self.capacity()
+        );
+    }
+
+    /// Removes `item` from the set, returning whether it was present. The remaining elements
+    /// keep their order, and the filter keeps `item`'s bit.
+    #[inline]
+    pub fn remove<B: Borrow<T>>(&mut self, item: B) -> bool {
+        let item = item.borrow();
+        let mask = self.bloom_mask_of(item);
+        if self.filter() & mask != mask {
+            return false;
+        }
+        let Some(index) = self.as_slice().iter().position(|it| it == item) else {
+            return false;
+        };
+        let len = self.len();
+        let removed = unsafe {
+            let base = self.ptr.as_ptr();
+            let removed = base.add(index).read();
+            ptr::copy(base.add(index + 1), base.add(index), len - index - 1);
+            removed
+        };
+        self.length -= 1;
+        drop(removed);
+        true
+    }
+
+    #[inline]
+    pub fn contains<B: Borrow<T>>(&self, item: B) -> bool {
+        let item = item.borrow();
+        let mask = self.bloom_mask_of(item);
+        self.filter() & mask == mask && self.as_slice().iter().any(|it| it == item)
+    }
+}
+
+impl<T: fmt::Debug, S> fmt::Debug for BloomSetBuf<'_, T, S> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.as_slice()).finish()
+    }
+}
+
+impl<T, S> Drop for BloomSetBuf<'_, T, S> {
+    /// Drops the elements; the buffer belongs to the caller.
+    #[inline]
+    fn drop(&mut self) {
+        let elements = ptr::slice_from_raw_parts_mut(self.ptr.as_ptr(), self.len());
+        unsafe { ptr::drop_in_place(elements) };
+    }
+}
+
+// The set is a `&mut [MaybeUninit<T>]` that owns the elements it has written, so it can cross
+// threads under the same conditions as one
+unsafe impl<T: Send, S: Send> Send for BloomSetBuf<'_, T, S> {}
+unsafe impl<T: Sync, S: Sync> Sync for BloomSetBuf<'_, T, S> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BloomSet;
+    use std::rc::Rc;
+
+    #[test]
+    #[cfg_attr(feature = "random-seed", ignore = "compares filter bits across sets")]
+    fn agrees_with_bloomset() {
+        let mut buffer = [MaybeUninit::uninit(); 300];
+        let mut set = BloomSetBuf::new_in(&mut buffer);
+        let mut heap = BloomSet::new();
+        assert_eq!(set.capacity(), MAX_CAPACITY);
+        for i in 0..255_u32 {
+            set.insert(i * 5);
+            heap.insert(i * 5);
+        }
+        assert_eq!(set.as_slice(), heap.as_slice());
+        assert_eq!(set.filter(), heap.filter_bits());
+        assert!((0..1300).all(|i| set.contains(i) == heap.contains(i)));
+        assert_eq!(set.try_insert(1), Err(1));
+        assert_eq!(set.try_insert(5), Ok(()));
+
+        assert!(set.remove(5) && !set.remove(5));
+        assert!(heap.remove(5));
+        assert_eq!(set.as_slice(), heap.as_slice());
+        assert_eq!(set.try_insert(1), Ok(()));
+    }
+
+    #[test]
+    fn drops_only_initialized_elements() {
+        let counted = Rc::new(());
+        let mut buffer: [MaybeUninit<(u32, Rc<()>)>; 8] = [(); 8].map(|()| MaybeUninit::uninit());
+        for round in 0..3 {
+            // Leave the buffer partially filled, so its tail is never initialized
+            let mut set = BloomSetBuf::new_in(&mut buffer);
+            for i in 0..5 {
+                set.insert((i, Rc::clone(&counted)));
+            }
+            assert_eq!(Rc::strong_count(&counted), 6);
+            assert!(set.remove((2, Rc::clone(&counted))));
+            assert_eq!(Rc::strong_count(&counted), 5);
+            if round == 1 {
+                set.clear();
+                assert!(set.is_empty() && set.filter() == 0);
+                assert_eq!(Rc::strong_count(&counted), 1);
+                set.insert((7, Rc::clone(&counted)));
+            }
+            drop(set);
+            assert_eq!(Rc::strong_count(&counted), 1);
+        }
+    }
+
+    #[test]
+    fn buf_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<BloomSetBuf<'_, u32>>();
+        assert_send_sync::<BloomSetBuf<'_, String>>();
+    }
+
+    #[test]
+    fn empty_buffer() {
+        let mut buffer = [];
+        let mut set = BloomSetBuf::new_in(&mut buffer);
+        assert!(set.is_full());
+        assert_eq!(set.try_insert("a"), Err("a"));
+        assert!(!set.contains("a"));
+    }
+}
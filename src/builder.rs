@@ -0,0 +1,167 @@
+use crate::{BloomSet, BuildBloomHasher, CapacityError, ElementHasher};
+
+/// Configures a [`BloomSet`] one option at a time, for when more than one of them differs from
+/// the default. Start from [`BloomSet::builder`].
+///
+/// ```
+/// use bloomset::BloomSet;
+/// use std::collections::hash_map::RandomState;
+///
+/// let template = BloomSet::builder().capacity(64).seed(7);
+/// let mut ids: BloomSet<u32> = template.clone().build()?;
+/// ids.insert(1);
+/// assert_eq!((ids.capacity(), ids.seed()), (64, 7));
+///
+/// let names: BloomSet<&str, RandomState, 2> =
+///     template.hasher(RandomState::new()).bits::<2>().build()?;
+/// assert_eq!(names.capacity(), 64);
+/// # Ok::<(), bloomset::CapacityError>(())
+/// ```
+///
+/// The builder doesn't depend on the element type, so a configured one can be cloned to make
+/// sets of several.
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct BloomSetBuilder<S = BuildBloomHasher, const K: usize = 1> {
+    capacity: usize,
+    seed: Option<u8>,
+    hash_builder: S,
+}
+
+impl BloomSetBuilder {
+    /// The same as [`BloomSet::builder`].
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            capacity: 0,
+            seed: None,
+            hash_builder: BuildBloomHasher,
+        }
+    }
+}
+
+impl Default for BloomSetBuilder {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S, const K: usize> BloomSetBuilder<S, K> {
+    /// Room for exactly `capacity` elements up front, like
+    /// [`with_capacity`](BloomSet::with_capacity). Past 255, [`build`](Self::build) fails.
+    #[inline]
+    pub const fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// The seed, like [`with_seed`](BloomSet::with_seed). Without one the set gets the seed
+    /// [`BloomSet::new`] would give it: 0, or a random one with the `random-seed` feature.
+    #[inline]
+    pub const fn seed(mut self, seed: u8) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Hashes the elements with `hash_builder` instead of [`BuildBloomHasher`].
+    #[inline]
+    pub fn hasher<H>(self, hash_builder: H) -> BloomSetBuilder<H, K> {
+        BloomSetBuilder {
+            capacity: self.capacity,
+            seed: self.seed,
+            hash_builder,
+        }
+    }
+
+    /// Sets `N` filter bits per element instead of `K`; see [`BloomSet`]'s `K` parameter.
+    #[inline]
+    pub fn bits<const N: usize>(self) -> BloomSetBuilder<S, N> {
+        BloomSetBuilder {
+            capacity: self.capacity,
+            seed: self.seed,
+            hash_builder: self.hash_builder,
+        }
+    }
+
+    /// Creates the set.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CapacityError`] if the capacity is more than 255.
+    #[inline]
+    pub fn build<T>(self) -> Result<BloomSet<T, S, K>, CapacityError>
+    where
+        S: ElementHasher<T>,
+    {
+        let mut set = BloomSet::try_with_capacity_and_hasher(self.capacity, self.hash_builder)?;
+        if let Some(seed) = self.seed {
+            set.set_seed(seed);
+        }
+        Ok(set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BloomSet;
+    use std::collections::hash_map::RandomState;
+
+    #[test]
+    fn each_knob() {
+        let plain: BloomSet<u32> = BloomSet::builder().build().unwrap();
+        assert_eq!((plain.capacity(), plain.len()), (0, 0));
+
+        let sized: BloomSet<u32> = BloomSet::builder().capacity(40).build().unwrap();
+        assert_eq!(sized.capacity(), 40);
+
+        let seeded: BloomSet<u32> = BloomSet::builder().seed(200).build().unwrap();
+        assert_eq!(seeded.seed(), 200);
+        assert_eq!(seeded.filter_bits(), 0);
+
+        let mut hashed: BloomSet<&str, RandomState> = BloomSet::builder()
+            .hasher(RandomState::new())
+            .build()
+            .unwrap();
+        hashed.insert("a");
+        assert!(hashed.contains("a") && !hashed.contains("b"));
+
+        let mut two: BloomSet<u64, _, 2> = BloomSet::builder().bits::<2>().build().unwrap();
+        two.insert(1);
+        assert!(two.filter_bits().count_ones() <= 2 && two.contains(1));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "no-filter", ignore = "checks the filter bits")]
+    fn matches_the_constructors() {
+        let mut built: BloomSet<u32> = BloomSet::builder().capacity(8).seed(3).build().unwrap();
+        let mut made = BloomSet::with_capacity_and_seed(8, 3);
+        built.extend(0..20);
+        made.extend(0..20);
+        assert_eq!(built.filter_bits(), made.filter_bits());
+        assert_eq!(built.as_slice(), made.as_slice());
+    }
+
+    #[test]
+    fn template() {
+        let template = BloomSet::builder().capacity(16).seed(9);
+        let sets: Vec<BloomSet<u8>> = (0..4).map(|_| template.clone().build().unwrap()).collect();
+        assert!(sets
+            .iter()
+            .all(|set| set.capacity() == 16 && set.seed() == 9));
+        let strings: BloomSet<String> = template.build().unwrap();
+        assert_eq!(strings.seed(), 9);
+    }
+
+    #[test]
+    fn capacity_too_large() {
+        assert!(BloomSet::builder().capacity(255).build::<u32>().is_ok());
+        let err = BloomSet::builder()
+            .capacity(256)
+            .seed(1)
+            .build::<u32>()
+            .err()
+            .unwrap();
+        assert_eq!((err.requested(), err.limit()), (256, 255));
+    }
+}
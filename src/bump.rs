@@ -0,0 +1,291 @@
+use crate::{
+    bloom_mask, grown_capacity, split_mask, unpack_filter, BuildBloomHasher, CapacityError,
+    ElementHasher, Packed8, FILTER_BITS, MAX_CAPACITY,
+};
+use alloc::alloc::Layout;
+use bumpalo::Bump;
+use core::borrow::Borrow;
+use core::mem;
+use core::ptr::{self, NonNull};
+use core::slice;
+
+/// A [`BloomSet`](crate::BloomSet) whose elements live in a [`bumpalo`] arena, for sets that are
+/// created in bulk and all die together.
+///
+/// Growing the set is a pointer bump in the arena, and the old buffer is simply abandoned.
+/// Dropping the set drops its elements but never frees memory; that happens when the arena is
+/// reset or dropped, which the borrow of the arena ensures can't happen while the set is alive.
+///
+/// ```
+/// use bloomset::BumpBloomSet;
+/// use bumpalo::Bump;
+///
+/// let mut arena = Bump::new();
+/// for round in 0..3 {
+///     let mut set = BumpBloomSet::new_in(&arena);
+///     set.insert(round);
+///     assert!(set.contains(round));
+///     drop(set);
+///     arena.reset();
+/// }
+/// ```
+pub struct BumpBloomSet<'bump, T, S = BuildBloomHasher> {
+    ptr: NonNull<T>,
+    length: usize,
+    capacity: usize,
+    bump: &'bump Bump,
+    hash_builder: S,
+}
+
+impl<'bump, T> BumpBloomSet<'bump, T> {
+    #[inline]
+    #[must_use]
+    pub const fn new_in(bump: &'bump Bump) -> Self {
+        Self::with_hasher_in(BuildBloomHasher, bump)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_capacity_in(cap: usize, bump: &'bump Bump) -> Self {
+        Self::with_capacity_and_hasher_in(cap, BuildBloomHasher, bump)
+    }
+}
+
+impl<'bump, T, S> BumpBloomSet<'bump, T, S> {
+    /// Creates an empty set in `bump` which will use `hash_builder` to hash its elements.
+    #[inline]
+    #[must_use]
+    pub const fn with_hasher_in(hash_builder: S, bump: &'bump Bump) -> Self {
+        Self {
+            ptr: NonNull::dangling(),
+            length: 0,
+            capacity: 0,
+            bump,
+            hash_builder,
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `cap` is more than 255.
+    #[inline]
+    #[must_use]
+    pub fn with_capacity_and_hasher_in(cap: usize, hash_builder: S, bump: &'bump Bump) -> Self {
+        assert!(
+            cap <= MAX_CAPACITY,
+            "{}",
+            CapacityError::new(cap, MAX_CAPACITY)
+        );
+        let mut set = Self::with_hasher_in(hash_builder, bump);
+        if cap > 0 {
+            set.reallocate(cap);
+        }
+        set
+    }
+
+    /// Returns the arena the set's elements live in.
+    #[inline]
+    #[must_use]
+    pub const fn bump(&self) -> &'bump Bump {
+        self.bump
+    }
+
+    /// Returns a reference to the set's `BuildHasher`.
+    #[inline]
+    #[must_use]
+    pub const fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.length & MAX_CAPACITY
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.capacity & MAX_CAPACITY
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len()) }
+    }
+
+    /// Drops the elements and zeroes the filter, keeping the buffer.
+    #[inline]
+    pub fn clear(&mut self) {
+        let elements = ptr::slice_from_raw_parts_mut(self.ptr.as_ptr(), self.len());
+        // Reset the length first, so a panicking Drop impl can't lead to a double drop
+        self.length = 0;
+        self.capacity &= MAX_CAPACITY;
+        unsafe { ptr::drop_in_place(elements) };
+    }
+
+    const fn filter(&self) -> u128 {
+        unpack_filter::<Packed8>(self.length, self.capacity)
+    }
+
+    /// Moves the elements into a fresh buffer of `new_capacity` from the arena. The old buffer
+    /// stays allocated until the arena is reset.
+    fn reallocate(&mut self, new_capacity: usize) {
+        if mem::size_of::<T>() == 0 {
+            self.capacity = MAX_CAPACITY | (self.capacity & !MAX_CAPACITY);
+            return;
+        }
+        let layout = Layout::array::<T>(new_capacity).unwrap_or_else(|_| unreachable!());
+        let new_ptr = self.bump.alloc_layout(layout).cast::<T>();
+        unsafe { ptr::copy_nonoverlapping(self.ptr.as_ptr(), new_ptr.as_ptr(), self.len()) };
+        self.ptr = new_ptr;
+        self.capacity = new_capacity | (self.capacity & !MAX_CAPACITY);
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T>> BumpBloomSet<'_, T, S> {
+    fn bloom_mask_of(&self, item: &T) -> u128 {
+        bloom_mask::<1>(self.hash_builder.hash_element(item), FILTER_BITS)
+    }
+
+    /// # Errors
+    ///
+    /// Returns a [`CapacityError`] if `item` would be the set's 256th element.
+    #[inline]
+    pub fn try_insert(&mut self, item: T) -> Result<(), CapacityError> {
+        let mask = self.bloom_mask_of(&item);
+        if self.filter() & mask == mask && self.as_slice().contains(&item) {
+            return Ok(());
+        }
+        let len = self.len();
+        if len == self.capacity() {
+            if len == MAX_CAPACITY {
+                return Err(CapacityError::new(MAX_CAPACITY + 1, MAX_CAPACITY));
+            }
+            self.reallocate(grown_capacity(len, MAX_CAPACITY));
+        }
+        unsafe { self.ptr.as_ptr().add(len).write(item) };
+        let (low, high) = split_mask::<Packed8>(mask);
+        self.capacity |= low;
+        self.length = (self.length | high) + 1;
+        Ok(())
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `item` would be the set's 256th element.
+    #[inline]
+    pub fn insert(&mut self, item: T) {
+        if let Err(err) = self.try_insert(item) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Removes `item` from the set, returning whether it was present. The remaining elements
+    /// keep their order, and the filter keeps `item`'s bit.
+    #[inline]
+    pub fn remove<B: Borrow<T>>(&mut self, item: B) -> bool {
+        let item = item.borrow();
+        let mask = self.bloom_mask_of(item);
+        if self.filter() & mask != mask {
+            return false;
+        }
+        let Some(index) = self.as_slice().iter().position(|it| it == item) else {
+            return false;
+        };
+        let len = self.len();
+        let removed = unsafe {
+            let base = self.ptr.as_ptr();
+            let removed = base.add(index).read();
+            ptr::copy(base.add(index + 1), base.add(index), len - index - 1);
+            removed
+        };
+        self.length -= 1;
+        drop(removed);
+        true
+    }
+
+    #[inline]
+    pub fn contains<B: Borrow<T>>(&self, item: B) -> bool {
+        let item = item.borrow();
+        let mask = self.bloom_mask_of(item);
+        self.filter() & mask == mask && self.as_slice().iter().any(|it| it == item)
+    }
+}
+
+impl<T, S> Drop for BumpBloomSet<'_, T, S> {
+    /// Drops the elements; the arena owns the memory.
+    #[inline]
+    fn drop(&mut self) {
+        let elements = ptr::slice_from_raw_parts_mut(self.ptr.as_ptr(), self.len());
+        unsafe { ptr::drop_in_place(elements) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BloomSet;
+    use std::rc::Rc;
+
+    #[test]
+    fn agrees_with_bloomset() {
+        let arena = Bump::new();
+        let mut bump = BumpBloomSet::new_in(&arena);
+        // A BumpBloomSet is never seeded
+        let mut heap = BloomSet::with_seed(0);
+        for i in 0..255_u32 {
+            bump.insert(i * 5);
+            heap.insert(i * 5);
+        }
+        assert_eq!(bump.as_slice(), heap.as_slice());
+        assert_eq!(bump.filter(), heap.filter_bits());
+        assert!((0..1300).all(|i| bump.contains(i) == heap.contains(i)));
+        assert!(bump.try_insert(1).is_err());
+
+        assert!(bump.remove(5) && !bump.remove(5));
+        assert!(heap.remove(5));
+        assert_eq!(bump.as_slice(), heap.as_slice());
+        assert!(bump.try_insert(1).is_ok());
+    }
+
+    #[test]
+    fn drops_elements_but_not_memory() {
+        let mut arena = Bump::new();
+        let counted = Rc::new(());
+        for _ in 0..3 {
+            let mut set = BumpBloomSet::with_capacity_in(2, &arena);
+            for i in 0..50_u32 {
+                set.insert((i, Rc::clone(&counted)));
+            }
+            assert_eq!(Rc::strong_count(&counted), 51);
+            assert!(set.remove((3, Rc::clone(&counted))));
+            assert_eq!(Rc::strong_count(&counted), 50);
+            set.clear();
+            assert_eq!(Rc::strong_count(&counted), 1);
+            assert!(set.is_empty() && set.filter() == 0 && set.capacity() >= 50);
+            set.insert((1, Rc::clone(&counted)));
+            drop(set);
+            assert_eq!(Rc::strong_count(&counted), 1);
+            assert!(arena.allocated_bytes() > 0);
+            arena.reset();
+        }
+    }
+
+    #[test]
+    fn zero_sized() {
+        let arena = Bump::new();
+        let mut set = BumpBloomSet::new_in(&arena);
+        set.insert(());
+        set.insert(());
+        assert_eq!(set.len(), 1);
+        assert!(set.contains(()));
+    }
+}
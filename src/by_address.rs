@@ -0,0 +1,129 @@
+use crate::BloomSet;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::mem;
+use core::ops::Deref;
+use core::ptr;
+
+/// Wraps a reference so that it compares and hashes by the address it points to, not the value
+/// there, for sets of objects by identity.
+///
+/// Two distinct objects are two elements however equal their values, and the same object is
+/// one element however it is reached. The hash is the address with the bits alignment keeps at
+/// zero shifted out, so objects laid out next to each other, like the nodes of a `Vec`, spread
+/// across the whole filter. Distinct zero-sized values may share an address, and then count as
+/// one element.
+///
+/// ```
+/// use bloomset::{ByAddress, IdentitySet};
+///
+/// let nodes = [String::from("leaf"), String::from("leaf")];
+/// let mut visited = IdentitySet::new();
+/// visited.insert(ByAddress(&nodes[0]));
+/// assert!(visited.contains(ByAddress(&nodes[0])));
+/// assert!(!visited.contains(ByAddress(&nodes[1])));
+/// ```
+pub struct ByAddress<'a, T: ?Sized>(pub &'a T);
+
+/// A [`BloomSet`] of references by identity.
+pub type IdentitySet<'a, T> = BloomSet<ByAddress<'a, T>>;
+
+impl<T: ?Sized> ByAddress<'_, T> {
+    /// The address, without the low bits that are zero for every `T`-aligned pointer.
+    fn key(&self) -> usize {
+        ptr::from_ref(self.0).cast::<()>() as usize / mem::align_of_val(self.0)
+    }
+}
+
+impl<T: ?Sized> Clone for ByAddress<'_, T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized> Copy for ByAddress<'_, T> {}
+
+impl<T: ?Sized> PartialEq for ByAddress<'_, T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self.0, other.0)
+    }
+}
+
+impl<T: ?Sized> Eq for ByAddress<'_, T> {}
+
+impl<T: ?Sized> Hash for ByAddress<'_, T> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl<T: ?Sized> Deref for ByAddress<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        self.0
+    }
+}
+
+impl<'a, T: ?Sized> From<&'a T> for ByAddress<'a, T> {
+    #[inline]
+    fn from(value: &'a T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for ByAddress<'_, T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} @ {:p}", self.0, self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equal_values_are_distinct_members() {
+        let a = vec![1, 2, 3];
+        let b = a.clone();
+        let mut set = IdentitySet::new();
+        set.insert(ByAddress(&a));
+        set.insert(ByAddress(&b));
+        set.insert(ByAddress(&a));
+        assert_eq!(set.len(), 2);
+        assert!(set.remove(ByAddress(&a)));
+        assert!(!set.contains(ByAddress(&a)) && set.contains(ByAddress(&b)));
+        assert_eq!(*set.as_slice()[0], [1, 2, 3]);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "no-filter", ignore = "checks the filter bits")]
+    fn neighbours_spread_across_the_filter() {
+        let nodes: Vec<[u64; 4]> = vec![[0; 4]; 80];
+        let set: IdentitySet<[u64; 4]> = nodes.iter().map(ByAddress).collect();
+        assert_eq!(set.len(), 80);
+        // 80 random bits out of 96 would set about 54 of them
+        let bits = set.filter_bits().count_ones();
+        assert!(bits > 45, "{}", bits);
+    }
+
+    #[test]
+    fn unsized_referents() {
+        let text = "abcabc";
+        let (first, second) = (&text[0..3], &text[3..6]);
+        assert_eq!(first, second);
+        let set: IdentitySet<str> = [first, second, &text[0..3]]
+            .iter()
+            .copied()
+            .map(ByAddress)
+            .collect();
+        assert_eq!(set.len(), 2);
+        // The same address with a different length is a different slice
+        assert!(!set.contains(ByAddress(&text[0..2])));
+    }
+}
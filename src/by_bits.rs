@@ -0,0 +1,103 @@
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+
+/// Wraps a float so that it compares, orders and hashes by its bit pattern, which makes it a
+/// well-behaved [`BloomSet`](crate::BloomSet) element.
+///
+/// Plain floats are only `PartialEq`: NaN isn't equal to itself, so a set can never find one it
+/// already holds, and inserting it again adds another copy. `ByBits` values are equal exactly
+/// when their bits are, so every NaN with the same payload is one element, while `0.0` and
+/// `-0.0` are two. Ordering follows [`f64::total_cmp`], which agrees with that equality, so a
+/// set of them can be [`sort`](crate::BloomSet::sort)ed.
+///
+/// ```
+/// use bloomset::{BloomSet, ByBits};
+///
+/// let mut set = BloomSet::new();
+/// set.insert(ByBits(f32::NAN));
+/// set.insert(ByBits(f32::NAN));
+/// set.insert(ByBits(1.5));
+/// assert_eq!(set.len(), 2);
+/// assert!(set.contains(ByBits(f32::NAN)));
+/// assert!(!set.contains(ByBits(-0.0)));
+/// ```
+#[derive(Clone, Copy, Default)]
+pub struct ByBits<T>(pub T);
+
+macro_rules! by_bits {
+    ($float:ty) => {
+        impl PartialEq for ByBits<$float> {
+            #[inline]
+            fn eq(&self, other: &Self) -> bool {
+                self.0.to_bits() == other.0.to_bits()
+            }
+        }
+
+        impl Eq for ByBits<$float> {}
+
+        impl PartialOrd for ByBits<$float> {
+            #[inline]
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for ByBits<$float> {
+            #[inline]
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.0.total_cmp(&other.0)
+            }
+        }
+
+        impl Hash for ByBits<$float> {
+            #[inline]
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.0.to_bits().hash(state);
+            }
+        }
+
+        impl From<$float> for ByBits<$float> {
+            #[inline]
+            fn from(value: $float) -> Self {
+                Self(value)
+            }
+        }
+
+        impl fmt::Debug for ByBits<$float> {
+            #[inline]
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+    };
+}
+
+by_bits!(f32);
+by_bits!(f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BloomSet;
+
+    #[test]
+    fn nans_dedup_and_zeros_differ() {
+        let quiet = f64::from_bits(0x7FF8_0000_0000_0001);
+        let mut set = BloomSet::new();
+        for value in [f64::NAN, f64::NAN, quiet, 0.0, -0.0, 0.0, f64::INFINITY] {
+            set.insert(ByBits(value));
+        }
+        assert_eq!(set.len(), 5);
+        assert!(set.contains(ByBits(f64::NAN)) && set.contains(ByBits(quiet)));
+        assert!(set.remove(ByBits(-0.0)) && set.contains(ByBits(0.0)));
+
+        set.sort();
+        let sorted: Vec<u64> = set.as_slice().iter().map(|v| v.0.to_bits()).collect();
+        assert_eq!(
+            sorted,
+            [0.0, f64::INFINITY, f64::NAN, quiet].map(f64::to_bits)
+        );
+        assert!(set.contains_sorted(ByBits(quiet)));
+    }
+}
@@ -0,0 +1,215 @@
+use alloc::boxed::Box;
+use core::borrow::Borrow;
+use core::iter::FromIterator;
+use core::{fmt, slice};
+
+/// The one allocation a `ByteSet` makes: a bit per possible byte, and the elements in the order
+/// they were inserted.
+#[derive(Clone)]
+struct Storage {
+    bitmap: [u64; 4],
+    elements: [u8; 256],
+}
+
+/// A set of bytes with the same interface as a [`BloomSet<u8>`](crate::BloomSet), whose
+/// "filter" is a bitmap with a bit for every possible byte.
+///
+/// A bitmap answers membership exactly, so `contains`, `insert` and `remove` never scan the
+/// elements to find one. The elements are still kept in insertion order for
+/// [`as_slice`](ByteSet::as_slice), and since there are only 256 bytes, the set never runs out of
+/// room. The bitmap and elements share one 288-byte allocation, made on the first insert, so the
+/// set itself is two words.
+///
+/// ```
+/// use bloomset::ByteSet;
+///
+/// let mut set: ByteSet = b"hello".iter().copied().collect();
+/// assert_eq!(set.as_slice(), b"helo");
+/// assert!(set.contains(b'l') && !set.contains(b'x'));
+/// assert!(set.remove(b'e'));
+/// assert_eq!(set.as_slice(), b"hlo");
+/// ```
+#[derive(Clone, Default)]
+pub struct ByteSet {
+    storage: Option<Box<Storage>>,
+    len: usize,
+}
+
+impl ByteSet {
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            storage: None,
+            len: 0,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The elements in the order they were inserted, like a `BloomSet`'s.
+    #[inline]
+    #[must_use]
+    pub fn as_slice(&self) -> &[u8] {
+        self.storage
+            .as_ref()
+            .map_or(&[], |storage| &storage.elements[..self.len])
+    }
+
+    #[inline]
+    pub fn iter(&self) -> slice::Iter<'_, u8> {
+        self.as_slice().iter()
+    }
+
+    /// Removes every element, keeping the allocation.
+    #[inline]
+    pub fn clear(&mut self) {
+        if let Some(storage) = &mut self.storage {
+            storage.bitmap = [0; 4];
+        }
+        self.len = 0;
+    }
+
+    /// Inserts `item`, which always fits.
+    #[inline]
+    pub fn insert(&mut self, item: u8) {
+        let storage = self.storage.get_or_insert_with(|| {
+            Box::new(Storage {
+                bitmap: [0; 4],
+                elements: [0; 256],
+            })
+        });
+        let (word, bit) = position(item);
+        if storage.bitmap[word] & bit == 0 {
+            storage.bitmap[word] |= bit;
+            storage.elements[self.len] = item;
+            self.len += 1;
+        }
+    }
+
+    /// Removes `item` from the set, returning whether it was present. The remaining elements
+    /// keep their order.
+    #[inline]
+    pub fn remove<B: Borrow<u8>>(&mut self, item: B) -> bool {
+        let item = *item.borrow();
+        let (word, bit) = position(item);
+        let Some(storage) = &mut self.storage else {
+            return false;
+        };
+        if storage.bitmap[word] & bit == 0 {
+            return false;
+        }
+        storage.bitmap[word] &= !bit;
+        if let Some(index) = storage.elements[..self.len]
+            .iter()
+            .position(|&it| it == item)
+        {
+            storage.elements.copy_within(index + 1..self.len, index);
+        }
+        self.len -= 1;
+        true
+    }
+
+    #[inline]
+    pub fn contains<B: Borrow<u8>>(&self, item: B) -> bool {
+        let (word, bit) = position(*item.borrow());
+        self.storage
+            .as_ref()
+            .is_some_and(|storage| storage.bitmap[word] & bit != 0)
+    }
+}
+
+/// The bitmap word and bit standing for `item`.
+const fn position(item: u8) -> (usize, u64) {
+    ((item >> 6) as usize, 1 << (item & 63))
+}
+
+impl FromIterator<u8> for ByteSet {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = u8>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<u8> for ByteSet {
+    #[inline]
+    fn extend<I: IntoIterator<Item = u8>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(item);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a ByteSet {
+    type Item = &'a u8;
+    type IntoIter = slice::Iter<'a, u8>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl fmt::Debug for ByteSet {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.as_slice()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BloomSet;
+
+    #[test]
+    fn agrees_with_bloomset() {
+        let mut bytes = ByteSet::new();
+        let mut set = BloomSet::new();
+        assert!(bytes.as_slice().is_empty() && !bytes.contains(0));
+        for i in 0..600_u32 {
+            #[allow(clippy::cast_possible_truncation)]
+            let byte = (i * 97 % 251) as u8;
+            bytes.insert(byte);
+            set.insert(byte);
+        }
+        assert_eq!(bytes.as_slice(), set.as_slice());
+        assert!((0..=255).all(|byte| bytes.contains(byte) == set.contains(byte)));
+
+        for byte in [0, 3, 250, 251] {
+            assert_eq!(bytes.remove(byte), set.remove(byte));
+        }
+        assert_eq!(bytes.as_slice(), set.as_slice());
+        assert!(!bytes.contains(3) && !bytes.remove(3));
+    }
+
+    #[test]
+    fn holds_every_byte() {
+        let mut set: ByteSet = (0..=255).rev().collect();
+        assert_eq!(set.len(), 256);
+        assert_eq!(set.as_slice()[..3], [255, 254, 253]);
+        set.insert(7);
+        assert_eq!(set.len(), 256);
+
+        set.clear();
+        assert!(set.is_empty() && !set.contains(7));
+        set.insert(7);
+        assert_eq!(set.as_slice(), [7]);
+        assert_eq!(
+            core::mem::size_of::<ByteSet>(),
+            2 * core::mem::size_of::<usize>()
+        );
+    }
+}
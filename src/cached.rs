@@ -0,0 +1,275 @@
+use crate::{BloomSet, BuildBloomHasher, ElementHasher, SetIndex};
+use core::borrow::Borrow;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// No lookup has hit since the set was created or its elements last moved. A `BloomSet` holds
+/// at most 255 elements, so this is never an index.
+const EMPTY: u8 = u8::MAX;
+
+/// A [`BloomSet`] that remembers where its last successful lookup found an element, for code
+/// that looks the same element up several times in a row, say once to validate it and again to
+/// use it.
+///
+/// [`contains`](CachedBloomSet::contains) and [`get`](CachedBloomSet::get) compare `item` with
+/// the element at that index first, and only go to the filter and scan when it differs. A
+/// repeated hit is then one comparison, with no hashing; any other lookup pays that one
+/// comparison on top of what `BloomSet` does. The `BloomSet` packs its words full, so the index
+/// is a byte kept next to it, and the other lookups through `Deref` don't use it.
+///
+/// The index lives in an atomic byte, so the set stays `Sync` and lookups can update it through
+/// `&self`. It only ever leads to a comparison, so a stale index can't give a wrong answer,
+/// only a miss. Even so, it is forgotten whenever elements move or go away: on
+/// [`remove`](CachedBloomSet::remove), [`swap_remove_index`](CachedBloomSet::swap_remove_index),
+/// [`retain`](CachedBloomSet::retain), [`sort`](CachedBloomSet::sort) and
+/// [`clear`](CachedBloomSet::clear). Inserting only appends, so it keeps the index.
+///
+/// ```
+/// use bloomset::CachedBloomSet;
+///
+/// let mut names: CachedBloomSet<String> =
+///     ["id", "name", "email"].iter().map(|s| s.to_string()).collect();
+/// let field = "email".to_string();
+/// if names.contains(&field) {
+///     // Found by the cache, without hashing `field` again
+///     assert_eq!(names.get(&field), Some(&field));
+/// }
+/// names.remove("id".to_string());
+/// assert_eq!(names.get(&field), Some(&field));
+/// ```
+pub struct CachedBloomSet<T, S = BuildBloomHasher, const K: usize = 1> {
+    set: BloomSet<T, S, K>,
+    /// The index of the last successful lookup, or `EMPTY`.
+    last_hit: AtomicU8,
+}
+
+impl<T> Default for CachedBloomSet<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::from(BloomSet::new())
+    }
+}
+
+impl<T> CachedBloomSet<T> {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T, S, const K: usize> CachedBloomSet<T, S, K> {
+    /// Creates an empty set which will use `hash_builder` to hash its elements.
+    #[inline]
+    #[must_use]
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self::from(BloomSet::with_hasher(hash_builder))
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> BloomSet<T, S, K> {
+        self.set
+    }
+
+    /// Forgets the last hit, once the element at its index may have changed.
+    fn forget(&mut self) {
+        *self.last_hit.get_mut() = EMPTY;
+    }
+
+    /// Removes the element at `index` and returns it, like [`BloomSet::swap_remove_index`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    #[inline]
+    #[track_caller]
+    pub fn swap_remove_index<I: Into<SetIndex>>(&mut self, index: I) -> T {
+        self.forget();
+        self.set.swap_remove_index(index)
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.forget();
+        self.set.clear();
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T>, const K: usize> CachedBloomSet<T, S, K> {
+    /// Where `item` is, checking the index of the last hit before the filter.
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    fn position(&self, item: &T) -> Option<usize> {
+        let last = usize::from(self.last_hit.load(Ordering::Relaxed));
+        if self.set.as_slice().get(last) == Some(item) {
+            return Some(last);
+        }
+        let index = self.set.position(item)?;
+        // Below 255, so it fits and isn't `EMPTY`
+        self.last_hit.store(index as u8, Ordering::Relaxed);
+        Some(index)
+    }
+
+    /// Returns `true` if the set contains `item`, like [`BloomSet::contains`], trying the
+    /// element the last hit found first.
+    #[inline]
+    pub fn contains<B: Borrow<T>>(&self, item: B) -> bool {
+        self.position(item.borrow()).is_some()
+    }
+
+    /// Returns the element equal to `item`, if there is one, like [`BloomSet::get`], trying the
+    /// element the last hit found first.
+    #[inline]
+    pub fn get<B: Borrow<T>>(&self, item: B) -> Option<&T> {
+        self.position(item.borrow())
+            .map(|index| &self.set.as_slice()[index])
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the set would need to grow beyond 255 elements.
+    #[inline]
+    pub fn insert(&mut self, item: T) {
+        self.set.insert(item);
+    }
+
+    #[inline]
+    pub fn remove<B: Borrow<T>>(&mut self, item: B) -> bool {
+        self.forget();
+        self.set.remove(item)
+    }
+
+    #[inline]
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, f: F) {
+        self.forget();
+        self.set.retain(f);
+    }
+}
+
+impl<T: Ord, S, const K: usize> CachedBloomSet<T, S, K> {
+    #[inline]
+    pub fn sort(&mut self) {
+        self.forget();
+        self.set.sort();
+    }
+}
+
+impl<T, S, const K: usize> Deref for CachedBloomSet<T, S, K> {
+    type Target = BloomSet<T, S, K>;
+
+    #[inline]
+    fn deref(&self) -> &BloomSet<T, S, K> {
+        &self.set
+    }
+}
+
+impl<T, S, const K: usize> From<BloomSet<T, S, K>> for CachedBloomSet<T, S, K> {
+    #[inline]
+    fn from(set: BloomSet<T, S, K>) -> Self {
+        Self {
+            set,
+            last_hit: AtomicU8::new(EMPTY),
+        }
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T> + Default, const K: usize> core::iter::FromIterator<T>
+    for CachedBloomSet<T, S, K>
+{
+    /// # Panics
+    ///
+    /// Panics if the iterator yields more than 255 unique elements.
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<BloomSet<T, S, K>>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+    use core::convert::TryFrom;
+
+    /// A key that counts how often it is compared.
+    #[derive(Clone, Debug)]
+    struct Counted<'a>(u32, &'a Cell<usize>);
+
+    impl PartialEq for Counted<'_> {
+        fn eq(&self, other: &Self) -> bool {
+            self.1.set(self.1.get() + 1);
+            self.0 == other.0
+        }
+    }
+
+    impl core::hash::Hash for Counted<'_> {
+        fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+            self.0.hash(state);
+        }
+    }
+
+    #[test]
+    fn repeated_hits_take_one_comparison() {
+        let comparisons = Cell::new(0);
+        let set: CachedBloomSet<Counted<'_>> = (0..100).map(|i| Counted(i, &comparisons)).collect();
+        let key = Counted(90, &comparisons);
+        comparisons.set(0);
+        assert!(set.contains(&key));
+        assert!(comparisons.get() > 1);
+        for _ in 0..3 {
+            comparisons.set(0);
+            assert_eq!(set.get(&key).map(|it| it.0), Some(90));
+            assert_eq!(comparisons.get(), 1);
+        }
+        assert!(!set.contains(Counted(100, &comparisons)));
+        assert!(set.contains(&key));
+    }
+
+    #[test]
+    fn mutations_between_repeated_lookups() {
+        let mut rng = 0x2545_F491_4F6C_DD1D_u64;
+        let mut cached: CachedBloomSet<u32> = CachedBloomSet::new();
+        let mut plain = BloomSet::with_seed(cached.seed());
+        for step in 0..3000 {
+            rng ^= rng << 13;
+            rng ^= rng >> 7;
+            rng ^= rng << 17;
+            let item = u32::try_from((rng >> 8) % 300).unwrap();
+            match rng % 12 {
+                0..=4 if plain.len() < 255 => {
+                    cached.insert(item);
+                    plain.insert(item);
+                }
+                5 | 6 => assert_eq!(cached.remove(item), plain.remove(item)),
+                7 if !plain.is_empty() => {
+                    let index = item as usize % plain.len();
+                    assert_eq!(
+                        cached.swap_remove_index(index),
+                        plain.swap_remove_index(index)
+                    );
+                }
+                8 => {
+                    cached.retain(|&i| i % 7 != item % 7);
+                    plain.retain(|&i| i % 7 != item % 7);
+                }
+                9 => {
+                    cached.sort();
+                    plain.sort();
+                }
+                10 if step % 5 == 0 => {
+                    cached.clear();
+                    plain.clear();
+                }
+                _ => {}
+            }
+            assert_eq!(cached.as_slice(), plain.as_slice());
+            // The same key again and again, and then another, as each mutation above moves
+            // elements out from under the last hit
+            for probe in [item, item, item ^ 1, item] {
+                assert_eq!(cached.contains(probe), plain.contains(probe), "step {step}");
+                assert_eq!(cached.get(probe), plain.get(probe), "step {step}");
+            }
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use crate::{BloomSet, BuildBloomHasher, ElementHasher, FILTER_BITS};
+use alloc::boxed::Box;
+use core::borrow::Borrow;
+
+/// One count per filter bit.
+#[allow(clippy::cast_possible_truncation)]
+const COUNTS: usize = FILTER_BITS as usize;
+
+/// A [`BloomSet`] that counts how many elements set each filter bit, so removing an element
+/// clears the bits no other element needs.
+///
+/// A plain `BloomSet` never clears filter bits on [`remove`](BloomSet::remove), so a long-lived
+/// set whose contents keep changing ends up with a saturated filter and scans on every lookup.
+/// The counts live in a separate heap allocation of one byte per filter bit, so this is a
+/// `BloomSet` plus a pointer to that allocation, and `insert`/`remove` are slightly slower;
+/// lookups are the same.
+///
+/// ```
+/// use bloomset::CountingBloomSet;
+///
+/// let mut set = CountingBloomSet::new();
+/// set.insert("a");
+/// set.remove("a");
+/// assert_eq!(set.saturation(), 0.0);
+/// ```
+pub struct CountingBloomSet<T, S = BuildBloomHasher, const K: usize = 1> {
+    set: BloomSet<T, S, K>,
+    counts: Box<[u8; COUNTS]>,
+}
+
+impl<T> Default for CountingBloomSet<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_hasher(BuildBloomHasher)
+    }
+}
+
+impl<T> CountingBloomSet<T> {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T, S, const K: usize> CountingBloomSet<T, S, K> {
+    /// Creates an empty set which will use `hash_builder` to hash its elements.
+    #[inline]
+    #[must_use]
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            set: BloomSet::with_hasher(hash_builder),
+            counts: Box::new([0; COUNTS]),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.set.is_empty()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.set.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn as_slice(&self) -> &[T] {
+        self.set.as_slice()
+    }
+
+    /// The fraction of filter bits that are set; see [`BloomSet::saturation`].
+    #[inline]
+    #[must_use]
+    pub const fn saturation(&self) -> f64 {
+        self.set.saturation()
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.set.clear();
+        *self.counts = [0; COUNTS];
+    }
+}
+
+/// The filter bits set in `mask`, lowest first.
+fn bits(mut mask: u128) -> impl Iterator<Item = usize> {
+    core::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+        let bit = mask.trailing_zeros();
+        mask &= mask - 1;
+        Some(bit as usize)
+    })
+}
+
+impl<T: PartialEq, S: ElementHasher<T>, const K: usize> CountingBloomSet<T, S, K> {
+    /// # Panics
+    ///
+    /// Panics if the set would need to grow beyond 255 elements.
+    #[inline]
+    pub fn insert(&mut self, item: T) {
+        let mask = self.set.bloom_mask_of(&item);
+        let len = self.set.len();
+        self.set.insert(item);
+        if self.set.len() > len {
+            // At most 255 elements can share a bit, so the count can't overflow
+            for bit in bits(mask) {
+                self.counts[bit] += 1;
+            }
+        }
+    }
+
+    /// Removes `item` from the set, returning whether it was present, and clears any filter bits
+    /// no remaining element sets.
+    #[inline]
+    pub fn remove<B: Borrow<T>>(&mut self, item: B) -> bool {
+        let item = item.borrow();
+        let mask = self.set.bloom_mask_of(item);
+        if !self.set.remove(item) {
+            return false;
+        }
+        for bit in bits(mask) {
+            self.counts[bit] -= 1;
+            if self.counts[bit] == 0 {
+                self.set.unset_bits(1 << bit);
+            }
+        }
+        true
+    }
+
+    #[inline]
+    pub fn contains<B: Borrow<T>>(&self, item: B) -> bool {
+        self.set.contains(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg_attr(feature = "random-seed", ignore = "compares filter bits across sets")]
+    #[cfg_attr(feature = "no-filter", ignore = "checks the filter bits")]
+    fn churn_keeps_saturation_bounded() {
+        const WINDOW: u32 = 30;
+        let mut counting = CountingBloomSet::new();
+        let mut plain = BloomSet::new();
+        for i in 0..5000_u32 {
+            counting.insert(i);
+            plain.insert(i);
+            if i >= WINDOW {
+                assert!(counting.remove(i - WINDOW));
+                assert!(plain.remove(i - WINDOW));
+            }
+            assert_eq!(counting.as_slice(), plain.as_slice());
+            let set = &counting.set;
+            assert_eq!(set.filter_bits_set(), set.filter_bits().count_ones());
+        }
+        assert_eq!(counting.len(), 30);
+
+        // With 30 elements about 25% of the filter bits are expected to be set
+        #[allow(clippy::cast_precision_loss)]
+        let expected = 1.0 - (1.0 - 1.0 / FILTER_BITS as f64).powi(30);
+        assert!(
+            (counting.saturation() - expected).abs() < 0.1,
+            "{}",
+            counting.saturation()
+        );
+        assert!(plain.saturation() > 0.99, "{}", plain.saturation());
+
+        // The counts agree with a filter rebuilt from scratch
+        let rebuilt: BloomSet<u32> = counting.as_slice().iter().copied().collect();
+        assert_eq!(counting.set.filter(), rebuilt.filter());
+        assert!((4970..5000).all(|i| counting.contains(i)));
+    }
+
+    #[test]
+    fn remove_and_reinsert() {
+        let mut set: CountingBloomSet<&str, _, 2> = CountingBloomSet::with_hasher(BuildBloomHasher);
+        set.insert("a");
+        set.insert("a");
+        set.insert("b");
+        assert!(set.remove("a"));
+        assert!(!set.remove("a"));
+        assert!(!set.contains("a") && set.contains("b"));
+        assert!(set.remove("b"));
+        assert_eq!(set.set.filter_bits(), 0);
+        assert_eq!(set.set.filter_bits_set(), 0);
+
+        set.insert("c");
+        assert!(set.contains("c"));
+        set.clear();
+        assert!(set.is_empty() && set.set.filter_bits() == 0);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::{BloomSet, BuildBloomHasher, ElementHasher};
+use alloc::sync::Arc;
+use core::borrow::Borrow;
+use core::fmt;
+use core::ops::Deref;
+
+/// A [`BloomSet`] behind an [`Arc`], which hands out [snapshots](CowBloomSet::snapshot) of
+/// itself without copying and only copies the elements when one of them is changed while a
+/// snapshot is still held.
+///
+/// Reads go straight to the set, through `Deref`. Changes that turn out to change nothing, like
+/// inserting an element already there, don't copy it either.
+///
+/// ```
+/// use bloomset::CowBloomSet;
+/// use std::sync::Arc;
+///
+/// let mut flags: CowBloomSet<String> = ["beta", "dark-mode"].iter().map(|f| f.to_string()).collect();
+/// let published = flags.snapshot();
+///
+/// flags.insert("beta".to_string());
+/// assert!(Arc::ptr_eq(&published, &flags.snapshot()));
+///
+/// flags.remove("beta".to_string());
+/// assert!(published.contains("beta".to_string()) && !flags.contains("beta".to_string()));
+/// ```
+pub struct CowBloomSet<T, S = BuildBloomHasher> {
+    set: Arc<BloomSet<T, S>>,
+}
+
+impl<T> CowBloomSet<T> {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T, S> CowBloomSet<T, S> {
+    /// The current contents, shared rather than copied. Later changes to this set don't show up
+    /// in it.
+    #[inline]
+    #[must_use]
+    pub fn snapshot(&self) -> Arc<BloomSet<T, S>> {
+        Arc::clone(&self.set)
+    }
+
+    /// The elements, in the set's order.
+    #[inline]
+    pub fn iter(&self) -> core::slice::Iter<'_, T> {
+        self.set.as_slice().iter()
+    }
+}
+
+impl<T: Clone + PartialEq, S: ElementHasher<T> + Clone> CowBloomSet<T, S> {
+    /// The set to change, copied first if a snapshot still shares it.
+    fn make_mut(&mut self) -> &mut BloomSet<T, S> {
+        Arc::make_mut(&mut self.set)
+    }
+
+    /// Inserts `item`, copying the set only if `item` is new and a snapshot shares it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the set already holds 255 elements and `item` isn't one of them.
+    #[inline]
+    pub fn insert(&mut self, item: T) {
+        if !self.set.contains(&item) {
+            self.make_mut().insert(item);
+        }
+    }
+
+    /// Removes `item`, returning whether it was present. The set is only copied if it was.
+    #[inline]
+    pub fn remove<B: Borrow<T>>(&mut self, item: B) -> bool {
+        let item = item.borrow();
+        self.set.contains(item) && self.make_mut().remove(item)
+    }
+
+    /// Keeps only the elements for which `f` returns `true`, like [`BloomSet::retain`]. The set
+    /// is only copied if `f` rejects one of them.
+    #[inline]
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let keep: alloc::vec::Vec<bool> = self.set.as_slice().iter().map(&mut f).collect();
+        if keep.contains(&false) {
+            let mut keep = keep.into_iter();
+            self.make_mut().retain(|_| keep.next().unwrap_or(true));
+        }
+    }
+
+    /// Removes every element, without copying the set if it is already empty or shared.
+    #[inline]
+    pub fn clear(&mut self) {
+        if self.set.is_empty() {
+            return;
+        }
+        if let Some(set) = Arc::get_mut(&mut self.set) {
+            set.clear();
+        } else {
+            let mut empty = BloomSet::with_hasher(self.set.hasher().clone());
+            empty.set_seed(self.set.seed());
+            self.set = Arc::new(empty);
+        }
+    }
+}
+
+impl<T, S> Deref for CowBloomSet<T, S> {
+    type Target = BloomSet<T, S>;
+
+    #[inline]
+    fn deref(&self) -> &BloomSet<T, S> {
+        &self.set
+    }
+}
+
+impl<T> Default for CowBloomSet<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::from(BloomSet::new())
+    }
+}
+
+impl<T, S> Clone for CowBloomSet<T, S> {
+    /// Shares the set, like [`snapshot`](CowBloomSet::snapshot).
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            set: self.snapshot(),
+        }
+    }
+}
+
+impl<T, S> From<BloomSet<T, S>> for CowBloomSet<T, S> {
+    #[inline]
+    fn from(set: BloomSet<T, S>) -> Self {
+        Self { set: Arc::new(set) }
+    }
+}
+
+impl<T, S> From<Arc<BloomSet<T, S>>> for CowBloomSet<T, S> {
+    #[inline]
+    fn from(set: Arc<BloomSet<T, S>>) -> Self {
+        Self { set }
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T> + Default> core::iter::FromIterator<T>
+    for CowBloomSet<T, S>
+{
+    /// # Panics
+    ///
+    /// Panics if the iterator yields more than 255 unique elements.
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<BloomSet<T, S>>())
+    }
+}
+
+impl<'a, T, S> IntoIterator for &'a CowBloomSet<T, S> {
+    type Item = &'a T;
+    type IntoIter = core::slice::Iter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: fmt::Debug, S> fmt::Debug for CowBloomSet<T, S> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flags(names: &[&str]) -> CowBloomSet<String> {
+        names.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn snapshots_share_until_a_change() {
+        let mut set = flags(&["a", "b", "c"]);
+        let first = set.snapshot();
+        assert!(Arc::ptr_eq(&first, &set.snapshot()));
+
+        // None of these change anything, so nothing is copied
+        set.insert("a".to_string());
+        assert!(!set.remove("z".to_string()));
+        set.retain(|_| true);
+        assert!(Arc::ptr_eq(&first, &set.snapshot()));
+        assert_eq!(Arc::strong_count(&first), 2);
+
+        set.insert("d".to_string());
+        assert!(!Arc::ptr_eq(&first, &set.snapshot()));
+        assert_eq!(first.as_slice(), ["a", "b", "c"]);
+        assert_eq!(set.as_slice(), ["a", "b", "c", "d"]);
+
+        let second = set.snapshot();
+        set.retain(|flag| flag != "b");
+        assert_eq!(second.len(), 4);
+        assert!(set.iter().eq(["a", "c", "d"].iter()));
+
+        set.clear();
+        assert!(set.is_empty() && second.len() == 4);
+
+        // Clearing a shared set starts a new one, with the same seed
+        let mut seeded = CowBloomSet::from(BloomSet::<u8>::with_seed(9));
+        seeded.insert(1);
+        let held = seeded.snapshot();
+        seeded.clear();
+        assert_eq!((seeded.seed(), seeded.len(), held.len()), (9, 0, 1));
+    }
+
+    #[test]
+    fn unshared_changes_in_place() {
+        let mut set = flags(&["a", "b"]);
+        let before = Arc::as_ptr(&set.snapshot());
+        // The snapshot above is already dropped
+        set.insert("c".to_string());
+        assert!(set.remove("a".to_string()));
+        set.clear();
+        assert_eq!(Arc::as_ptr(&set.snapshot()), before);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn shared_across_threads() {
+        let mut set = flags(&["on"]);
+        let snapshot = set.snapshot();
+        let reader = std::thread::spawn(move || snapshot.contains("on".to_string()));
+        set.remove("on".to_string());
+        assert!(reader.join().unwrap());
+        assert!(!set.contains("on".to_string()));
+    }
+}
@@ -0,0 +1,310 @@
+use crate::{
+    handle_reserve_error, BloomFilter, BloomSet, BuildBloomHasher, ElementHasher, Packed8, Packing,
+};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::hash::{BuildHasher, Hash};
+
+/// Removes the elements of `vec` equal to an earlier one, keeping the first of each in their
+/// original order, like deduplicating through a scratch `HashSet` but without allocating.
+///
+/// Each element is run past a filter of the ones kept so far, and only compared against them if
+/// it gets through. There is no limit on how many unique elements there can be, but the filter
+/// saturates after a hundred or so, and from then on every element scans all the ones kept, so
+/// this is quadratic in the number of unique elements; past a few hundred a `HashSet` wins.
+///
+/// If hashing or comparing panics, `vec` is left with all its elements, in some order.
+///
+/// ```
+/// let mut tags = vec!["db", "web", "db", "cache", "web"];
+/// bloomset::dedup_in_place(&mut tags);
+/// assert_eq!(tags, ["db", "web", "cache"]);
+/// ```
+#[inline]
+pub fn dedup_in_place<T: Hash + PartialEq>(vec: &mut Vec<T>) {
+    let mut filter = BloomFilter::<1>::new();
+    compact(vec, |kept, item| {
+        let hash = BuildBloomHasher.hash_one(item);
+        if filter.maybe_contains_hash(hash) && kept.contains(item) {
+            return false;
+        }
+        filter.insert_hash(hash);
+        true
+    });
+}
+
+/// Like [`dedup_in_place`], but two elements are duplicates if `key` returns equal keys for
+/// them.
+///
+/// `key` is called once per element, and the keys of the elements kept are held in a scratch
+/// `Vec` to compare against.
+///
+/// ```
+/// let mut users = vec![(1, "ann"), (2, "bob"), (1, "ann again")];
+/// bloomset::dedup_in_place_by_key(&mut users, |&(id, _)| id);
+/// assert_eq!(users, [(1, "ann"), (2, "bob")]);
+/// ```
+#[inline]
+pub fn dedup_in_place_by_key<T, K, F>(vec: &mut Vec<T>, mut key: F)
+where
+    K: Hash + PartialEq,
+    F: FnMut(&T) -> K,
+{
+    let mut filter = BloomFilter::<1>::new();
+    let mut keys = Vec::new();
+    compact(vec, |_, item| {
+        let key = key(item);
+        let hash = BuildBloomHasher.hash_one(&key);
+        if filter.maybe_contains_hash(hash) && keys.contains(&key) {
+            return false;
+        }
+        filter.insert_hash(hash);
+        keys.push(key);
+        true
+    });
+}
+
+/// Keeps the elements of `vec` for which `keep` returns `true`, in order. `keep` is called on
+/// each element in turn, along with the ones kept before it.
+fn compact<T>(vec: &mut Vec<T>, mut keep: impl FnMut(&[T], &T) -> bool) {
+    let mut kept = 0;
+    for i in 0..vec.len() {
+        if keep(&vec[..kept], &vec[i]) {
+            vec.swap(kept, i);
+            kept += 1;
+        }
+    }
+    vec.truncate(kept);
+}
+
+/// Remembers the last `window` distinct items pushed into it, for telling whether an item in a
+/// stream was already seen recently.
+///
+/// Pushing an item it doesn't remember makes it forget the oldest one once the window is full,
+/// so an item is forgotten after exactly `window` distinct items have been pushed since it;
+/// pushing a duplicate doesn't make it any younger. The answer is always exact, since a filter
+/// hit is checked against the items.
+///
+/// The forgotten items' filter bits stay set until the window has been replaced all the way
+/// through, and then the filter is rebuilt from the items it holds, inside that push. So the
+/// filter never holds more than twice the window's worth of items, and rebuilding it costs at
+/// most one hash per push on average.
+///
+/// ```
+/// use bloomset::DedupWindow;
+///
+/// let mut recent = DedupWindow::new(3);
+/// assert!(!recent.push("a"));
+/// assert!(!recent.push("b"));
+/// assert!(recent.push("a"));
+/// assert!(!recent.push("c"));
+/// // "a" has now been followed by three others, so it is forgotten
+/// assert!(!recent.push("d"));
+/// assert!(!recent.push("a"));
+/// ```
+pub struct DedupWindow<T, S = BuildBloomHasher> {
+    set: BloomSet<T, S>,
+    /// Where the next item goes once the window is full, which is where the oldest one is.
+    next: usize,
+}
+
+impl<T> DedupWindow<T> {
+    /// Creates an empty window that remembers up to `window` items.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` is 0 or more than 255.
+    #[inline]
+    #[must_use]
+    pub fn new(window: usize) -> Self {
+        Self::with_hasher(window, BuildBloomHasher)
+    }
+}
+
+impl<T, S> DedupWindow<T, S> {
+    /// Like [`new`](DedupWindow::new), but the window will use `hash_builder` to hash its items.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` is 0 or more than 255.
+    #[inline]
+    #[must_use]
+    pub fn with_hasher(window: usize, hash_builder: S) -> Self {
+        assert!(window > 0, "a DedupWindow must remember at least one item");
+        Self {
+            set: BloomSet::with_capacity_and_hasher(window, hash_builder),
+            next: 0,
+        }
+    }
+
+    /// How many items the window remembers once it is full.
+    #[inline]
+    #[must_use]
+    pub const fn window(&self) -> usize {
+        self.set.capacity()
+    }
+
+    /// How many items it remembers now.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.set.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.set.is_empty()
+    }
+
+    /// Forgets every item.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.set.clear();
+        self.next = 0;
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T>> DedupWindow<T, S> {
+    /// Returns whether `item` is one of the last [`window`](DedupWindow::window) distinct items
+    /// pushed. If it isn't, it is remembered, and the oldest item is forgotten if the window is
+    /// full.
+    #[inline]
+    pub fn push(&mut self, item: T) -> bool {
+        let (index, mask) = self.set.position_and_mask(&item);
+        if index.is_some() {
+            return true;
+        }
+        if self.set.len() < self.window() {
+            if let Err(err) = self.set.push_new(item, mask) {
+                handle_reserve_error(err);
+            }
+            return false;
+        }
+        // Put the item where the oldest one was; the order of the set's elements doesn't matter
+        let oldest = unsafe { self.set.as_mut_ptr().add(self.next).replace(item) };
+        self.set.set_bits(mask);
+        self.set.length &= !Packed8::SORTED_FLAG;
+        self.set.moved_elements();
+        self.next += 1;
+        if self.next == self.window() {
+            self.next = 0;
+            self.set.rebuild_filter();
+        }
+        drop(oldest);
+        false
+    }
+
+    /// Whether `item` is one of the items the window remembers, without pushing it.
+    #[inline]
+    pub fn contains<B: Borrow<T>>(&self, item: B) -> bool {
+        self.set.contains(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    fn xorshift(mut x: u64) -> impl Iterator<Item = u64> {
+        core::iter::repeat_with(move || {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x
+        })
+    }
+
+    #[test]
+    fn forgets_after_exactly_window_distinct_pushes() {
+        for window in [1_u8, 2, 5, 255] {
+            let mut recent = DedupWindow::new(usize::from(window));
+            assert!(!recent.push(u32::MAX));
+            for i in 0..u32::from(window) - 1 {
+                assert!(!recent.push(i));
+                // Duplicates don't count towards forgetting
+                assert!(recent.push(i));
+                assert!(recent.contains(u32::MAX));
+            }
+            assert!(recent.push(u32::MAX));
+            assert_eq!(recent.len(), usize::from(window));
+            assert!(!recent.push(u32::from(window)));
+            assert!(!recent.contains(u32::MAX));
+            assert!(!recent.push(u32::MAX));
+        }
+    }
+
+    #[test]
+    fn matches_a_queue() {
+        for window in [1, 3, 16, 100, 255] {
+            let mut recent = DedupWindow::new(window);
+            let mut model = VecDeque::new();
+            // Drawing from a few more values than the window holds gives plenty of both answers
+            for item in xorshift(window as u64)
+                .take(5000)
+                .map(|x| x % (window as u64 * 3 / 2 + 1))
+            {
+                let seen = model.contains(&item);
+                if !seen {
+                    if model.len() == window {
+                        model.pop_front();
+                    }
+                    model.push_back(item);
+                }
+                assert_eq!(recent.push(item), seen);
+                assert_eq!(recent.len(), model.len());
+                let set = &recent.set;
+                assert_eq!(set.filter_bits_set(), set.filter_bits().count_ones());
+            }
+            recent.clear();
+            assert!(recent.is_empty() && !recent.push(model[0]));
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "no-filter", ignore = "checks the filter bits")]
+    fn rebuilds_the_filter_on_wraparound() {
+        let mut recent = DedupWindow::new(20);
+        for item in xorshift(9).take(20 * 7) {
+            assert!(!recent.push(item));
+        }
+        // Seven times round, so the filter was just rebuilt from the last 20 items
+        let mut fresh = BloomSet::with_seed(recent.set.seed());
+        fresh.extend(recent.set.as_slice().iter().copied());
+        assert_eq!(recent.set.filter_bits(), fresh.filter_bits());
+        assert!(recent.set.saturation() < 0.25);
+    }
+
+    #[test]
+    fn dedup_vecs() {
+        let mut words: Vec<String> = "b a b c a d c".split(' ').map(String::from).collect();
+        dedup_in_place(&mut words);
+        assert_eq!(words, ["b", "a", "c", "d"]);
+
+        // Far more unique elements than a set holds
+        let mut numbers: Vec<u32> = (0..3000).map(|i| i * 7 % 1000).collect();
+        dedup_in_place(&mut numbers);
+        assert_eq!(numbers.len(), 1000);
+        assert_eq!(numbers[..4], [0, 7, 14, 21]);
+
+        let mut empty: Vec<u8> = Vec::new();
+        dedup_in_place(&mut empty);
+        assert!(empty.is_empty());
+
+        let mut lines = vec!["Foo", "bar", "FOO", "baz", "Bar"];
+        let mut calls = 0;
+        dedup_in_place_by_key(&mut lines, |line| {
+            calls += 1;
+            line.to_lowercase()
+        });
+        assert_eq!(lines, ["Foo", "bar", "baz"]);
+        assert_eq!(calls, 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "a DedupWindow must remember at least one item")]
+    fn empty_window() {
+        let _ = DedupWindow::<u32>::new(0);
+    }
+}
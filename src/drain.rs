@@ -0,0 +1,368 @@
+use crate::{BloomSet, ElementHasher, Packing};
+use core::fmt;
+use core::iter::FusedIterator;
+use core::ops::{Bound, RangeBounds};
+use core::ptr;
+use core::slice;
+
+impl<T: PartialEq, S: ElementHasher<T>, const K: usize, P: Packing> BloomSet<T, S, K, P> {
+    /// Removes the elements in `range` and yields them by value, like `Vec::drain`. The ones
+    /// after the range move down to close the gap, so the rest keep their order, and the filter
+    /// is rebuilt from them.
+    ///
+    /// That happens when the `Drain` is dropped, whether or not it was run to the end: any
+    /// elements in the range it didn't yield are dropped then. If it is leaked instead, the set
+    /// is left holding only the elements before the range.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let mut staged: BloomSet<u32> = (10..20).collect();
+    /// let archived: Vec<u32> = staged.drain(..4).collect();
+    /// assert_eq!(archived, [10, 11, 12, 13]);
+    /// assert_eq!(staged.as_slice(), [14, 15, 16, 17, 18, 19]);
+    /// assert!(!staged.contains(10) && staged.contains(14));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range starts after it ends or ends after the last element, like slice
+    /// indexing.
+    #[inline]
+    pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_, T, S, K, P> {
+        let len = self.len();
+        let (start, end) = bounds(&range, len);
+        // Only the elements before the range count until the `Drain` is done with the rest
+        self.length = (self.length & !P::COUNT_MASK) | start;
+        Drain {
+            set: self,
+            front: start,
+            back: end,
+            tail: end,
+            tail_len: len - end,
+        }
+    }
+}
+
+/// The indices `range` covers in a slice of `len` elements, panicking like slice indexing does
+/// if they aren't all in it.
+fn bounds<R: RangeBounds<usize>>(range: &R, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start
+            .checked_add(1)
+            .expect("attempted to index slice from after maximum usize"),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end
+            .checked_add(1)
+            .expect("attempted to index slice up to maximum usize"),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+    assert!(
+        start <= end,
+        "slice index starts at {} but ends at {}",
+        start,
+        end
+    );
+    assert!(
+        end <= len,
+        "range end index {} out of range for slice of length {}",
+        end,
+        len
+    );
+    (start, end)
+}
+
+/// The iterator returned by [`BloomSet::drain`].
+///
+/// It isn't `Clone`, since the elements it yields are moved out of the set it borrows.
+pub struct Drain<'a, T: PartialEq, S: ElementHasher<T>, const K: usize, P: Packing> {
+    set: &'a mut BloomSet<T, S, K, P>,
+    /// The elements in `front..back` are still to be yielded.
+    front: usize,
+    back: usize,
+    /// Where the elements after the range start, and how many there are.
+    tail: usize,
+    tail_len: usize,
+}
+
+impl<T: PartialEq, S: ElementHasher<T>, const K: usize, P: Packing> Drain<'_, T, S, K, P> {
+    /// The elements not yet yielded.
+    #[inline]
+    #[must_use]
+    pub const fn as_slice(&self) -> &[T] {
+        unsafe {
+            slice::from_raw_parts(
+                self.set.as_mut_ptr().add(self.front),
+                self.back - self.front,
+            )
+        }
+    }
+
+    /// Stops draining, and keeps the elements not yet yielded in the set instead of dropping
+    /// them. They stay where they were relative to the others, and the filter is rebuilt from
+    /// them all, as when the `Drain` is dropped.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let mut queue: BloomSet<u32> = (1..=5).collect();
+    /// let mut drain = queue.drain(..);
+    /// let batch: Vec<u32> = drain.by_ref().take(2).collect();
+    /// drain.keep_rest();
+    /// assert_eq!(batch, [1, 2]);
+    /// assert_eq!(queue.as_slice(), [3, 4, 5]);
+    /// ```
+    #[inline]
+    pub fn keep_rest(mut self) {
+        let start = self.set.len();
+        let remaining = self.back - self.front;
+        unsafe {
+            let base = self.set.as_mut_ptr();
+            if self.front != start {
+                ptr::copy(base.add(self.front), base.add(start), remaining);
+            }
+        }
+        // The set owns them again, so dropping `self` only has the tail left to move
+        self.set.length = (self.set.length & !P::COUNT_MASK) | (start + remaining);
+        self.front = self.back;
+    }
+
+    /// Moves the tail down to the end of the elements before the range and rebuilds the filter.
+    fn close_gap(&mut self) {
+        let start = self.set.len();
+        unsafe {
+            let base = self.set.as_mut_ptr();
+            if self.tail != start {
+                ptr::copy(base.add(self.tail), base.add(start), self.tail_len);
+            }
+        }
+        self.set.finish_retain(start + self.tail_len, false);
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T>, const K: usize, P: Packing> Iterator
+    for Drain<'_, T, S, K, P>
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        if self.front == self.back {
+            return None;
+        }
+        let item = unsafe { self.set.as_mut_ptr().add(self.front).read() };
+        self.front += 1;
+        Some(item)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T>, const K: usize, P: Packing> DoubleEndedIterator
+    for Drain<'_, T, S, K, P>
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<T> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(unsafe { self.set.as_mut_ptr().add(self.back).read() })
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T>, const K: usize, P: Packing> ExactSizeIterator
+    for Drain<'_, T, S, K, P>
+{
+}
+
+impl<T: PartialEq, S: ElementHasher<T>, const K: usize, P: Packing> FusedIterator
+    for Drain<'_, T, S, K, P>
+{
+}
+
+impl<T: PartialEq, S: ElementHasher<T>, const K: usize, P: Packing> Drop for Drain<'_, T, S, K, P> {
+    #[inline]
+    fn drop(&mut self) {
+        /// Closes the gap when dropped, so the set is whole again even if an element's `Drop`
+        /// panics.
+        struct CloseGap<'r, 'a, T: PartialEq, S: ElementHasher<T>, const K: usize, P: Packing>(
+            &'r mut Drain<'a, T, S, K, P>,
+        );
+
+        impl<T: PartialEq, S: ElementHasher<T>, const K: usize, P: Packing> Drop
+            for CloseGap<'_, '_, T, S, K, P>
+        {
+            fn drop(&mut self) {
+                self.0.close_gap();
+            }
+        }
+
+        let remaining = ptr::slice_from_raw_parts_mut(
+            unsafe { self.set.as_mut_ptr().add(self.front) },
+            self.back - self.front,
+        );
+        self.front = self.back;
+        let _close_gap = CloseGap(self);
+        unsafe { ptr::drop_in_place(remaining) };
+    }
+}
+
+impl<T, S, const K: usize, P> fmt::Debug for Drain<'_, T, S, K, P>
+where
+    T: PartialEq + fmt::Debug,
+    S: ElementHasher<T>,
+    P: Packing,
+{
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Drain").field(&self.as_slice()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BloomSet, BloomSet16};
+
+    #[test]
+    fn ranges() {
+        let mut set: BloomSet<u32> = (0..10).collect();
+        assert!(set.drain(..3).eq(0..3));
+        assert_eq!(set.as_slice(), [3, 4, 5, 6, 7, 8, 9]);
+        assert!(set.drain(5..).eq(8..10));
+        assert!(set.drain(1..=2).eq(4..6));
+        assert_eq!(set.as_slice(), [3, 6, 7]);
+        assert_eq!(set.drain(1..1).count(), 0);
+        assert_eq!(set.drain(3..).count(), 0);
+        assert_eq!(set.as_slice(), [3, 6, 7]);
+        assert!(set.drain(..).rev().eq([7, 6, 3]));
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "no-filter", ignore = "checks the filter bits")]
+    fn rebuilds_the_filter() {
+        let mut set: BloomSet<u32> = BloomSet::with_seed(4);
+        set.extend(0..60);
+        let mut rest: BloomSet<u32> = BloomSet::with_seed(4);
+        rest.extend((0..20).chain(40..60));
+        set.drain(20..40);
+        assert_eq!(set.as_slice(), rest.as_slice());
+        assert_eq!(set.filter_bits(), rest.filter_bits());
+        assert_eq!(set.seed(), 4);
+
+        let mut wide: BloomSet16<u32> = (0..1000).collect();
+        assert_eq!(wide.drain(100..).len(), 900);
+        assert!((0..100).all(|i| wide.contains(i)));
+        assert!(wide.filter_bits().count_ones() < 100);
+    }
+
+    #[test]
+    fn keeps_the_sorted_mark() {
+        let mut set: BloomSet<u32> = (0..10).rev().collect();
+        set.sort();
+        set.drain(2..5);
+        assert!(set.is_sorted());
+        assert_eq!(set.as_slice(), [0, 1, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn partly_consumed() {
+        let mut set: BloomSet<String> = (0..8).map(|i| i.to_string()).collect();
+        let mut drain = set.drain(2..6);
+        assert_eq!(drain.next().as_deref(), Some("2"));
+        assert_eq!(drain.next_back().as_deref(), Some("5"));
+        assert_eq!(drain.as_slice(), ["3", "4"]);
+        assert_eq!(format!("{drain:?}"), r#"Drain(["3", "4"])"#);
+        drop(drain);
+        assert_eq!(set.as_slice(), ["0", "1", "6", "7"]);
+        assert!(!set.contains("3".to_string()) && set.contains("6".to_string()));
+        set.insert("3".to_string());
+        assert_eq!(set.len(), 5);
+    }
+
+    #[test]
+    fn keep_rest() {
+        let mut set: BloomSet<String> = (0..5).map(|i| i.to_string()).collect();
+        let mut drain = set.drain(..);
+        assert_eq!(drain.next().as_deref(), Some("0"));
+        assert_eq!(drain.next().as_deref(), Some("1"));
+        drain.keep_rest();
+        assert_eq!(set.as_slice(), ["2", "3", "4"]);
+        assert!(["2", "3", "4"].iter().all(|&s| set.contains(s.to_string())));
+        assert!(!set.contains("0".to_string()));
+        set.insert("0".to_string());
+        assert_eq!(set.len(), 4);
+
+        // Yielding from both ends of a range in the middle
+        let mut set: BloomSet<u32> = (0..8).collect();
+        set.sort();
+        let mut drain = set.drain(2..6);
+        assert_eq!((drain.next(), drain.next_back()), (Some(2), Some(5)));
+        drain.keep_rest();
+        assert_eq!(set.as_slice(), [0, 1, 3, 4, 6, 7]);
+        assert!(set.is_sorted());
+        assert!((0..8).all(|i| set.contains(i) != [2, 5].contains(&i)));
+
+        // Keeping everything or nothing
+        set.drain(1..3).keep_rest();
+        assert_eq!(set.as_slice(), [0, 1, 3, 4, 6, 7]);
+        let mut drain = set.drain(4..);
+        drain.by_ref().for_each(drop);
+        drain.keep_rest();
+        assert_eq!(set.as_slice(), [0, 1, 3, 4]);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "no-filter", ignore = "checks the filter bits")]
+    fn keep_rest_rebuilds_the_filter() {
+        let mut set: BloomSet<u32> = BloomSet::with_seed(2);
+        set.extend(0..50);
+        let mut drain = set.drain(10..40);
+        drain.by_ref().take(20).for_each(drop);
+        drain.keep_rest();
+        let mut rest: BloomSet<u32> = BloomSet::with_seed(2);
+        rest.extend((0..10).chain(30..50));
+        assert_eq!(set.as_slice(), rest.as_slice());
+        assert_eq!(set.filter_bits(), rest.filter_bits());
+    }
+
+    #[test]
+    fn leaked() {
+        let mut set: BloomSet<u32> = (0..10).collect();
+        core::mem::forget(set.drain(4..6));
+        assert_eq!(set.as_slice(), [0, 1, 2, 3]);
+        set.insert(9);
+        assert_eq!(set.as_slice(), [0, 1, 2, 3, 9]);
+    }
+
+    #[test]
+    #[should_panic(expected = "range end index 11 out of range for slice of length 10")]
+    fn end_out_of_range() {
+        let mut set: BloomSet<u32> = (0..10).collect();
+        set.drain(5..11);
+    }
+
+    #[test]
+    #[should_panic(expected = "slice index starts at 3 but ends at 2")]
+    fn inverted() {
+        let mut set: BloomSet<u32> = (0..10).collect();
+        #[allow(clippy::reversed_empty_ranges)]
+        set.drain(3..2);
+    }
+
+    #[test]
+    #[should_panic(expected = "attempted to index slice up to maximum usize")]
+    fn inclusive_end_overflow() {
+        let mut set: BloomSet<u32> = (0..10).collect();
+        set.drain(..=usize::MAX);
+    }
+}
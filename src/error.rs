@@ -0,0 +1,252 @@
+use core::alloc::Layout;
+use core::fmt;
+
+/// The error returned when an operation would need a `BloomSet` to hold more elements than its
+/// packing allows (255 by default).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CapacityError {
+    requested: usize,
+    limit: usize,
+}
+
+impl CapacityError {
+    #[inline]
+    pub(crate) const fn new(requested: usize, limit: usize) -> Self {
+        Self { requested, limit }
+    }
+
+    /// The number of elements the failed operation needed room for.
+    ///
+    /// When the elements come from an iterator, as in
+    /// [`try_from_iter`](crate::BloomSet::try_from_iter), that is one more than the
+    /// [`limit`](CapacityError::limit): the iterator isn't read past the first unique element
+    /// that doesn't fit, so how many more it would have yielded isn't known.
+    #[inline]
+    #[must_use]
+    pub const fn requested(&self) -> usize {
+        self.requested
+    }
+
+    /// The most elements the set could hold.
+    #[inline]
+    #[must_use]
+    pub const fn limit(&self) -> usize {
+        self.limit
+    }
+}
+
+impl fmt::Display for CapacityError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "a BloomSet cannot hold more than {} elements ({} requested)",
+            self.limit, self.requested
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CapacityError {}
+
+/// The error returned by [`BloomSet::insert_new`](crate::BloomSet::insert_new) when the set
+/// already holds an element equal to the one being inserted. It gives that one back.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DuplicateError<T> {
+    item: T,
+    index: usize,
+}
+
+impl<T> DuplicateError<T> {
+    #[inline]
+    pub(crate) const fn new(item: T, index: usize) -> Self {
+        Self { item, index }
+    }
+
+    /// The element that wasn't inserted.
+    #[inline]
+    #[must_use]
+    pub const fn item(&self) -> &T {
+        &self.item
+    }
+
+    /// Where the equal element already in the set is, in
+    /// [`as_slice`](crate::BloomSet::as_slice).
+    #[inline]
+    #[must_use]
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Takes back the element that wasn't inserted.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> T {
+        self.item
+    }
+}
+
+impl<T> fmt::Display for DuplicateError<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the BloomSet already holds an equal element, at index {}",
+            self.index
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: fmt::Debug> std::error::Error for DuplicateError<T> {}
+
+/// The error returned by [`BloomSet::try_reserve`](crate::BloomSet::try_reserve) and
+/// [`try_reserve_exact`](crate::BloomSet::try_reserve_exact).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TryReserveError {
+    /// The set would need to hold more elements than its packing allows.
+    Capacity(CapacityError),
+    /// The buffer couldn't be allocated. `layout` is the one the allocator refused, or `None` if
+    /// the buffer would have been too large to describe with a `Layout` at all.
+    AllocFailed { layout: Option<Layout> },
+}
+
+impl From<CapacityError> for TryReserveError {
+    #[inline]
+    fn from(err: CapacityError) -> Self {
+        Self::Capacity(err)
+    }
+}
+
+impl fmt::Display for TryReserveError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Capacity(err) => err.fmt(f),
+            Self::AllocFailed {
+                layout: Some(layout),
+            } => write!(
+                f,
+                "allocating {} bytes for a BloomSet's elements failed",
+                layout.size()
+            ),
+            Self::AllocFailed { layout: None } => {
+                f.write_str("a BloomSet's elements would not fit in any allocation")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TryReserveError {
+    #[inline]
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Capacity(err) => Some(err),
+            Self::AllocFailed { .. } => None,
+        }
+    }
+}
+
+/// The error returned by [`FrozenBloomSetRef::from_bytes`](crate::FrozenBloomSetRef::from_bytes).
+///
+/// It means the bytes aren't a set written by
+/// [`FrozenBloomSet::write_to`](crate::FrozenBloomSet::write_to) for the element type asked for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LayoutError {
+    /// There are fewer bytes than the header takes up.
+    TooShort { len: usize },
+    /// The bytes don't start with the header's magic number.
+    Magic,
+    /// The header is from a version of the format this one doesn't read.
+    Version(u16),
+    /// The elements were written on a target with the other byte order.
+    ByteOrder,
+    /// The elements aren't the size of the element type asked for.
+    ElementSize { expected: usize, found: u64 },
+    /// The header says there are `count` elements, but `len` bytes follow it.
+    Length { count: u64, len: usize },
+    /// The elements don't start at a multiple of the element type's alignment.
+    Misaligned { align: usize },
+}
+
+impl fmt::Display for LayoutError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::TooShort { len } => write!(
+                f,
+                "{len} bytes are too few to hold a frozen BloomSet's header"
+            ),
+            Self::Magic => f.write_str("the bytes don't hold a frozen BloomSet"),
+            Self::Version(version) => write!(
+                f,
+                "the frozen BloomSet was written in version {version} of the format, which isn't supported"
+            ),
+            Self::ByteOrder => {
+                f.write_str("the frozen BloomSet was written on a target with the other byte order")
+            }
+            Self::ElementSize { expected, found } => write!(
+                f,
+                "the frozen BloomSet's elements are {found} bytes, not {expected}"
+            ),
+            Self::Length { count, len } => write!(
+                f,
+                "the frozen BloomSet's header says it holds {count} elements, but {len} bytes follow it"
+            ),
+            Self::Misaligned { align } => write!(
+                f,
+                "the frozen BloomSet's elements aren't aligned to {align} bytes"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LayoutError {}
+
+/// The error returned by [`BloomSet::try_from_bytes`](crate::BloomSet::try_from_bytes), like
+/// bytemuck's `PodCastError`.
+#[cfg(feature = "zerocopy")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PodCastError {
+    /// The bytes don't start at a multiple of the element type's alignment.
+    Misaligned { align: usize },
+    /// The `len` bytes aren't a whole number of `size`-byte elements.
+    Length { len: usize, size: usize },
+    /// The bytes hold more unique elements than the set can.
+    Capacity(CapacityError),
+}
+
+#[cfg(feature = "zerocopy")]
+impl fmt::Display for PodCastError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Misaligned { align } => {
+                write!(
+                    f,
+                    "the bytes aren't aligned to {align} bytes for the elements"
+                )
+            }
+            Self::Length { len, size } => write!(
+                f,
+                "{len} bytes aren't a whole number of {size}-byte elements"
+            ),
+            Self::Capacity(err) => err.fmt(f),
+        }
+    }
+}
+
+#[cfg(all(feature = "zerocopy", feature = "std"))]
+impl std::error::Error for PodCastError {
+    #[inline]
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Capacity(err) => Some(err),
+            _ => None,
+        }
+    }
+}
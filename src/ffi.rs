@@ -0,0 +1,201 @@
+//! A C interface to sets of `u64`, for the `ffi` feature.
+//!
+//! The set is opaque to C: it is created by [`bloomset_u64_new`] or
+//! [`bloomset_u64_with_capacity`], passed back by pointer, and destroyed by
+//! [`bloomset_u64_free`]. The declarations are in `include/bloomset.h`. Nothing panics across
+//! the boundary; a caught panic comes back as [`BLOOMSET_PANICKED`] or a null pointer.
+//!
+//! To link against it from C, build the crate as a static library, e.g.
+//! `cargo rustc --release --features ffi --crate-type staticlib`.
+
+use crate::BloomSet;
+use alloc::boxed::Box;
+use core::ptr;
+use std::os::raw::c_int;
+use std::panic::{self, AssertUnwindSafe};
+
+/// The set behind a `BloomSetU64 *`.
+pub struct BloomSetU64(BloomSet<u64>);
+
+/// [`bloomset_u64_insert`] added the value.
+pub const BLOOMSET_INSERTED: c_int = 1;
+/// [`bloomset_u64_contains`] found the value.
+pub const BLOOMSET_PRESENT: c_int = 1;
+/// [`bloomset_u64_insert`]: the set already holds 255 values, and this one isn't among them.
+pub const BLOOMSET_FULL: c_int = -1;
+/// The set pointer was null.
+pub const BLOOMSET_NULL: c_int = -2;
+/// The operation panicked. The set is still valid, and holds what it held before.
+pub const BLOOMSET_PANICKED: c_int = -3;
+
+/// Runs `f`, turning a panic into `on_panic`.
+fn guard<R>(on_panic: R, f: impl FnOnce() -> R) -> R {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(on_panic)
+}
+
+/// Creates an empty set. Returns null if that panics.
+#[no_mangle]
+pub extern "C" fn bloomset_u64_new() -> *mut BloomSetU64 {
+    guard(ptr::null_mut(), || {
+        Box::into_raw(Box::new(BloomSetU64(BloomSet::new())))
+    })
+}
+
+/// Creates an empty set with room for `capacity` values. Returns null if `capacity` is more
+/// than 255, or if allocating panics.
+#[no_mangle]
+pub extern "C" fn bloomset_u64_with_capacity(capacity: usize) -> *mut BloomSetU64 {
+    guard(ptr::null_mut(), || {
+        BloomSet::try_with_capacity(capacity).map_or(ptr::null_mut(), |set| {
+            Box::into_raw(Box::new(BloomSetU64(set)))
+        })
+    })
+}
+
+/// Inserts `value`. Returns [`BLOOMSET_INSERTED`] if it was added, 0 if it was already present,
+/// or a negative error code.
+///
+/// # Safety
+///
+/// `set` must be null or a pointer returned by one of the constructors and not yet freed, and
+/// no other call may be using it at the same time.
+#[no_mangle]
+pub unsafe extern "C" fn bloomset_u64_insert(set: *mut BloomSetU64, value: u64) -> c_int {
+    let Some(set) = set.as_mut() else {
+        return BLOOMSET_NULL;
+    };
+    guard(BLOOMSET_PANICKED, || {
+        let len = set.0.len();
+        match set.0.insert_within_limit(value) {
+            Ok(()) if set.0.len() > len => BLOOMSET_INSERTED,
+            Ok(()) => 0,
+            Err(_) => BLOOMSET_FULL,
+        }
+    })
+}
+
+/// Returns [`BLOOMSET_PRESENT`] if `value` is in the set, 0 if it isn't, or a negative error
+/// code.
+///
+/// # Safety
+///
+/// `set` must be null or a pointer returned by one of the constructors and not yet freed, and
+/// no call that changes it may be running at the same time.
+#[no_mangle]
+pub unsafe extern "C" fn bloomset_u64_contains(set: *const BloomSetU64, value: u64) -> c_int {
+    let Some(set) = set.as_ref() else {
+        return BLOOMSET_NULL;
+    };
+    guard(BLOOMSET_PANICKED, || c_int::from(set.0.contains(value)))
+}
+
+/// The number of values in the set, or 0 for a null pointer.
+///
+/// # Safety
+///
+/// As for [`bloomset_u64_contains`].
+#[no_mangle]
+pub unsafe extern "C" fn bloomset_u64_len(set: *const BloomSetU64) -> usize {
+    set.as_ref().map_or(0, |set| set.0.len())
+}
+
+/// Destroys the set. Null is ignored.
+///
+/// # Safety
+///
+/// `set` must be null or a pointer returned by one of the constructors and not yet freed. It
+/// must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn bloomset_u64_free(set: *mut BloomSetU64) {
+    if !set.is_null() {
+        drop(Box::from_raw(set));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The functions as a C caller sees them, through the C ABI by pointer.
+    struct Api {
+        new: extern "C" fn() -> *mut BloomSetU64,
+        with_capacity: extern "C" fn(usize) -> *mut BloomSetU64,
+        insert: unsafe extern "C" fn(*mut BloomSetU64, u64) -> c_int,
+        contains: unsafe extern "C" fn(*const BloomSetU64, u64) -> c_int,
+        len: unsafe extern "C" fn(*const BloomSetU64) -> usize,
+        free: unsafe extern "C" fn(*mut BloomSetU64),
+    }
+
+    const API: Api = Api {
+        new: bloomset_u64_new,
+        with_capacity: bloomset_u64_with_capacity,
+        insert: bloomset_u64_insert,
+        contains: bloomset_u64_contains,
+        len: bloomset_u64_len,
+        free: bloomset_u64_free,
+    };
+
+    #[test]
+    fn symbol_dedup() {
+        unsafe {
+            let set = (API.new)();
+            assert!(!set.is_null());
+            for symbol in [7, 3, 7, 9, 3] {
+                (API.insert)(set, symbol);
+            }
+            assert_eq!((API.len)(set), 3);
+            assert_eq!((API.insert)(set, 9), 0);
+            assert_eq!((API.insert)(set, 10), BLOOMSET_INSERTED);
+            assert_eq!((API.contains)(set, 3), BLOOMSET_PRESENT);
+            assert_eq!((API.contains)(set, 4), 0);
+            (API.free)(set);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        unsafe {
+            assert!((API.with_capacity)(256).is_null());
+            let set = (API.with_capacity)(255);
+            for value in 0..255 {
+                assert_eq!((API.insert)(set, value), BLOOMSET_INSERTED);
+            }
+            assert_eq!((API.insert)(set, 255), BLOOMSET_FULL);
+            // A value already present is fine even when full
+            assert_eq!((API.insert)(set, 0), 0);
+            assert_eq!((API.len)(set), 255);
+            (API.free)(set);
+
+            let null = ptr::null_mut();
+            assert_eq!((API.insert)(null, 1), BLOOMSET_NULL);
+            assert_eq!((API.contains)(null, 1), BLOOMSET_NULL);
+            assert_eq!((API.len)(null), 0);
+            (API.free)(null);
+        }
+    }
+
+    #[test]
+    fn header_declares_every_function() {
+        let header = include_str!("../include/bloomset.h");
+        for declaration in [
+            "BloomSetU64 *bloomset_u64_new(void);",
+            "BloomSetU64 *bloomset_u64_with_capacity(size_t capacity);",
+            "int bloomset_u64_insert(BloomSetU64 *set, uint64_t value);",
+            "int bloomset_u64_contains(const BloomSetU64 *set, uint64_t value);",
+            "size_t bloomset_u64_len(const BloomSetU64 *set);",
+            "void bloomset_u64_free(BloomSetU64 *set);",
+        ] {
+            assert!(header.contains(declaration), "{}", declaration);
+        }
+        for (name, value) in [
+            ("BLOOMSET_INSERTED", BLOOMSET_INSERTED),
+            ("BLOOMSET_PRESENT", BLOOMSET_PRESENT),
+            ("BLOOMSET_FULL", BLOOMSET_FULL),
+            ("BLOOMSET_NULL", BLOOMSET_NULL),
+            ("BLOOMSET_PANICKED", BLOOMSET_PANICKED),
+        ] {
+            let define = format!("#define {name} ({value})");
+            assert!(header.contains(&define), "{}", define);
+        }
+    }
+}
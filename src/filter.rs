@@ -0,0 +1,405 @@
+use crate::{bloom_mask, seeded, BloomSet, BuildBloomHasher, Packed8, FILTER_BITS};
+use core::hash::{BuildHasher, Hash};
+#[cfg(target_has_atomic = "64")]
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// The byte of `to_bytes` that holds the seed; every filter bit comes before it.
+const SEED_BYTE: usize = 15;
+
+/// A [`BloomSet`]'s bloom filter on its own, without the elements, for answering "definitely not
+/// in that set" somewhere the set isn't.
+///
+/// It is 16 bytes and `Copy`. [`BloomSet::filter`] extracts one from a set with the default
+/// packing, and one built with [`insert`](BloomFilter::insert) from the same elements, seed and
+/// `K` is identical to it.
+///
+/// # Stability
+///
+/// Filters built by different processes agree as long as both sides have the same
+/// [`FILTER_BITS`] (96 on 64-bit targets, 32 on 32-bit ones) and hash elements the same way:
+///
+/// - An element with hash `h` sets the `K` bits given by double hashing `h` remixed with the
+///   seed, each taken modulo `FILTER_BITS`, exactly as in a `BloomSet`. With the default hasher,
+///   [`BloomHasher`](crate::BloomHasher), `h` only depends on the element.
+/// - In [`to_bytes`](BloomFilter::to_bytes), filter bit `i` is bit `i % 8` of byte `i / 8`, and
+///   the last byte is the seed. Bytes between the filter bits and the seed are zero.
+///
+/// ```
+/// use bloomset::{BloomFilter, BloomSet};
+///
+/// let set: BloomSet<&str> = ["alpha", "beta"].iter().copied().collect();
+/// let bytes = set.filter().to_bytes();
+///
+/// // Elsewhere, without the elements
+/// let filter = BloomFilter::<1>::from_bytes(bytes);
+/// assert!(filter.maybe_contains("alpha"));
+/// # #[cfg(not(feature = "no-filter"))]
+/// assert!(!filter.maybe_contains("gamma"));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BloomFilter<const K: usize = 1> {
+    bits: u128,
+}
+
+impl<const K: usize> BloomFilter<K> {
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self::with_seed(0)
+    }
+
+    /// Creates an empty filter that spreads hashes according to `seed`, like a set made with
+    /// [`BloomSet::with_seed`].
+    #[inline]
+    #[must_use]
+    pub const fn with_seed(seed: u8) -> Self {
+        Self {
+            bits: (seed as u128) << (SEED_BYTE * 8),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn seed(&self) -> u8 {
+        (self.bits >> (SEED_BYTE * 8)) as u8
+    }
+
+    /// The filter bits, with bit `i` standing for filter bit `i`.
+    #[inline]
+    #[must_use]
+    pub const fn bits(&self) -> u128 {
+        self.bits & ((1 << FILTER_BITS) - 1)
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.bits() == 0
+    }
+
+    const fn mask(&self, hash: u64) -> u128 {
+        bloom_mask::<K>(seeded(hash, self.seed()), FILTER_BITS)
+    }
+
+    /// Adds an element by its hash, as computed by the hasher the filter's users agree on.
+    #[inline]
+    pub const fn insert_hash(&mut self, hash: u64) {
+        self.bits |= self.mask(hash);
+    }
+
+    /// Adds an element, hashed with the default hasher.
+    #[inline]
+    pub fn insert<Q: Hash + ?Sized>(&mut self, item: &Q) {
+        self.insert_hash(BuildBloomHasher.hash_one(item));
+    }
+
+    /// Returns `false` if no element with this hash was added; `true` means it may have been.
+    #[inline]
+    #[must_use]
+    pub const fn maybe_contains_hash(&self, hash: u64) -> bool {
+        let mask = self.mask(hash);
+        self.bits & mask == mask
+    }
+
+    /// Returns `false` if `item` was definitely not added, hashing it with the default hasher.
+    #[inline]
+    pub fn maybe_contains<Q: Hash + ?Sized>(&self, item: &Q) -> bool {
+        self.maybe_contains_hash(BuildBloomHasher.hash_one(item))
+    }
+
+    /// Returns a filter that may contain everything either filter may contain.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the filters have different seeds, since their bits don't mean the same thing.
+    #[inline]
+    #[must_use]
+    pub fn union(&self, other: &Self) -> Self {
+        assert_eq!(
+            self.seed(),
+            other.seed(),
+            "cannot combine filters with different seeds"
+        );
+        Self {
+            bits: self.bits | other.bits,
+        }
+    }
+
+    /// Returns whether every bit set in this filter is also set in `other`, which is the case
+    /// whenever the elements this filter was built from are a subset of `other`'s. Filters with
+    /// different seeds are never subsets of each other unless this one is empty.
+    #[inline]
+    #[must_use]
+    pub const fn is_subset_of(&self, other: &Self) -> bool {
+        self.bits() & !other.bits() == 0 && (self.is_empty() || self.seed() == other.seed())
+    }
+
+    /// The filter in its stable byte layout; see the [type docs](BloomFilter#stability).
+    #[inline]
+    #[must_use]
+    pub const fn to_bytes(&self) -> [u8; 16] {
+        self.bits.to_le_bytes()
+    }
+
+    /// Reads a filter written by [`to_bytes`](BloomFilter::to_bytes). Bits beyond the filter
+    /// are ignored.
+    #[inline]
+    #[must_use]
+    pub const fn from_bytes(bytes: [u8; 16]) -> Self {
+        let bits = u128::from_le_bytes(bytes);
+        let seed = bits >> (SEED_BYTE * 8);
+        Self {
+            bits: (bits & ((1 << FILTER_BITS) - 1)) | seed << (SEED_BYTE * 8),
+        }
+    }
+}
+
+impl<T, S, const K: usize> BloomSet<T, S, K, Packed8> {
+    /// Copies out the set's filter and seed.
+    #[inline]
+    #[must_use]
+    pub const fn filter(&self) -> BloomFilter<K> {
+        let mut filter = BloomFilter::with_seed(self.seed());
+        filter.bits |= self.filter_bits();
+        filter
+    }
+}
+
+/// A [`BloomFilter`] that many threads can update and query at once, for answering most misses
+/// before taking the lock around a shared set.
+///
+/// It is made of two `AtomicU64`s and uses the same bits as an unseeded `BloomFilter` or
+/// [`BloomSet`] with the same `K`, hashing with the default hasher unless given hashes directly.
+/// Keep it next to the set and follow this protocol:
+///
+/// - While holding the lock, [`insert`](SharedBloomFilter::insert) each element into the filter
+///   before inserting it into the set, so its bits are set before anyone can see the element.
+/// - Without the lock, a `false` from [`maybe_contains`](SharedBloomFilter::maybe_contains) means
+///   the element has not been inserted; a `true` means it may have been, so take the lock and ask
+///   the set. A lookup racing an insert may miss it, as if it had happened just before.
+/// - Bits are never cleared, except by [`reset`](SharedBloomFilter::reset), which must only be
+///   called while holding the lock, and only together with clearing the set.
+///
+/// ```
+/// use bloomset::{BloomSet, SharedBloomFilter};
+/// use std::sync::Mutex;
+///
+/// let filter = SharedBloomFilter::<1>::new();
+/// let set = Mutex::new(BloomSet::new());
+///
+/// {
+///     let mut set = set.lock().unwrap();
+///     filter.insert("alpha");
+///     set.insert("alpha");
+/// }
+///
+/// let contains = |item| filter.maybe_contains(item) && set.lock().unwrap().contains(item);
+/// assert!(contains("alpha"));
+/// assert!(!contains("beta"));
+/// ```
+#[cfg(target_has_atomic = "64")]
+#[derive(Debug, Default)]
+pub struct SharedBloomFilter<const K: usize = 1> {
+    words: [AtomicU64; 2],
+}
+
+#[cfg(target_has_atomic = "64")]
+impl<const K: usize> SharedBloomFilter<K> {
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            words: [AtomicU64::new(0), AtomicU64::new(0)],
+        }
+    }
+
+    /// Adds an element by its hash, as computed by the hasher the set uses.
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn insert_hash(&self, hash: u64) {
+        let mask = bloom_mask::<K>(hash, FILTER_BITS);
+        if mask as u64 != 0 {
+            self.words[0].fetch_or(mask as u64, Ordering::Relaxed);
+        }
+        if (mask >> 64) as u64 != 0 {
+            self.words[1].fetch_or((mask >> 64) as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// Adds an element, hashed with the default hasher.
+    #[inline]
+    pub fn insert<Q: Hash + ?Sized>(&self, item: &Q) {
+        self.insert_hash(BuildBloomHasher.hash_one(item));
+    }
+
+    /// Returns `false` if no element with this hash was inserted; `true` means it may have been.
+    #[inline]
+    #[must_use]
+    pub fn maybe_contains_hash(&self, hash: u64) -> bool {
+        let mask = bloom_mask::<K>(hash, FILTER_BITS);
+        self.bits() & mask == mask
+    }
+
+    /// Returns `false` if `item` was definitely not inserted, hashing it with the default hasher.
+    #[inline]
+    pub fn maybe_contains<Q: Hash + ?Sized>(&self, item: &Q) -> bool {
+        self.maybe_contains_hash(BuildBloomHasher.hash_one(item))
+    }
+
+    /// Clears every bit. Only call this while holding the lock that guards the set, when
+    /// clearing the set, or lookups may miss elements that are still in it.
+    #[inline]
+    pub fn reset(&self) {
+        for word in &self.words {
+            word.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Copies the bits set so far into a [`BloomFilter`].
+    #[inline]
+    #[must_use]
+    pub fn snapshot(&self) -> BloomFilter<K> {
+        BloomFilter { bits: self.bits() }
+    }
+
+    fn bits(&self) -> u128 {
+        u128::from(self.words[0].load(Ordering::Relaxed))
+            | u128::from(self.words[1].load(Ordering::Relaxed)) << 64
+    }
+}
+
+// The seed byte must stay clear of the filter on every target
+const _: () = assert!(FILTER_BITS <= SEED_BYTE as u64 * 8);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BuildBloomHasher;
+
+    #[test]
+    fn no_false_negatives() {
+        for seed in [0, 1, 200] {
+            let mut set = BloomSet::with_seed(seed);
+            let mut built = BloomFilter::<1>::with_seed(seed);
+            for i in 0..100_u32 {
+                set.insert(i * 13);
+                built.insert(&(i * 13));
+            }
+            let filter = set.filter();
+            assert!((0..100).all(|i| filter.maybe_contains(&(i * 13))));
+            assert_eq!(filter.seed(), seed);
+            assert_eq!(filter, built);
+        }
+
+        let mut set: BloomSet<&str, _, 3> = BloomSet::with_hasher(BuildBloomHasher);
+        for word in ["a", "b", "c", "d"] {
+            set.insert(word);
+        }
+        let filter = set.filter();
+        assert_eq!(filter.bits().count_ones(), set.filter_bits().count_ones());
+        assert!(["a", "b", "c", "d"]
+            .iter()
+            .all(|w| filter.maybe_contains(w)));
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let mut set = BloomSet::with_seed(7);
+        set.insert("x");
+        let filter = set.filter();
+        let bytes = filter.to_bytes();
+        assert_eq!(bytes[SEED_BYTE], 7);
+        assert_eq!(BloomFilter::from_bytes(bytes), filter);
+
+        let mut garbage = bytes;
+        garbage[SEED_BYTE - 1] = 0xFF;
+        assert_eq!(BloomFilter::<1>::from_bytes(garbage), filter);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "random-seed", ignore = "compares filter bits across sets")]
+    #[cfg_attr(feature = "no-filter", ignore = "checks the filter bits")]
+    fn union_and_subset() {
+        let a: BloomSet<u32> = (0..10).collect();
+        let b: BloomSet<u32> = (5..20).collect();
+        let both: BloomSet<u32> = (0..20).collect();
+        let union = a.filter().union(&b.filter());
+        assert_eq!(union, both.filter());
+        assert!(a.filter().is_subset_of(&union) && b.filter().is_subset_of(&union));
+        assert!(!union.is_subset_of(&a.filter()) || a.filter() == union);
+        assert!(BloomFilter::<1>::new().is_subset_of(&a.filter()));
+
+        let seeded = BloomFilter::<1>::with_seed(3);
+        assert!(!a.filter().is_subset_of(&seeded.union(&seeded)));
+    }
+
+    #[test]
+    fn shared_matches_bloomset() {
+        let shared = SharedBloomFilter::<2>::new();
+        let mut set: BloomSet<u32, _, 2> = BloomSet::with_hasher(BuildBloomHasher);
+        for i in 0..40 {
+            shared.insert(&i);
+            set.insert(i);
+        }
+        assert_eq!(shared.snapshot(), set.filter());
+        assert!((0..40).all(|i| shared.maybe_contains(&i)));
+        shared.reset();
+        assert!(shared.snapshot().is_empty());
+    }
+
+    #[test]
+    fn shared_has_no_false_negatives_under_contention() {
+        use std::sync::atomic::AtomicU64;
+        use std::sync::Mutex;
+
+        const WRITERS: u64 = 4;
+        const PER_WRITER: u64 = 200;
+        let filter = SharedBloomFilter::<1>::new();
+        // Stands in for the locked set; the filter is what's under test
+        let set = Mutex::new(Vec::new());
+        // How many elements each writer has finished inserting
+        let done: [AtomicU64; 4] = Default::default();
+
+        std::thread::scope(|scope| {
+            for (writer, done) in (0..WRITERS).zip(&done) {
+                let (filter, set) = (&filter, &set);
+                scope.spawn(move || {
+                    for i in 0..PER_WRITER {
+                        let item = writer * PER_WRITER + i;
+                        let mut set = set.lock().unwrap();
+                        filter.insert(&item);
+                        set.push(item);
+                        drop(set);
+                        done.store(i + 1, Ordering::Release);
+                    }
+                });
+            }
+            for _ in 0..WRITERS {
+                let (filter, done) = (&filter, &done);
+                scope.spawn(move || {
+                    while done.iter().any(|d| d.load(Ordering::Acquire) < PER_WRITER) {
+                        for (writer, done) in (0..WRITERS).zip(done) {
+                            for i in 0..done.load(Ordering::Acquire) {
+                                assert!(filter.maybe_contains(&(writer * PER_WRITER + i)));
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        let set = set.into_inner().unwrap();
+        assert_eq!(set.len(), 800);
+        assert!(set
+            .as_slice()
+            .iter()
+            .all(|item| filter.maybe_contains(item)));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot combine filters with different seeds")]
+    fn union_rejects_mixed_seeds() {
+        let _ = BloomFilter::<1>::new().union(&BloomFilter::with_seed(1));
+    }
+}
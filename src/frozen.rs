@@ -0,0 +1,478 @@
+use crate::{
+    bloom_mask, seeded, BloomHasher, BloomSet, BuildBloomHasher, ElementHasher, Packed8, Packing,
+    FILTER_BITS,
+};
+use alloc::boxed::Box;
+use core::borrow::Borrow;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::ops::{Bound, RangeBounds};
+use core::slice;
+
+/// An immutable [`BloomSet`] with its elements sorted, made by [`BloomSet::into_frozen`].
+///
+/// Lookups check the same filter the set had, then binary search, so a lookup that gets past the
+/// filter costs O(log n) comparisons rather than a scan. That matters most once the filter is
+/// saturated and nearly every miss gets past it. A `FrozenBloomSet` is `Send` and `Sync` whenever
+/// its elements and hasher are, so it can be shared between threads behind an `Arc`.
+///
+/// ```
+/// use bloomset::BloomSet;
+///
+/// let mut set = BloomSet::new();
+/// for word in ["pear", "apple", "fig"] {
+///     set.insert(word);
+/// }
+/// let frozen = set.into_frozen();
+/// assert!(frozen.contains("fig"));
+/// assert_eq!(frozen.iter().copied().collect::<Vec<_>>(), ["apple", "fig", "pear"]);
+/// ```
+pub struct FrozenBloomSet<T, S = BuildBloomHasher, const K: usize = 1, P: Packing = Packed8> {
+    elements: Box<[T]>,
+    filter: u128,
+    seed: u8,
+    hash_builder: S,
+    packing: PhantomData<P>,
+}
+
+impl<T: Ord, S, const K: usize, P: Packing> BloomSet<T, S, K, P> {
+    /// Sorts the elements and freezes the set, keeping its filter, seed and hasher.
+    #[inline]
+    #[must_use]
+    pub fn into_frozen(self) -> FrozenBloomSet<T, S, K, P> {
+        let filter = self.filter_bits();
+        let seed = self.seed();
+        let sorted = self.is_sorted();
+        let (mut elements, hash_builder) = self.into_parts();
+        if !sorted {
+            elements.sort_unstable();
+        }
+        FrozenBloomSet {
+            elements: elements.into_boxed_slice(),
+            filter,
+            seed,
+            hash_builder,
+            packing: PhantomData,
+        }
+    }
+}
+
+impl<T, S, const K: usize, P: Packing> FrozenBloomSet<T, S, K, P> {
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Returns the elements in sorted order.
+    #[inline]
+    #[must_use]
+    pub const fn as_slice(&self) -> &[T] {
+        &self.elements
+    }
+
+    /// Iterates over the elements in sorted order.
+    #[inline]
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.elements.iter()
+    }
+
+    /// Returns a reference to the set's `BuildHasher`.
+    #[inline]
+    #[must_use]
+    pub const fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    /// Iterates in sorted order over the elements within `range`, like [`BTreeSet::range`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range's start is greater than its end, or if both are excluded and equal.
+    ///
+    /// [`BTreeSet::range`]: std::collections::BTreeSet::range
+    #[inline]
+    pub fn range<Q, R>(&self, range: R) -> slice::Iter<'_, T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        sorted_range(&self.elements, &range).iter()
+    }
+}
+
+impl<T: Ord, S: ElementHasher<T>, const K: usize, P: Packing> FrozenBloomSet<T, S, K, P> {
+    #[inline]
+    pub fn contains<B: Borrow<T>>(&self, item: B) -> bool {
+        let item = item.borrow();
+        let maybe_in_set = self.filter.count_ones() >= P::SATURATION_THRESHOLD || {
+            let hash = seeded(self.hash_builder.hash_element(item), self.seed);
+            let mask = bloom_mask::<K>(hash, P::FILTER_BITS);
+            self.filter & mask == mask
+        };
+        maybe_in_set && self.elements.binary_search(item).is_ok()
+    }
+}
+
+impl<'a, T, S, const K: usize, P: Packing> IntoIterator for &'a FrozenBloomSet<T, S, K, P> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.elements.iter()
+    }
+}
+
+/// A read-only view over a sorted slice of unique elements, paired with a precomputed bloom
+/// filter.
+///
+/// Lookups check the filter first and fall back to a binary search. A `FrozenBloomSetRef` can be
+/// built entirely at compile time with [`static_bloomset!`](crate::static_bloomset).
+#[derive(Clone, Copy, Debug)]
+pub struct FrozenBloomSetRef<'a, T> {
+    elements: &'a [T],
+    filter: u128,
+}
+
+impl<'a> FrozenBloomSetRef<'a, &'a str> {
+    /// Builds a view over `elements`, computing the filter in a `const` context with the same
+    /// bit mapping a `BloomSet` uses at runtime.
+    ///
+    /// # Panics
+    ///
+    /// Panics (at compile time, when used to initialize a `static`) if `elements` is not sorted
+    /// or contains duplicates.
+    #[inline]
+    #[must_use]
+    pub const fn from_sorted_strs(elements: &'a [&'a str]) -> Self {
+        let mut filter = 0;
+        let mut i = 0;
+        while i < elements.len() {
+            if i > 0 {
+                assert!(
+                    compare_strs(elements[i - 1], elements[i]) < 0,
+                    "elements must be sorted and unique"
+                );
+            }
+            filter |= bloom_mask::<1>(BloomHasher::hash_str(elements[i]), FILTER_BITS);
+            i += 1;
+        }
+        Self { elements, filter }
+    }
+}
+
+impl<'a, T> FrozenBloomSetRef<'a, T> {
+    /// A view over `elements`, which must be sorted and unique, with `filter` as its filter.
+    #[inline]
+    pub(crate) const fn from_parts(elements: &'a [T], filter: u128) -> Self {
+        Self { elements, filter }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Returns the elements in sorted order.
+    #[inline]
+    #[must_use]
+    pub const fn as_slice(&self) -> &'a [T] {
+        self.elements
+    }
+
+    #[inline]
+    pub fn contains<Q>(&self, item: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Ord + ?Sized,
+    {
+        let mut hasher = BloomHasher::default();
+        item.hash(&mut hasher);
+        let mask = bloom_mask::<1>(hasher.finish(), FILTER_BITS);
+        if self.filter & mask != mask {
+            return false;
+        }
+        self.elements
+            .binary_search_by(|it| it.borrow().cmp(item))
+            .is_ok()
+    }
+
+    /// Iterates in sorted order over the elements within `range`, like [`BTreeSet::range`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range's start is greater than its end, or if both are excluded and equal.
+    ///
+    /// [`BTreeSet::range`]: std::collections::BTreeSet::range
+    #[inline]
+    pub fn range<Q, R>(&self, range: R) -> slice::Iter<'a, T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        sorted_range(self.elements, &range).iter()
+    }
+}
+
+/// Finds the part of a sorted slice within `range`.
+fn sorted_range<'a, T, Q, R>(elements: &'a [T], range: &R) -> &'a [T]
+where
+    T: Borrow<Q>,
+    Q: Ord + ?Sized,
+    R: RangeBounds<Q>,
+{
+    match (range.start_bound(), range.end_bound()) {
+        (Bound::Excluded(start), Bound::Excluded(end)) if start == end => {
+            panic!("range start and end are equal and excluded")
+        }
+        (
+            Bound::Included(start) | Bound::Excluded(start),
+            Bound::Included(end) | Bound::Excluded(end),
+        ) if start > end => {
+            panic!("range start is greater than range end")
+        }
+        _ => {}
+    }
+    let start = match range.start_bound() {
+        Bound::Included(start) => elements.partition_point(|it| it.borrow() < start),
+        Bound::Excluded(start) => elements.partition_point(|it| it.borrow() <= start),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(end) => elements.partition_point(|it| it.borrow() <= end),
+        Bound::Excluded(end) => elements.partition_point(|it| it.borrow() < end),
+        Bound::Unbounded => elements.len(),
+    };
+    &elements[start..end.max(start)]
+}
+
+/// Lexicographically compares two strings the same way `Ord for str` does.
+const fn compare_strs(a: &str, b: &str) -> i8 {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let mut i = 0;
+    while i < a.len() && i < b.len() {
+        if a[i] != b[i] {
+            return if a[i] < b[i] { -1 } else { 1 };
+        }
+        i += 1;
+    }
+    if a.len() < b.len() {
+        -1
+    } else if a.len() > b.len() {
+        1
+    } else {
+        0
+    }
+}
+
+/// Sorts an array of strings in a `const` context, for use by `static_bloomset!`.
+#[inline]
+#[must_use]
+pub const fn sort_strs<const N: usize>(mut elements: [&str; N]) -> [&str; N] {
+    let mut i = 1;
+    while i < N {
+        let mut j = i;
+        while j > 0 && compare_strs(elements[j - 1], elements[j]) > 0 {
+            let tmp = elements[j];
+            elements[j] = elements[j - 1];
+            elements[j - 1] = tmp;
+            j -= 1;
+        }
+        i += 1;
+    }
+    elements
+}
+
+/// Declares a `static` [`FrozenBloomSetRef`] over a list of string literals, with the elements
+/// sorted and the bloom filter computed at compile time.
+///
+/// Duplicate elements are rejected at compile time.
+///
+/// ```
+/// bloomset::static_bloomset! {
+///     static KEYWORDS = ["fn", "let", "match", "struct"];
+/// }
+///
+/// assert!(KEYWORDS.contains("let"));
+/// assert!(!KEYWORDS.contains("enum"));
+/// ```
+#[macro_export]
+macro_rules! static_bloomset {
+    ($(#[$attr:meta])* $vis:vis static $name:ident = [$($elem:expr),* $(,)?];) => {
+        $(#[$attr])*
+        $vis static $name: $crate::FrozenBloomSetRef<'static, &'static str> = {
+            const ELEMENTS: &[&str] = &$crate::__private::sort_strs([$($elem),*]);
+            $crate::FrozenBloomSetRef::from_sorted_strs(ELEMENTS)
+        };
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BloomSet, BuildBloomHasher, FrozenBloomSet, Packed16};
+    use std::ops::Bound;
+    use std::sync::Arc;
+
+    static_bloomset! {
+        static KEYWORDS = [
+            "while", "as", "break", "const", "continue", "crate", "else", "enum", "extern",
+            "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+            "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super",
+            "trait", "true", "type", "unsafe", "use", "where",
+        ];
+    }
+
+    static_bloomset! {
+        static EMPTY = [];
+    }
+
+    #[test]
+    fn static_is_sorted() {
+        let elements = KEYWORDS.as_slice();
+        assert!(elements.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(KEYWORDS.len(), 35);
+    }
+
+    #[test]
+    fn agrees_with_runtime_set() {
+        let mut set = BloomSet::new();
+        for keyword in KEYWORDS.as_slice() {
+            set.insert(*keyword);
+        }
+        let probes = KEYWORDS.as_slice().iter().copied().chain([
+            "", "w", "whil", "whilee", "As", "async", "await", "dyn", "macro", "union", "yield",
+            "selff", "ff", "nf", "tpye",
+        ]);
+        for probe in probes {
+            assert_eq!(KEYWORDS.contains(probe), set.contains(probe), "{probe:?}");
+        }
+    }
+
+    #[test]
+    fn empty() {
+        assert!(EMPTY.is_empty());
+        assert!(!EMPTY.contains(""));
+    }
+
+    #[test]
+    #[should_panic(expected = "elements must be sorted and unique")]
+    fn rejects_duplicates() {
+        let _ = crate::FrozenBloomSetRef::from_sorted_strs(&["a", "b", "b"]);
+    }
+
+    #[test]
+    fn frozen_agrees_with_bloomset() {
+        let set: BloomSet<u32> = (0..200).map(|i| i * 7919 % 1000).collect();
+        let expected: Vec<bool> = (0..1000).map(|i| set.contains(i)).collect();
+        let mut sorted = set.as_slice().to_vec();
+        sorted.sort_unstable();
+
+        let frozen = set.into_frozen();
+        assert_eq!(frozen.as_slice(), sorted.as_slice());
+        assert!((0..1000).all(|i| frozen.contains(i) == expected[i as usize]));
+
+        let seeded = BloomSet::with_seed(3);
+        let frozen = seeded.into_frozen();
+        assert!(frozen.is_empty() && !frozen.contains(0_u32));
+    }
+
+    #[test]
+    fn frozen_keeps_seed_and_packing() {
+        let mut set = BloomSet::<&str>::with_seed(9);
+        for word in ["d", "b", "a", "c"] {
+            set.insert(word);
+        }
+        let frozen = set.into_frozen();
+        assert!(["a", "b", "c", "d"].iter().all(|w| frozen.contains(w)));
+        assert!(!frozen.contains("e"));
+
+        let set: BloomSet<u32, BuildBloomHasher, 2, Packed16> = (0..1000).rev().collect();
+        let frozen = set.into_frozen();
+        assert!(frozen.iter().copied().eq(0..1000));
+        assert!((0..1000).all(|i| frozen.contains(i)) && !frozen.contains(1000));
+    }
+
+    #[test]
+    fn frozen_is_shareable() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<FrozenBloomSet<String>>();
+
+        let frozen = Arc::new(
+            ["x", "y"]
+                .iter()
+                .copied()
+                .collect::<BloomSet<_>>()
+                .into_frozen(),
+        );
+        let handles = [(); 4].map(|()| {
+            let frozen = Arc::clone(&frozen);
+            std::thread::spawn(move || frozen.contains("x") && !frozen.contains("z"))
+        });
+        assert!(IntoIterator::into_iter(handles).all(|h| h.join().unwrap()));
+    }
+
+    #[test]
+    fn range() {
+        let frozen = (0..50_u32)
+            .map(|i| i * 2)
+            .collect::<BloomSet<_>>()
+            .into_frozen();
+        let collect = |iter: std::slice::Iter<'_, u32>| iter.copied().collect::<Vec<_>>();
+        assert_eq!(collect(frozen.range(10..16)), [10, 12, 14]);
+        assert_eq!(collect(frozen.range(11..=16)), [12, 14, 16]);
+        assert_eq!(collect(frozen.range(95..)), [96, 98]);
+        assert_eq!(collect(frozen.range(..3)), [0, 2]);
+        assert_eq!(frozen.range(..).len(), 50);
+        assert_eq!(frozen.range(11..12).len(), 0);
+        assert_eq!(frozen.range(10..10).len(), 0);
+        assert_eq!(frozen.range(200..).len(), 0);
+        assert_eq!(
+            collect(frozen.range((Bound::Excluded(10), Bound::Included(14)))),
+            [12, 14]
+        );
+
+        let words = KEYWORDS
+            .range::<&str, _>("m".."s")
+            .copied()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            words,
+            ["match", "mod", "move", "mut", "pub", "ref", "return"]
+        );
+        assert_eq!(EMPTY.range::<str, _>(..).len(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "range start is greater than range end")]
+    fn range_rejects_inverted_bounds() {
+        let frozen = ["a", "b"]
+            .iter()
+            .copied()
+            .collect::<BloomSet<_>>()
+            .into_frozen();
+        #[allow(clippy::reversed_empty_ranges)]
+        let _ = frozen.range::<&str, _>("b".."a");
+    }
+
+    #[test]
+    #[should_panic(expected = "range start and end are equal and excluded")]
+    fn range_rejects_empty_exclusive_bounds() {
+        let _ = KEYWORDS.range::<str, _>((Bound::Excluded("fn"), Bound::Excluded("fn")));
+    }
+}
@@ -0,0 +1,469 @@
+use core::hash::{BuildHasher, Hash, Hasher};
+
+/// The multiplier applied after each word is mixed in; odd, so multiplication is a bijection.
+const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+/// The hasher `BloomSet` uses by default.
+///
+/// Input is consumed a word at a time: each word is xored into the rotated state, which is then
+/// multiplied by an odd constant. Unlike a plain xor of the bytes this is sensitive to byte
+/// order and repeated bytes don't cancel out, so `"ab"`/`"ba"` and `"aa"`/`""` land on
+/// different filter bits. Earlier versions of this crate used a byte-wise xor, so hashes (and
+/// the filter bits derived from them) differ from those versions.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BloomHasher {
+    state: u64,
+}
+
+impl BloomHasher {
+    #[inline]
+    const fn add_to_hash(state: u64, word: u64) -> u64 {
+        (state.rotate_left(5) ^ word).wrapping_mul(SEED)
+    }
+
+    /// Folds `bytes` into `state` exactly as `Hasher::write` does, but in a `const` context.
+    ///
+    /// The bytes are consumed as native-endian `u64`, `u32`, `u16`, and `u8` chunks, so writing a
+    /// value's bytes is equivalent to the matching fixed-width `write_*` call.
+    #[inline]
+    pub(crate) const fn mix(mut state: u64, bytes: &[u8]) -> u64 {
+        let mut i = 0;
+        while i + 8 <= bytes.len() {
+            let word = u64::from_ne_bytes([
+                bytes[i],
+                bytes[i + 1],
+                bytes[i + 2],
+                bytes[i + 3],
+                bytes[i + 4],
+                bytes[i + 5],
+                bytes[i + 6],
+                bytes[i + 7],
+            ]);
+            state = Self::add_to_hash(state, word);
+            i += 8;
+        }
+        if i + 4 <= bytes.len() {
+            let word = u32::from_ne_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+            state = Self::add_to_hash(state, word as u64);
+            i += 4;
+        }
+        if i + 2 <= bytes.len() {
+            let word = u16::from_ne_bytes([bytes[i], bytes[i + 1]]);
+            state = Self::add_to_hash(state, word as u64);
+            i += 2;
+        }
+        if i < bytes.len() {
+            state = Self::add_to_hash(state, bytes[i] as u64);
+        }
+        state
+    }
+
+    /// Computes the hash that `<str as Hash>::hash` produces with this hasher, in a `const`
+    /// context.
+    #[inline]
+    pub(crate) const fn hash_str(s: &str) -> u64 {
+        // str's Hash impl writes the bytes followed by a 0xff terminator
+        Self::mix(Self::mix(0, s.as_bytes()), &[0xff])
+    }
+}
+
+impl Hasher for BloomHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        self.state = Self::mix(self.state, bytes);
+    }
+
+    // The fixed-width writes mix the whole value in one step instead of going through the byte
+    // slice, and must produce the same state as writing the value's bytes through `write`.
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.state = Self::add_to_hash(self.state, u64::from(i));
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.state = Self::add_to_hash(self.state, u64::from(i));
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.state = Self::add_to_hash(self.state, u64::from(i));
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.state = Self::add_to_hash(self.state, i);
+    }
+
+    #[inline]
+    fn write_u128(&mut self, i: u128) {
+        // The two halves are mixed in memory order
+        self.write(&i.to_ne_bytes());
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        // A 32-bit usize is mixed as a single u32 chunk, which is the same as zero-extending it
+        self.state = Self::add_to_hash(self.state, i as u64);
+    }
+
+    #[inline]
+    fn write_i8(&mut self, i: i8) {
+        self.write_u8(i.cast_unsigned());
+    }
+
+    #[inline]
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i.cast_unsigned());
+    }
+
+    #[inline]
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i.cast_unsigned());
+    }
+
+    #[inline]
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i.cast_unsigned());
+    }
+
+    #[inline]
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i.cast_unsigned());
+    }
+
+    #[inline]
+    fn write_isize(&mut self, i: isize) {
+        self.write_usize(i.cast_unsigned());
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.state
+    }
+}
+
+/// Builds [`BloomHasher`]s, so the hash function `BloomSet` uses by default can also be plugged
+/// into std's collections, e.g. `HashSet<T, BuildBloomHasher>`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BuildBloomHasher;
+
+impl BuildHasher for BuildBloomHasher {
+    type Hasher = BloomHasher;
+
+    #[inline]
+    fn build_hasher(&self) -> BloomHasher {
+        BloomHasher::default()
+    }
+}
+
+/// A hasher that passes integers through unchanged, for elements that are already
+/// well-spread small integers, like interned ids. Built by [`BuildIdentityHasher`].
+///
+/// A value that writes a single integer, as the integer types do, hashes to that integer, so
+/// in a set with seed 0 an id below 256 sets filter bit `id % FILTER_BITS` and ids that differ
+/// by less than [`FILTER_BITS`](crate::FILTER_BITS) never share a bit. Larger values are folded
+/// down a byte at a time first, which keeps nearby ones apart too. Values that write more than
+/// one integer xor each into the rotated state, and byte slices are mixed as
+/// [`BloomHasher`] mixes them, so any `Hash` type works, just not always well.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IdentityHasher {
+    state: u64,
+}
+
+impl IdentityHasher {
+    #[inline]
+    const fn add(&mut self, word: u64) {
+        self.state = self.state.rotate_left(21) ^ word;
+    }
+}
+
+impl Hasher for IdentityHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        self.state = BloomHasher::mix(self.state, bytes);
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.add(u64::from(i));
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.add(u64::from(i));
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.add(u64::from(i));
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.add(i);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.add(i as u64);
+    }
+
+    #[inline]
+    fn write_i8(&mut self, i: i8) {
+        self.write_u8(i.cast_unsigned());
+    }
+
+    #[inline]
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i.cast_unsigned());
+    }
+
+    #[inline]
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i.cast_unsigned());
+    }
+
+    #[inline]
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i.cast_unsigned());
+    }
+
+    #[inline]
+    fn write_isize(&mut self, i: isize) {
+        self.write_usize(i.cast_unsigned());
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.state
+    }
+}
+
+/// Builds [`IdentityHasher`]s; the hasher of an [`IntBloomSet`](crate::IntBloomSet).
+///
+/// ```
+/// use bloomset::IntBloomSet;
+///
+/// let symbols: IntBloomSet<u32> = [4, 17, 90].iter().copied().collect();
+/// assert!(symbols.contains(17) && !symbols.contains(18));
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BuildIdentityHasher;
+
+impl BuildHasher for BuildIdentityHasher {
+    type Hasher = IdentityHasher;
+
+    #[inline]
+    fn build_hasher(&self) -> IdentityHasher {
+        IdentityHasher::default()
+    }
+}
+
+/// Computes the hash a `BloomSet` derives an element's filter bits from.
+///
+/// Every [`BuildHasher`] hashes elements through their [`Hash`] impl. [`ByBloomIndex`] instead
+/// asks the element for its filter bit directly.
+pub trait ElementHasher<T: ?Sized> {
+    fn hash_element(&self, item: &T) -> u64;
+}
+
+impl<T: Hash + ?Sized, S: BuildHasher> ElementHasher<T> for S {
+    #[inline]
+    fn hash_element(&self, item: &T) -> u64 {
+        self.hash_one(item)
+    }
+}
+
+/// An element type that picks its own bloom filter bit, for use with [`ByBloomIndex`].
+///
+/// If every value returns a stable bit below [`FILTER_BITS`](crate::FILTER_BITS) (96 on 64-bit
+/// targets, or 88 for a [`BloomSet16`](crate::BloomSet16)), and distinct values return distinct
+/// bits, the filter is exact: a set of such elements never has a false positive, so `contains`
+/// never falls back to scanning for an absent element. Larger bits wrap around. This only
+/// holds for an unseeded set with one bit per element, which is what [`ByBloomIndex`] sets are
+/// unless created otherwise.
+pub trait BloomIndex {
+    fn bloom_bit(&self) -> u8;
+}
+
+/// Makes a `BloomSet` use each element's [`BloomIndex`] as its filter bit, skipping hashing
+/// altogether.
+///
+/// ```
+/// use bloomset::{BloomIndex, BloomSet, ByBloomIndex};
+///
+/// #[derive(Clone, Copy, PartialEq)]
+/// enum Color {
+///     Red,
+///     Green,
+///     Blue,
+/// }
+///
+/// impl BloomIndex for Color {
+///     fn bloom_bit(&self) -> u8 {
+///         *self as u8
+///     }
+/// }
+///
+/// let mut set: BloomSet<Color, _> = BloomSet::with_hasher(ByBloomIndex);
+/// set.insert(Color::Red);
+/// set.insert(Color::Blue);
+/// assert!(set.contains(Color::Blue));
+/// assert!(!set.contains(Color::Green));
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ByBloomIndex;
+
+impl<T: BloomIndex + ?Sized> ElementHasher<T> for ByBloomIndex {
+    #[inline]
+    fn hash_element(&self, item: &T) -> u64 {
+        // bloom_bit maps hashes below 256 to themselves modulo the filter size
+        u64::from(item.bloom_bit())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bloom_bit, IntBloomSet, FILTER_BITS};
+
+    #[test]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    fn specialized_writes_match_slice_path() {
+        fn via_slice(bytes: &[u8]) -> u64 {
+            let mut hasher = BloomHasher::default();
+            hasher.write(bytes);
+            hasher.finish()
+        }
+        fn via<F: FnOnce(&mut BloomHasher)>(f: F) -> u64 {
+            let mut hasher = BloomHasher::default();
+            f(&mut hasher);
+            hasher.finish()
+        }
+
+        let mut x = 0x0123_4567_89AB_CDEF_u64;
+        for _ in 0..1000 {
+            // xorshift, so every byte position gets exercised
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            let wide = u128::from(x) << 64 | u128::from(!x);
+            assert_eq!(
+                via(|h| h.write_u8(x as u8)),
+                via_slice(&(x as u8).to_ne_bytes())
+            );
+            assert_eq!(
+                via(|h| h.write_u16(x as u16)),
+                via_slice(&(x as u16).to_ne_bytes())
+            );
+            assert_eq!(
+                via(|h| h.write_u32(x as u32)),
+                via_slice(&(x as u32).to_ne_bytes())
+            );
+            assert_eq!(via(|h| h.write_u64(x)), via_slice(&x.to_ne_bytes()));
+            assert_eq!(via(|h| h.write_u128(wide)), via_slice(&wide.to_ne_bytes()));
+            let size = x as usize;
+            assert_eq!(via(|h| h.write_usize(size)), via_slice(&size.to_ne_bytes()));
+            let signed = x as i64;
+            assert_eq!(
+                via(|h| h.write_i64(signed)),
+                via_slice(&signed.to_ne_bytes())
+            );
+            let signed = x as i16;
+            assert_eq!(
+                via(|h| h.write_i16(signed)),
+                via_slice(&signed.to_ne_bytes())
+            );
+        }
+    }
+
+    #[test]
+    fn identity_hashes() {
+        fn hash<T: Hash>(item: T) -> u64 {
+            BuildIdentityHasher.hash_one(item)
+        }
+        assert_eq!(hash(7_u8), 7);
+        assert_eq!(hash(70_000_u32), 70_000);
+        assert_eq!(hash(u64::MAX), u64::MAX);
+        assert_eq!(hash(-1_i16), 0xffff);
+        assert_eq!(hash(12_usize), 12);
+        // Anything else is hashed too, if not as an identity
+        assert_ne!(hash((1_u32, 2_u32)), hash((2_u32, 1_u32)));
+        assert_ne!(hash("ab"), hash("ba"));
+
+        // Ids below the filter size each get a bit of their own
+        for id in 0..FILTER_BITS {
+            assert_eq!(bloom_bit(hash(id), FILTER_BITS), id);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "no-filter", ignore = "checks the filter bits")]
+    fn int_sets_are_exact_below_the_filter_size() {
+        let mut set: IntBloomSet<u64> = crate::BloomSet::builder()
+            .hasher(BuildIdentityHasher)
+            .seed(0)
+            .build()
+            .unwrap();
+        set.extend(0..50);
+        assert_eq!(set.filter_bits(), (1 << 50) - 1);
+        // The rest of the ids below the filter size are turned away without a scan
+        assert!((50..FILTER_BITS).all(|id| !set.maybe_contains(&id)));
+        assert!((0..50).all(|id| set.contains(id)));
+    }
+
+    #[test]
+    fn position_sensitive() {
+        let hash = |s: &str| BuildBloomHasher.hash_one(s);
+        assert_ne!(hash("ab"), hash("ba"));
+        assert_ne!(hash("aa"), hash(""));
+        assert_ne!(hash("abcdefghij"), hash("abcdefghji"));
+        assert_eq!(hash("abc"), BloomHasher::hash_str("abc"));
+    }
+
+    /// Pearson's chi-squared statistic of the filter bit histogram for `hashes`.
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+    fn chi_squared(hashes: impl Iterator<Item = u64>) -> f64 {
+        let mut counts = [0_u32; FILTER_BITS as usize];
+        let mut total = 0;
+        for hash in hashes {
+            counts[bloom_bit(hash, FILTER_BITS) as usize] += 1;
+            total += 1;
+        }
+        let expected = f64::from(total) / FILTER_BITS as f64;
+        counts
+            .iter()
+            .map(|&count| (f64::from(count) - expected).powi(2) / expected)
+            .sum()
+    }
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn flatter_than_xor() {
+        // Every lowercase string of one to three letters
+        let mut corpus = Vec::new();
+        for len in 1..=3_u32 {
+            for mut n in 0..26_u32.pow(len) {
+                let mut s = String::new();
+                for _ in 0..len {
+                    s.push(char::from(b'a' + (n % 26) as u8));
+                    n /= 26;
+                }
+                corpus.push(s);
+            }
+        }
+
+        let xor_hash = |s: &String| u64::from(s.bytes().fold(0xff, |state, b| state ^ b));
+        let old = chi_squared(corpus.iter().map(xor_hash));
+        let new = chi_squared(corpus.iter().map(|s| BuildBloomHasher.hash_one(s)));
+
+        // A uniform distribution has FILTER_BITS - 1 degrees of freedom, and exceeds its mean by
+        // five standard deviations well under 0.1% of the time
+        let freedom = (FILTER_BITS - 1) as f64;
+        let bound = freedom + 5.0 * (2.0 * freedom).sqrt();
+        assert!(new < bound, "chi-squared {} against {}", new, bound);
+        assert!(new * 100.0 < old, "chi-squared {} vs {} for xor", new, old);
+    }
+}
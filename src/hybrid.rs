@@ -0,0 +1,396 @@
+//! [`HybridBloomSet`] and its iterator.
+
+use crate::{BloomSet, BuildBloomHasher, MAX_CAPACITY};
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use core::iter::FromIterator;
+use core::mem;
+use core::slice;
+use std::collections::hash_set::{self, HashSet};
+
+/// The number of elements a [`HybridBloomSet`] holds before it moves them into a hash table,
+/// unless it was created with another threshold.
+pub const DEFAULT_THRESHOLD: usize = 64;
+
+enum Repr<T, S> {
+    Inline(BloomSet<T, S>),
+    Hashed(HashSet<T, S>),
+}
+
+/// A [`BloomSet`] that moves its elements into a `HashSet` once it grows past a threshold, so
+/// that lookups in a large set don't scan.
+///
+/// Up to [`threshold`](HybridBloomSet::threshold) elements, which is
+/// [`DEFAULT_THRESHOLD`] unless picked with
+/// [`with_threshold`](HybridBloomSet::with_threshold), it is a `BloomSet`. Inserting one more
+/// unique element moves everything into a `HashSet` that uses the same hasher.
+/// [`clear`](HybridBloomSet::clear) moves back, and so does
+/// [`shrink_to_fit`](HybridBloomSet::shrink_to_fit) once enough elements have been removed;
+/// removing alone never does, so a set whose size hovers around the threshold doesn't move back
+/// and forth. The threshold can be at most 255, the most a `BloomSet` holds.
+///
+/// Iteration is in insertion order until the set moves into a `HashSet`, and in the table's
+/// order after that.
+///
+/// ```
+/// use bloomset::HybridBloomSet;
+///
+/// let mut set = HybridBloomSet::with_threshold(100);
+/// set.extend(0..100);
+/// assert!(!set.is_hashed());
+/// set.insert(100);
+/// assert!(set.is_hashed());
+/// assert!(set.contains(42) && !set.contains(101));
+///
+/// for i in 50..=100 {
+///     set.remove(i);
+/// }
+/// assert!(set.is_hashed());
+/// set.shrink_to_fit();
+/// assert!(!set.is_hashed());
+/// assert_eq!(set.len(), 50);
+/// ```
+pub struct HybridBloomSet<T, S = BuildBloomHasher> {
+    repr: Repr<T, S>,
+    threshold: usize,
+}
+
+impl<T> Default for HybridBloomSet<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_hasher(BuildBloomHasher)
+    }
+}
+
+impl<T> HybridBloomSet<T> {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty set which moves into a `HashSet` once it holds more than `threshold`
+    /// elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threshold` is more than 255.
+    #[inline]
+    #[must_use]
+    pub const fn with_threshold(threshold: usize) -> Self {
+        Self::with_threshold_and_hasher(threshold, BuildBloomHasher)
+    }
+}
+
+impl<T, S> HybridBloomSet<T, S> {
+    /// Creates an empty set which will use `hash_builder` to hash its elements.
+    #[inline]
+    #[must_use]
+    pub const fn with_hasher(hash_builder: S) -> Self {
+        Self::with_threshold_and_hasher(DEFAULT_THRESHOLD, hash_builder)
+    }
+
+    /// Creates an empty set which will use `hash_builder` to hash its elements, and moves into a
+    /// `HashSet` once it holds more than `threshold` elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threshold` is more than 255.
+    #[inline]
+    #[must_use]
+    pub const fn with_threshold_and_hasher(threshold: usize, hash_builder: S) -> Self {
+        assert!(
+            threshold <= MAX_CAPACITY,
+            "a HybridBloomSet's threshold can be at most 255"
+        );
+        Self {
+            repr: Repr::Inline(BloomSet::with_hasher(hash_builder)),
+            threshold,
+        }
+    }
+
+    /// The most elements the set holds before it moves into a `HashSet`.
+    #[inline]
+    #[must_use]
+    pub const fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Returns whether the set's elements are in a `HashSet`.
+    #[inline]
+    #[must_use]
+    pub const fn is_hashed(&self) -> bool {
+        matches!(self.repr, Repr::Hashed(_))
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        match &self.repr {
+            Repr::Inline(set) => set.len(),
+            Repr::Hashed(set) => set.len(),
+        }
+    }
+
+    /// Returns a reference to the set's `BuildHasher`.
+    #[inline]
+    #[must_use]
+    pub fn hasher(&self) -> &S {
+        match &self.repr {
+            Repr::Inline(set) => set.hasher(),
+            Repr::Hashed(set) => set.hasher(),
+        }
+    }
+
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, T> {
+        let inner = match &self.repr {
+            Repr::Inline(set) => Inner::Inline(set.as_slice().iter()),
+            Repr::Hashed(set) => Inner::Hashed(set.iter()),
+        };
+        Iter { inner }
+    }
+}
+
+impl<T, S: Clone> HybridBloomSet<T, S> {
+    /// Removes every element, going back to a `BloomSet` if the set had moved into a `HashSet`.
+    #[inline]
+    pub fn clear(&mut self) {
+        match &mut self.repr {
+            Repr::Inline(set) => set.clear(),
+            Repr::Hashed(set) => {
+                self.repr = Repr::Inline(BloomSet::with_hasher(set.hasher().clone()));
+            }
+        }
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher + Clone> HybridBloomSet<T, S> {
+    #[inline]
+    pub fn insert(&mut self, item: T) {
+        match &mut self.repr {
+            Repr::Inline(set) if set.len() < self.threshold || set.contains(&item) => {
+                set.insert(item);
+            }
+            Repr::Inline(set) => {
+                let hash_builder = set.hasher().clone();
+                let inline = mem::replace(set, BloomSet::with_hasher(hash_builder.clone()));
+                let mut hashed = HashSet::with_capacity_and_hasher(inline.len() * 2, hash_builder);
+                hashed.extend(inline.into_vec());
+                hashed.insert(item);
+                self.repr = Repr::Hashed(hashed);
+            }
+            Repr::Hashed(set) => {
+                set.insert(item);
+            }
+        }
+    }
+
+    /// Removes `item` from the set, returning whether it was present.
+    #[inline]
+    pub fn remove<B: Borrow<T>>(&mut self, item: B) -> bool {
+        match &mut self.repr {
+            Repr::Inline(set) => set.remove(item),
+            Repr::Hashed(set) => set.remove(item.borrow()),
+        }
+    }
+
+    #[inline]
+    pub fn contains<B: Borrow<T>>(&self, item: B) -> bool {
+        match &self.repr {
+            Repr::Inline(set) => set.contains(item),
+            Repr::Hashed(set) => set.contains(item.borrow()),
+        }
+    }
+
+    /// Frees unused memory, and goes back to a `BloomSet` if the set has moved into a `HashSet`
+    /// but no longer holds more than [`threshold`](HybridBloomSet::threshold) elements.
+    #[inline]
+    pub fn shrink_to_fit(&mut self) {
+        match &mut self.repr {
+            Repr::Inline(set) => set.shrink_to_fit(),
+            Repr::Hashed(set) if set.len() <= self.threshold => {
+                let mut inline =
+                    BloomSet::with_capacity_and_hasher(set.len(), set.hasher().clone());
+                inline.extend(set.drain());
+                self.repr = Repr::Inline(inline);
+            }
+            Repr::Hashed(set) => set.shrink_to_fit(),
+        }
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher + Clone + Default> FromIterator<T> for HybridBloomSet<T, S> {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::with_hasher(S::default());
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher + Clone> Extend<T> for HybridBloomSet<T, S> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(item);
+        }
+    }
+}
+
+impl<T: fmt::Debug, S> fmt::Debug for HybridBloomSet<T, S> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, T, S> IntoIterator for &'a HybridBloomSet<T, S> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Inner<'a, T> {
+    Inline(slice::Iter<'a, T>),
+    Hashed(hash_set::Iter<'a, T>),
+}
+
+/// An iterator over the elements of a [`HybridBloomSet`].
+#[derive(Clone, Debug)]
+pub struct Iter<'a, T> {
+    inner: Inner<'a, T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            Inner::Inline(iter) => iter.next(),
+            Inner::Hashed(iter) => iter.next(),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.inner {
+            Inner::Inline(iter) => iter.size_hint(),
+            Inner::Hashed(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks `set` against `model` for every value up to `limit`.
+    fn agrees(set: &HybridBloomSet<u32>, model: &HashSet<u32>, limit: u32) {
+        assert_eq!(set.len(), model.len());
+        assert_eq!(set.iter().len(), model.len());
+        assert_eq!(set.iter().copied().collect::<HashSet<_>>(), *model);
+        assert!((0..limit).all(|i| set.contains(i) == model.contains(&i)));
+    }
+
+    #[test]
+    fn moves_past_the_threshold() {
+        let mut set = HybridBloomSet::with_threshold(10);
+        let mut model = HashSet::new();
+        for i in 0..10 {
+            set.insert(i);
+            model.insert(i);
+        }
+        // Duplicates don't count towards the threshold
+        set.insert(3);
+        assert!(!set.is_hashed());
+        assert_eq!(
+            set.iter().copied().collect::<Vec<_>>(),
+            (0..10).collect::<Vec<_>>()
+        );
+        agrees(&set, &model, 20);
+
+        set.insert(10);
+        model.insert(10);
+        assert!(set.is_hashed());
+        agrees(&set, &model, 20);
+        set.insert(10);
+        agrees(&set, &model, 20);
+    }
+
+    #[test]
+    fn moves_back_on_shrink_and_clear() {
+        let mut set: HybridBloomSet<u32> = (0..200).collect();
+        let mut model: HashSet<u32> = (0..200).collect();
+        assert!(set.is_hashed());
+        for i in 0..135 {
+            assert!(set.remove(i) && model.remove(&i));
+        }
+        assert!(!set.remove(0));
+        // 65 elements left, one past the default threshold
+        set.shrink_to_fit();
+        assert!(set.is_hashed());
+        agrees(&set, &model, 300);
+
+        set.remove(135);
+        model.remove(&135);
+        set.shrink_to_fit();
+        assert!(!set.is_hashed());
+        agrees(&set, &model, 300);
+        // Back inline, the set moves out again at the same size
+        set.remove(136);
+        set.insert(135);
+        model.remove(&136);
+        model.insert(135);
+        assert!(!set.is_hashed());
+        set.insert(1000);
+        model.insert(1000);
+        assert!(set.is_hashed());
+        agrees(&set, &model, 1001);
+
+        set.clear();
+        assert!(!set.is_hashed() && set.is_empty());
+        assert!(!set.contains(1000));
+        set.insert(1);
+        assert!(set.contains(1));
+    }
+
+    #[test]
+    fn thresholds_at_the_extremes() {
+        let mut set = HybridBloomSet::with_threshold(0);
+        set.insert("a");
+        assert!(set.is_hashed() && set.contains("a"));
+        set.shrink_to_fit();
+        assert!(set.is_hashed());
+        set.remove("a");
+        set.shrink_to_fit();
+        assert!(!set.is_hashed() && set.is_empty());
+
+        let mut set = HybridBloomSet::with_threshold(MAX_CAPACITY);
+        set.extend(0..255);
+        assert!(!set.is_hashed());
+        set.insert(255);
+        assert!(set.is_hashed() && set.len() == 256);
+    }
+
+    #[test]
+    #[should_panic(expected = "at most 255")]
+    fn threshold_over_limit() {
+        let _ = HybridBloomSet::<u32>::with_threshold(256);
+    }
+}
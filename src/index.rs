@@ -0,0 +1,333 @@
+use crate::{BloomSet, ElementHasher, Packing};
+use core::ops::{Bound, RangeBounds};
+use core::ptr;
+
+/// An index into a [`BloomSet`]'s [`as_slice`](BloomSet::as_slice), for
+/// [`get_index`](BloomSet::get_index) and [`swap_remove_index`](BloomSet::swap_remove_index).
+///
+/// A plain `usize` converts into one, and is used as it is. One from
+/// [`find_index`](BloomSet::find_index) or [`index`](BloomSet::index) also remembers the set it
+/// came from as it was then, and with the `debug-checks` feature using it after the elements
+/// may have changed places panics, instead of quietly reaching a different element. Removing,
+/// retaining, draining, sorting, promoting and clearing all count; inserting doesn't, since it
+/// only appends, and neither does growing, since indices don't depend on where the buffer is.
+///
+/// Without the feature this is just the index, and nothing is checked.
+///
+/// ```
+/// use bloomset::BloomSet;
+///
+/// let mut set: BloomSet<&str> = ["a", "b", "c"].iter().copied().collect();
+/// let b = set.find_index("b").unwrap();
+/// assert_eq!(set.get_index(b), Some(&"b"));
+/// set.remove("a");
+/// // `b` is stale now, and with `debug-checks` `set.get_index(b)` panics. As a plain index it
+/// // reaches whatever moved there
+/// assert_eq!(set.get_index(b.get()), Some(&"c"));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SetIndex {
+    index: usize,
+    #[cfg(feature = "debug-checks")]
+    generation: Option<u32>,
+}
+
+impl SetIndex {
+    /// The position in [`as_slice`](BloomSet::as_slice).
+    #[inline]
+    #[must_use]
+    pub const fn get(self) -> usize {
+        self.index
+    }
+}
+
+impl From<usize> for SetIndex {
+    /// An index that isn't checked against any set.
+    #[inline]
+    fn from(index: usize) -> Self {
+        Self {
+            index,
+            #[cfg(feature = "debug-checks")]
+            generation: None,
+        }
+    }
+}
+
+impl<T, S, const K: usize, P: Packing> BloomSet<T, S, K, P> {
+    /// `index`, as a [`SetIndex`] that remembers the set as it is now.
+    #[inline]
+    #[must_use]
+    pub const fn index(&self, index: usize) -> SetIndex {
+        SetIndex {
+            index,
+            #[cfg(feature = "debug-checks")]
+            generation: Some(self.generation),
+        }
+    }
+
+    /// Where `index` points now, panicking with the `debug-checks` feature if elements may have
+    /// changed places since it was taken.
+    #[inline]
+    #[track_caller]
+    #[cfg_attr(not(feature = "debug-checks"), allow(clippy::unused_self))]
+    const fn checked(&self, index: SetIndex) -> usize {
+        #[cfg(feature = "debug-checks")]
+        if let Some(generation) = index.generation {
+            assert!(
+                generation == self.generation,
+                "set was modified since this index was obtained"
+            );
+        }
+        index.index
+    }
+
+    /// The element at `index`, or `None` past the end.
+    ///
+    /// # Panics
+    ///
+    /// With the `debug-checks` feature, panics if `index` is a [`SetIndex`] taken before
+    /// elements last changed places.
+    #[inline]
+    #[must_use]
+    #[track_caller]
+    pub fn get_index<I: Into<SetIndex>>(&self, index: I) -> Option<&T> {
+        self.as_slice().get(self.checked(index.into()))
+    }
+
+    /// The elements in `range`, or `None` if it starts after it ends or ends past the last
+    /// element, where slicing [`as_slice`](BloomSet::as_slice) would panic.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let set: BloomSet<u32> = (0..70).collect();
+    /// let batches: Vec<&[u32]> = (0..set.len())
+    ///     .step_by(32)
+    ///     .map(|start| set.get_range(start..(start + 32).min(set.len())).unwrap())
+    ///     .collect();
+    /// assert_eq!(batches.iter().map(|batch| batch.len()).collect::<Vec<_>>(), [32, 32, 6]);
+    /// assert_eq!(set.get_range(60..80), None);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn get_range<R: RangeBounds<usize>>(&self, range: R) -> Option<&[T]> {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.checked_add(1)?,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end.checked_add(1)?,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len(),
+        };
+        self.as_slice().get(start..end)
+    }
+
+    /// Removes the element at `index` and returns it, moving the last element into its place.
+    /// That doesn't shift the others like [`remove`](BloomSet::remove) does, but does clear
+    /// the [sorted](BloomSet::is_sorted) mark unless it was the last element. Its filter bits
+    /// stay set, as with `remove`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds, and with the `debug-checks` feature, if it is a
+    /// [`SetIndex`] taken before elements last changed places.
+    #[inline]
+    #[track_caller]
+    pub fn swap_remove_index<I: Into<SetIndex>>(&mut self, index: I) -> T {
+        let index = self.checked(index.into());
+        let len = self.len();
+        assert!(
+            index < len,
+            "swap_remove_index index (is {}) should be < len (is {})",
+            index,
+            len
+        );
+        if index + 1 < len {
+            self.length &= !P::SORTED_FLAG;
+        }
+        let removed = unsafe {
+            let base = self.as_mut_ptr();
+            let removed = base.add(index).read();
+            if index + 1 < len {
+                ptr::copy_nonoverlapping(base.add(len - 1), base.add(index), 1);
+            }
+            removed
+        };
+        self.length -= 1;
+        self.moved_elements();
+        removed
+    }
+
+    /// Removes and returns the first element for which `f` returns `true`, or `None` if there
+    /// is none, like [`swap_remove_index`](BloomSet::swap_remove_index) at the element's index.
+    /// Nothing is hashed; `f` is called on each element in order until it matches.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let mut ready: BloomSet<(u32, &str)> = [(3, "parse"), (1, "lex"), (1, "fmt")].iter().copied().collect();
+    /// assert_eq!(ready.take_if(|&(cost, _)| cost == 1), Some((1, "lex")));
+    /// assert_eq!(ready.take_if(|&(cost, _)| cost > 5), None);
+    /// assert_eq!(ready.as_slice(), [(3, "parse"), (1, "fmt")]);
+    /// ```
+    #[inline]
+    pub fn take_if<F: FnMut(&T) -> bool>(&mut self, f: F) -> Option<T> {
+        let index = self.as_slice().iter().position(f)?;
+        Some(self.swap_remove_index(index))
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T>, const K: usize, P: Packing> BloomSet<T, S, K, P> {
+    /// Where `item` is, as a [`SetIndex`] that remembers the set as it is now.
+    #[inline]
+    pub fn find_index<B: core::borrow::Borrow<T>>(&self, item: B) -> Option<SetIndex> {
+        self.position(item.borrow()).map(|index| self.index(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BloomSet, SetIndex};
+    use core::ops::Bound;
+
+    #[test]
+    fn get_and_swap_remove() {
+        let mut set: BloomSet<u32> = (0..5).collect();
+        set.sort();
+        assert_eq!(set.get_index(2), Some(&2));
+        assert_eq!(set.get_index(5), None);
+        assert_eq!(set.swap_remove_index(4), 4);
+        assert!(set.is_sorted());
+        assert_eq!(set.swap_remove_index(set.find_index(1).unwrap()), 1);
+        assert_eq!(set.as_slice(), [0, 3, 2]);
+        assert!(!set.is_sorted() && !set.contains(1));
+        assert_eq!(SetIndex::from(7).get(), 7);
+        assert_eq!(set.index(2).get(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "swap_remove_index index (is 3) should be < len (is 3)")]
+    fn swap_remove_out_of_bounds() {
+        let mut set: BloomSet<u32> = (0..3).collect();
+        set.swap_remove_index(3);
+    }
+
+    #[test]
+    fn ranges() {
+        let set: BloomSet<u32> = (0..10).collect();
+        assert_eq!(set.get_range(..), Some(set.as_slice()));
+        assert_eq!(set.get_range(0..10), Some(set.as_slice()));
+        assert_eq!(set.get_range(7..), Some(&[7, 8, 9][..]));
+        assert_eq!(set.get_range(..=1), Some(&[0, 1][..]));
+        assert_eq!(set.get_range(4..4), Some(&[][..]));
+        assert_eq!(set.get_range(10..10), Some(&[][..]));
+        assert_eq!(set.get_range(10..), Some(&[][..]));
+        assert_eq!(set.get_range(8..11), None);
+        assert_eq!(set.get_range(..=10), None);
+        assert_eq!(set.get_range(11..), None);
+        #[allow(clippy::reversed_empty_ranges)]
+        let inverted = set.get_range(5..4);
+        assert_eq!(inverted, None);
+        assert_eq!(set.get_range(..=usize::MAX), None);
+        assert_eq!(
+            set.get_range((Bound::Excluded(usize::MAX), Bound::Unbounded)),
+            None
+        );
+
+        let empty: BloomSet<u32> = BloomSet::new();
+        assert_eq!(empty.get_range(..), Some(&[][..]));
+        assert_eq!(empty.get_range(0..1), None);
+    }
+
+    #[test]
+    fn take_if() {
+        let mut set: BloomSet<u32> = (1..=6).collect();
+        set.sort();
+        assert_eq!(set.take_if(|i| i % 2 == 0), Some(2));
+        assert_eq!(set.as_slice(), [1, 6, 3, 4, 5]);
+        assert!(!set.is_sorted() && !set.contains(2) && set.contains(4));
+        assert_eq!(set.take_if(|_| true), Some(1));
+        assert_eq!(set.take_if(|&i| i > 10), None);
+        assert_eq!(set.len(), 4);
+
+        // The search stops at the first match
+        let mut calls = 0;
+        assert_eq!(
+            set.take_if(|&i| {
+                calls += 1;
+                i < 5
+            }),
+            Some(3)
+        );
+        assert_eq!(calls, 3);
+        assert_eq!(set.as_slice(), [5, 6, 4]);
+    }
+
+    #[test]
+    fn plain_indices_are_never_checked() {
+        let mut set: BloomSet<u32> = (0..5).collect();
+        set.remove(0);
+        set.sort();
+        assert_eq!(set.get_index(0), Some(&1));
+    }
+
+    #[test]
+    fn appending_keeps_indices() {
+        let mut set: BloomSet<u32> = BloomSet::with_capacity(1);
+        set.insert(10);
+        let index = set.find_index(10).unwrap();
+        set.extend(0..100);
+        set.shrink_to_fit();
+        assert_eq!(set.get_index(index), Some(&10));
+    }
+
+    #[cfg(feature = "debug-checks")]
+    mod debug_checks {
+        use crate::BloomSet;
+
+        fn set() -> BloomSet<u32> {
+            (0..10).collect()
+        }
+
+        #[test]
+        #[should_panic(expected = "set was modified since this index was obtained")]
+        fn after_remove() {
+            let mut set = set();
+            let index = set.find_index(5).unwrap();
+            set.remove(0);
+            let _ = set.get_index(index);
+        }
+
+        #[test]
+        #[should_panic(expected = "set was modified since this index was obtained")]
+        fn after_swap_remove() {
+            let mut set = set();
+            let first = set.index(0);
+            let second = set.index(1);
+            set.swap_remove_index(first);
+            set.swap_remove_index(second);
+        }
+
+        #[test]
+        fn each_move_counts() {
+            let moves: [fn(&mut BloomSet<u32>); 7] = [
+                |set| set.retain(|&i| i != 3),
+                |set| set.retain_unordered(|_| true),
+                |set| drop(set.drain(..1)),
+                BloomSet::sort,
+                |set| assert!(set.promote(9)),
+                |set| assert!(set.contains_promoting(9)),
+                BloomSet::clear,
+            ];
+            for change in moves {
+                let mut set = set();
+                let index = set.index(0);
+                change(&mut set);
+                let stale = std::panic::catch_unwind(|| set.get_index(index).copied());
+                assert!(stale.is_err());
+            }
+        }
+    }
+}
@@ -0,0 +1,287 @@
+use crate::{bloom_mask, BuildBloomHasher, ElementHasher, FILTER_BITS, MAX_CAPACITY};
+use core::borrow::Borrow;
+use core::mem::MaybeUninit;
+use core::{fmt, ptr, slice};
+
+/// The filter, stored as bytes so it doesn't raise the set's alignment.
+#[allow(clippy::cast_possible_truncation)]
+const FILTER_BYTES: usize = FILTER_BITS.div_ceil(8) as usize;
+
+/// A set that stores up to `N` elements inline, with no heap allocation, and the same filter as
+/// a [`BloomSet`](crate::BloomSet).
+///
+/// The filter and the length take 13 bytes (5 on 32-bit targets) with an alignment of 1, so they
+/// can share a word with the tail of the elements: the set is only that much larger than
+/// `[T; N]`, rounded up to `T`'s alignment. `N` can be at most 255.
+///
+/// ```
+/// use bloomset::InlineBloomSet;
+///
+/// let mut set = InlineBloomSet::<u8, 2>::new();
+/// assert_eq!(set.try_insert(1), Ok(()));
+/// assert_eq!(set.try_insert(2), Ok(()));
+/// assert_eq!(set.try_insert(1), Ok(()));
+/// assert_eq!(set.try_insert(3), Err(3));
+/// assert!(set.contains(2));
+/// ```
+pub struct InlineBloomSet<T, const N: usize, S = BuildBloomHasher> {
+    elements: [MaybeUninit<T>; N],
+    filter: [u8; FILTER_BYTES],
+    len: u8,
+    hash_builder: S,
+}
+
+impl<T, const N: usize> Default for InlineBloomSet<T, N> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_hasher(BuildBloomHasher)
+    }
+}
+
+impl<T, const N: usize> InlineBloomSet<T, N> {
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self::with_hasher(BuildBloomHasher)
+    }
+}
+
+impl<T, const N: usize, S> InlineBloomSet<T, N, S> {
+    const VALID_N: () = assert!(N <= MAX_CAPACITY, "N must be at most 255");
+
+    /// Creates an empty set which will use `hash_builder` to hash its elements.
+    #[inline]
+    #[must_use]
+    pub const fn with_hasher(hash_builder: S) -> Self {
+        let () = Self::VALID_N;
+        Self {
+            // An array of MaybeUninit doesn't need initializing
+            elements: unsafe { MaybeUninit::uninit().assume_init() },
+            filter: [0; FILTER_BYTES],
+            len: 0,
+            hash_builder,
+        }
+    }
+
+    /// Returns a reference to the set's `BuildHasher`.
+    #[inline]
+    #[must_use]
+    pub const fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_full(&self) -> bool {
+        self.len() == N
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.elements.as_ptr().cast(), self.len()) }
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        let elements =
+            ptr::slice_from_raw_parts_mut(self.elements.as_mut_ptr().cast::<T>(), self.len());
+        // Reset the length first, so a panicking Drop impl can't lead to a double drop
+        self.len = 0;
+        self.filter = [0; FILTER_BYTES];
+        unsafe { ptr::drop_in_place(elements) };
+    }
+
+    const fn filter(&self) -> u128 {
+        let mut bytes = [0; 16];
+        let mut i = 0;
+        while i < FILTER_BYTES {
+            bytes[i] = self.filter[i];
+            i += 1;
+        }
+        u128::from_le_bytes(bytes)
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn set_filter(&mut self, filter: u128) {
+        self.filter
+            .copy_from_slice(&filter.to_le_bytes()[..FILTER_BYTES]);
+    }
+}
+
+impl<T: PartialEq, const N: usize, S: ElementHasher<T>> InlineBloomSet<T, N, S> {
+    fn bloom_mask_of(&self, item: &T) -> u128 {
+        bloom_mask::<1>(self.hash_builder.hash_element(item), FILTER_BITS)
+    }
+
+    /// Inserts `item`, or hands it back if the set is full and doesn't already contain it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `item` if there is no room for it.
+    #[inline]
+    pub fn try_insert(&mut self, item: T) -> Result<(), T> {
+        let mask = self.bloom_mask_of(&item);
+        if self.filter() & mask == mask && self.as_slice().contains(&item) {
+            return Ok(());
+        }
+        if self.is_full() {
+            return Err(item);
+        }
+        self.elements[self.len()].write(item);
+        self.len += 1;
+        self.set_filter(self.filter() | mask);
+        Ok(())
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the set is full and doesn't already contain `item`.
+    #[inline]
+    pub fn insert(&mut self, item: T) {
+        assert!(
+            self.try_insert(item).is_ok(),
+            "an InlineBloomSet cannot hold more than {} elements",
+            N
+        );
+    }
+
+    /// Removes `item` from the set, returning whether it was present. The remaining elements
+    /// keep their order, and the filter keeps `item`'s bits.
+    #[inline]
+    pub fn remove<B: Borrow<T>>(&mut self, item: B) -> bool {
+        let item = item.borrow();
+        let mask = self.bloom_mask_of(item);
+        if self.filter() & mask != mask {
+            return false;
+        }
+        let Some(index) = self.as_slice().iter().position(|it| it == item) else {
+            return false;
+        };
+        let len = self.len();
+        let removed = unsafe {
+            let base = self.elements.as_mut_ptr().cast::<T>();
+            let removed = base.add(index).read();
+            ptr::copy(base.add(index + 1), base.add(index), len - index - 1);
+            removed
+        };
+        self.len -= 1;
+        drop(removed);
+        true
+    }
+
+    #[inline]
+    pub fn contains<B: Borrow<T>>(&self, item: B) -> bool {
+        let item = item.borrow();
+        let mask = self.bloom_mask_of(item);
+        self.filter() & mask == mask && self.as_slice().iter().any(|it| it == item)
+    }
+}
+
+impl<T: fmt::Debug, const N: usize, S> fmt::Debug for InlineBloomSet<T, N, S> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.as_slice()).finish()
+    }
+}
+
+impl<T, const N: usize, S> Drop for InlineBloomSet<T, N, S> {
+    #[inline]
+    fn drop(&mut self) {
+        let elements =
+            ptr::slice_from_raw_parts_mut(self.elements.as_mut_ptr().cast::<T>(), self.len());
+        unsafe { ptr::drop_in_place(elements) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BloomSet;
+    use std::mem::size_of;
+    use std::rc::Rc;
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn filter_fits_in_padding() {
+        // 13 bytes of filter and length, rounded up to the alignment
+        assert_eq!(size_of::<InlineBloomSet<u64, 4>>(), 32 + 16);
+        assert_eq!(size_of::<InlineBloomSet<u8, 2>>(), 2 + 13);
+        // 12 bytes of elements and 13 of filter and length round up to 28, not 32
+        assert_eq!(size_of::<InlineBloomSet<u32, 3>>(), 28);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "random-seed", ignore = "compares filter bits across sets")]
+    fn agrees_with_bloomset() {
+        let mut inline = InlineBloomSet::<u32, 100>::new();
+        let mut heap = BloomSet::new();
+        for i in 0..100 {
+            inline.insert(i * 3);
+            heap.insert(i * 3);
+        }
+        assert!(inline.is_full());
+        assert_eq!(inline.filter(), heap.filter_bits());
+        assert_eq!(inline.as_slice(), heap.as_slice());
+        assert!((0..400).all(|i| inline.contains(i) == heap.contains(i)));
+        assert_eq!(inline.try_insert(1), Err(1));
+        assert_eq!(inline.try_insert(3), Ok(()));
+
+        assert!(inline.remove(0) && inline.remove(297));
+        assert!(!inline.remove(0));
+        assert_eq!(inline.as_slice(), &heap.as_slice()[1..99]);
+    }
+
+    #[test]
+    fn drops_only_initialized_elements() {
+        let counted = Rc::new(());
+        {
+            let mut set = InlineBloomSet::<(u8, Rc<()>), 8>::new();
+            for i in 0..3 {
+                set.insert((i, Rc::clone(&counted)));
+            }
+            assert_eq!(Rc::strong_count(&counted), 4);
+            assert!(set.remove((1, Rc::clone(&counted))));
+            assert_eq!(Rc::strong_count(&counted), 3);
+        }
+        assert_eq!(Rc::strong_count(&counted), 1);
+
+        let mut set = InlineBloomSet::<(u8, Rc<()>), 2>::new();
+        set.insert((0, Rc::clone(&counted)));
+        set.insert((1, Rc::clone(&counted)));
+        let rejected = set.try_insert((2, Rc::clone(&counted))).unwrap_err();
+        assert_eq!(Rc::strong_count(&counted), 4);
+        drop(rejected);
+        set.clear();
+        assert_eq!(Rc::strong_count(&counted), 1);
+        assert!(set.is_empty() && set.filter() == 0);
+        set.insert((5, Rc::clone(&counted)));
+        drop(set);
+        assert_eq!(Rc::strong_count(&counted), 1);
+    }
+
+    #[test]
+    fn zero_capacity() {
+        let mut set = InlineBloomSet::<String, 0>::new();
+        assert_eq!(set.try_insert("a".to_string()), Err("a".to_string()));
+        assert!(!set.contains("a".to_string()));
+    }
+}
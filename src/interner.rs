@@ -0,0 +1,163 @@
+use crate::{bloom_mask, BloomSet, BuildBloomHasher, CapacityError, ElementHasher, FILTER_BITS};
+use core::borrow::Borrow;
+
+/// Hands out one-byte ids for up to 255 distinct values, backed by a [`BloomSet`].
+///
+/// Ids are dense and stable: the first value interned gets 0, the next new one 1, and so on, and
+/// interning a value again returns the id it already has. Looking up a value checks the set's
+/// filter first, so misses are usually answered without comparing any values.
+///
+/// ```
+/// use bloomset::SmallInterner;
+///
+/// let mut opcodes = SmallInterner::<&str>::new();
+/// let add = opcodes.intern("add").unwrap();
+/// let mul = opcodes.intern("mul").unwrap();
+/// assert_eq!((add, mul), (0, 1));
+/// assert_eq!(opcodes.intern("add"), Ok(add));
+/// assert_eq!(opcodes.lookup("mul"), Some(mul));
+/// assert_eq!(opcodes.lookup("sub"), None);
+/// assert_eq!(*opcodes.resolve(mul), "mul");
+/// ```
+pub struct SmallInterner<T, S = BuildBloomHasher> {
+    set: BloomSet<T, S>,
+}
+
+impl<T> Default for SmallInterner<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_hasher(BuildBloomHasher)
+    }
+}
+
+impl<T> SmallInterner<T> {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T, S> SmallInterner<T, S> {
+    /// Creates an empty interner which will use `hash_builder` to hash its values.
+    #[inline]
+    #[must_use]
+    pub const fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            set: BloomSet::with_hasher(hash_builder),
+        }
+    }
+
+    /// The number of distinct values interned, which is also the next id to be handed out.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.set.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.set.is_empty()
+    }
+
+    /// Returns the value with id `id`, or `None` if no value has that id yet.
+    #[inline]
+    #[must_use]
+    pub fn get(&self, id: u8) -> Option<&T> {
+        self.set.as_slice().get(usize::from(id))
+    }
+
+    /// Returns the value with id `id`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this interner hasn't handed out `id`.
+    #[inline]
+    #[must_use]
+    pub fn resolve(&self, id: u8) -> &T {
+        self.get(id)
+            .unwrap_or_else(|| panic!("id {} was not handed out by this interner", id))
+    }
+
+    /// The interned values, in id order.
+    #[inline]
+    #[must_use]
+    pub const fn as_slice(&self) -> &[T] {
+        self.set.as_slice()
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T>> SmallInterner<T, S> {
+    /// Returns the id of `value`, interning it first if it is new.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CapacityError`] if `value` is new and 255 values have been interned already.
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn intern(&mut self, value: impl Into<T>) -> Result<u8, CapacityError> {
+        let value = value.into();
+        if let Some(id) = self.lookup(&value) {
+            return Ok(id);
+        }
+        self.set.insert_within_limit(value)?;
+        // At most 255 values, so the last index fits in a byte
+        Ok((self.set.len() - 1) as u8)
+    }
+
+    /// Returns the id of `value` if it has been interned.
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn lookup<Q: PartialEq + ?Sized>(&self, value: &Q) -> Option<u8>
+    where
+        T: Borrow<Q>,
+        S: ElementHasher<Q>,
+    {
+        // Interners are never seeded and set one bit per value, like the set they wrap
+        let mask = bloom_mask::<1>(self.set.hasher().hash_element(value), FILTER_BITS);
+        if !self.set.bloom_contains(mask) {
+            return None;
+        }
+        let index = self
+            .set
+            .as_slice()
+            .iter()
+            .position(|it| it.borrow() == value)?;
+        Some(index as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_round_trip() {
+        let words: Vec<String> = (0..255).map(|i| format!("word{i}")).collect();
+        let mut interner = SmallInterner::<&str>::new();
+        for (i, word) in words.iter().enumerate() {
+            assert_eq!(interner.intern(word.as_str()).map(usize::from), Ok(i));
+        }
+        for (i, word) in words.iter().enumerate().rev() {
+            let id = interner.intern(word.as_str()).unwrap();
+            assert_eq!(usize::from(id), i);
+            // Looked up by `str`, the borrowed form of the `&str` values
+            assert_eq!(interner.lookup(word.as_str()), Some(id));
+            assert_eq!(*interner.resolve(id), word);
+        }
+        assert_eq!(interner.len(), 255);
+        assert_eq!(interner.lookup("word255"), None);
+        assert_eq!(interner.get(255), None);
+
+        let err = interner.intern("word255").unwrap_err();
+        assert_eq!((err.requested(), err.limit()), (256, 255));
+        assert_eq!(interner.intern("word3"), Ok(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "id 0 was not handed out by this interner")]
+    fn resolve_unknown_id() {
+        let _ = SmallInterner::<u8>::new().resolve(0);
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{BloomSet, Packing};
+use alloc::vec;
+use core::fmt;
+use core::iter::FusedIterator;
+use core::slice;
+
+impl<T, S, const K: usize, P: Packing> BloomSet<T, S, K, P> {
+    /// The elements, in the set's order, like `as_slice().iter()`.
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            inner: self.as_slice().iter(),
+        }
+    }
+}
+
+impl<'a, T, S, const K: usize, P: Packing> IntoIterator for &'a BloomSet<T, S, K, P> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+/// Yields the elements by value, in the set's order. The iterator takes over the set's buffer,
+/// so nothing is copied or allocated.
+impl<T, S, const K: usize, P: Packing> IntoIterator for BloomSet<T, S, K, P> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    #[inline]
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter {
+            inner: self.into_vec().into_iter(),
+        }
+    }
+}
+
+/// An iterator over the elements of a [`BloomSet`], made by [`BloomSet::iter`].
+///
+/// Cloning it is free, and the clone carries on from the same place independently. The
+/// default is an empty iterator.
+#[derive(Clone, Default)]
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct Iter<'a, T> {
+    inner: slice::Iter<'a, T>,
+}
+
+impl<'a, T> Iter<'a, T> {
+    /// The elements not yet yielded.
+    #[inline]
+    #[must_use]
+    pub fn as_slice(&self) -> &'a [T] {
+        self.inner.as_slice()
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        self.inner.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+/// An iterator that moves the elements out of a [`BloomSet`], made by its `into_iter`.
+///
+/// Cloning it clones the elements not yet yielded into a new buffer, so it needs `T: Clone`.
+/// The default is an empty iterator, which doesn't allocate.
+#[derive(Default)]
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct IntoIter<T> {
+    inner: vec::IntoIter<T>,
+}
+
+impl<T> IntoIter<T> {
+    /// The elements not yet yielded.
+    #[inline]
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        self.inner.as_slice()
+    }
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.inner.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<T> {
+        self.inner.next_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> FusedIterator for IntoIter<T> {}
+
+impl<T: Clone> Clone for IntoIter<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Iter<'_, T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Iter").field(&self.as_slice()).finish()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for IntoIter<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("IntoIter").field(&self.as_slice()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BloomSet, IntoIter, Iter};
+
+    #[test]
+    fn clones_carry_on_independently() {
+        let set: BloomSet<u32> = (0..6).collect();
+        let mut iter = set.iter();
+        assert_eq!(iter.next(), Some(&0));
+        assert_eq!(iter.next_back(), Some(&5));
+        let mut copy = iter.clone();
+        assert!(iter.by_ref().take(2).eq([1, 2].iter()));
+        assert!(copy.by_ref().eq([1, 2, 3, 4].iter()));
+        assert_eq!(iter.as_slice(), [3, 4]);
+        assert_eq!(copy.len(), 0);
+        assert_eq!(format!("{iter:?}"), "Iter([3, 4])");
+
+        let set: BloomSet<String> = ["a", "b", "c"].iter().map(ToString::to_string).collect();
+        let mut owned = set.into_iter();
+        assert_eq!(owned.next().as_deref(), Some("a"));
+        let copy = owned.clone();
+        assert_eq!(owned.next_back().as_deref(), Some("c"));
+        assert_eq!(format!("{owned:?}"), r#"IntoIter(["b"])"#);
+        assert_eq!(copy.collect::<Vec<_>>(), ["b", "c"]);
+        assert_eq!(owned.as_slice(), ["b"]);
+    }
+
+    #[test]
+    fn defaults_are_empty() {
+        assert_eq!(Iter::<u32>::default().len(), 0);
+        assert_eq!(IntoIter::<String>::default().next(), None);
+    }
+
+    #[test]
+    fn loops() {
+        let set: BloomSet<u32> = (0..4).rev().collect();
+        let mut seen = Vec::new();
+        for &item in &set {
+            seen.push(item);
+        }
+        assert_eq!(seen, [3, 2, 1, 0]);
+        assert!(set.into_iter().eq(seen));
+    }
+}
@@ -2,85 +2,663 @@
 //! membership checks, because `x86_64` only supports 48 bits of address space. So we can embed a
 //! bloom filter in the 32 free bits between its capacity and length.
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+#![cfg_attr(feature = "nightly-simd", feature(portable_simd, min_specialization))]
 #![warn(clippy::pedantic, clippy::nursery, clippy::restriction)]
 #![deny(clippy::missing_inline_in_public_items)]
+#![cfg_attr(
+    not(feature = "inline-more"),
+    allow(clippy::missing_inline_in_public_items)
+)]
 
-use std::hash::{Hash, Hasher};
-use std::mem::ManuallyDrop;
-use std::ptr::NonNull;
-use std::slice;
+extern crate alloc;
 
-pub struct BloomSet<T> {
+use alloc::alloc::{self as heap, Layout};
+use alloc::rc::Rc;
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::Hash;
+use core::iter::FromIterator;
+use core::marker::PhantomData;
+use core::mem::{self, ManuallyDrop, MaybeUninit};
+use core::ptr::{self, NonNull};
+use core::slice;
+
+#[cfg(feature = "bincode")]
+mod bincode_impls;
+mod bucketed;
+mod buf;
+mod builder;
+#[cfg(feature = "bumpalo")]
+mod bump;
+mod by_address;
+mod by_bits;
+mod byte_set;
+#[cfg(target_has_atomic = "8")]
+mod cached;
+mod counting;
+#[cfg(target_has_atomic = "ptr")]
+mod cow;
+mod dedup;
+mod drain;
+mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod filter;
+mod frozen;
+mod hasher;
+#[cfg(feature = "std")]
+pub mod hybrid;
+mod index;
+mod inline;
+mod interner;
+mod iter;
+pub mod map;
+mod mapped;
+pub mod multiset;
+mod packing;
+mod pool;
+mod probable;
+#[cfg(kani)]
+mod proofs;
+mod raw;
+mod raw_entry;
+#[cfg(feature = "rayon")]
+mod rayon_impls;
+mod rebuilding;
+#[cfg(feature = "safe-fallback")]
+pub mod safe;
+mod scoped;
+#[cfg(feature = "serde")]
+mod serde_impls;
+mod set_ref;
+#[cfg(feature = "std")]
+mod sharded;
+#[cfg(feature = "nightly-simd")]
+mod simd;
+mod small;
+#[cfg(feature = "smallvec")]
+mod smallvec_impls;
+#[cfg(feature = "serde")]
+pub mod sorted;
+#[cfg(doctest)]
+mod soundness;
+mod spilling;
+#[cfg(feature = "stats")]
+mod stats;
+#[cfg(test)]
+mod stress;
+mod tagged;
+mod wide;
+#[cfg(feature = "zerocopy")]
+mod zerocopy_impls;
+
+pub use bucketed::BucketedBloomSet;
+pub use buf::BloomSetBuf;
+pub use builder::BloomSetBuilder;
+#[cfg(feature = "bumpalo")]
+pub use bump::BumpBloomSet;
+pub use by_address::{ByAddress, IdentitySet};
+pub use by_bits::ByBits;
+pub use byte_set::ByteSet;
+#[cfg(target_has_atomic = "8")]
+pub use cached::CachedBloomSet;
+pub use counting::CountingBloomSet;
+#[cfg(target_has_atomic = "ptr")]
+pub use cow::CowBloomSet;
+pub use dedup::{dedup_in_place, dedup_in_place_by_key, DedupWindow};
+pub use drain::Drain;
+#[cfg(feature = "zerocopy")]
+pub use error::PodCastError;
+pub use error::{CapacityError, DuplicateError, LayoutError, TryReserveError};
+pub use filter::BloomFilter;
+#[cfg(target_has_atomic = "64")]
+pub use filter::SharedBloomFilter;
+pub use frozen::{FrozenBloomSet, FrozenBloomSetRef};
+pub use hasher::{
+    BloomHasher, BloomIndex, BuildBloomHasher, BuildIdentityHasher, ByBloomIndex, ElementHasher,
+    IdentityHasher,
+};
+#[cfg(feature = "std")]
+pub use hybrid::HybridBloomSet;
+pub use index::SetIndex;
+pub use inline::InlineBloomSet;
+pub use interner::SmallInterner;
+pub use iter::{IntoIter, Iter};
+pub use map::BloomMap;
+pub use mapped::Pod;
+pub use multiset::BloomMultiSet;
+pub use packing::{Packed16, Packed8, Packing};
+pub use pool::BloomSetPool;
+pub use probable::FilterProbable;
+pub use raw_entry::{RawEntry, RawOccupiedEntry, RawVacantEntry};
+pub use rebuilding::RebuildingBloomSet;
+pub use scoped::EnterGuard;
+pub use set_ref::BloomSetRef;
+#[cfg(feature = "std")]
+pub use sharded::ShardedBloomSet;
+pub use small::SmallBloomSet;
+pub use spilling::SpillingBloomSet;
+#[cfg(feature = "stats")]
+pub use stats::{reset_stats, stats, BloomStats};
+pub use tagged::TaggedBloomSet;
+pub use wide::WideBloomSet;
+
+#[cfg(not(feature = "nightly-simd"))]
+use scalar_scan as scan_elements;
+#[cfg(feature = "nightly-simd")]
+use simd::scan as scan_elements;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::frozen::sort_strs;
+}
+
+/// Fails the way growing a set always has when it can't: panicking with the error, or aborting
+/// through `handle_alloc_error` if the allocator refused.
+#[cold]
+fn handle_reserve_error(err: TryReserveError) -> ! {
+    match err {
+        TryReserveError::Capacity(err) => panic!("{}", err),
+        TryReserveError::AllocFailed {
+            layout: Some(layout),
+        } => heap::handle_alloc_error(layout),
+        TryReserveError::AllocFailed { layout: None } => panic!("capacity overflow"),
+    }
+}
+
+/// The largest number of elements a `BloomSet` can hold with the default packing; its length and
+/// capacity are stored in a single byte each.
+const MAX_CAPACITY: usize = Packed8::MAX_CAPACITY;
+
+/// The number of bits in the bloom filter with the default packing.
+///
+/// That is everything but the low byte of the length and capacity words, the top byte of the
+/// capacity word, which holds the set's seed, and the top byte of the length word, which records
+/// whether the set is sorted and counts the set filter bits. That is 96 bits on 64-bit targets
+/// (48 from each word) and 32 on 32-bit ones.
+pub const FILTER_BITS: u64 = Packed8::FILTER_BITS;
+
+/// The number of set filter bits (75% of them) at which `contains` stops consulting the filter,
+/// with the default packing. Other packings also stop at three quarters of their bits.
+///
+/// When this many bits are set, most lookups for absent elements pass the filter check anyway,
+/// so hashing the element only adds to the cost of the scan that follows. The set keeps count of
+/// its set bits, so lookups check this first and go straight to the scan.
+pub const SATURATION_THRESHOLD: u32 = Packed8::SATURATION_THRESHOLD;
+
+/// How many elements an iterator must promise, by the lower bound of its size hint, before
+/// collecting or extending from it deduplicates in bulk instead of inserting one at a time.
+///
+/// Inserting one at a time scans the set for every element, which is quadratic; the bulk path
+/// sorts hashes instead, which costs more up front but is O(n log n). For strings the two break
+/// even at around 40 elements.
+const BULK_THRESHOLD: usize = 48;
+
+/// [`BULK_THRESHOLD`] for elements that are [cheap to compare](cheap_to_compare). Their scan is
+/// so fast that inserting one at a time wins at every size a [`Packed8`] set can hold, so only
+/// larger packings ever take the bulk path for them.
+const CHEAP_BULK_THRESHOLD: usize = 512;
+
+/// Whether `T` is no bigger than a `u64` and doesn't need dropping, like the primitive
+/// integers, which is taken to mean comparing two of them is about as cheap as it gets.
+const fn cheap_to_compare<T>() -> bool {
+    mem::size_of::<T>() <= mem::size_of::<u64>() && !mem::needs_drop::<T>()
+}
+
+/// The [`BULK_THRESHOLD`] for `T`.
+const fn bulk_threshold<T>() -> usize {
+    if cheap_to_compare::<T>() {
+        CHEAP_BULK_THRESHOLD
+    } else {
+        BULK_THRESHOLD
+    }
+}
+
+/// The capacity a full buffer of `capacity` elements grows to when one more is added: double,
+/// like a `Vec`, but at least 4 and never more than `max`, the most the capacity bits can
+/// represent. Every set type in the crate grows this way, so a set of 255 single inserts
+/// reallocates at 4, 8, 16, 32, 64, 128 and 255.
+const fn grown_capacity(capacity: usize, max: usize) -> usize {
+    let doubled = capacity.saturating_mul(2);
+    if doubled < 4 {
+        4
+    } else if doubled > max {
+        max
+    } else {
+        doubled
+    }
+}
+
+/// Where the seed lives in the capacity word.
+const SEED_SHIFT: u32 = usize::BITS - 8;
+
+/// Maps a hash onto the index of the filter bit it sets.
+///
+/// This is the only place a hash becomes a filter bit: every set's `insert` and `contains`, and
+/// the raw-hash APIs like [`BloomFilter::insert_hash`], go through it via [`bloom_mask`], so
+/// they can't disagree.
+///
+/// Every byte of the hash contributes, so hashers that only mix well into their high bits still
+/// spread elements across the filter. Hashes that fit in a byte map to `hash % filter_bits`.
+#[inline]
+const fn bloom_bit(hash: u64, filter_bits: u64) -> u64 {
+    let folded = hash ^ (hash >> 32);
+    let folded = folded ^ (folded >> 16);
+    let folded = folded ^ (folded >> 8);
+    folded % filter_bits
+}
+
+/// Computes the `K` filter bits for a hash, as a mask with bit `i` standing for filter bit `i`.
+///
+/// The first bit is always `bloom_bit(hash)`; the rest are derived by double hashing, stepping
+/// by a multiplicative remix of the hash, so `insert` and `contains` agree on all of them.
+///
+/// With the `no-filter` feature the mask is always empty, so no filter bit is ever set and every
+/// lookup gets past the filter and scans, for comparing against the same code with a filter.
+#[inline]
+const fn bloom_mask<const K: usize>(hash: u64, filter_bits: u64) -> u128 {
+    if cfg!(feature = "no-filter") {
+        return 0;
+    }
+    let step = hash.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+    let mut mask = 0;
+    let mut i = 0;
+    while i < K {
+        mask |= 1 << bloom_bit(hash.wrapping_add(step.wrapping_mul(i as u64)), filter_bits);
+        i += 1;
+    }
+    mask
+}
+
+/// Remixes a hash with a set's seed. A seed of 0 leaves the hash unchanged; any other seed
+/// multiplies it by a distinct odd constant, so the same elements collide on different bits.
+#[inline]
+const fn seeded(hash: u64, seed: u8) -> u64 {
+    hash.wrapping_mul((seed as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+}
+
+/// Splits a filter mask into the bits it occupies in the capacity and length words. With the
+/// default packing on 64-bit targets those hold filter bits 0..48 and 48..96 respectively above
+/// their low byte. The sorted flag, the count of set bits and the seed are never touched.
+#[inline]
+#[allow(clippy::cast_possible_truncation)]
+const fn split_mask<P: Packing>(mask: u128) -> (usize, usize) {
+    let low_mask = (1 << P::LOW_BITS) - 1;
+    let high_mask = (1 << P::HIGH_BITS) - 1;
+    let low = ((mask as u64 & low_mask) << P::COUNT_BITS) as usize;
+    let high = (((mask >> P::LOW_BITS) as u64 & high_mask) << P::COUNT_BITS) as usize;
+    (low, high)
+}
+
+/// The inverse of [`split_mask`]: reassembles the filter from the length and capacity words.
+#[inline]
+const fn unpack_filter<P: Packing>(length: usize, capacity: usize) -> u128 {
+    let low = ((capacity >> P::COUNT_BITS) & ((1 << P::LOW_BITS) - 1)) as u128;
+    let high = ((length >> P::COUNT_BITS) & ((1 << P::HIGH_BITS) - 1)) as u128;
+    low | high << P::LOW_BITS
+}
+
+/// Whether `elements` contains `item`, for lookups that got past the filter.
+///
+/// This is kept out of line, so that `contains` and `insert` inline as little more than the
+/// hash and the filter check; the scan is the uncommon path while the filter has bits to
+/// spare. Any change here should keep a `BloomSet<u32>::contains` instantiation near 100
+/// instructions on `x86_64`, which `cargo asm` or `--emit asm` shows.
+#[cold]
+#[inline(never)]
+fn scan<T: PartialEq>(elements: &[T], item: &T) -> bool {
+    scan_elements(elements, item)
+}
+
+/// The scan behind [`scan`]. With the `nightly-simd` feature, `simd::scan` takes over for the
+/// unsigned integers.
+///
+/// Elements that are [cheap to compare](cheap_to_compare) are compared a chunk of 16 at a time
+/// without stopping inside a chunk, which LLVM turns into vector compares. Comparing such
+/// elements is assumed to be cheap, so `==` may be called on the rest of a chunk after a match.
+/// Everything else is scanned one element at a time.
+#[inline]
+// The non-short-circuiting `|` is what keeps each chunk free of branches
+#[allow(clippy::needless_bitwise_bool)]
+fn scalar_scan<T: PartialEq>(elements: &[T], item: &T) -> bool {
+    const LANES: usize = 16;
+    if !cheap_to_compare::<T>() {
+        return elements.iter().any(|it| it == item);
+    }
+    let chunks = elements.chunks_exact(LANES);
+    let rest = chunks.remainder();
+    chunks
+        .into_iter()
+        .any(|chunk| chunk.iter().fold(false, |found, it| found | (it == item)))
+        || rest.iter().any(|it| it == item)
+}
+
+/// The index of `item` in `elements`, found chunk by chunk like [`scalar_scan`] does before
+/// looking for it within the chunk that matched.
+#[inline]
+#[allow(clippy::needless_bitwise_bool)]
+fn scan_position<T: PartialEq>(elements: &[T], item: &T) -> Option<usize> {
+    const LANES: usize = 16;
+    if !cheap_to_compare::<T>() {
+        return elements.iter().position(|it| it == item);
+    }
+    let chunk = elements
+        .chunks(LANES)
+        .position(|chunk| chunk.iter().fold(false, |found, it| found | (it == item)))?;
+    let start = chunk * LANES;
+    elements[start..]
+        .iter()
+        .position(|it| it == item)
+        .map(|index| start + index)
+}
+
+/// A set of elements stored contiguously in insertion order, with a bloom filter packed into the
+/// spare bits of its length and capacity.
+///
+/// `K` is the number of filter bits each element sets. More bits lower the false-positive rate
+/// while the set is sparsely filled, at the cost of saturating the filter sooner. It is
+/// picked with a type annotation:
+///
+/// ```
+/// use bloomset::{BloomSet, BuildBloomHasher};
+///
+/// let mut set: BloomSet<u64, _, 2> = BloomSet::with_hasher(BuildBloomHasher);
+/// set.insert(7);
+/// assert!(set.contains(7));
+/// ```
+///
+/// `P` picks how the length and capacity words are split between counts and filter; see
+/// [`Packing`] and [`BloomSet16`].
+///
+/// Elements only need `PartialEq`, and the set finds an element by comparing it with `==`. A
+/// value that isn't equal to itself, such as a float NaN, is therefore never found: each
+/// `insert` of it adds another element, and `contains` and `remove` always miss it. Wrap floats
+/// in [`ByBits`] to compare them by their bits instead.
+///
+/// For `u8` elements, [`ByteSet`] has the same interface but an exact bitmap instead of a filter.
+pub struct BloomSet<T, S = BuildBloomHasher, const K: usize = 1, P: Packing = Packed8> {
     ptr: NonNull<T>,
     length: usize,
     capacity: usize,
+    hash_builder: S,
+    packing: PhantomData<P>,
+    /// With the `debug-checks` feature, bumped whenever elements may have changed places; see
+    /// [`SetIndex`].
+    #[cfg(feature = "debug-checks")]
+    generation: u32,
 }
 
-#[derive(Default)]
-pub struct BloomHasher {
-    state: u8,
+/// A [`BloomSet`] with two-byte length and capacity, which can hold up to 65,535 elements and has
+/// an 88-bit filter (24 bits on 32-bit targets).
+///
+/// `new`, `with_capacity` and `Default` only exist for the default packing, so that
+/// `BloomSet::new()` doesn't need a type annotation. Create one with a hasher or by collecting:
+///
+/// ```
+/// use bloomset::{BloomSet16, BuildBloomHasher};
+///
+/// let set: BloomSet16<u32> = (0..1000).collect();
+/// assert_eq!(set.len(), 1000);
+/// assert!(set.contains(999));
+///
+/// let mut set: BloomSet16<u32> = BloomSet16::with_capacity_and_hasher(300, BuildBloomHasher);
+/// set.insert(1);
+/// ```
+pub type BloomSet16<T, S = BuildBloomHasher, const K: usize = 1> = BloomSet<T, S, K, Packed16>;
+
+/// A [`BloomSet`] that uses integer elements as their own hashes, through
+/// [`BuildIdentityHasher`].
+///
+/// For ids that are already spread out this saves the hashing on every insert and lookup, and
+/// ids below [`FILTER_BITS`] each get a filter bit of their own. A seed other than 0, as the
+/// `random-seed` feature gives, remixes the ids and gives that up.
+///
+/// Like any set with a hasher other than the default, create one by collecting, from the
+/// [builder](BloomSet::builder) or with [`with_hasher`](BloomSet::with_hasher).
+pub type IntBloomSet<T, const K: usize = 1> = BloomSet<T, BuildIdentityHasher, K>;
+
+impl<T> Default for BloomSet<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_hasher(BuildBloomHasher).with_random_seed()
+    }
 }
 
-impl Hasher for BloomHasher {
+impl<T> BloomSet<T> {
     #[inline]
-    fn write(&mut self, bytes: &[u8]) {
-        for b in bytes {
-            self.state ^= b;
-        }
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty set with room for exactly `cap` elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cap` is more than 255; see [`try_with_capacity`](BloomSet::try_with_capacity).
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[must_use]
+    pub fn with_capacity(cap: usize) -> Self {
+        Self::with_capacity_and_hasher(cap, BuildBloomHasher)
+    }
+
+    /// Like [`with_capacity`](BloomSet::with_capacity), but fails instead of panicking if `cap`
+    /// is more than 255.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CapacityError`] if `cap` is more than 255.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn try_with_capacity(cap: usize) -> Result<Self, CapacityError> {
+        Self::try_with_capacity_and_hasher(cap, BuildBloomHasher)
+    }
+
+    /// Reconstitutes a set from the parts returned by [`into_raw_parts`](BloomSet::into_raw_parts).
+    ///
+    /// # Safety
+    ///
+    /// `ptr`, `length` and `capacity` must have come from `into_raw_parts` on a `BloomSet<T>`,
+    /// and must not have been used to reconstitute a set already. See
+    /// [`from_raw_parts_with_hasher`](BloomSet::from_raw_parts_with_hasher).
+    #[inline]
+    #[must_use]
+    pub const unsafe fn from_raw_parts(ptr: NonNull<T>, length: usize, capacity: usize) -> Self {
+        Self::from_raw_parts_with_hasher(ptr, length, capacity, BuildBloomHasher)
     }
 
+    /// Creates an empty set whose elements are spread over the filter according to `seed`.
+    ///
+    /// Sets with different seeds map the same element to different filter bits (seed 0 behaves
+    /// like an unseeded set), so many sets with overlapping contents don't all saturate the same
+    /// bits. The seed occupies the top byte of the capacity word, which is why the filter has
+    /// 96 bits rather than 104 (32 rather than 40 on 32-bit targets).
     #[inline]
-    fn finish(&self) -> u64 {
-        u64::from(self.state)
+    #[must_use]
+    pub fn with_seed(seed: u8) -> Self {
+        Self::with_capacity_and_seed(0, seed)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `cap` is more than 255.
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[must_use]
+    pub fn with_capacity_and_seed(cap: usize, seed: u8) -> Self {
+        let mut set = Self::with_capacity(cap);
+        set.set_seed(seed);
+        set
     }
 }
 
-impl<T> Default for BloomSet<T> {
+impl BloomSet<()> {
+    /// Starts configuring a set with a [`BloomSetBuilder`], for setting its capacity, seed,
+    /// hasher and bits per element together. The element type is picked when it is built.
     #[inline]
-    fn default() -> Self {
+    pub const fn builder() -> BloomSetBuilder {
+        BloomSetBuilder::new()
+    }
+}
+
+impl<T, S, const K: usize, P: Packing> BloomSet<T, S, K, P> {
+    #[allow(clippy::cast_possible_truncation)]
+    const VALID_K: () = assert!(
+        K >= 1 && K <= P::FILTER_BITS as usize,
+        "K must be at least 1 and at most the number of filter bits"
+    );
+
+    /// Creates an empty set which will use `hash_builder` to hash its elements.
+    ///
+    /// Like a `Vec`, a set of zero-sized elements never allocates; its capacity is always the
+    /// packing's [`MAX_CAPACITY`](Packing::MAX_CAPACITY).
+    ///
+    /// The set is unseeded even with the `random-seed` feature, because this is a `const fn` and
+    /// drawing a seed isn't, and so are the sets other constructors build on it. For a randomly
+    /// seeded set with a custom hasher, use
+    /// [`with_capacity_and_hasher`](BloomSet::with_capacity_and_hasher), with a capacity of 0 if
+    /// it shouldn't allocate yet.
+    #[inline]
+    #[must_use]
+    pub const fn with_hasher(hash_builder: S) -> Self {
+        let () = Self::VALID_K;
         Self {
             ptr: NonNull::dangling(),
             length: 0,
-            capacity: 0,
+            capacity: if mem::size_of::<T>() == 0 {
+                P::MAX_CAPACITY
+            } else {
+                0
+            },
+            hash_builder,
+            packing: PhantomData,
+            #[cfg(feature = "debug-checks")]
+            generation: 0,
         }
     }
-}
 
-impl<T> BloomSet<T> {
+    /// Creates an empty set with room for exactly `cap` elements, which will use `hash_builder`
+    /// to hash its elements.
+    ///
+    /// The set allocates exactly `cap` elements' worth of memory, so its capacity is always what
+    /// was asked for.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cap` is more than the packing's [`MAX_CAPACITY`](Packing::MAX_CAPACITY) (255 by
+    /// default), since the capacity bits couldn't represent it.
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[must_use]
+    pub fn with_capacity_and_hasher(cap: usize, hash_builder: S) -> Self {
+        match Self::try_with_capacity_and_hasher(cap, hash_builder) {
+            Ok(set) => set,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Like [`with_capacity_and_hasher`](BloomSet::with_capacity_and_hasher), but fails instead
+    /// of panicking if `cap` is too large.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CapacityError`] if `cap` is more than the packing's
+    /// [`MAX_CAPACITY`](Packing::MAX_CAPACITY).
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn try_with_capacity_and_hasher(
+        cap: usize,
+        hash_builder: S,
+    ) -> Result<Self, CapacityError> {
+        if cap > P::MAX_CAPACITY {
+            return Err(CapacityError::new(cap, P::MAX_CAPACITY));
+        }
+        let mut set = Self::with_hasher(hash_builder).with_random_seed();
+        if cap > 0 {
+            set.reallocate(cap);
+        }
+        Ok(set)
+    }
+
+    /// With the `random-seed` feature, gives a new set a seed drawn from `RandomState`, so which
+    /// elements share filter bits differs from set to set and can't be planned by whoever picks
+    /// the elements. Without the feature, and for packings without a seed, this does nothing.
+    #[cfg(feature = "random-seed")]
     #[inline]
     #[must_use]
-    pub fn new() -> Self {
-        Self::default()
+    #[allow(clippy::cast_possible_truncation)]
+    fn with_random_seed(mut self) -> Self {
+        if P::SEEDED {
+            use std::hash::{BuildHasher, Hasher};
+            let random = std::collections::hash_map::RandomState::new()
+                .build_hasher()
+                .finish();
+            self.capacity |= (random as usize) & P::SEED_MASK;
+        }
+        self
     }
 
+    #[cfg(not(feature = "random-seed"))]
     #[inline]
     #[must_use]
-    pub fn with_capacity(cap: usize) -> Self {
-        let mut vec = ManuallyDrop::new(Vec::with_capacity(cap));
-        let ptr = unsafe { NonNull::new_unchecked(vec.as_mut_ptr()) };
-        Self {
-            ptr,
-            length: 0,
-            capacity: cap,
+    const fn with_random_seed(self) -> Self {
+        self
+    }
+
+    /// Replaces the seed of a set that has no filter bits set yet. Packings without a seed
+    /// ignore it.
+    const fn set_seed(&mut self, seed: u8) {
+        debug_assert!(self.filter_bits() == 0);
+        self.capacity =
+            self.capacity & !P::SEED_MASK | (seed as usize) << SEED_SHIFT & P::SEED_MASK;
+    }
+
+    /// Returns the seed the set was created with, or 0 if it wasn't given one.
+    #[inline]
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn seed(&self) -> u8 {
+        if P::SEEDED {
+            (self.capacity >> SEED_SHIFT) as u8
+        } else {
+            0
         }
     }
 
+    /// Returns a reference to the set's `BuildHasher`.
+    #[inline]
+    #[must_use]
+    pub const fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
     #[inline]
     #[must_use]
     pub const fn is_empty(&self) -> bool {
-        self.len() != 0
+        self.len() == 0
     }
 
     #[inline]
     #[must_use]
     pub const fn len(&self) -> usize {
-        self.length & 0x0000_0000_0000_00FF
+        self.length & P::COUNT_MASK
     }
 
     #[inline]
     #[must_use]
     pub const fn capacity(&self) -> usize {
-        self.capacity & 0x0000_0000_0000_00FF
+        self.capacity & P::COUNT_MASK
+    }
+
+    /// Returns whether the elements are known to be in ascending order, because
+    /// [`sort`](BloomSet::sort) was called and nothing has been inserted since.
+    ///
+    /// The flag lives in the top bit of the length word. Writing through
+    /// [`as_mut_ptr`](BloomSet::as_mut_ptr) doesn't clear it, so code that reorders or replaces
+    /// elements that way must not leave a sorted set out of order.
+    #[inline]
+    #[must_use]
+    pub const fn is_sorted(&self) -> bool {
+        self.length & P::SORTED_FLAG != 0
     }
 
     #[inline]
@@ -91,159 +669,4143 @@ impl<T> BloomSet<T> {
 
     #[inline]
     #[must_use]
-    pub fn as_slice(&self) -> &[T] {
+    pub const fn as_slice(&self) -> &[T] {
         unsafe { slice::from_raw_parts(self.as_mut_ptr(), self.len()) }
     }
 
-    #[inline(never)]
-    fn insert_resizing(&mut self, item: T) {
-        let mut vec = unsafe {
-            // Use ManuallyDrop to ensure that the Vec is never dropped
-            ManuallyDrop::new(Vec::from_raw_parts(
-                self.as_mut_ptr(),
-                self.len(),
+    /// Returns the unused part of the buffer, like `Vec::spare_capacity_mut`, for writing
+    /// elements in place before committing them with
+    /// [`assume_init_inserted`](BloomSet::assume_init_inserted).
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let mut set = BloomSet::with_capacity(4);
+    /// set.insert(1);
+    /// let spare = set.spare_capacity_mut();
+    /// spare[0].write(2);
+    /// spare[1].write(3);
+    /// unsafe { set.assume_init_inserted(2) };
+    /// assert_eq!(set.as_slice(), [1, 2, 3]);
+    /// assert!(set.contains(3));
+    /// ```
+    #[inline]
+    pub const fn spare_capacity_mut(&mut self) -> &mut [MaybeUninit<T>] {
+        let len = self.len();
+        unsafe {
+            slice::from_raw_parts_mut(
+                self.as_mut_ptr().add(len).cast::<MaybeUninit<T>>(),
+                self.capacity() - len,
+            )
+        }
+    }
+
+    /// Moves the elements into an allocation with room for exactly `new_capacity` of them, which
+    /// must be at least the length. A capacity of 0 frees the buffer.
+    ///
+    /// This is the only place the set allocates, and it always records `new_capacity` in the
+    /// capacity bits, so they describe the allocation exactly and `Drop` frees it with the
+    /// layout it was allocated with. Zero-sized elements never allocate, and always have the
+    /// maximum capacity.
+    fn reallocate(&mut self, new_capacity: usize) {
+        if let Err(err) = self.try_reallocate(new_capacity) {
+            handle_reserve_error(err);
+        }
+    }
+
+    /// Like [`reallocate`](BloomSet::reallocate), but returns allocation failures, leaving the
+    /// set as it was.
+    fn try_reallocate(&mut self, new_capacity: usize) -> Result<(), TryReserveError> {
+        if mem::size_of::<T>() == 0 {
+            self.capacity |= P::MAX_CAPACITY;
+            return Ok(());
+        }
+        let ptr = unsafe {
+            raw::reallocate(
+                self.ptr.cast(),
+                Layout::new::<T>(),
                 self.capacity(),
-            ))
+                new_capacity,
+            )?
         };
-        if vec.capacity() > u8::MAX as usize {
-            panic!("A BloomSet's capacity cannot exceed 255");
+        self.ptr = ptr.cast();
+        self.capacity = (new_capacity & P::COUNT_MASK) | (self.capacity & !P::COUNT_MASK);
+        Ok(())
+    }
+
+    #[inline(never)]
+    fn insert_resizing(&mut self, item: T) -> Result<(), TryReserveError> {
+        let len = self.len();
+        if len == P::MAX_CAPACITY {
+            return Err(CapacityError::new(P::MAX_CAPACITY + 1, P::MAX_CAPACITY).into());
+        }
+        self.try_reallocate(grown_capacity(self.capacity(), P::MAX_CAPACITY))?;
+        unsafe { self.as_mut_ptr().add(len).write(item) };
+        Ok(())
+    }
+
+    /// Makes room for at least `additional` more elements, so inserting that many doesn't
+    /// reallocate. Like a `Vec`, the set may reserve more than that, up to its packing's
+    /// [`MAX_CAPACITY`](Packing::MAX_CAPACITY), to make later growth cheaper.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len() + additional` is more than the packing's
+    /// [`MAX_CAPACITY`](Packing::MAX_CAPACITY) (255 by default).
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn reserve(&mut self, additional: usize) {
+        if let Err(err) = self.try_reserve(additional) {
+            handle_reserve_error(err);
+        }
+    }
+
+    /// Like [`reserve`](BloomSet::reserve), but doesn't reserve room for more than `additional`
+    /// more elements. Calling this with a fixed step whenever the set is full grows it linearly
+    /// instead of by doubling.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len() + additional` is more than the packing's
+    /// [`MAX_CAPACITY`](Packing::MAX_CAPACITY) (255 by default).
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn reserve_exact(&mut self, additional: usize) {
+        if let Err(err) = self.try_reserve_exact(additional) {
+            handle_reserve_error(err);
+        }
+    }
+
+    /// Like [`reserve`](BloomSet::reserve), but returns an error instead of panicking or
+    /// aborting. On failure the set is left as it was.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TryReserveError::Capacity`] if `len() + additional` is more than the packing's
+    /// [`MAX_CAPACITY`](Packing::MAX_CAPACITY), and [`TryReserveError::AllocFailed`] if the
+    /// allocator can't provide the buffer.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let needed = self.needed_capacity(additional)?;
+        if needed > self.capacity() {
+            self.try_reallocate(needed.max(grown_capacity(self.capacity(), P::MAX_CAPACITY)))?;
+        }
+        Ok(())
+    }
+
+    /// Like [`reserve_exact`](BloomSet::reserve_exact), but returns an error instead of
+    /// panicking or aborting. On failure the set is left as it was.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TryReserveError::Capacity`] if `len() + additional` is more than the packing's
+    /// [`MAX_CAPACITY`](Packing::MAX_CAPACITY), and [`TryReserveError::AllocFailed`] if the
+    /// allocator can't provide the buffer.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let needed = self.needed_capacity(additional)?;
+        if needed > self.capacity() {
+            self.try_reallocate(needed)?;
+        }
+        Ok(())
+    }
+
+    /// The capacity `additional` more elements need, if the packing can represent it.
+    const fn needed_capacity(&self, additional: usize) -> Result<usize, CapacityError> {
+        let needed = self.len().saturating_add(additional);
+        if needed > P::MAX_CAPACITY {
+            return Err(CapacityError::new(needed, P::MAX_CAPACITY));
+        }
+        Ok(needed)
+    }
+
+    /// Shrinks the buffer to hold exactly the elements in the set, freeing it if the set is
+    /// empty. Sets of zero-sized elements keep the maximum capacity, since they never allocate.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink_to(0);
+    }
+
+    /// Shrinks the buffer to hold `min` elements, or all the elements in the set if there are
+    /// more. Does nothing if the capacity is already at most that.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn shrink_to(&mut self, min: usize) {
+        let target = self.len().max(min);
+        if target < self.capacity() {
+            self.reallocate(target);
         }
-        vec.push(item);
-        unsafe { self.ptr = NonNull::new_unchecked(vec.as_mut_ptr()) };
-        self.capacity =
-            (vec.capacity() & 0x0000_0000_0000_00FF) | (self.capacity & 0xFFFF_FFFF_FFFF_FF00);
     }
 
+    #[cfg_attr(feature = "inline-more", inline)]
     pub fn clear(&mut self) {
-        let mut vec = unsafe {
-            // Use ManuallyDrop to ensure that the Vec is never dropped
-            ManuallyDrop::new(Vec::from_raw_parts(
-                self.as_mut_ptr(),
-                self.len(),
-                self.capacity(),
-            ))
-        };
-        // Drop all the elements
-        vec.clear();
-        // Zero the bloom filter
-        self.capacity &= 0x0000_0000_0000_00FF;
+        let elements = ptr::slice_from_raw_parts_mut(self.as_mut_ptr(), self.len());
+        // Reset the length first, so a panicking Drop impl can't lead to a double drop
         self.length = 0;
+        // Zero the bloom filter, keeping the seed
+        self.capacity &= P::SEED_MASK | P::COUNT_MASK;
+        self.moved_elements();
+        unsafe { ptr::drop_in_place(elements) };
+    }
+
+    /// Moves the elements out into a `Vec` that takes over the allocation.
+    fn into_vec(self) -> Vec<T> {
+        self.into_parts().0
+    }
+
+    /// Moves the elements out into a `Vec` that takes over the allocation, and hands back the
+    /// hasher. The allocation has the layout a `Vec` of the set's capacity would use, so the
+    /// `Vec` can free or grow it.
+    fn into_parts(self) -> (Vec<T>, S) {
+        let this = ManuallyDrop::new(self);
+        unsafe {
+            let hash_builder = ptr::read(ptr::addr_of!(this.hash_builder));
+            let vec = Vec::from_raw_parts(this.as_mut_ptr(), this.len(), this.capacity());
+            (vec, hash_builder)
+        }
+    }
+
+    /// Decomposes the set into its pointer and its length and capacity words, like
+    /// `Vec::into_raw_parts`. The hasher is dropped.
+    ///
+    /// The low [`COUNT_BITS`](Packing::COUNT_BITS) of the two words are the length and capacity;
+    /// the rest of their bits carry the filter, the count of set filter bits, the sorted flag and,
+    /// for [`Packed8`], the seed. Pass all three back to
+    /// [`from_raw_parts_with_hasher`](BloomSet::from_raw_parts_with_hasher) unchanged to
+    /// reconstitute the set.
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[must_use]
+    pub fn into_raw_parts(self) -> (NonNull<T>, usize, usize) {
+        let mut this = ManuallyDrop::new(self);
+        unsafe { ptr::drop_in_place(ptr::addr_of_mut!(this.hash_builder)) };
+        (this.ptr, this.length, this.capacity)
     }
 
+    /// Reconstitutes a set from the parts returned by [`into_raw_parts`](BloomSet::into_raw_parts),
+    /// which will use `hash_builder` to hash its elements.
+    ///
+    /// # Safety
+    ///
+    /// - `ptr`, `length` and `capacity` must have come from `into_raw_parts` on a set with the
+    ///   same `T`, `K` and `P`, and must not have been used to reconstitute a set already.
+    /// - `hash_builder` must hash elements the same way the original set's hasher did, or lookups
+    ///   for elements already in the set may fail.
     #[inline]
     #[must_use]
-    const fn bloom_contains(&self, bloom_bit: u64) -> bool {
-        if bloom_bit >= 56 {
-            let bloom = 1 << (8 + bloom_bit - 56);
-            (self.length & 0xFFFF_FFFF_FFFF_FF00 & bloom) != 0
-        } else {
-            let bloom = 1 << (8 + bloom_bit);
-            (self.capacity & 0xFFFF_FFFF_FFFF_FF00 & bloom) != 0
+    pub const unsafe fn from_raw_parts_with_hasher(
+        ptr: NonNull<T>,
+        length: usize,
+        capacity: usize,
+        hash_builder: S,
+    ) -> Self {
+        Self {
+            ptr,
+            length,
+            capacity,
+            hash_builder,
+            packing: PhantomData,
+            #[cfg(feature = "debug-checks")]
+            generation: 0,
+        }
+    }
+
+    /// The filter bits, with bit `i` standing for filter bit `i`.
+    #[inline]
+    #[must_use]
+    const fn filter_bits(&self) -> u128 {
+        unpack_filter::<P>(self.length, self.capacity)
+    }
+
+    /// Whether every bit in `mask` is set. The filter is tested as one `u128`, whichever words
+    /// its bits live in, so this compiles to shifts and a conditional move rather than a branch
+    /// on where the element's bit is.
+    #[inline]
+    #[must_use]
+    const fn bloom_contains(&self, mask: u128) -> bool {
+        self.filter_bits() & mask == mask
+    }
+
+    /// Records that elements may have changed places, so [`SetIndex`]es taken before now are
+    /// stale. Only does anything with the `debug-checks` feature.
+    #[inline]
+    #[cfg_attr(
+        not(feature = "debug-checks"),
+        allow(clippy::unused_self, clippy::needless_pass_by_ref_mut)
+    )]
+    const fn moved_elements(&mut self) {
+        #[cfg(feature = "debug-checks")]
+        {
+            self.generation = self.generation.wrapping_add(1);
         }
     }
+
+    /// Moves the element at `index` out of the set, shifting the ones after it forward. Its
+    /// filter bits stay set.
+    fn take(&mut self, index: usize) -> T {
+        let len = self.len();
+        debug_assert!(index < len);
+        let removed = unsafe {
+            let base = self.as_mut_ptr();
+            let removed = base.add(index).read();
+            ptr::copy(base.add(index + 1), base.add(index), len - index - 1);
+            removed
+        };
+        self.length -= 1;
+        self.moved_elements();
+        removed
+    }
+
+    /// Sets the filter bits in `mask`, adding the ones that weren't set yet to the count.
+    #[inline]
+    const fn set_bits(&mut self, mask: u128) {
+        let new = mask & !self.filter_bits();
+        // With one bit per element, an element adds one bit or none
+        let added = if K == 1 {
+            (new != 0) as usize
+        } else {
+            new.count_ones() as usize
+        };
+        let (low, high) = split_mask::<P>(new);
+        self.capacity |= low;
+        self.length = (self.length | high) + (added << P::POPCOUNT_SHIFT);
+    }
+
+    /// Clears the filter bits in `mask`, taking the ones that were set off the count.
+    #[inline]
+    const fn unset_bits(&mut self, mask: u128) {
+        let cleared = mask & self.filter_bits();
+        let (low, high) = split_mask::<P>(cleared);
+        self.capacity &= !low;
+        self.length =
+            (self.length & !high) - ((cleared.count_ones() as usize) << P::POPCOUNT_SHIFT);
+    }
+
+    /// How many filter bits are set, out of the packing's
+    /// [`FILTER_BITS`](Packing::FILTER_BITS).
+    ///
+    /// The length word keeps this count, updated whenever filter bits are set or cleared, so
+    /// the lookups that skip a saturated filter can ask on every call for the price of a shift.
+    #[inline]
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn filter_bits_set(&self) -> u32 {
+        ((self.length & P::POPCOUNT_MASK) >> P::POPCOUNT_SHIFT) as u32
+    }
+
+    /// The fraction of filter bits that are set, from
+    /// [`filter_bits_set`](BloomSet::filter_bits_set). Once this nears 1, almost every lookup
+    /// has to scan the elements.
+    #[inline]
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub const fn saturation(&self) -> f64 {
+        self.filter_bits_set() as f64 / P::FILTER_BITS as f64
+    }
 }
 
 impl<T: Hash + PartialEq> BloomSet<T> {
-    #[inline]
-    pub fn insert(&mut self, item: T) {
-        let mut hasher = BloomHasher { state: 0 };
-        item.hash(&mut hasher);
-        let hash = hasher.finish();
-        let mut bloom_bit = hash;
-        if bloom_bit >= 224 {
-            bloom_bit -= 224;
-        } else if bloom_bit >= 112 {
-            bloom_bit -= 112;
-        }
-
-        let maybe_in_set = if bloom_bit >= 56 {
-            let bloom = 1 << (8 + bloom_bit - 56);
-            if (self.length & 0xFFFF_FFFF_FFFF_FF00 & bloom) != 0 {
-                true
-            } else {
-                self.length |= bloom;
-                false
+    /// Builds a set from `iter`, failing instead of panicking if it yields more than 255 unique
+    /// elements.
+    ///
+    /// Duplicates do not count towards the limit. On failure the error's
+    /// [`requested`](CapacityError::requested) is 256: the iterator had already produced 255
+    /// unique elements when another one arrived.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CapacityError`] if the iterator yields a 256th unique element.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn try_from_iter<I: IntoIterator<Item = T>>(iter: I) -> Result<Self, CapacityError> {
+        Self::try_from_iter_with_hasher(iter, BuildBloomHasher)
+    }
+}
+
+impl<'a, T: Hash + PartialEq> BloomSet<&'a T> {
+    /// Builds a set of references to the elements of `elements`, so large elements can be
+    /// deduplicated without cloning them. Elements are hashed and compared through the
+    /// references, and the source can't be changed or dropped while the set exists.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let names = [String::from("a"), String::from("b"), String::from("a")];
+    /// let set = BloomSet::from_refs(&names);
+    /// assert_eq!(set.len(), 2);
+    /// assert!(set.contains(&names[2]));
+    /// ```
+    ///
+    /// The set borrows the source, so this doesn't compile:
+    ///
+    /// ```compile_fail
+    /// use bloomset::BloomSet;
+    ///
+    /// let mut names = vec![String::from("a")];
+    /// let set = BloomSet::from_refs(&names);
+    /// names.clear();
+    /// assert!(set.is_empty());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `elements` holds more than 255 unique elements.
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[must_use]
+    pub fn from_refs(elements: &'a [T]) -> Self {
+        elements.iter().collect()
+    }
+
+    /// Returns the indices of the elements of `elements` that are equal to an earlier one, in
+    /// ascending order.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// assert_eq!(BloomSet::duplicate_indices(&["x", "y", "x", "x", "z"]), [2, 3]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `elements` holds more than 255 unique elements.
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[must_use]
+    pub fn duplicate_indices(elements: &'a [T]) -> Vec<usize> {
+        let mut seen = Self::new();
+        let mut duplicates = Vec::new();
+        for (i, item) in elements.iter().enumerate() {
+            let len = seen.len();
+            seen.insert(item);
+            if seen.len() == len {
+                duplicates.push(i);
             }
-        } else {
-            let bloom = 1 << (8 + bloom_bit);
-            if (self.capacity & 0xFFFF_FFFF_FFFF_FF00 & bloom) != 0 {
-                true
-            } else {
-                self.capacity |= bloom;
-                false
+        }
+        duplicates
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T>, const K: usize, P: Packing> BloomSet<T, S, K, P> {
+    /// Like [`try_from_iter`](BloomSet::try_from_iter), but the set will use `hash_builder` to
+    /// hash its elements.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CapacityError`] if the iterator yields more unique elements than the set can
+    /// hold.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn try_from_iter_with_hasher<I: IntoIterator<Item = T>>(
+        iter: I,
+        hash_builder: S,
+    ) -> Result<Self, CapacityError> {
+        let iter = iter.into_iter();
+        let mut set =
+            Self::with_capacity_and_hasher(iter.size_hint().0.min(P::MAX_CAPACITY), hash_builder);
+        set.try_extend(iter)?;
+        Ok(set)
+    }
+
+    /// Builds a set from `iter`, trusting it not to yield any element twice, so each one is
+    /// appended and its filter bits set without comparing it against the others. For input
+    /// that is already deduplicated, like the keys of a map, this skips the scans that
+    /// collecting would do.
+    ///
+    /// Debug builds still check the promise, and panic if it is broken. In release builds a
+    /// duplicate is kept as a second element; that is a logic error rather than undefined
+    /// behavior, but `len` counts it and removing one copy leaves the other.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    /// use std::collections::BTreeMap;
+    ///
+    /// let totals: BTreeMap<&str, u32> = [("eu", 7), ("us", 3)].iter().copied().collect();
+    /// let regions: BloomSet<&str> = BloomSet::from_unique_iter(totals.keys().copied());
+    /// assert_eq!(regions.as_slice(), ["eu", "us"]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the iterator yields more elements than the set can hold (255 by default), and
+    /// in debug builds if it yields one twice.
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[must_use]
+    pub fn from_unique_iter<I: IntoIterator<Item = T>>(iter: I) -> Self
+    where
+        S: Default,
+    {
+        let iter = iter.into_iter();
+        let mut set =
+            Self::with_capacity_and_hasher(iter.size_hint().0.min(P::MAX_CAPACITY), S::default());
+        for item in iter {
+            debug_assert!(
+                !scan(set.as_slice(), &item),
+                "from_unique_iter was given a duplicate"
+            );
+            set.push_unique(item);
+        }
+        set
+    }
+
+    /// Builds a [sorted](BloomSet::is_sorted) set from a copy of `elements`, which must be in
+    /// strictly ascending order. That is checked while copying, a comparison per element, and
+    /// rules out duplicates, so nothing else is compared.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    /// use std::collections::BTreeSet;
+    ///
+    /// let ids: BTreeSet<u32> = [40, 2, 17].iter().copied().collect();
+    /// let ids: Vec<u32> = ids.into_iter().collect();
+    /// let set: BloomSet<u32> = BloomSet::from_sorted_slice(&ids);
+    /// assert!(set.is_sorted() && set.contains_sorted(17));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `elements` isn't strictly ascending, or holds more elements than the set can
+    /// (255 by default).
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[must_use]
+    pub fn from_sorted_slice(elements: &[T]) -> Self
+    where
+        T: Ord + Clone,
+        S: Default,
+    {
+        let mut set = Self::with_capacity_and_hasher(elements.len(), S::default());
+        for (i, item) in elements.iter().enumerate() {
+            if let Some(previous) = i.checked_sub(1).map(|j| &elements[j]) {
+                assert!(
+                    previous < item,
+                    "from_sorted_slice was given elements out of order at index {}",
+                    i
+                );
             }
+            set.push_unique(item.clone());
+        }
+        set.length |= P::SORTED_FLAG;
+        set
+    }
+
+    /// Appends `item`, trusted not to be in the set, panicking if there is no room for it.
+    fn push_unique(&mut self, item: T) {
+        let mask = self.bloom_mask_of(&item);
+        if let Err(err) = self.push_new(item, mask) {
+            handle_reserve_error(err);
+        }
+    }
+
+    /// The elements every one of `sets` holds, in the order the smallest of them holds them.
+    ///
+    /// Only the smallest set is walked; each of its elements is looked up in the others until
+    /// one lacks it, which their filters usually settle without scanning. So there are no
+    /// intermediate sets, and an empty set among the inputs makes the whole call free. An empty
+    /// slice gives an empty set, not every possible element.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let layers: Vec<BloomSet<&str>> = vec![
+    ///     ["read", "write", "exec"].iter().copied().collect(),
+    ///     ["exec", "read"].iter().copied().collect(),
+    ///     ["read", "admin", "exec"].iter().copied().collect(),
+    /// ];
+    /// let granted = BloomSet::intersect_all(&layers.iter().collect::<Vec<_>>());
+    /// assert_eq!(granted.as_slice(), ["exec", "read"]);
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[must_use]
+    pub fn intersect_all(sets: &[&Self]) -> Self
+    where
+        T: Clone,
+        S: Default,
+    {
+        let mut result = Self::with_hasher(S::default());
+        let Some(smallest) = sets.iter().min_by_key(|set| set.len()) else {
+            return result;
         };
+        for item in smallest.as_slice() {
+            if sets
+                .iter()
+                .all(|set| ptr::eq(*set, *smallest) || set.contains(item))
+            {
+                // The smallest set's elements are distinct, and it can't hold more than fits
+                result.insert(item.clone());
+            }
+        }
+        result
+    }
 
-        let in_set = if maybe_in_set {
-            self.as_slice().iter().any(|it| *it == item)
+    /// How many elements this set and `other` both hold, without building the intersection.
+    ///
+    /// The smaller of the two is walked and each of its elements looked up in the larger, so
+    /// most of the misses are settled by its filter.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let a: BloomSet<u32> = (0..10).collect();
+    /// let b: BloomSet<u32> = (5..20).collect();
+    /// let jaccard = a.intersection_len(&b) as f64 / (a.len() + b.difference_len(&a)) as f64;
+    /// assert_eq!(jaccard, 0.25);
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[must_use]
+    pub fn intersection_len(&self, other: &Self) -> usize {
+        let (smaller, larger) = if self.len() <= other.len() {
+            (self, other)
         } else {
-            false
+            (other, self)
         };
-        if !in_set {
-            if self.len() == self.capacity() {
-                self.insert_resizing(item);
-            } else {
-                unsafe {
-                    use std::convert::TryInto;
-                    *self.ptr.as_ptr().offset(self.len().try_into().unwrap()) = item;
-                }
+        smaller
+            .as_slice()
+            .iter()
+            .filter(|item| larger.contains(*item))
+            .count()
+    }
+
+    /// How many of this set's elements `other` doesn't hold. That is the length minus the
+    /// [`intersection_len`](BloomSet::intersection_len), so it walks the smaller set too.
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[must_use]
+    pub fn difference_len(&self, other: &Self) -> usize {
+        self.len() - self.intersection_len(other)
+    }
+
+    /// Whether this set and `other` have at least `k` elements in common. Stops as soon as it
+    /// has found `k` of them, or as soon as too few elements are left to look up to get there.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let a: BloomSet<u32> = (0..100).collect();
+    /// let b: BloomSet<u32> = (90..200).collect();
+    /// assert!(a.intersection_at_least(&b, 10));
+    /// assert!(!a.intersection_at_least(&b, 11));
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[must_use]
+    pub fn intersection_at_least(&self, other: &Self, k: usize) -> bool {
+        let (smaller, larger) = if self.len() <= other.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        if k > smaller.len() {
+            return false;
+        }
+        let mut needed = k;
+        for (i, item) in smaller.as_slice().iter().enumerate() {
+            if needed == 0 || smaller.len() - i < needed {
+                break;
+            }
+            if larger.contains(item) {
+                needed -= 1;
             }
-            self.length += 1;
         }
+        needed == 0
     }
 
-    #[inline]
-    pub fn contains<B: std::borrow::Borrow<T>>(&self, item: B) -> bool {
-        let item = item.borrow();
-        let mut hasher = BloomHasher { state: 0 };
-        item.hash(&mut hasher);
-        let hash = hasher.finish();
-        let bloom_bit = hash % 112;
-
-        let maybe_in_set = self.bloom_contains(bloom_bit);
-        if maybe_in_set {
-            self.as_slice().iter().any(|it| it == item)
-        } else {
-            false
+    /// The Jaccard similarity of this set and `other`: how many elements they have in common,
+    /// out of how many they hold between them. That is 1 for equal sets and 0 for disjoint
+    /// ones, and by convention 1 for two empty sets.
+    ///
+    /// Like [`intersection_len`](BloomSet::intersection_len), it walks the smaller set once.
+    /// [`estimated_jaccard`](BloomSet::estimated_jaccard) is a cheap screen to try first.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let a: BloomSet<u32> = (0..10).collect();
+    /// let b: BloomSet<u32> = (5..20).collect();
+    /// assert_eq!(a.jaccard_similarity(&b), 0.25);
+    /// assert_eq!(BloomSet::<u32>::new().jaccard_similarity(&BloomSet::new()), 1.0);
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn jaccard_similarity(&self, other: &Self) -> f64 {
+        let common = self.intersection_len(other);
+        let union = self.len() + other.len() - common;
+        if union == 0 {
+            return 1.0;
         }
+        common as f64 / union as f64
     }
-}
 
-impl<T> Drop for BloomSet<T> {
+    /// An estimate of the [`jaccard_similarity`](BloomSet::jaccard_similarity) from the
+    /// filters alone: the bits both have set, out of the bits either has. Two empty sets give
+    /// 1, and an empty set and a non-empty one 0, like the exact value.
+    ///
+    /// Elements of one set that share a bit with different elements of the other count as
+    /// common, so the estimate runs high, more so the fuller the filters are; bits left behind
+    /// by removed elements skew it too. It is meant for picking out the pairs worth the exact
+    /// computation, with a threshold somewhat below the one that matters. Sets whose filters
+    /// can't be compared, because they have different seeds or hashers with state, always get
+    /// 1, so they always pass such a screen.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let a: BloomSet<u32> = (0..6).collect();
+    /// let b: BloomSet<u32> = (3..9).collect();
+    /// let estimate = a.estimated_jaccard(&b);
+    /// # #[cfg(not(feature = "no-filter"))]
+    /// assert!(estimate > 0.0 && estimate <= 1.0);
+    /// assert_eq!(a.estimated_jaccard(&a), 1.0);
+    /// ```
     #[inline]
-    fn drop(&mut self) {
-        unsafe { Vec::from_raw_parts(self.as_mut_ptr(), self.len(), self.capacity()) };
+    #[must_use]
+    pub fn estimated_jaccard(&self, other: &Self) -> f64 {
+        match (self.is_empty(), other.is_empty()) {
+            (true, true) => return 1.0,
+            (true, false) | (false, true) => return 0.0,
+            (false, false) => {}
+        }
+        if !self.filters_comparable(other) {
+            return 1.0;
+        }
+        let (a, b) = (self.filter_bits(), other.filter_bits());
+        f64::from((a & b).count_ones()) / f64::from((a | b).count_ones())
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
-    #[test]
-    fn size_is_right() {
-        use core::mem::size_of;
-        assert_eq!(size_of::<BloomSet<u8>>(), size_of::<Vec<u8>>());
+    /// Whether this set's filter bits mean the same as `other`'s, so that comparing them says
+    /// something about the elements. That takes the same seed and a hasher without state, like
+    /// the default one: two `RandomState`s, say, put the same element on different bits.
+    const fn filters_comparable(&self, other: &Self) -> bool {
+        !cfg!(feature = "no-filter") && mem::size_of::<S>() == 0 && self.seed() == other.seed()
     }
 
-    #[test]
+    /// A screen for [`is_subset`](BloomSet::is_subset) that doesn't compare any elements:
+    /// `false` means this set is definitely not a subset of `other`, `true` only that it may
+    /// be.
+    ///
+    /// Usually this just checks that `other`'s filter has every bit this set's does, which
+    /// takes no time at all. Removing an element leaves its bits set, though, so if that check
+    /// fails this set's bits are worked out again from the elements it still holds, hashing
+    /// each once. Sets whose filters can't be compared, because they have different seeds or
+    /// hashers with state, always pass.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let small: BloomSet<u32> = (0..3).collect();
+    /// let large: BloomSet<u32> = (0..50).collect();
+    /// let others: BloomSet<u32> = (100..150).collect();
+    /// assert!(small.maybe_subset_of(&large));
+    /// # #[cfg(not(any(feature = "no-filter", feature = "random-seed")))]
+    /// assert!(!small.maybe_subset_of(&others));
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[must_use]
+    pub fn maybe_subset_of(&self, other: &Self) -> bool {
+        if self.len() > other.len() {
+            return false;
+        }
+        if self.is_empty() || !self.filters_comparable(other) {
+            return true;
+        }
+        let missing = self.filter_bits() & !other.filter_bits();
+        missing == 0
+            || self
+                .as_slice()
+                .iter()
+                .all(|item| self.bloom_mask_of(item) & missing == 0)
+    }
+
+    /// A screen for [`is_disjoint`](BloomSet::is_disjoint) that only looks at the filters:
+    /// `false` means the sets definitely have no element in common, `true` only that they may.
+    ///
+    /// Two sets with a common element have all of its bits set in both filters, so filters
+    /// that share no bit rule that out. Sets whose filters can't be compared, because they
+    /// have different seeds or hashers with state, always pass unless one is empty.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let a: BloomSet<u32> = [1, 2].iter().copied().collect();
+    /// let b: BloomSet<u32> = [2, 3].iter().copied().collect();
+    /// assert!(a.maybe_intersects(&b));
+    /// assert!(!a.maybe_intersects(&BloomSet::new()));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn maybe_intersects(&self, other: &Self) -> bool {
+        if self.is_empty() || other.is_empty() {
+            return false;
+        }
+        !self.filters_comparable(other) || self.filter_bits() & other.filter_bits() != 0
+    }
+
+    /// Whether `other` holds every element of this set.
+    ///
+    /// This tries [`maybe_subset_of`](BloomSet::maybe_subset_of) first, so most sets that
+    /// aren't subsets are ruled out without looking any element up in `other`.
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[must_use]
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.maybe_subset_of(other) && self.as_slice().iter().all(|item| other.contains(item))
+    }
+
+    /// Whether this set and `other` have no element in common.
+    ///
+    /// This tries [`maybe_intersects`](BloomSet::maybe_intersects) first, so sets whose filters
+    /// share no bit are settled without looking any element up.
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[must_use]
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        !self.maybe_intersects(other) || !self.intersection_at_least(other, 1)
+    }
+
+    /// Like [`extend`](Extend::extend), returning how many of the items were new to the set.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let mut seen: BloomSet<&str> = BloomSet::new();
+    /// assert_eq!(seen.extend_count(["a", "b", "a"]), 2);
+    /// assert_eq!(seen.extend_count(["b", "c"]), 1);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics like `extend` if the set would need to hold more unique elements than it can.
+    /// The new elements inserted before that stay in the set.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn extend_count<I: IntoIterator<Item = T>>(&mut self, iter: I) -> usize {
+        let len = self.len();
+        self.extend(iter);
+        self.len() - len
+    }
+
+    /// Inserts everything `iter` yields, in bulk if it promises at least the
+    /// [bulk threshold](bulk_threshold) of elements. Like inserting one at a time, this only
+    /// fails once a new element arrives for a full set.
+    fn try_extend<I: Iterator<Item = T>>(&mut self, mut iter: I) -> Result<(), CapacityError> {
+        if iter.size_hint().0 >= bulk_threshold::<T>() {
+            self.extend_bulk(&mut iter);
+        }
+        for item in iter {
+            self.insert_within_limit(item)?;
+        }
+        Ok(())
+    }
+
+    /// Adds elements from `iter` in batches as big as the room left in the set. Each batch is
+    /// written to the spare capacity, deduplicated by sorting the hashes of the set's elements
+    /// and the batch's, and only then counted as part of the set. Stops once `iter` runs dry or
+    /// the room left is below the [bulk threshold](bulk_threshold), leaving the rest to be
+    /// inserted one at a time.
+    ///
+    /// If hashing or comparing panics, the set keeps the elements it had before the batch, and
+    /// the batch is leaked.
+    fn extend_bulk<I: Iterator<Item = T>>(&mut self, iter: &mut I) {
+        // Sized for the batch up front, so only an iterator that underestimates grows it
+        let batch = iter.size_hint().0.min(P::MAX_CAPACITY - self.len());
+        let mut hashes: Vec<u64> = Vec::with_capacity(self.len() + batch);
+        hashes.extend(
+            self.as_slice()
+                .iter()
+                .map(|item| self.hash_builder.hash_element(item)),
+        );
+        loop {
+            let len = self.len();
+            let room = P::MAX_CAPACITY - len;
+            if room < bulk_threshold::<T>() {
+                return;
+            }
+            let mut n = 0;
+            for item in iter.by_ref().take(room) {
+                hashes.push(self.hash_builder.hash_element(&item));
+                if len + n == self.capacity() {
+                    // realloc keeps the batch written so far, though the length doesn't cover it
+                    self.reallocate(grown_capacity(len + n, P::MAX_CAPACITY));
+                }
+                unsafe { self.as_mut_ptr().add(len + n).write(item) };
+                n += 1;
+            }
+
+            // Among elements with equal hashes, compare each new one with those before it
+            let elements = unsafe { slice::from_raw_parts(self.as_mut_ptr(), len + n) };
+            let duplicate =
+                raw::mark_duplicates(&hashes, len, &mut |i, j| elements[i] == elements[j]);
+
+            // Move the new elements to the front of the batch, keeping their order
+            let base = unsafe { self.as_mut_ptr().add(len) };
+            let kept = unsafe {
+                raw::compact(
+                    base.cast(),
+                    mem::size_of::<T>(),
+                    &mut hashes[len..],
+                    &duplicate,
+                )
+            };
+            hashes.truncate(len + kept);
+            let mask = hashes[len..].iter().fold(0, |mask, &hash| {
+                mask | bloom_mask::<K>(seeded(hash, self.seed()), P::FILTER_BITS)
+            });
+            if kept > 0 {
+                self.set_bits(mask);
+                self.length = (self.length + kept) & !P::SORTED_FLAG;
+            }
+            unsafe { ptr::drop_in_place(ptr::slice_from_raw_parts_mut(base.add(kept), n - kept)) };
+            if n < room {
+                return;
+            }
+        }
+    }
+
+    /// Takes the first `n` slots of the [spare capacity](BloomSet::spare_capacity_mut) as
+    /// elements, setting their filter bits as `insert` would.
+    ///
+    /// # Safety
+    ///
+    /// - `n` must be at most `capacity() - len()`.
+    /// - The first `n` slots of the spare capacity must have been initialized.
+    /// - Those elements must differ from each other and from the elements already in the set.
+    ///
+    /// If hashing one of the new elements panics, none of them are taken, and they are leaked
+    /// rather than dropped.
+    #[inline]
+    pub unsafe fn assume_init_inserted(&mut self, n: usize) {
+        debug_assert!(n <= self.capacity() - self.len());
+        // Hash everything before changing the length, so a panic leaves the set as it was
+        let new = slice::from_raw_parts(self.as_mut_ptr().add(self.len()), n);
+        let mask = new
+            .iter()
+            .fold(0, |mask, item| mask | self.bloom_mask_of(item));
+        self.set_bits(mask);
+        self.length = (self.length + n) & !P::SORTED_FLAG;
+    }
+
+    #[inline]
+    fn bloom_mask_of(&self, item: &T) -> u128 {
+        let hash = seeded(self.hash_builder.hash_element(item), self.seed());
+        bloom_mask::<K>(hash, P::FILTER_BITS)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the set would need to grow beyond its packing's
+    /// [`MAX_CAPACITY`](Packing::MAX_CAPACITY) (255 by default).
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn insert(&mut self, item: T) {
+        if let Err(err) = self.try_insert(item) {
+            handle_reserve_error(err);
+        }
+    }
+
+    /// Like [`insert`](BloomSet::insert), but returns an error instead of panicking or aborting.
+    /// On failure the set is left as it was and `item` is dropped, as it is if hashing or
+    /// comparing panics.
+    ///
+    /// Nothing here panics unless the element's `Hash`, `PartialEq` or `Drop` do, so together
+    /// with [`try_reserve`](BloomSet::try_reserve) and [`BloomSet::new`] this is enough for code
+    /// that must not be able to panic. `examples/no_panic.rs` checks that the compiler agrees.
+    ///
+    /// ```
+    /// use bloomset::{BloomSet, TryReserveError};
+    ///
+    /// let mut set: BloomSet<u32> = (0..255).collect();
+    /// assert_eq!(set.try_insert(7), Ok(()));
+    /// assert!(matches!(set.try_insert(255), Err(TryReserveError::Capacity(_))));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TryReserveError::Capacity`] if `item` is new and the set already holds its
+    /// packing's [`MAX_CAPACITY`](Packing::MAX_CAPACITY) elements, and
+    /// [`TryReserveError::AllocFailed`] if the allocator can't provide a bigger buffer.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn try_insert(&mut self, item: T) -> Result<(), TryReserveError> {
+        let mask = self.bloom_mask_of(&item);
+        let passed = self.bloom_contains(mask);
+        #[cfg(feature = "stats")]
+        self.record_lookup(&item, passed);
+        let duplicate = passed && scan(self.as_slice(), &item);
+        #[cfg(feature = "paranoid")]
+        self.check_lookup(&item, duplicate);
+        if duplicate {
+            return Ok(());
+        }
+        self.push_new(item, mask)
+    }
+
+    /// Appends `item`, known not to be in the set, and sets the filter bits in its `mask`.
+    fn push_new(&mut self, item: T, mask: u128) -> Result<(), TryReserveError> {
+        if self.len() == self.capacity() {
+            self.insert_resizing(item)?;
+        } else {
+            // The slot is uninitialized, so write without dropping what's there
+            unsafe { self.as_mut_ptr().add(self.len()).write(item) };
+        }
+        // Only set the element's bits once it is in, so a failed insert leaves the filter alone
+        self.set_bits(mask);
+        self.length = (self.length + 1) & !P::SORTED_FLAG;
+        Ok(())
+    }
+
+    /// Inserts `item` if the set doesn't hold an equal element yet, and otherwise hands it back
+    /// in an error along with where that element is, for sets in which a duplicate is a bug.
+    /// The set is only searched once either way.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let mut commands: BloomSet<&str> = BloomSet::new();
+    /// commands.insert_new("build").unwrap();
+    /// commands.insert_new("test").unwrap();
+    /// let err = commands.insert_new("build").unwrap_err();
+    /// assert_eq!((err.index(), err.into_inner()), (0, "build"));
+    /// assert_eq!(commands.len(), 2);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`DuplicateError`] holding `item` if an equal element is already present.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `item` is new and the set already holds its packing's
+    /// [`MAX_CAPACITY`](Packing::MAX_CAPACITY) elements, like [`insert`](BloomSet::insert).
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn insert_new(&mut self, item: T) -> Result<(), DuplicateError<T>> {
+        let (index, mask) = self.position_and_mask(&item);
+        if let Some(index) = index {
+            return Err(DuplicateError::new(item, index));
+        }
+        if let Err(err) = self.push_new(item, mask) {
+            handle_reserve_error(err);
+        }
+        Ok(())
+    }
+
+    /// Inserts `item` if the set doesn't hold an equal element yet, and otherwise folds it into
+    /// that element with `merge(stored, item)`, returning whether it was merged.
+    ///
+    /// `merge` must leave the stored element equal to what it was, with the same hash; it is
+    /// for the fields that aren't compared, like a count or a list of sources. Debug builds
+    /// check the hash afterwards. The set is only searched once either way.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    /// use std::hash::{Hash, Hasher};
+    ///
+    /// struct Hits { path: &'static str, count: u32 }
+    /// impl PartialEq for Hits {
+    ///     fn eq(&self, other: &Self) -> bool { self.path == other.path }
+    /// }
+    /// impl Hash for Hits {
+    ///     fn hash<H: Hasher>(&self, state: &mut H) { self.path.hash(state) }
+    /// }
+    ///
+    /// let mut hits = BloomSet::new();
+    /// for path in ["/", "/about", "/"] {
+    ///     hits.update_or_insert(Hits { path, count: 1 }, |stored, new| stored.count += new.count);
+    /// }
+    /// let counts: Vec<_> = hits.as_slice().iter().map(|h| (h.path, h.count)).collect();
+    /// assert_eq!(counts, [("/", 2), ("/about", 1)]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `item` is new and the set already holds its packing's
+    /// [`MAX_CAPACITY`](Packing::MAX_CAPACITY) elements, like [`insert`](BloomSet::insert), and
+    /// in debug builds if `merge` changes the stored element's hash.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn update_or_insert<F: FnOnce(&mut T, T)>(&mut self, item: T, merge: F) -> bool {
+        let (index, mask) = self.position_and_mask(&item);
+        let Some(index) = index else {
+            if let Err(err) = self.push_new(item, mask) {
+                handle_reserve_error(err);
+            }
+            return false;
+        };
+        let stored = unsafe { &mut *self.as_mut_ptr().add(index) };
+        #[cfg(debug_assertions)]
+        let hash = self.hash_builder.hash_element(stored);
+        merge(stored, item);
+        #[cfg(debug_assertions)]
+        assert!(
+            self.hash_builder.hash_element(stored) == hash,
+            "update_or_insert's merge changed the hash of the stored element"
+        );
+        true
+    }
+
+    /// Where `item` is, if it is in the set, along with its filter bits for inserting it if it
+    /// isn't.
+    #[inline]
+    fn position_and_mask(&self, item: &T) -> (Option<usize>, u128) {
+        let mask = self.bloom_mask_of(item);
+        let passed = self.bloom_contains(mask);
+        #[cfg(feature = "stats")]
+        self.record_lookup(item, passed);
+        let index = if passed {
+            scan_position(self.as_slice(), item)
+        } else {
+            None
+        };
+        #[cfg(feature = "paranoid")]
+        self.check_lookup(item, index.is_some());
+        (index, mask)
+    }
+
+    /// Moves the elements of `batch` that are new into the set, in order, and drops the ones
+    /// already in it, leaving `batch` empty with its capacity intact for the next batch.
+    ///
+    /// Each element is moved once, straight into the set, or not at all if it is a duplicate.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let mut seen: BloomSet<u32> = BloomSet::new();
+    /// let mut batch = vec![3, 1, 3, 2];
+    /// seen.absorb(&mut batch)?;
+    /// batch.extend([2, 4]);
+    /// seen.absorb(&mut batch)?;
+    /// assert_eq!(seen.as_slice(), [3, 1, 2, 4]);
+    /// assert!(batch.is_empty() && batch.capacity() >= 4);
+    /// # Ok::<(), bloomset::CapacityError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CapacityError`] when a new element arrives once the set holds its packing's
+    /// [`MAX_CAPACITY`](Packing::MAX_CAPACITY) elements. Everything before that element has
+    /// been absorbed or dropped by then; it and the rest are left in `batch`, in order. The same
+    /// holds if hashing or comparing panics, except that the element being looked at stays in
+    /// `batch` too.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn absorb(&mut self, batch: &mut Vec<T>) -> Result<(), CapacityError> {
+        /// Moves the elements from `done` on to the front of the vector when dropped, so
+        /// whatever wasn't absorbed stays in it, however the loop ends.
+        struct Rest<'a, T> {
+            batch: &'a mut Vec<T>,
+            done: usize,
+            len: usize,
+        }
+
+        impl<T> Drop for Rest<'_, T> {
+            fn drop(&mut self) {
+                let rest = self.len - self.done;
+                unsafe {
+                    let base = self.batch.as_mut_ptr();
+                    ptr::copy(base.add(self.done), base, rest);
+                    self.batch.set_len(rest);
+                }
+            }
+        }
+
+        let len = batch.len();
+        // Until the guard puts them back, the elements belong to this loop
+        unsafe { batch.set_len(0) };
+        let mut rest = Rest {
+            batch,
+            done: 0,
+            len,
+        };
+        let base = rest.batch.as_mut_ptr();
+        while rest.done < len {
+            let slot = unsafe { base.add(rest.done) };
+            let (index, mask) = self.position_and_mask(unsafe { &*slot });
+            if index.is_none() && self.len() == P::MAX_CAPACITY {
+                return Err(CapacityError::new(P::MAX_CAPACITY + 1, P::MAX_CAPACITY));
+            }
+            // Counted as done first, so a panicking drop doesn't leave it to be dropped again
+            rest.done += 1;
+            if index.is_some() {
+                unsafe { ptr::drop_in_place(slot) };
+            } else if let Err(err) = self.push_new(unsafe { slot.read() }, mask) {
+                handle_reserve_error(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Like [`try_insert`](BloomSet::try_insert), but aborts like `insert` if allocating fails,
+    /// for the callers that only report a full set.
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn insert_within_limit(&mut self, item: T) -> Result<(), CapacityError> {
+        match self.try_insert(item) {
+            Ok(()) => Ok(()),
+            Err(TryReserveError::Capacity(err)) => Err(err),
+            Err(err) => handle_reserve_error(err),
+        }
+    }
+
+    /// Removes `item` from the set, returning whether it was present. The remaining elements
+    /// keep their order.
+    ///
+    /// The filter bits `item` set are left in place, since other elements may share them; use a
+    /// [`CountingBloomSet`] if the set sees a lot of churn.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn remove<B: core::borrow::Borrow<T>>(&mut self, item: B) -> bool {
+        // The lookup is the one contains does, paranoid check and all
+        let Some(index) = self.position(item.borrow()) else {
+            return false;
+        };
+        drop(self.take(index));
+        true
+    }
+
+    /// Keeps only the elements for which `f` returns `true`, like `Vec::retain`. Each element
+    /// is visited once, in order, and the ones kept keep their order, so a
+    /// [sorted](BloomSet::is_sorted) set stays sorted.
+    ///
+    /// Unlike [`remove`](BloomSet::remove), this rebuilds the filter from the elements that are
+    /// left, which clears the bits only removed elements had set, at the cost of hashing each of
+    /// them. If `f` or dropping an element panics, the set is left empty and the elements not
+    /// yet dropped are leaked.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let mut set: BloomSet<u32> = (0..10).collect();
+    /// set.retain(|&i| i % 3 == 0);
+    /// assert_eq!(set.as_slice(), [0, 3, 6, 9]);
+    /// assert!(!set.contains(4));
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let len = self.take_for_retain();
+        let base = self.as_mut_ptr();
+        let mut kept = 0;
+        for i in 0..len {
+            unsafe {
+                let item = base.add(i);
+                if f(&*item) {
+                    if kept != i {
+                        ptr::copy_nonoverlapping(item, base.add(kept), 1);
+                    }
+                    kept += 1;
+                } else {
+                    ptr::drop_in_place(item);
+                }
+            }
+        }
+        self.finish_retain(kept, false);
+    }
+
+    /// Like [`retain`](BloomSet::retain), but fills the gap each removed element leaves with the
+    /// last element that is kept, so only elements from the end move, rather than every element
+    /// after the first gap. Each element is still visited exactly once, though not in order, and
+    /// removing any clears the [sorted](BloomSet::is_sorted) mark.
+    ///
+    /// Both are a single pass, so this only saves moves, which matters for large elements or
+    /// when few are removed.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let mut set: BloomSet<u32> = (0..10).collect();
+    /// set.retain_unordered(|&i| i % 3 == 0);
+    /// assert_eq!(set.as_slice(), [0, 9, 6, 3]);
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn retain_unordered<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let len = self.take_for_retain();
+        let base = self.as_mut_ptr();
+        let mut end = len;
+        let mut i = 0;
+        while i < end {
+            unsafe {
+                if f(&*base.add(i)) {
+                    i += 1;
+                    continue;
+                }
+                ptr::drop_in_place(base.add(i));
+                // Fill the hole with the last element that is kept, dropping the ones after it
+                loop {
+                    end -= 1;
+                    if end == i {
+                        break;
+                    }
+                    let last = base.add(end);
+                    if f(&*last) {
+                        ptr::copy_nonoverlapping(last, base.add(i), 1);
+                        i += 1;
+                        break;
+                    }
+                    ptr::drop_in_place(last);
+                }
+            }
+        }
+        self.finish_retain(end, end < len);
+    }
+
+    /// Sets the length to 0 for the duration of a retain, so that a panic leaks the elements
+    /// instead of leaving dropped ones in the set, and returns the length it had.
+    #[inline]
+    const fn take_for_retain(&mut self) -> usize {
+        let len = self.len();
+        self.length &= !P::COUNT_MASK;
+        len
+    }
+
+    /// Ends a retain that kept the first `kept` elements, replacing the filter with one built
+    /// from them alone.
+    #[inline]
+    fn finish_retain(&mut self, kept: usize, reordered: bool) {
+        let sorted = if reordered {
+            0
+        } else {
+            self.length & P::SORTED_FLAG
+        };
+        self.length = sorted | kept;
+        self.rebuild_filter();
+        self.moved_elements();
+    }
+
+    /// Replaces the filter with one built from the elements the set holds now, clearing the
+    /// bits that only removed elements had set. This hashes every element; see
+    /// [`RebuildingBloomSet`] for a set that does it by itself after enough removals.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let mut set: BloomSet<u32> = (0..100).collect();
+    /// for i in 10..100 {
+    ///     set.remove(i);
+    /// }
+    /// let saturation = set.saturation();
+    /// set.rebuild_filter();
+    /// assert!(set.saturation() <= saturation);
+    /// assert!((0..10).all(|i| set.contains(i)));
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn rebuild_filter(&mut self) {
+        let mask = self
+            .as_slice()
+            .iter()
+            .fold(0, |mask, item| mask | self.bloom_mask_of(item));
+        let (low, high) = split_mask::<P>(mask);
+        self.capacity = (self.capacity & (P::SEED_MASK | P::COUNT_MASK)) | low;
+        self.length = (self.length & (P::SORTED_FLAG | P::COUNT_MASK))
+            | high
+            | ((mask.count_ones() as usize) << P::POPCOUNT_SHIFT);
+    }
+
+    /// Whether `item` gets past the filter, skipping it once it is saturated.
+    #[inline]
+    fn maybe_contains(&self, item: &T) -> bool {
+        self.filter_bits_set() >= P::SATURATION_THRESHOLD
+            || self.bloom_contains(self.bloom_mask_of(item))
+    }
+
+    #[inline]
+    pub fn contains<B: core::borrow::Borrow<T>>(&self, item: B) -> bool {
+        let item = item.borrow();
+        let passed = self.maybe_contains(item);
+        #[cfg(feature = "stats")]
+        self.record_lookup(item, passed);
+        let found = passed && scan(self.as_slice(), item);
+        #[cfg(feature = "paranoid")]
+        self.check_lookup(item, found);
+        found
+    }
+
+    /// Returns the element equal to `item`, if there is one.
+    ///
+    /// Code that checks for an element and then uses it can do both with one lookup this way,
+    /// rather than calling [`contains`](BloomSet::contains) and searching again.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let set: BloomSet<String> = ["add", "mul"].iter().map(|op| op.to_string()).collect();
+    /// assert_eq!(set.get("mul".to_string()), Some(&set.as_slice()[1]));
+    /// assert_eq!(set.get("sub".to_string()), None);
+    /// ```
+    #[inline]
+    pub fn get<B: core::borrow::Borrow<T>>(&self, item: B) -> Option<&T> {
+        let index = self.position(item.borrow())?;
+        self.as_slice().get(index)
+    }
+
+    /// Looks up every one of `keys` at once, returning for each the element that borrows as it,
+    /// if there is one. Keys may repeat.
+    ///
+    /// All the keys are hashed and run past the filter first, and the ones that get through are
+    /// then found in a single pass over the elements, rather than a scan each. The pass goes a
+    /// chunk of elements at a time, so each chunk is read from memory once for all the keys.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let fields: BloomSet<String> = ["id", "name", "email"].iter().map(|f| f.to_string()).collect();
+    /// let [id, phone, name] = fields.get_many(["id", "phone", "name"]);
+    /// assert_eq!(id.map(String::as_str), Some("id"));
+    /// assert_eq!(phone, None);
+    /// assert_eq!(name.map(String::as_str), Some("name"));
+    /// ```
+    #[inline]
+    pub fn get_many<Q, const N: usize>(&self, keys: [&Q; N]) -> [Option<&T>; N]
+    where
+        T: core::borrow::Borrow<Q>,
+        Q: PartialEq + ?Sized,
+        S: ElementHasher<Q>,
+    {
+        let saturated = self.filter_bits_set() >= P::SATURATION_THRESHOLD;
+        // The indices of the keys that got past the filter and haven't been found yet
+        let mut pending = [0; N];
+        let mut left = 0;
+        #[cfg(feature = "stats")]
+        let mut passed = [false; N];
+        for (i, key) in keys.iter().enumerate() {
+            let hash = seeded(
+                ElementHasher::<Q>::hash_element(&self.hash_builder, *key),
+                self.seed(),
+            );
+            if saturated || self.bloom_contains(bloom_mask::<K>(hash, P::FILTER_BITS)) {
+                pending[left] = i;
+                left += 1;
+                #[cfg(feature = "stats")]
+                {
+                    passed[i] = true;
+                }
+            }
+        }
+        let mut found = [None; N];
+        // A chunk at a time, each remaining key scanning it in turn, so the pass goes over the
+        // elements once while each scan stays a tight loop
+        for chunk in self.as_slice().chunks(16) {
+            if left == 0 {
+                break;
+            }
+            let mut j = 0;
+            while j < left {
+                let i = pending[j];
+                if let Some(item) = chunk.iter().find(|it| (*it).borrow() == keys[i]) {
+                    found[i] = Some(item);
+                    left -= 1;
+                    pending[j] = pending[left];
+                } else {
+                    j += 1;
+                }
+            }
+        }
+        // One lookup per key, as if each had been looked up on its own
+        #[cfg(feature = "stats")]
+        for (key, &key_passed) in keys.iter().zip(&passed) {
+            let index = if key_passed {
+                self.as_slice().iter().position(|it| it.borrow() == *key)
+            } else {
+                None
+            };
+            stats::record(self.len(), key_passed, index);
+        }
+        #[cfg(feature = "paranoid")]
+        for (key, item) in keys.iter().zip(&found) {
+            self.check_lookup_by(|it| it.borrow() == *key, item.is_some());
+        }
+        found
+    }
+
+    /// The queries in `iter` that aren't in the set, in the order they came. Nothing is
+    /// deduplicated, so a missing query repeated in `iter` is repeated in the result too.
+    ///
+    /// Each query is a [`contains`](BloomSet::contains), so the filter turns most misses away
+    /// without a scan.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let given: BloomSet<&str> = ["host", "port"].iter().copied().collect();
+    /// let missing = given.missing_from(["host", "user", "port", "password"]);
+    /// assert_eq!(missing, ["user", "password"]);
+    /// ```
+    #[inline]
+    pub fn missing_from<I>(&self, iter: I) -> Vec<I::Item>
+    where
+        I: IntoIterator,
+        I::Item: core::borrow::Borrow<T>,
+    {
+        iter.into_iter()
+            .filter(|query| !self.contains(core::borrow::Borrow::<T>::borrow(query)))
+            .collect()
+    }
+
+    /// Returns the element `key` borrows as, first inserting the one `make` builds from `key`
+    /// if there is none. `make` is only called on a miss, and if it fails the set is left
+    /// exactly as it was, filter bits included.
+    ///
+    /// If `make` builds an element that is already in the set under another key, nothing is
+    /// inserted and that element is returned.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let mut paths: BloomSet<String> = BloomSet::new();
+    /// let canonical = |raw: &str| match raw.strip_prefix('/') {
+    ///     Some(path) => Ok(path.to_lowercase()),
+    ///     None => Err(format!("{} is not absolute", raw)),
+    /// };
+    /// assert_eq!(paths.get_or_try_insert_with("/Etc", canonical).unwrap(), "etc");
+    /// assert!(paths.get_or_try_insert_with("tmp", canonical).is_err());
+    /// assert_eq!(paths.as_slice(), ["etc"]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns whatever error `make` does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a new element is needed and the set is already at its packing's
+    /// [`MAX_CAPACITY`](Packing::MAX_CAPACITY) (255 by default).
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn get_or_try_insert_with<Q, E>(
+        &mut self,
+        key: &Q,
+        make: impl FnOnce(&Q) -> Result<T, E>,
+    ) -> Result<&T, E>
+    where
+        T: core::borrow::Borrow<Q>,
+        Q: PartialEq + ?Sized,
+        S: ElementHasher<Q>,
+    {
+        if let Some(index) = self.position_of_key(key) {
+            return Ok(&self.as_slice()[index]);
+        }
+        // Nothing is touched until `make` has succeeded
+        let item = make(key)?;
+        let (index, mask) = self.position_and_mask(&item);
+        if let Some(index) = index {
+            return Ok(&self.as_slice()[index]);
+        }
+        if let Err(err) = self.push_new(item, mask) {
+            handle_reserve_error(err);
+        }
+        Ok(&self.as_slice()[self.len() - 1])
+    }
+
+    /// Takes the element `key` borrows as out of the set, passes it through `f`, and inserts
+    /// what `f` returns in its place, returning whether there was such an element.
+    ///
+    /// The new element is hashed afresh, so `f` may change the parts it is compared by, and it
+    /// goes at the end of the set like any inserted element. If it equals another element
+    /// already in the set, that one is kept and the new one is dropped, as with
+    /// [`insert`](BloomSet::insert). The old element's filter bits stay set, as with
+    /// [`remove`](BloomSet::remove). If `f` panics, the element is gone.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let mut set: BloomSet<String> = ["draft", "final"].iter().map(|s| s.to_string()).collect();
+    /// assert!(set.replace_with("draft", |old| old.replace("draft", "review")));
+    /// assert!(!set.replace_with("draft", |old| old));
+    /// assert_eq!(set.as_slice(), ["final", "review"]);
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn replace_with<Q>(&mut self, key: &Q, f: impl FnOnce(T) -> T) -> bool
+    where
+        T: core::borrow::Borrow<Q>,
+        Q: PartialEq + ?Sized,
+        S: ElementHasher<Q>,
+    {
+        let Some(index) = self.position_of_key(key) else {
+            return false;
+        };
+        let new = f(self.take(index));
+        // The old element's slot is free, so this never needs to grow past the capacity
+        self.insert(new);
+        true
+    }
+
+    /// Moves the element `key` borrows as from this set to the end of `other`, returning
+    /// whether it moved. Each set is searched once.
+    ///
+    /// If `other` already holds an equal element, nothing moves: both sets are left as they
+    /// were, and this returns `false`, as it does when this set holds no such element. Its
+    /// filter bits stay set here, as with [`remove`](BloomSet::remove).
+    ///
+    /// The element is never lost: room is made in `other` before it leaves this set, so if
+    /// hashing, comparing or allocating panics, it is still here.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let mut pending: BloomSet<String> = ["job-1", "job-2"].iter().map(|s| s.to_string()).collect();
+    /// let mut active = BloomSet::new();
+    /// assert!(pending.move_to(&mut active, "job-2"));
+    /// assert!(!pending.move_to(&mut active, "job-2"));
+    /// assert_eq!(pending.as_slice(), ["job-1"]);
+    /// assert_eq!(active.as_slice(), ["job-2"]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the element would be new to `other` and `other` already holds its packing's
+    /// [`MAX_CAPACITY`](Packing::MAX_CAPACITY) elements. Neither set changes then.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn move_to<Q>(&mut self, other: &mut Self, key: &Q) -> bool
+    where
+        T: core::borrow::Borrow<Q>,
+        Q: PartialEq + ?Sized,
+        S: ElementHasher<Q>,
+    {
+        let Some(index) = self.position_of_key(key) else {
+            return false;
+        };
+        let (duplicate, mask) = other.position_and_mask(&self.as_slice()[index]);
+        if duplicate.is_some() {
+            return false;
+        }
+        other.reserve(1);
+        let item = self.take(index);
+        // There is room, so this can't fail
+        let _ = other.push_new(item, mask);
+        true
+    }
+
+    /// The index of the element that borrows as `key`, if it gets past the filter and is found.
+    fn position_of_key<Q>(&self, key: &Q) -> Option<usize>
+    where
+        T: core::borrow::Borrow<Q>,
+        Q: PartialEq + ?Sized,
+        S: ElementHasher<Q>,
+    {
+        let hash = seeded(
+            ElementHasher::<Q>::hash_element(&self.hash_builder, key),
+            self.seed(),
+        );
+        let passed = self.filter_bits_set() >= P::SATURATION_THRESHOLD
+            || self.bloom_contains(bloom_mask::<K>(hash, P::FILTER_BITS));
+        let index = if passed {
+            self.as_slice().iter().position(|it| it.borrow() == key)
+        } else {
+            None
+        };
+        #[cfg(feature = "stats")]
+        stats::record(self.len(), passed, index);
+        #[cfg(feature = "paranoid")]
+        self.check_lookup_by(|it| it.borrow() == key, index.is_some());
+        index
+    }
+
+    /// Moves `item` to the front of the set, so later lookups of it scan one element, and
+    /// returns whether it was present. The elements before it shift back one slot, keeping
+    /// their order.
+    ///
+    /// For lookups skewed towards a few hot keys, promoting them keeps the scan short however
+    /// they were inserted. Moving elements doesn't touch the filter, but it does clear the
+    /// [sorted](BloomSet::is_sorted) mark unless `item` was already first.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let mut set: BloomSet<u32> = (0..5).collect();
+    /// assert!(set.promote(3));
+    /// assert_eq!(set.as_slice(), [3, 0, 1, 2, 4]);
+    /// assert!(!set.promote(7));
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn promote<B: core::borrow::Borrow<T>>(&mut self, item: B) -> bool {
+        let Some(index) = self.position(item.borrow()) else {
+            return false;
+        };
+        if index > 0 {
+            unsafe { slice::from_raw_parts_mut(self.as_mut_ptr(), index + 1) }.rotate_right(1);
+            self.length &= !P::SORTED_FLAG;
+            self.moved_elements();
+        }
+        true
+    }
+
+    /// Like [`contains`](BloomSet::contains), but swaps a found `item` one slot towards the
+    /// front.
+    ///
+    /// Unlike [`promote`](BloomSet::promote), a lookup of a cold key only moves it past one
+    /// element, so it can't push the hot keys far back. Over many lookups, the most frequent
+    /// keys drift to the front. Like `promote`, this clears the [sorted](BloomSet::is_sorted)
+    /// mark when it moves an element.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn contains_promoting<B: core::borrow::Borrow<T>>(&mut self, item: B) -> bool {
+        let Some(index) = self.position(item.borrow()) else {
+            return false;
+        };
+        if index > 0 {
+            unsafe { slice::from_raw_parts_mut(self.as_mut_ptr(), index + 1) }
+                .swap(index - 1, index);
+            self.length &= !P::SORTED_FLAG;
+            self.moved_elements();
+        }
+        true
+    }
+
+    /// The index of `item`, if it gets past the filter and is found.
+    #[inline]
+    fn position(&self, item: &T) -> Option<usize> {
+        let passed = self.maybe_contains(item);
+        #[cfg(feature = "stats")]
+        self.record_lookup(item, passed);
+        let index = if passed {
+            scan_position(self.as_slice(), item)
+        } else {
+            None
+        };
+        #[cfg(feature = "paranoid")]
+        self.check_lookup(item, index.is_some());
+        index
+    }
+
+    /// With the `stats` feature, counts a lookup of `item` that did or didn't get past the
+    /// filter. Where a scan would find it is worked out again here, so the scans themselves
+    /// stay as they are.
+    #[cfg(feature = "stats")]
+    fn record_lookup(&self, item: &T, passed_filter: bool) {
+        let index = if passed_filter {
+            self.as_slice().iter().position(|it| it == item)
+        } else {
+            None
+        };
+        stats::record(self.len(), passed_filter, index);
+    }
+
+    /// With the `paranoid` feature, checks the answer to a lookup against a scan that ignores
+    /// the filter, so a filter that misses a present element fails loudly in debug builds.
+    #[cfg(feature = "paranoid")]
+    #[track_caller]
+    fn check_lookup(&self, item: &T, found: bool) {
+        self.check_lookup_by(|it| it == item, found);
+    }
+
+    /// Like `check_lookup`, for a lookup that matched elements with `is_match`.
+    #[cfg(feature = "paranoid")]
+    #[track_caller]
+    fn check_lookup_by(&self, is_match: impl FnMut(&T) -> bool, found: bool) {
+        debug_assert_eq!(
+            found,
+            self.as_slice().iter().any(is_match),
+            "the filter disagreed with a scan of the elements"
+        );
+    }
+}
+
+impl<T: Ord, S, const K: usize, P: Packing> BloomSet<T, S, K, P> {
+    /// Sorts the elements in ascending order and marks the set as sorted, so that
+    /// [`contains_sorted`](BloomSet::contains_sorted) can binary search instead of scanning.
+    /// The mark stays until the next element is inserted; removing elements keeps the order.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn sort(&mut self) {
+        unsafe { slice::from_raw_parts_mut(self.as_mut_ptr(), self.len()) }.sort_unstable();
+        self.length |= P::SORTED_FLAG;
+        self.moved_elements();
+    }
+
+    /// Binary searches the elements for `item`, like `slice::binary_search`: `Ok` with its
+    /// index if it is there, and otherwise `Err` with the index it would have to be inserted
+    /// at to keep the order.
+    ///
+    /// The elements must be in ascending order, as [`sort`](BloomSet::sort) leaves them; on a
+    /// set that isn't the result is meaningless. Debug builds check, and panic if it isn't.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let mut set: BloomSet<u32> = [30, 10, 20].iter().copied().collect();
+    /// set.sort();
+    /// assert_eq!(set.binary_search(&20), Ok(1));
+    /// assert_eq!(set.binary_search(&25), Err(2));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` with the insertion point if `item` isn't in the set.
+    #[inline]
+    #[track_caller]
+    pub fn binary_search(&self, item: &T) -> Result<usize, usize> {
+        self.debug_assert_in_order();
+        self.as_slice().binary_search(item)
+    }
+
+    /// Like [`binary_search`](BloomSet::binary_search), with `f` comparing each element it
+    /// visits to the target, like `slice::binary_search_by`. `f` must order the elements the
+    /// same way `Ord` does.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` with the insertion point if `f` doesn't return `Equal` for any element.
+    #[inline]
+    #[track_caller]
+    pub fn binary_search_by<F>(&self, f: F) -> Result<usize, usize>
+    where
+        F: FnMut(&T) -> core::cmp::Ordering,
+    {
+        self.debug_assert_in_order();
+        self.as_slice().binary_search_by(f)
+    }
+
+    /// The index of the first element for which `pred` returns `false`, like
+    /// `slice::partition_point`, given the set is sorted as for
+    /// [`binary_search`](BloomSet::binary_search) and `pred` holds for a prefix of it.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let mut scores: BloomSet<u32> = [88, 42, 97, 65].iter().copied().collect();
+    /// scores.sort();
+    /// let failing = scores.partition_point(|&score| score < 50);
+    /// assert_eq!(&scores.as_slice()[failing..], [65, 88, 97]);
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn partition_point<F: FnMut(&T) -> bool>(&self, pred: F) -> usize {
+        self.debug_assert_in_order();
+        self.as_slice().partition_point(pred)
+    }
+
+    /// In debug builds, panics unless the set is marked sorted or its elements happen to be in
+    /// ascending order anyway.
+    #[inline]
+    #[track_caller]
+    fn debug_assert_in_order(&self) {
+        debug_assert!(
+            self.is_sorted() || self.as_slice().windows(2).all(|pair| pair[0] <= pair[1]),
+            "binary searched a set that isn't sorted"
+        );
+    }
+}
+
+impl<T: Ord, S: ElementHasher<T>, const K: usize, P: Packing> BloomSet<T, S, K, P> {
+    /// Like [`contains`](BloomSet::contains), but binary searches the elements instead of
+    /// scanning them if the set [is sorted](BloomSet::is_sorted).
+    ///
+    /// For a set that is built once and then queried many times, calling
+    /// [`sort`](BloomSet::sort) first makes a lookup that gets past the filter cost O(log n)
+    /// comparisons, which matters most once the filter is saturated.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let mut set: BloomSet<u32> = (0..200).rev().collect();
+    /// set.sort();
+    /// assert!(set.contains_sorted(150));
+    /// assert!(!set.contains_sorted(200));
+    /// ```
+    #[inline]
+    pub fn contains_sorted<B: core::borrow::Borrow<T>>(&self, item: B) -> bool {
+        let item = item.borrow();
+        let passed = self.maybe_contains(item);
+        #[cfg(feature = "stats")]
+        self.record_lookup(item, passed);
+        let found = if !passed {
+            false
+        } else if self.is_sorted() {
+            self.as_slice().binary_search(item).is_ok()
+        } else {
+            scan(self.as_slice(), item)
+        };
+        #[cfg(feature = "paranoid")]
+        self.check_lookup(item, found);
+        found
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T> + Default, const K: usize, P: Packing> FromIterator<T>
+    for BloomSet<T, S, K, P>
+{
+    /// # Panics
+    ///
+    /// Panics if the iterator yields more unique elements than the set can hold (255 by
+    /// default); use [`BloomSet::try_from_iter`] to handle that case.
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        match Self::try_from_iter_with_hasher(iter, S::default()) {
+            Ok(set) => set,
+            Err(err) => panic!("{}", err),
+        }
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T>, const K: usize, P: Packing> Extend<T>
+    for BloomSet<T, S, K, P>
+{
+    /// # Panics
+    ///
+    /// Panics if the set would need to hold more unique elements than it can.
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        if let Err(err) = self.try_extend(iter.into_iter()) {
+            panic!("{}", err);
+        }
+    }
+}
+
+/// Writes the elements in the order they are stored, separated by `", "` and with no brackets,
+/// so an empty set writes nothing. Formatting flags like a width apply to each element.
+///
+/// ```
+/// use bloomset::BloomSet;
+///
+/// let formats: BloomSet<&str> = ["json", "yaml", "toml"].iter().copied().collect();
+/// assert_eq!(
+///     format!("expected one of: {}", formats),
+///     "expected one of: json, yaml, toml"
+/// );
+/// ```
+impl<T: fmt::Display, S, const K: usize, P: Packing> fmt::Display for BloomSet<T, S, K, P> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut elements = self.as_slice().iter();
+        if let Some(first) = elements.next() {
+            first.fmt(f)?;
+            for element in elements {
+                f.write_str(", ")?;
+                element.fmt(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Moves the elements into a shared slice, in the set's order. `Arc<[T]>` keeps its reference
+/// counts in the same allocation as the elements, so they are moved once more, into a new one,
+/// and the set's buffer is freed. An empty set gives an empty slice without allocating.
+#[cfg(target_has_atomic = "ptr")]
+impl<T, S, const K: usize, P: Packing> From<BloomSet<T, S, K, P>> for Arc<[T]> {
+    #[inline]
+    fn from(set: BloomSet<T, S, K, P>) -> Self {
+        if set.is_empty() {
+            return Self::default();
+        }
+        Self::from(set.into_vec())
+    }
+}
+
+/// Clones the elements into a shared slice, in the set's order.
+#[cfg(target_has_atomic = "ptr")]
+impl<T: Clone, S, const K: usize, P: Packing> From<&BloomSet<T, S, K, P>> for Arc<[T]> {
+    #[inline]
+    fn from(set: &BloomSet<T, S, K, P>) -> Self {
+        if set.is_empty() {
+            return Self::default();
+        }
+        Self::from(set.as_slice())
+    }
+}
+
+/// Like the conversion into `Arc<[T]>`, for a slice shared within one thread. Unlike an empty
+/// `Arc<[T]>`, an empty `Rc<[T]>` still allocates its reference counts.
+impl<T, S, const K: usize, P: Packing> From<BloomSet<T, S, K, P>> for Rc<[T]> {
+    #[inline]
+    fn from(set: BloomSet<T, S, K, P>) -> Self {
+        Self::from(set.into_vec())
+    }
+}
+
+/// Clones the elements into a shared slice, in the set's order.
+impl<T: Clone, S, const K: usize, P: Packing> From<&BloomSet<T, S, K, P>> for Rc<[T]> {
+    #[inline]
+    fn from(set: &BloomSet<T, S, K, P>) -> Self {
+        Self::from(set.as_slice())
+    }
+}
+
+impl<T, S: Clone, const K: usize, P: Packing> BloomSet<T, S, K, P> {
+    /// An empty set configured like this one: the same hasher, seed and capacity, already
+    /// allocated, but no elements and no filter bits. The elements aren't touched, so unlike
+    /// `clone` followed by `clear` this costs one allocation whatever they are.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let template: BloomSet<String> = BloomSet::with_capacity_and_seed(32, 7);
+    /// let mut scratch = template.clone_empty();
+    /// assert_eq!((scratch.len(), scratch.capacity(), scratch.seed()), (0, 32, 7));
+    /// scratch.insert("request-1".to_string());
+    /// assert!(template.clone_empty().is_empty());
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[must_use]
+    pub fn clone_empty(&self) -> Self {
+        let mut set = Self::with_hasher(self.hash_builder.clone());
+        set.set_seed(self.seed());
+        if self.capacity() > set.capacity() {
+            set.reallocate(self.capacity());
+        }
+        set
+    }
+}
+
+impl<T: Clone, S: Clone, const K: usize, P: Packing> BloomSet<T, S, K, P> {
+    /// Like [`clone`](Clone::clone), but returns an error instead of aborting if the buffer
+    /// can't be allocated. The clone has the same elements, in the same order, and the same
+    /// filter bits and seed.
+    ///
+    /// Only the buffer is allocated fallibly: the elements are copied with their own `clone`,
+    /// so one that allocates can still abort on its own, and a panic in one drops the clones
+    /// made so far and unwinds.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TryReserveError::AllocFailed`] if the allocator can't provide the buffer.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn try_clone(&self) -> Result<Self, TryReserveError> {
+        let mut clone = Self::with_hasher(self.hash_builder.clone());
+        // Nothing to allocate for an empty set, but it may still have filter bits and a seed
+        if !self.is_empty() {
+            clone.try_reallocate(self.len())?;
+            for item in self.as_slice() {
+                // Counted one at a time, so a panicking `clone` drops the ones made so far
+                unsafe { clone.as_mut_ptr().add(clone.len()).write(item.clone()) };
+                clone.length += 1;
+            }
+        }
+        clone.length |= self.length & !P::COUNT_MASK;
+        clone.capacity |= self.capacity & !P::COUNT_MASK;
+        Ok(clone)
+    }
+}
+
+/// The clone holds clones of the same elements in the same order, with the same filter bits
+/// and seed, in a buffer with room for exactly those elements.
+impl<T: Clone, S: Clone, const K: usize, P: Packing> Clone for BloomSet<T, S, K, P> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn clone(&self) -> Self {
+        self.try_clone()
+            .unwrap_or_else(|err| handle_reserve_error(err))
+    }
+}
+
+impl<T, S, const K: usize, P: Packing> Drop for BloomSet<T, S, K, P> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn drop(&mut self) {
+        /// Frees the allocation when dropped, so it is freed even if an element's `Drop` panics.
+        struct Deallocate(NonNull<u8>, Layout, usize);
+
+        impl Drop for Deallocate {
+            fn drop(&mut self) {
+                unsafe { raw::deallocate(self.0, self.1, self.2) };
+            }
+        }
+
+        let _deallocate = Deallocate(self.ptr.cast(), Layout::new::<T>(), self.capacity());
+        // If an element panics, the rest are still dropped before unwinding continues
+        unsafe { ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.as_mut_ptr(), self.len())) };
+    }
+}
+
+// The set owns its elements like a Vec does, so it can cross threads under the same conditions
+unsafe impl<T: Send, S: Send, const K: usize, P: Packing> Send for BloomSet<T, S, K, P> {}
+unsafe impl<T: Sync, S: Sync, const K: usize, P: Packing> Sync for BloomSet<T, S, K, P> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::hash::BuildHasher;
+
+    #[test]
+    fn raw_parts_round_trip() {
+        let mut set = BloomSet::with_seed(42);
+        for word in ["a", "b", "c"] {
+            set.insert(word);
+        }
+        let filter = set.filter_bits();
+        let (ptr, length, capacity) = set.into_raw_parts();
+        assert_eq!(length & MAX_CAPACITY, 3);
+        assert_eq!(unpack_filter::<Packed8>(length, capacity), filter);
+
+        let mut set = unsafe { BloomSet::from_raw_parts(ptr, length, capacity) };
+        assert_eq!(set.seed(), 42);
+        assert_eq!(set.as_slice(), ["a", "b", "c"]);
+        assert!(set.contains("b") && !set.contains("d"));
+        set.insert("d");
+        assert!(set.contains("d"));
+    }
+
+    /// Stands in for a plugin on the other side of an ABI that only passes words.
+    #[no_mangle]
+    extern "C" fn bloomset_test_insert_across_abi(
+        ptr: *mut u32,
+        length: &mut usize,
+        capacity: &mut usize,
+        item: u32,
+    ) -> *mut u32 {
+        let mut set =
+            unsafe { BloomSet::from_raw_parts(NonNull::new(ptr).unwrap(), *length, *capacity) };
+        set.insert(item);
+        let (ptr, new_length, new_capacity) = set.into_raw_parts();
+        *length = new_length;
+        *capacity = new_capacity;
+        ptr.as_ptr()
+    }
+
+    #[test]
+    fn raw_parts_across_abi() {
+        let (ptr, mut length, mut capacity) = BloomSet::<u32>::new().into_raw_parts();
+        let mut ptr = ptr.as_ptr();
+        for i in 0..100 {
+            ptr = bloomset_test_insert_across_abi(ptr, &mut length, &mut capacity, i % 40);
+        }
+        let set = unsafe { BloomSet::from_raw_parts(NonNull::new(ptr).unwrap(), length, capacity) };
+        assert_eq!(set.as_slice(), (0..40).collect::<Vec<_>>().as_slice());
+        assert!((0..40).all(|i| set.contains(i)) && !set.contains(40));
+    }
+
+    #[test]
+    #[cfg_attr(
+        feature = "debug-checks",
+        ignore = "debug-checks adds a generation counter"
+    )]
+    fn size_is_right() {
+        use core::mem::size_of;
+        assert_eq!(size_of::<BloomSet<u8>>(), size_of::<Vec<u8>>());
+    }
+
+    #[test]
     fn insert() {
         let mut set = BloomSet::default();
         assert_eq!(set.len(), 0);
         set.insert(2u8);
         assert_eq!(set.len(), 1);
-        set.insert(4u8);
-        assert_eq!(set.len(), 2);
-        assert_eq!(set.as_slice()[0], 2);
-        assert_eq!(set.as_slice()[1], 4);
-        assert_eq!(set.as_slice().len(), 2);
+        set.insert(4u8);
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.as_slice()[0], 2);
+        assert_eq!(set.as_slice()[1], 4);
+        assert_eq!(set.as_slice().len(), 2);
+
+        set.insert(2);
+        assert_eq!(set.len(), 2);
+
+        set.insert(31);
+        assert_eq!(set.len(), 3);
+        set.insert(31);
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "random-seed", ignore = "compares filter bits across sets")]
+    fn fill_through_spare_capacity() {
+        let words = ["a", "b", "c", "d", "e", "f"];
+        let mut set = BloomSet::with_capacity(8);
+        set.insert("z");
+        assert_eq!(set.spare_capacity_mut().len(), 7);
+        for (slot, word) in set.spare_capacity_mut().iter_mut().zip(&words[..4]) {
+            slot.write(*word);
+        }
+        // Only the first three slots are committed; the fourth stays spare
+        unsafe { set.assume_init_inserted(3) };
+        assert_eq!(set.as_slice(), ["z", "a", "b", "c"]);
+        assert!(set.contains("c") && !set.contains("d"));
+        let reference: BloomSet<&str> = ["z", "a", "b", "c"].iter().copied().collect();
+        assert_eq!(set.filter_bits(), reference.filter_bits());
+
+        for (slot, word) in set.spare_capacity_mut().iter_mut().zip(&words[3..]) {
+            slot.write(*word);
+        }
+        unsafe { set.assume_init_inserted(3) };
+        assert_eq!(set.len(), 7);
+        assert!(words.iter().all(|word| set.contains(word)));
+        unsafe { set.assume_init_inserted(0) };
+        assert_eq!(set.len(), 7);
+
+        set.sort();
+        set.spare_capacity_mut()[0].write("g");
+        unsafe { set.assume_init_inserted(1) };
+        assert!(!set.is_sorted() && set.spare_capacity_mut().is_empty());
+    }
+
+    /// An element that owns heap memory and records its serial number when dropped. Elements
+    /// with the same key are equal.
+    struct Tracked<'a> {
+        key: u32,
+        serial: u32,
+        name: String,
+        drops: &'a std::cell::RefCell<Vec<u32>>,
+    }
+
+    impl PartialEq for Tracked<'_> {
+        fn eq(&self, other: &Self) -> bool {
+            self.key == other.key
+        }
+    }
+
+    impl Hash for Tracked<'_> {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.key.hash(state);
+        }
+    }
+
+    impl Drop for Tracked<'_> {
+        fn drop(&mut self) {
+            assert_eq!(self.name, self.key.to_string());
+            self.drops.borrow_mut().push(self.serial);
+        }
+    }
+
+    #[test]
+    fn heap_owning_elements_drop_exactly_once() {
+        let drops = std::cell::RefCell::new(Vec::new());
+        let mut serial = 0;
+        let mut tracked = |key: u32| {
+            serial += 1;
+            Tracked {
+                key,
+                serial,
+                name: key.to_string(),
+                drops: &drops,
+            }
+        };
+
+        let mut set = BloomSet::with_capacity(4);
+        for key in 0..3 {
+            set.insert(tracked(key));
+        }
+        // A duplicate is dropped right away, and the original stays
+        set.insert(tracked(1));
+        assert_eq!(*drops.borrow(), [4]);
+        assert!(set.as_slice().iter().map(|t| t.serial).eq(1..=3));
+
+        // Reuse the slots freed by clear, then grow several times
+        set.clear();
+        assert_eq!(drops.borrow().len(), 4);
+        for key in 0..200 {
+            set.insert(tracked(key));
+        }
+        assert!(set.as_slice().iter().all(|t| t.name == t.key.to_string()));
+        assert!(set.remove(tracked(7)));
+        drop(set);
+
+        let mut drops = drops.into_inner();
+        drops.sort_unstable();
+        assert!(
+            drops.iter().copied().eq(1..=serial),
+            "each value dropped exactly once"
+        );
+    }
+
+    #[test]
+    fn capacity_limits() {
+        let set = BloomSet::<u32>::with_capacity(255);
+        assert_eq!((set.capacity(), set.filter_bits()), (255, 0));
+        let set = BloomSet::<u32>::try_with_capacity(255).unwrap();
+        assert_eq!((set.capacity(), set.filter_bits()), (255, 0));
+
+        for cap in [256, 300, 1 << 20] {
+            let err = BloomSet::<u32>::try_with_capacity(cap).err().unwrap();
+            assert_eq!((err.requested(), err.limit()), (cap, 255));
+        }
+        assert!(BloomSet16::<u32>::try_with_capacity_and_hasher(300, BuildBloomHasher).is_ok());
+
+        // The filter starts empty whatever the capacity, so no lookup passes it
+        for cap in [0, 1, 128, 255] {
+            let set = BloomSet::<u32>::with_capacity_and_seed(cap, 9);
+            assert_eq!((set.filter_bits(), set.seed(), set.len()), (0, 9, 0));
+            assert!(!(0..1000).any(|i| set.contains(i)));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot hold more than 255 elements (256 requested)")]
+    fn with_capacity_over_limit() {
+        let _ = BloomSet::<u32>::with_capacity(256);
+    }
+
+    #[test]
+    fn grow_past_128() {
+        for n in [129_u32, 200, 255] {
+            let mut set = BloomSet::new();
+            for i in 0..n {
+                set.insert(i.to_string());
+                // Duplicates never count towards the limit
+                set.insert(i.to_string());
+            }
+            assert_eq!(set.len(), n as usize);
+            assert!(set.capacity() >= set.len() && set.capacity() <= MAX_CAPACITY);
+            assert!((0..n).all(|i| set.contains(i.to_string())));
+            assert!(!(n..n + 100).any(|i| set.contains(i.to_string())));
+            assert!(set
+                .as_slice()
+                .iter()
+                .map(String::as_str)
+                .eq((0..n).map(|i| i.to_string())));
+        }
+
+        let mut set: BloomSet<String> = (0..255_u32).map(|i| i.to_string()).collect();
+        let filter = set.filter_bits();
+        assert_eq!(set.try_insert("0".to_string()), Ok(()));
+        let Err(TryReserveError::Capacity(err)) = set.try_insert("255".to_string()) else {
+            panic!("a full set took a new element");
+        };
+        assert_eq!((err.requested(), err.limit()), (256, 255));
+        assert_eq!(
+            (set.len(), set.capacity(), set.filter_bits()),
+            (255, 255, filter)
+        );
+        assert!(!set.contains("255".to_string()));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "no-filter", ignore = "checks the filter bits")]
+    fn zero_sized() {
+        let mut set = BloomSet::new();
+        assert_eq!(set.capacity(), MAX_CAPACITY);
+        assert!(!set.contains(()));
+        set.insert(());
+        set.insert(());
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.capacity(), MAX_CAPACITY);
+        assert!(set.contains(()));
+        // The filter is consulted the same way as for any other element
+        assert_eq!(set.filter_bits().count_ones(), 1);
+        assert!(set.remove(()) && set.is_empty());
+        set.insert(());
+        set.clear();
+        assert!(set.is_empty() && set.filter_bits() == 0 && !set.contains(()));
+        assert_eq!(
+            BloomSet::<()>::with_capacity(3).capacity(),
+            MAX_CAPACITY,
+            "zero-sized elements always have the maximum capacity"
+        );
+        assert_eq!(
+            BloomSet16::<(), BuildBloomHasher>::with_hasher(BuildBloomHasher).capacity(),
+            Packed16::MAX_CAPACITY
+        );
+    }
+
+    #[test]
+    fn zero_sized_drops() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Hash, PartialEq)]
+        struct Marker;
+
+        impl Drop for Marker {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let mut set = BloomSet::new();
+        set.insert(Marker);
+        set.insert(Marker);
+        // The duplicate was dropped on insertion
+        assert_eq!(DROPS.load(Ordering::Relaxed), 1);
+        set.clear();
+        assert_eq!(DROPS.load(Ordering::Relaxed), 2);
+        set.insert(Marker);
+        drop(set);
+        assert_eq!(DROPS.load(Ordering::Relaxed), 3);
+    }
+
+    std::thread_local! {
+        /// The keys whose `PartialEq` and `Drop` should panic next, once each.
+        static PANIC_IN_EQ: Cell<Option<u32>> = const { Cell::new(None) };
+        static PANIC_IN_DROP: Cell<Option<u32>> = const { Cell::new(None) };
+        static LIVE: Cell<isize> = const { Cell::new(0) };
+    }
+
+    /// An element that panics on cue and tracks how many of its kind are alive.
+    struct Fragile(u32, String);
+
+    impl Fragile {
+        fn new(key: u32) -> Self {
+            LIVE.with(|live| live.set(live.get() + 1));
+            Self(key, key.to_string())
+        }
+    }
+
+    fn cued(cue: &'static std::thread::LocalKey<Cell<Option<u32>>>, key: u32) -> bool {
+        cue.with(|cue| cue.get() == Some(key) && cue.take().is_some())
+    }
+
+    impl PartialEq for Fragile {
+        fn eq(&self, other: &Self) -> bool {
+            assert!(!cued(&PANIC_IN_EQ, self.0), "eq panicked");
+            self.0 == other.0
+        }
+    }
+
+    impl Hash for Fragile {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.0.hash(state);
+        }
+    }
+
+    impl Drop for Fragile {
+        fn drop(&mut self) {
+            LIVE.with(|live| live.set(live.get() - 1));
+            assert!(!cued(&PANIC_IN_DROP, self.0), "drop panicked");
+        }
+    }
+
+    fn catch(f: impl FnOnce()) {
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn panic_safety() {
+        let mut set = BloomSet::with_capacity(4);
+        for key in 0..10 {
+            set.insert(Fragile::new(key));
+        }
+
+        // A comparison panicking during insert leaves the set as it was
+        let filter = set.filter_bits();
+        PANIC_IN_EQ.with(|cue| cue.set(Some(3)));
+        catch(|| set.insert(Fragile::new(3)));
+        assert_eq!((set.len(), set.filter_bits()), (10, filter));
+        assert!(set.as_slice().iter().all(|it| it.1 == it.0.to_string()));
+
+        // A panicking drop during remove happens after the element is out of the set
+        PANIC_IN_DROP.with(|cue| cue.set(Some(4)));
+        catch(|| {
+            set.remove(Fragile::new(4));
+        });
+        assert_eq!(set.len(), 9);
+        assert!(!set.contains(Fragile::new(4)));
+
+        // clear drops every element even if one of them panics
+        PANIC_IN_DROP.with(|cue| cue.set(Some(5)));
+        catch(|| set.clear());
+        assert!(set.is_empty() && set.filter_bits() == 0);
+        assert_eq!(LIVE.with(Cell::get), 0);
+
+        // So does dropping the set, which still frees the buffer
+        for key in 0..20 {
+            set.insert(Fragile::new(key));
+        }
+        PANIC_IN_DROP.with(|cue| cue.set(Some(0)));
+        catch(|| drop(set));
+        assert_eq!(LIVE.with(Cell::get), 0);
+    }
+
+    #[test]
+    fn growth_sequence() {
+        /// The capacities `insert` goes through, one element at a time, up to `n` elements.
+        fn capacities<P: Packing>(
+            mut set: BloomSet<u32, BuildBloomHasher, 1, P>,
+            n: u32,
+        ) -> Vec<usize> {
+            let mut seen = alloc::vec![set.capacity()];
+            for i in 0..n {
+                set.insert(i);
+                if set.capacity() != seen[seen.len() - 1] {
+                    seen.push(set.capacity());
+                }
+                assert!(set.capacity() <= P::MAX_CAPACITY && set.capacity() >= set.len());
+            }
+            seen
+        }
+        assert_eq!(
+            capacities(BloomSet::new(), 255),
+            [0, 4, 8, 16, 32, 64, 128, 255]
+        );
+        assert_eq!(
+            capacities(BloomSet::with_capacity(100), 255),
+            [100, 200, 255]
+        );
+        assert_eq!(
+            capacities(BloomSet16::with_hasher(BuildBloomHasher), 1000),
+            [0, 4, 8, 16, 32, 64, 128, 256, 512, 1024]
+        );
+
+        assert_eq!(grown_capacity(0, 255), 4);
+        assert_eq!(grown_capacity(1, 255), 4);
+        assert_eq!(grown_capacity(127, 255), 254);
+        assert_eq!(grown_capacity(128, 255), 255);
+        assert_eq!(grown_capacity(usize::MAX, 255), 255);
+    }
+
+    #[test]
+    fn reserve() {
+        let mut set: BloomSet<String> = (0..10).map(|i| i.to_string()).collect();
+        let filter = set.filter_bits();
+        set.reserve(100);
+        assert!(set.capacity() >= 110);
+        assert_eq!(set.filter_bits(), filter);
+        assert!((0..10).all(|i| set.contains(i.to_string())));
+        let ptr = set.as_mut_ptr();
+        for i in 10..110 {
+            set.insert(i.to_string());
+        }
+        assert_eq!(set.as_mut_ptr(), ptr);
+
+        // Already enough room, so nothing changes
+        set.reserve(set.capacity() - set.len());
+        assert_eq!(set.as_mut_ptr(), ptr);
+
+        let mut set = BloomSet::<u32>::with_capacity(4);
+        set.insert(1);
+        set.reserve_exact(50);
+        assert_eq!(set.capacity(), 51);
+        let ptr = set.as_mut_ptr();
+        for i in 2..52 {
+            set.insert(i);
+        }
+        assert_eq!(set.as_mut_ptr(), ptr);
+        assert!((1..52).all(|i| set.contains(i)));
+
+        // reserve never asks for more than the set can hold
+        let mut set: BloomSet<u32> = (0..150).collect();
+        set.reserve(55);
+        assert_eq!(set.capacity(), MAX_CAPACITY);
+    }
+
+    #[test]
+    fn shrink() {
+        let mut set: BloomSet<String> = (0..200).map(|i| i.to_string()).collect();
+        for i in 10..200 {
+            set.remove(i.to_string());
+        }
+        let filter = set.filter_bits();
+        set.shrink_to(50);
+        assert_eq!(set.capacity(), 50);
+        set.shrink_to(100);
+        assert_eq!(set.capacity(), 50);
+        set.shrink_to_fit();
+        assert_eq!(set.capacity(), 10);
+        assert_eq!(set.filter_bits(), filter);
+        assert!((0..10).all(|i| set.contains(i.to_string())));
+        assert!(!set.contains("10".to_string()));
+        set.insert("new".to_string());
+        assert!(set.contains("new".to_string()) && set.capacity() > 10);
+
+        // An empty set gives its buffer back entirely, and can still grow again
+        let mut set = BloomSet::<u32>::with_seed(4);
+        set.reserve(100);
+        set.shrink_to_fit();
+        assert_eq!((set.capacity(), set.seed()), (0, 4));
+        assert_eq!(set.as_mut_ptr(), NonNull::dangling().as_ptr());
+        set.insert(3);
+        assert!(set.contains(3));
+
+        let mut set = BloomSet::<()>::new();
+        set.shrink_to_fit();
+        assert_eq!(set.capacity(), MAX_CAPACITY);
+    }
+
+    #[test]
+    fn get_between_mutations() {
+        let mut set: BloomSet<String> = (0..20).map(|i| i.to_string()).collect();
+        let key = "7".to_string();
+        for _ in 0..2 {
+            assert!(core::ptr::eq(
+                set.get(&key).unwrap(),
+                set.as_slice().as_ptr().wrapping_add(7)
+            ));
+        }
+        assert!(set.remove(&key));
+        assert_eq!(set.get(&key), None);
+        assert_eq!(set.get("8".to_string()), Some(&"8".to_string()));
+        set.insert(key.clone());
+        assert!(core::ptr::eq(
+            set.get(&key).unwrap(),
+            set.as_slice().as_ptr().wrapping_add(19)
+        ));
+        set.sort();
+        assert_eq!(set.get(&key), Some(&key));
+        set.retain(|item| item != "8");
+        assert_eq!(
+            (set.get(&key), set.get("8".to_string())),
+            (Some(&key), None)
+        );
+        set.clear();
+        assert_eq!(set.get(&key), None);
+    }
+
+    #[test]
+    fn promote() {
+        let mut set: BloomSet<u32> = (0..10).collect();
+        let filter = set.filter_bits();
+        assert!(set.promote(6) && set.promote(0));
+        assert_eq!(set.as_slice(), [0, 6, 1, 2, 3, 4, 5, 7, 8, 9]);
+        assert!(!set.promote(10));
+
+        assert!(set.contains_promoting(9) && set.contains_promoting(9));
+        assert!(set.contains_promoting(0) && !set.contains_promoting(10));
+        assert_eq!(set.as_slice(), [0, 6, 1, 2, 3, 4, 5, 9, 7, 8]);
+        assert_eq!(set.filter_bits(), filter);
+        assert!((0..10).all(|i| set.contains(i)));
+
+        // Only moving an element forgets that the set was sorted
+        set.sort();
+        assert!(set.promote(0) && set.contains_promoting(0) && set.is_sorted());
+        assert!(set.promote(5) && !set.is_sorted());
+        set.sort();
+        assert!(set.contains_promoting(5) && !set.is_sorted());
+
+        // Past the first chunk of a cheap scan, and with elements that aren't cheap to compare
+        let mut set: BloomSet<u64> = (0..100).collect();
+        assert!(set.promote(70) && set.contains_promoting(99));
+        assert_eq!(set.as_slice()[..2], [70, 0]);
+        assert_eq!(set.as_slice()[97..], [97, 99, 98]);
+        let mut set: BloomSet<String> = (0..40).map(|i| i.to_string()).collect();
+        assert!(set.promote("33".to_string()) && !set.promote("40".to_string()));
+        assert_eq!(set.as_slice()[..2], ["33", "0"]);
+    }
+
+    #[test]
+    fn try_reserve() {
+        let mut set: BloomSet<u32> = (0..10).collect();
+        let filter = set.filter_bits();
+        let err = set.try_reserve(246).unwrap_err();
+        assert_eq!(err, TryReserveError::Capacity(CapacityError::new(256, 255)));
+        assert_eq!(err.to_string(), CapacityError::new(256, 255).to_string());
+        assert!(matches!(
+            set.try_reserve_exact(usize::MAX),
+            Err(TryReserveError::Capacity(_))
+        ));
+        assert_eq!((set.len(), set.filter_bits()), (10, filter));
+        assert_eq!(set.try_reserve_exact(245), Ok(()));
+        assert_eq!(set.capacity(), 255);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn try_reserve_alloc_failure() {
+        // Eight of these are more than any allocation can hold
+        let mut set = BloomSet::<[u8; 1 << 60]>::new();
+        assert_eq!(
+            set.try_reserve_exact(8),
+            Err(TryReserveError::AllocFailed { layout: None })
+        );
+        assert_eq!(set.capacity(), 0);
+
+        // Four of these are a valid layout, but far more memory than the allocator has
+        let mut set = BloomSet::<[u8; 1 << 46]>::new();
+        let err = set.try_reserve(4).unwrap_err();
+        assert!(
+            matches!(err, TryReserveError::AllocFailed { layout: Some(layout) } if layout.size() == 1 << 48)
+        );
+        assert_eq!(set.capacity(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "255")]
+    fn reserve_over_limit() {
+        let mut set: BloomSet<u32> = (0..10).collect();
+        set.reserve(246);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "random-seed", ignore = "compares filter bits across sets")]
+    fn grow_from_zero() {
+        let mut set = BloomSet::new();
+        let mut capacities = vec![set.capacity()];
+        for i in 0..255_u64 {
+            set.insert(i);
+            if capacities.last() != Some(&set.capacity()) {
+                capacities.push(set.capacity());
+            }
+        }
+        assert_eq!(capacities, [0, 4, 8, 16, 32, 64, 128, 255]);
+        assert!((0..255).all(|i| set.contains(i)));
+        assert_eq!(
+            set.filter_bits(),
+            (0..255).collect::<BloomSet<u64>>().filter_bits()
+        );
+    }
+
+    #[test]
+    fn grow_from_dangling() {
+        for mut set in [BloomSet::with_capacity(0), BloomSet::with_seed(3)] {
+            assert_eq!(set.capacity(), 0);
+            assert_eq!(set.as_mut_ptr(), NonNull::dangling().as_ptr());
+            set.insert(1_u16);
+            assert_eq!(set.capacity(), 4);
+            assert_ne!(set.as_mut_ptr(), NonNull::dangling().as_ptr());
+            assert!(set.contains(1));
+        }
+
+        let mut set = BloomSet::with_capacity(5);
+        for i in 0..6_u16 {
+            set.insert(i);
+        }
+        assert_eq!((set.len(), set.capacity()), (6, 10));
+    }
+
+    #[test]
+    fn drop_after_many_grows() {
+        for len in [1_u8, 4, 5, 100, 128, 129, 255] {
+            let mut set = BloomSet::new();
+            for i in 0..len {
+                set.insert([i; 8]);
+            }
+            assert!(set.remove([0; 8]));
+            assert_eq!(set.len(), usize::from(len) - 1);
+            set.clear();
+            set.insert([7; 8]);
+            let (vec, _) = set.into_parts();
+            assert_eq!(vec, [[7; 8]]);
+        }
+    }
+
+    #[test]
+    fn bloom_bit_of_byte_hashes() {
+        for hash in 0..256 {
+            assert_eq!(bloom_bit(hash, FILTER_BITS), hash % FILTER_BITS);
+        }
+    }
+
+    /// Passes `u64` elements through as their own hash, so tests can pick the hash.
+    struct IdentityHasher;
+
+    impl ElementHasher<u64> for IdentityHasher {
+        fn hash_element(&self, item: &u64) -> u64 {
+            *item
+        }
+    }
+
+    /// Inserts each hash into a fresh set and one that accumulates them all, and checks both
+    /// find it afterwards.
+    fn insert_then_contains<const K: usize, P: Packing>(hashes: impl Iterator<Item = u64>) {
+        let mut all = BloomSet::<u64, _, K, P>::with_hasher(IdentityHasher);
+        for hash in hashes {
+            let mut one = BloomSet::<u64, _, K, P>::with_hasher(IdentityHasher);
+            one.insert(hash);
+            assert!(one.contains(hash), "{:#x}", hash);
+            assert_eq!(one.filter_bits(), bloom_mask::<K>(hash, P::FILTER_BITS));
+            if all.len() == P::MAX_CAPACITY {
+                all.clear();
+            }
+            all.insert(hash);
+            assert!(all.contains(hash), "{:#x}", hash);
+        }
+    }
+
+    #[test]
+    fn insert_then_contains_every_byte_hash() {
+        insert_then_contains::<1, Packed8>(0..256);
+        insert_then_contains::<3, Packed8>(0..256);
+        insert_then_contains::<1, Packed16>(0..256);
+    }
+
+    #[test]
+    fn insert_then_contains_wide_hashes() {
+        let hashes = || xorshift(0x2545_F491_4F6C_DD1D).take(5000);
+        insert_then_contains::<1, Packed8>(hashes());
+        insert_then_contains::<2, Packed8>(hashes());
+        insert_then_contains::<1, Packed16>(hashes());
+        insert_then_contains::<1, Packed8>(
+            [u64::MAX, 1 << 63, 1 << 32, 0xFF << 56].iter().copied(),
+        );
+    }
+
+    /// Like a float NaN: hashes consistently but is never equal to anything, itself included.
+    struct Unequal(u32);
+
+    impl PartialEq for Unequal {
+        fn eq(&self, _: &Self) -> bool {
+            false
+        }
+    }
+
+    impl Hash for Unequal {
+        fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+            self.0.hash(state);
+        }
+    }
+
+    #[test]
+    fn self_unequal_elements_are_never_found() {
+        let mut set = BloomSet::new();
+        for _ in 0..3 {
+            set.insert(Unequal(1));
+        }
+        assert_eq!(set.len(), 3);
+        assert!(!set.contains(Unequal(1)));
+        assert!(!set.remove(Unequal(1)));
+        assert_eq!(set.len(), 3);
+        assert!(set.try_insert(Unequal(1)).is_ok());
+        assert_eq!(set.len(), 4);
+    }
+
+    #[test]
+    fn chunked_scan_matches_plain_scan() {
+        fn check<T: PartialEq + Copy>(values: &[T], absent: T) {
+            for len in 0..=values.len() {
+                let elements = &values[..len];
+                for item in elements {
+                    assert!(scalar_scan(elements, item));
+                }
+                assert!(!scalar_scan(elements, &absent));
+            }
+        }
+        let values: Vec<u64> = xorshift(7).take(50).map(|x| x | 1).collect();
+        check(&values, 0);
+        #[allow(clippy::cast_possible_truncation)]
+        check(&values.iter().map(|&x| x as u32).collect::<Vec<_>>(), 0);
+        check(&(1..=50_u8).collect::<Vec<_>>(), 0);
+        check(&values.iter().map(|&x| (x, x)).collect::<Vec<_>>(), (0, 0));
+
+        let strings: Vec<String> = (0..40).map(|i| i.to_string()).collect();
+        assert!(strings.iter().all(|it| scalar_scan(&strings, it)));
+        assert!(!scalar_scan(&strings, &"x".to_string()));
+    }
+
+    #[test]
+    fn custom_hasher() {
+        use std::collections::hash_map::RandomState;
+
+        let mut set: BloomSet<_, _> = BloomSet::with_capacity_and_hasher(4, RandomState::new());
+        for i in 0..200u32 {
+            set.insert(i * 7919);
+        }
+        assert_eq!(set.len(), 200);
+        assert!((0..200u32).all(|i| set.contains(i * 7919)));
+        assert!(!(0..200u32).any(|i| set.contains(i * 7919 + 1)));
+
+        let set: BloomSet<_, _> =
+            BloomSet::try_from_iter_with_hasher(["a", "b"], RandomState::new()).unwrap();
+        assert!(set.contains("a") && set.contains("b") && !set.contains("c"));
+        let _: &RandomState = set.hasher();
+    }
+
+    #[test]
+    fn agrees_with_hashset_using_same_hasher() {
+        use std::collections::HashSet;
+
+        let data = [
+            "The",
+            "Rust",
+            "Programming",
+            "Language",
+            "Rust",
+            "a",
+            "ab",
+            "ba",
+        ];
+        let set: BloomSet<&str> = data.iter().copied().collect();
+        let mut std_set = HashSet::with_hasher(BuildBloomHasher);
+        std_set.extend(data.iter().copied());
+
+        assert_eq!(set.len(), std_set.len());
+        for probe in data.iter().chain(&["rust", "", "b", "Lang", "aa"]) {
+            assert_eq!(set.contains(probe), std_set.contains(probe), "{probe}");
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "no-filter", ignore = "checks the filter bits")]
+    #[allow(clippy::cast_possible_truncation)]
+    fn bloom_index_is_exact() {
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        struct Bit(u8);
+        impl BloomIndex for Bit {
+            fn bloom_bit(&self) -> u8 {
+                self.0
+            }
+        }
+
+        // The middle two straddle the capacity and length words
+        let low_bits = Packed8::LOW_BITS as u8;
+        let all = [
+            Bit(0),
+            Bit(low_bits - 1),
+            Bit(low_bits),
+            Bit(FILTER_BITS as u8 - 1),
+        ];
+        let mut expected = 0;
+        let mut set: BloomSet<Bit, _> = BloomSet::with_hasher(ByBloomIndex);
+        for (i, elem) in all.iter().enumerate() {
+            assert!(!set.contains(elem));
+            set.insert(*elem);
+            expected |= 1 << elem.0;
+            assert_eq!(set.filter_bits(), expected, "{elem:?}");
+            assert!(all[..=i].iter().all(|e| set.contains(e)));
+            assert!(!all[i + 1..]
+                .iter()
+                .any(|e| set.bloom_contains(set.bloom_mask_of(e))));
+        }
+        assert_eq!(set.len(), 4);
+    }
+
+    /// Tests run against every packing, so they can't drift apart.
+    macro_rules! packing_tests {
+        ($($name:ident: $packing:ty $(, #[$fill_attr:meta])?;)*) => {$(
+            mod $name {
+                use super::*;
+
+                type Set<T> = BloomSet<T, BuildBloomHasher, 1, $packing>;
+                const MAX: usize = <$packing>::MAX_CAPACITY;
+
+                #[test]
+                fn filter_bits_round_trip() {
+                    for bit in 0..<$packing>::FILTER_BITS {
+                        let (low, high) = split_mask::<$packing>(1 << bit);
+                        assert_eq!((low | high) & <$packing>::COUNT_MASK, 0, "{bit}");
+                        assert_eq!(low & <$packing>::SEED_MASK, 0, "{bit}");
+                        assert_eq!(high & <$packing>::SORTED_FLAG, 0, "{bit}");
+                        assert_eq!(unpack_filter::<$packing>(high, low), 1 << bit, "{bit}");
+                    }
+                }
+
+                #[test]
+                fn agrees_with_hashset() {
+                    use std::collections::HashSet;
+
+                    let elements: Vec<u64> = xorshift(5).map(|x| x % 250).take(400).collect();
+                    let mut set = Set::with_capacity_and_hasher(3, BuildBloomHasher);
+                    let mut std_set = HashSet::new();
+                    for elem in &elements {
+                        set.insert(*elem);
+                        std_set.insert(*elem);
+                        assert_eq!(set.len(), std_set.len());
+                    }
+                    for probe in 0..300 {
+                        assert_eq!(set.contains(probe), std_set.contains(&probe), "{probe}");
+                    }
+                    for elem in &elements[..100] {
+                        assert_eq!(set.remove(elem), std_set.remove(elem));
+                    }
+                    assert!((0..300).all(|p| set.contains(p) == std_set.contains(&p)));
+                }
+
+                #[test]
+                $(#[$fill_attr])?
+                fn fills_to_max_capacity() {
+                    let set = Set::try_from_iter_with_hasher(
+                        (0..MAX).filter(|_| true),
+                        BuildBloomHasher,
+                    )
+                    .unwrap();
+                    assert_eq!((set.len(), set.capacity()), (MAX, MAX));
+                    assert!(set.contains(0) && set.contains(MAX - 1));
+                    assert!(!set.contains(MAX));
+
+                    let err = Set::try_from_iter_with_hasher(0..=MAX, BuildBloomHasher)
+                        .err()
+                        .unwrap();
+                    assert_eq!((err.requested(), err.limit()), (MAX + 1, MAX));
+                }
+
+                #[test]
+                fn clear() {
+                    let mut set: Set<u32> = (0..50).collect();
+                    let capacity = set.capacity();
+                    set.clear();
+                    assert!(set.is_empty());
+                    assert_eq!((set.filter_bits(), set.capacity()), (0, capacity));
+                    assert!(!set.contains(1));
+                    set.insert(1);
+                    assert!(set.contains(1));
+                }
+            }
+        )*};
+    }
+
+    packing_tests! {
+        packed8: Packed8;
+        // Every insert near the limit scans tens of thousands of elements
+        packed16: Packed16, #[cfg_attr(debug_assertions, ignore = "slow without optimizations")];
+    }
+
+    /// A deterministic stream of well-mixed `u64`s.
+    fn xorshift(mut x: u64) -> impl Iterator<Item = u64> {
+        std::iter::repeat_with(move || {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x
+        })
+    }
+
+    #[test]
+    fn two_bits_per_element() {
+        let elements: Vec<u64> = xorshift(1).take(255).collect();
+        let mut set: BloomSet<u64, _, 2> = BloomSet::with_hasher(BuildBloomHasher);
+        for (i, elem) in elements.iter().enumerate() {
+            set.insert(*elem);
+            assert!(elements[..=i].iter().all(|e| set.contains(e)));
+        }
+        assert_eq!(set.len(), 255);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "no-filter", ignore = "checks the filter bits")]
+    fn two_bits_lower_false_positive_rate() {
+        let elements: Vec<u64> = xorshift(2).take(30).collect();
+        // The bounds below are for these seeds; random ones would move the rates around
+        let mut one: BloomSet<u64> = BloomSet::with_seed(0);
+        let mut two: BloomSet<u64, BuildBloomHasher, 2> =
+            BloomSet::builder().seed(0).bits::<2>().build().unwrap();
+        one.extend(elements.iter().copied());
+        two.extend(elements.iter().copied());
+
+        // The probes are all absent, so any filter hit is a false positive
+        let probes: Vec<u64> = xorshift(3).take(10_000).collect();
+        let rate = |contains: &dyn Fn(u64) -> bool| probes.iter().filter(|&&p| contains(p)).count();
+        let one_rate = rate(&|p| one.bloom_contains(one.bloom_mask_of(&p)));
+        let two_rate = rate(&|p| two.bloom_contains(two.bloom_mask_of(&p)));
+        // Theory predicts about 27% and 22% for 30 elements
+        assert!(two_rate < one_rate, "{} vs {}", two_rate, one_rate);
+        assert!((2300..3100).contains(&one_rate), "{}", one_rate);
+        assert!((1900..2700).contains(&two_rate), "{}", two_rate);
+    }
+
+    /// The fraction of `probes` random absent keys that get past the filters of 20 sets of `n`
+    /// random keys each, split evenly between them.
+    #[allow(clippy::cast_precision_loss)]
+    fn false_positive_rate<S: BuildHasher + Clone, const K: usize>(
+        hash_builder: &S,
+        n: usize,
+        probes: usize,
+    ) -> f64 {
+        let mut random = xorshift(0x2545_F491_4F6C_DD1D + n as u64);
+        let mut hits = 0;
+        for _ in 0..20 {
+            let mut set = BloomSet::<u64, S, K>::with_hasher(hash_builder.clone());
+            set.extend(random.by_ref().take(n));
+            // xorshift never repeats within its period, so these are all absent
+            hits += random
+                .by_ref()
+                .take(probes / 20)
+                .filter(|probe| set.bloom_contains(set.bloom_mask_of(probe)))
+                .count();
+        }
+        hits as f64 / probes as f64
+    }
+
+    /// The textbook false positive rate of a bloom filter of `m` bits holding `n` elements with
+    /// `k` bits each, assuming every element's bits are independent and uniform.
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_possible_wrap
+    )]
+    fn expected_false_positive_rate(m: u64, k: usize, n: usize) -> f64 {
+        let unset = (1.0 - 1.0 / m as f64).powi((k * n) as i32);
+        (1.0 - unset).powi(k as i32)
+    }
+
+    #[test]
+    #[cfg_attr(feature = "no-filter", ignore = "checks the filter bits")]
+    fn false_positive_rate_matches_theory() {
+        fn check<S: BuildHasher + Clone, const K: usize>(name: &str, hash_builder: &S) {
+            for n in [16, 64, 128] {
+                let measured = false_positive_rate::<S, K>(hash_builder, n, 10_000);
+                let expected = expected_false_positive_rate(FILTER_BITS, K, n);
+                // Sampling noise is about a percentage point; the rest of the band is room
+                // for a hasher that is merely decent
+                assert!(
+                    (measured - expected).abs() < 0.03,
+                    "{} with K = {} and {} elements: {:.3} false positives, expected {:.3}",
+                    name,
+                    K,
+                    n,
+                    measured,
+                    expected
+                );
+            }
+        }
+        let sip =
+            std::hash::BuildHasherDefault::<std::collections::hash_map::DefaultHasher>::default();
+        check::<_, 1>("BloomHasher", &BuildBloomHasher);
+        check::<_, 2>("BloomHasher", &BuildBloomHasher);
+        check::<_, 1>("SipHasher", &sip);
+        check::<_, 2>("SipHasher", &sip);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "no-filter", ignore = "checks the filter bits")]
+    fn seeds_spread_elements() {
+        let filters: Vec<u128> = (0..=u8::MAX)
+            .map(|seed| {
+                let mut set = BloomSet::with_seed(seed);
+                set.insert("shared");
+                assert_eq!(set.seed(), seed);
+                assert_eq!(set.filter_bits().count_ones(), 1);
+                set.filter_bits()
+            })
+            .collect();
+        // Seed 0 is the same as no seed
+        #[cfg(not(feature = "random-seed"))]
+        {
+            let mut unseeded = BloomSet::new();
+            unseeded.insert("shared");
+            assert_eq!(unseeded.filter_bits(), filters[0]);
+        }
+
+        let mut distinct = filters;
+        distinct.sort_unstable();
+        distinct.dedup();
+        assert!(distinct.len() > 50, "{}", distinct.len());
+    }
+
+    #[test]
+    #[cfg(feature = "random-seed")]
+    fn random_seeds_scatter_colliding_keys() {
+        // Keys that all land on one filter bit in an unseeded set
+        let unseeded = BloomSet::<u64>::with_seed(0);
+        let target = unseeded.bloom_mask_of(&0);
+        let colliding: Vec<u64> = (0..100_000)
+            .filter(|key| unseeded.bloom_mask_of(key) == target)
+            .take(50)
+            .collect();
+        assert_eq!(colliding.len(), 50);
+
+        let sets: Vec<BloomSet<u64>> = (0..8)
+            .map(|_| colliding.iter().copied().collect())
+            .collect();
+        let mut seeds: Vec<u8> = sets.iter().map(BloomSet::seed).collect();
+        seeds.sort_unstable();
+        seeds.dedup();
+        assert!(seeds.len() > 1, "every set got seed {}", seeds[0]);
+        for set in sets.iter().filter(|set| set.seed() != 0) {
+            assert!(
+                set.filter_bits().count_ones() > 20,
+                "{:#x}",
+                set.filter_bits()
+            );
+        }
+        // An explicit seed still wins
+        assert_eq!(BloomSet::<u64>::with_capacity_and_seed(4, 9).seed(), 9);
+        assert_eq!(BloomSet::<u64>::with_seed(0).seed(), 0);
+    }
+
+    #[test]
+    fn seeded_lookups() {
+        for seed in [1, 77, 255] {
+            let mut set = BloomSet::with_capacity_and_seed(4, seed);
+            let elements: Vec<u64> = xorshift(u64::from(seed)).take(200).collect();
+            for elem in &elements {
+                set.insert(*elem);
+            }
+            assert_eq!(set.len(), 200);
+            assert_eq!(set.seed(), seed);
+            assert!(elements.iter().all(|e| set.contains(e)));
+            assert!(xorshift(1000).take(200).all(|e| !set.contains(e)));
 
-        set.insert(2);
-        assert_eq!(set.len(), 2);
+            set.clear();
+            assert_eq!(set.seed(), seed);
+            assert_eq!(set.filter_bits(), 0);
+        }
+    }
 
-        set.insert(31);
+    #[test]
+    fn try_from_iter_boundary() {
+        let set = BloomSet::try_from_iter(0..255u16).unwrap();
+        assert_eq!(set.len(), 255);
+        assert!((0..255u16).all(|i| set.contains(i)));
+        assert!(!set.contains(255u16));
+
+        let err = BloomSet::try_from_iter(0..256u16).err().unwrap();
+        assert_eq!(err.requested(), 256);
+
+        // Without a useful size hint the set has to grow all the way to the limit
+        let err = BloomSet::try_from_iter((0..1000u16).filter(|_| true))
+            .err()
+            .unwrap();
+        assert_eq!(err.requested(), 256);
+    }
+
+    #[test]
+    fn try_from_iter_duplicates_do_not_consume_capacity() {
+        let set = BloomSet::try_from_iter((0..1000u16).map(|i| i % 255)).unwrap();
+        assert_eq!(set.len(), 255);
+
+        let set = BloomSet::try_from_iter((0..600u16).filter(|_| true).map(|i| i % 200)).unwrap();
+        assert_eq!(set.len(), 200);
+    }
+
+    /// Collects `items` in bulk and, by hiding their number, one at a time, and checks both
+    /// ways build the same set.
+    fn collect_both_ways<T: Hash + PartialEq + Clone + core::fmt::Debug, P: Packing>(
+        items: &[T],
+        seed: u8,
+    ) -> BloomSet<T, BuildBloomHasher, 1, P> {
+        let mut bulk = BloomSet::<T, _, 1, P>::with_hasher(BuildBloomHasher);
+        let mut single = BloomSet::<T, _, 1, P>::with_hasher(BuildBloomHasher);
+        bulk.capacity |= usize::from(seed) << SEED_SHIFT;
+        single.capacity |= usize::from(seed) << SEED_SHIFT;
+        bulk.extend(items.iter().cloned());
+        single.extend(items.iter().filter(|_| true).cloned());
+        assert_eq!(bulk.as_slice(), single.as_slice());
+        assert_eq!(bulk.filter_bits(), single.filter_bits());
+        bulk
+    }
+
+    #[test]
+    fn bulk_matches_one_at_a_time() {
+        let strings: Vec<String> = (0..1000).map(|i| (i * 7 % 250).to_string()).collect();
+        for len in [BULK_THRESHOLD - 1, BULK_THRESHOLD, 100, 254, 255, 1000] {
+            let set = collect_both_ways::<_, Packed8>(&strings[..len], 0);
+            let unique: std::collections::HashSet<_> = strings[..len].iter().collect();
+            assert_eq!(set.len(), unique.len());
+            collect_both_ways::<_, Packed8>(&strings[..len], 9);
+        }
+        // A set that already has elements, including some of the new ones
+        let mut set: BloomSet<String> = strings[..20].iter().cloned().collect();
+        set.extend(strings[10..100].iter().cloned());
+        assert_eq!(set.as_slice(), &strings[..100]);
+
+        let numbers: Vec<u32> = (0..3000).map(|i| i * 7 % 2000).collect();
+        for len in [CHEAP_BULK_THRESHOLD - 1, CHEAP_BULK_THRESHOLD, 3000] {
+            collect_both_ways::<_, Packed16>(&numbers[..len], 3);
+        }
+    }
+
+    #[test]
+    fn bulk_fails_only_on_a_new_element() {
+        let unique: Vec<String> = (0..256).map(|i| i.to_string()).collect();
+        let err = BloomSet::try_from_iter(unique.iter()).err().unwrap();
+        assert_eq!((err.requested(), err.limit()), (256, 255));
+        let repeated = unique[..255].iter().cycle().take(2000);
+        assert_eq!(BloomSet::try_from_iter(repeated).unwrap().len(), 255);
+    }
+
+    /// Too big to count as cheap to compare, and counts its comparisons.
+    #[derive(Clone, Debug)]
+    struct CountsEq(u128);
+
+    std::thread_local! {
+        static COMPARISONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    impl PartialEq for CountsEq {
+        fn eq(&self, other: &Self) -> bool {
+            COMPARISONS.with(|count| count.set(count.get() + 1));
+            self.0 == other.0
+        }
+    }
+
+    impl Hash for CountsEq {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.0.hash(state);
+        }
+    }
+
+    #[test]
+    fn bulk_threshold() {
+        let comparisons = |len: u64| {
+            COMPARISONS.with(|count| count.set(0));
+            let set: BloomSet<CountsEq> = (0..len).map(|i| CountsEq(i.into())).collect();
+            assert_eq!(set.len() as u64, len);
+            COMPARISONS.with(Cell::get)
+        };
+        // One at a time, false positives in the filter lead to comparisons; in bulk, elements
+        // with distinct hashes are never compared
+        assert!(comparisons(BULK_THRESHOLD as u64 - 1) > 0);
+        assert_eq!(comparisons(BULK_THRESHOLD as u64), 0);
+        assert_eq!(comparisons(255), 0);
+        assert!(!cheap_to_compare::<CountsEq>() && !cheap_to_compare::<String>());
+        assert!(cheap_to_compare::<u64>() && cheap_to_compare::<(u32, u16)>());
+    }
+
+    #[test]
+    fn bulk_panic_safety() {
+        let mut set: BloomSet<Fragile> = (0..10).map(Fragile::new).collect();
+        let filter = set.filter_bits();
+        // Comparing a duplicate panics, so the set keeps only what it had and the batch leaks
+        PANIC_IN_EQ.with(|cue| cue.set(Some(5)));
+        catch(|| set.extend((0..100).map(Fragile::new)));
+        assert_eq!((set.len(), set.filter_bits()), (10, filter));
+        LIVE.with(|live| live.set(10));
+
+        // Dropping a duplicate panics once the new elements are in
+        PANIC_IN_DROP.with(|cue| cue.set(Some(5)));
+        catch(|| set.extend((0..100).map(Fragile::new)));
+        assert_eq!(set.len(), 100);
+        assert!((0..100).all(|key| set.as_slice()[key as usize].0 == key));
+        assert_eq!(LIVE.with(Cell::get), 100);
+        drop(set);
+        assert_eq!(LIVE.with(Cell::get), 0);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "random-seed", ignore = "compares filter bits across sets")]
+    fn retain_visits_each_element_once() {
+        let mut bits = 0x2545_F491_4F6C_DD1D_u64;
+        for round in 0..200 {
+            let ordered = round % 2 == 0;
+            let len = round % 70;
+            // A random half, or a random few, or all but a few
+            let odds = [2, 8, 1][round % 3];
+            let keep: Vec<bool> = (0..len)
+                .map(|_| {
+                    bits ^= bits << 13;
+                    bits ^= bits >> 7;
+                    bits ^= bits << 17;
+                    (bits % 9 < odds) != (odds == 1)
+                })
+                .collect();
+
+            let all: Vec<String> = (0..len).map(|i| i.to_string()).collect();
+            let mut set: BloomSet<String> = all.iter().cloned().collect();
+            let mut visits = alloc::vec![0; len];
+            let mut order = Vec::new();
+            let visit = |item: &String| {
+                let i: usize = item.parse().unwrap();
+                visits[i] += 1;
+                order.push(i);
+                keep[i]
+            };
+            if ordered {
+                set.retain(visit);
+            } else {
+                set.retain_unordered(visit);
+            }
+            assert!(visits.iter().all(|&n| n == 1));
+            if ordered {
+                assert!(order.windows(2).all(|w| w[0] < w[1]));
+            }
+
+            let mut kept: Vec<&String> = set.as_slice().iter().collect();
+            if !ordered {
+                kept.sort_by_key(|item| item.parse::<usize>().unwrap());
+            }
+            let expected: Vec<&String> = all
+                .iter()
+                .filter(|item| keep[item.parse::<usize>().unwrap()])
+                .collect();
+            assert_eq!(kept, expected);
+            let fresh: BloomSet<String> = expected.iter().map(|&item| item.clone()).collect();
+            assert_eq!(set.filter_bits(), fresh.filter_bits());
+            assert!(all
+                .iter()
+                .zip(&keep)
+                .all(|(item, &keep)| set.contains(item) == keep));
+        }
+    }
+
+    #[test]
+    fn retain_keeps_the_seed_and_sorted_mark() {
+        let mut set: BloomSet<u32> = BloomSet::with_seed(9);
+        set.extend((0..50).rev());
+        set.sort();
+        set.retain(|&i| i % 2 == 0);
+        assert!(set.is_sorted() && set.seed() == 9 && set.len() == 25);
+        set.retain_unordered(|_| true);
+        assert!(set.is_sorted());
+        set.retain_unordered(|&i| i != 48);
+        assert!(!set.is_sorted());
+        assert!((0..50).all(|i| set.contains(i) == (i % 2 == 0 && i != 48)));
+
+        set.retain(|_| false);
+        assert!(set.is_empty() && set.filter_bits() == 0 && set.seed() == 9);
+        set.insert(3);
+        assert!(set.contains(3));
+    }
+
+    #[test]
+    fn retain_panic_safety() {
+        for ordered in [true, false] {
+            let mut set: BloomSet<Fragile> = (0..10).map(Fragile::new).collect();
+            PANIC_IN_DROP.with(|cue| cue.set(Some(4)));
+            if ordered {
+                catch(|| set.retain(|item| item.0 % 2 == 1));
+            } else {
+                catch(|| set.retain_unordered(|item| item.0 % 2 == 1));
+            }
+            // Nothing is dropped twice; what wasn't dropped yet leaks. Unordered, 8 is visited
+            // and dropped before 4.
+            assert!(set.is_empty());
+            let leaked = LIVE.with(Cell::get);
+            assert_eq!(leaked, if ordered { 7 } else { 6 });
+            set.insert(Fragile::new(20));
+            assert!(set.contains(Fragile::new(20)));
+            drop(set);
+            assert_eq!(LIVE.with(Cell::get), leaked);
+            LIVE.with(|live| live.set(0));
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "no-filter", ignore = "checks the filter bits")]
+    fn saturated_lookups() {
+        let mut set = BloomSet::new();
+        for i in 0..220_u32 {
+            set.insert(i);
+            assert!((0..=i).all(|e| set.contains(e)));
+            assert!(!(1000..1050).any(|e| set.contains(e)));
+        }
+        assert!(set.filter_bits().count_ones() >= SATURATION_THRESHOLD);
+    }
+
+    #[test]
+    fn sorted_lookups() {
+        let mut set: BloomSet<u32> = (0..200).map(|i| i * 7919 % 1000).collect();
+        assert!(!set.is_sorted());
+        let filter = set.filter_bits();
+        set.sort();
+        assert!(set.is_sorted());
+        assert_eq!(set.filter_bits(), filter);
+        assert!(set.as_slice().windows(2).all(|w| w[0] < w[1]));
+        assert!((0..1000).all(|i| set.contains_sorted(i) == set.contains(i)));
+
+        // Removing keeps the order, inserting doesn't
+        assert!(set.remove(set.as_slice()[10]));
+        assert!(set.is_sorted() && set.len() == 199);
+        set.insert(1000);
+        assert!(!set.is_sorted());
+        assert_eq!(set.len(), 200);
+        assert!((0..=1000).all(|i| set.contains_sorted(i) == set.contains(i)));
+
+        set.sort();
+        set.insert(1000);
+        assert!(
+            set.is_sorted(),
+            "inserting an element already present changes nothing"
+        );
+        set.clear();
+        assert!(!set.is_sorted() && set.filter_bits() == 0);
+    }
+
+    #[test]
+    fn refs_without_cloning() {
+        #[derive(Hash, PartialEq)]
+        struct Large([u64; 16], &'static str);
+
+        let items: Vec<Large> = ["a", "b", "a", "c", "b"]
+            .iter()
+            .map(|&name| Large([7; 16], name))
+            .collect();
+        let set = BloomSet::from_refs(&items);
         assert_eq!(set.len(), 3);
-        set.insert(31);
+        assert!(set
+            .as_slice()
+            .iter()
+            .all(|item| items.iter().any(|it| ptr::eq(it, *item))));
+        assert!(set.contains(&items[4]));
+        assert!(!set.contains(&Large([7; 16], "d")));
+        assert_eq!(BloomSet::duplicate_indices(&items), [2, 4]);
+        assert!(BloomSet::<&Large>::duplicate_indices(&[]).is_empty());
+    }
+
+    #[test]
+    fn remove() {
+        let mut set: BloomSet<u8> = [1, 2, 3, 4].iter().copied().collect();
+        assert!(set.remove(2));
+        assert!(!set.remove(2));
+        assert!(!set.remove(9));
+        assert_eq!(set.as_slice(), &[1, 3, 4]);
+        assert!(!set.contains(2) && set.contains(3));
+        set.insert(2);
+        assert_eq!(set.as_slice(), &[1, 3, 4, 2]);
+    }
+
+    #[test]
+    fn from_iter() {
+        let set: BloomSet<u8> = [1, 2, 3, 2, 1].iter().copied().collect();
+        assert_eq!(set.as_slice(), &[1, 2, 3]);
+
+        let set: BloomSet<u8> = std::iter::empty().collect();
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot hold more than 255 elements")]
+    fn from_iter_over_capacity() {
+        let _: BloomSet<u16> = (0..256).collect();
+    }
+
+    #[test]
+    fn display() {
+        let empty: BloomSet<&str> = BloomSet::new();
+        assert_eq!(empty.to_string(), "");
+        let mut one = BloomSet::new();
+        one.insert("only");
+        assert_eq!(one.to_string(), "only");
+        let mut set: BloomSet<u32> = [3, 1, 2, 1].iter().copied().collect();
+        assert_eq!(set.to_string(), "3, 1, 2");
+        set.remove(1);
+        assert_eq!(format!("[{set}]"), "[3, 2]");
+        // Flags apply to each element, not to the whole list
+        assert_eq!(format!("{set:>3}"), "  3,   2");
+    }
+
+    #[test]
+    fn get_or_try_insert_with() {
+        let mut set: BloomSet<String> = ["a", "b"].iter().map(ToString::to_string).collect();
+        let found = set
+            .get_or_try_insert_with("b", |_| -> Result<String, ()> { panic!("called on a hit") });
+        assert_eq!(found.map(String::as_str), Ok("b"));
+
+        let before = (set.filter_bits(), set.len(), set.capacity());
+        for key in (0..200).map(|i| i.to_string()) {
+            assert_eq!(set.get_or_try_insert_with(key.as_str(), |_| Err(7)), Err(7));
+        }
+        assert_eq!((set.filter_bits(), set.len(), set.capacity()), before);
+        assert_eq!(set.as_slice(), ["a", "b"]);
+
+        let made = set.get_or_try_insert_with("c", |key| Ok::<_, ()>(key.to_string()));
+        assert_eq!(made.map(String::as_str), Ok("c"));
+        assert!(set.contains("c".to_string()));
+        // A key that builds an element already there under another key inserts nothing
+        let upper = set.get_or_try_insert_with("A", |key| Ok::<_, ()>(key.to_lowercase()));
+        assert_eq!(upper.map(String::as_str), Ok("a"));
+        assert_eq!(set.as_slice(), ["a", "b", "c"]);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "no-filter", ignore = "checks the filter bits")]
+    fn rebuild_filter() {
+        let mut set: BloomSet<u32> = BloomSet::with_seed(0);
+        set.extend(0..100);
+        set.sort();
+        for i in 10..100 {
+            set.remove(i);
+        }
+        set.rebuild_filter();
+        let mut fresh = BloomSet::with_seed(0);
+        fresh.extend(0..10);
+        assert_eq!(set.filter_bits(), fresh.filter_bits());
+        assert!(set.is_sorted() && set.len() == 10 && set.seed() == 0);
+        assert!((0..10).all(|i| set.contains(i)));
+    }
+
+    #[test]
+    fn filter_bits_set() {
+        let mut set: BloomSet<u64> = BloomSet::new();
+        assert_eq!(set.filter_bits_set(), 0);
+        let mut random = 0x2545_F491_4F6C_DD1D_u64;
+        for step in 0..5000 {
+            random ^= random << 13;
+            random ^= random >> 7;
+            random ^= random << 17;
+            let item = random % 300;
+            match random >> 60 {
+                0..=9 => drop(set.try_insert(item)),
+                10..=12 => drop(set.remove(item)),
+                13 => set.rebuild_filter(),
+                14 => set.retain(|&it| it % 3 != 0),
+                _ => set.clear(),
+            }
+            assert_eq!(
+                set.filter_bits_set(),
+                set.filter_bits().count_ones(),
+                "{step}"
+            );
+            // Removals leave bits behind, so a fresh filter has at most as many
+            let mut fresh = BloomSet::with_seed(set.seed());
+            fresh.extend(set.as_slice().iter().copied());
+            assert!(fresh.filter_bits_set() <= set.filter_bits_set(), "{}", step);
+            if random >> 60 >= 13 {
+                assert_eq!(set.filter_bits_set(), fresh.filter_bits_set(), "{step}");
+            }
+        }
+    }
+
+    #[test]
+    fn filter_bits_set_through_every_write() {
+        // Two bits per element, a wider packing, and the bulk and clone paths
+        let mut random = 0x9E37_79B9_7F4A_7C15_u64;
+        let mut set: BloomSet16<u64, BuildBloomHasher, 2> =
+            BloomSet16::with_hasher(BuildBloomHasher);
+        for step in 0..2000 {
+            random ^= random << 13;
+            random ^= random >> 7;
+            random ^= random << 17;
+            let item = random % 2000;
+            match random >> 60 {
+                0..=7 => set.insert(item),
+                8 => set.extend(item..item + 60),
+                9..=11 => drop(set.remove(item)),
+                12 if !set.is_empty() => drop(set.swap_remove_index(0)),
+                13 => set = set.clone(),
+                14 => set.retain(|&it| it % 5 != 0),
+                _ => set.clear(),
+            }
+            assert_eq!(
+                set.filter_bits_set(),
+                set.filter_bits().count_ones(),
+                "{step}"
+            );
+        }
+    }
+
+    #[test]
+    fn from_unique_iter() {
+        let set: BloomSet<u32> = BloomSet::from_unique_iter((0..255).rev());
+        let mut collected = BloomSet::with_seed(set.seed());
+        collected.extend((0..255).rev());
+        assert_eq!(set.as_slice(), collected.as_slice());
+        assert_eq!(set.filter_bits(), collected.filter_bits());
+        assert!(!set.is_sorted());
+        let empty: BloomSet<u32> = BloomSet::from_unique_iter(None);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "from_unique_iter was given a duplicate")]
+    fn from_unique_iter_checks_in_debug() {
+        let _: BloomSet<u32> = BloomSet::from_unique_iter([1, 2, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot hold more than 255 elements")]
+    fn from_unique_iter_over_capacity() {
+        let _: BloomSet<u32> = BloomSet::from_unique_iter((0..256).filter(|_| true));
+    }
+
+    #[test]
+    fn from_sorted_slice() {
+        let elements: Vec<String> = (10..99).map(|i| i.to_string()).collect();
+        let set: BloomSet<String> = BloomSet::from_sorted_slice(&elements);
+        assert_eq!(set.as_slice(), &elements[..]);
+        assert!(set.is_sorted() && set.contains_sorted("42".to_string()));
+        let mut collected = BloomSet::with_seed(set.seed());
+        collected.extend(elements.iter().cloned());
+        assert_eq!(set.filter_bits(), collected.filter_bits());
+        let empty: BloomSet<u8> = BloomSet::from_sorted_slice(&[]);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "from_sorted_slice was given elements out of order at index 2")]
+    fn from_sorted_slice_rejects_duplicates() {
+        let _: BloomSet<u32> = BloomSet::from_sorted_slice(&[1, 2, 2, 3]);
+    }
+
+    #[test]
+    fn sorted_searches() {
+        let mut set: BloomSet<u32> = (0..100).map(|i| i * 7 % 101).collect();
+        set.sort();
+        let slice = set.as_slice().to_vec();
+        for target in 0..110 {
+            assert_eq!(set.binary_search(&target), slice.binary_search(&target));
+            assert_eq!(
+                set.binary_search_by(|it| it.cmp(&target)),
+                slice.binary_search_by(|it| it.cmp(&target))
+            );
+            assert_eq!(
+                set.partition_point(|&it| it < target),
+                slice.partition_point(|&it| it < target)
+            );
+        }
+        // Removing keeps the order, and so does appending larger elements, though that clears
+        // the sorted mark
+        set.remove(50);
+        set.insert(200);
+        assert!(!set.is_sorted());
+        assert_eq!(set.binary_search(&51), Ok(50));
+        assert_eq!(set.binary_search(&200), Ok(99));
+        let empty: BloomSet<u32> = BloomSet::new();
+        assert_eq!(empty.binary_search(&1), Err(0));
+        assert_eq!(empty.partition_point(|_| true), 0);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "binary searched a set that isn't sorted")]
+    fn binary_search_checks_the_order_in_debug() {
+        let set: BloomSet<u32> = [3, 1, 2].iter().copied().collect();
+        let _ = set.binary_search(&1);
+    }
+
+    #[test]
+    fn extend_count() {
+        let mut set: BloomSet<u32> = BloomSet::new();
+        assert_eq!(set.extend_count(0..10), 10);
+        assert_eq!(set.extend_count(0..10), 0);
+        assert_eq!(set.extend_count([5, 10, 5, 11, 0].iter().copied()), 2);
+        assert_eq!(set.extend_count(None), 0);
+        // Enough to take the bulk path
+        assert_eq!(set.extend_count(0..200), 188);
+        assert_eq!(set.len(), 200);
+        assert_eq!(set.extend_count((150..255).filter(|_| true)), 55);
+        assert_eq!(set.extend_count(0..255), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot hold more than 255 elements")]
+    fn extend_count_over_capacity() {
+        let mut set: BloomSet<u32> = (0..250).collect();
+        set.extend_count(245..260);
+    }
+
+    #[test]
+    fn get_many() {
+        let set: BloomSet<String> = (0..200).map(|i| i.to_string()).collect();
+        let found = set.get_many(["7", "x", "199", "7", ""]);
+        assert_eq!(found[0], set.get("7".to_string()));
+        assert_eq!(found[2], set.get("199".to_string()));
+        assert!(found[1].is_none() && found[4].is_none());
+        assert!(core::ptr::eq(found[0].unwrap(), found[3].unwrap()));
+        assert_eq!(set.get_many::<str, 0>([]), []);
+
+        let keys: Vec<String> = (150..250).map(|i| i.to_string()).collect();
+        for window in keys.windows(8) {
+            let many = set.get_many([0, 1, 2, 3, 4, 5, 6, 7].map(|i| window[i].as_str()));
+            let one_by_one = [0, 1, 2, 3, 4, 5, 6, 7].map(|i| set.get(window[i].clone()));
+            assert_eq!(many, one_by_one);
+        }
+    }
+
+    #[test]
+    fn missing_from() {
+        let set: BloomSet<String> = ["a", "b", "c"].iter().map(ToString::to_string).collect();
+        let queries = ["x", "a", "y", "x", "c", "a", "z"].map(String::from);
+        assert_eq!(
+            set.missing_from(&queries),
+            [&queries[0], &queries[2], &queries[3], &queries[6]]
+        );
+        assert_eq!(set.missing_from(queries), ["x", "y", "x", "z"]);
+        assert!(set.missing_from(set.as_slice()).is_empty());
+        assert!(set.missing_from(Vec::<String>::new()).is_empty());
+        let empty: BloomSet<u32> = BloomSet::new();
+        assert_eq!(empty.missing_from(0..3), [0, 1, 2]);
+    }
+
+    #[test]
+    fn move_to() {
+        let mut pending = BloomSet::<u32>::new();
+        pending.extend(0..10);
+        let mut active: BloomSet<u32> = (100..104).chain([5]).collect();
+        assert!(pending.move_to(&mut active, &3));
+        assert_eq!(pending.as_slice(), [0, 1, 2, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(active.as_slice(), [100, 101, 102, 103, 5, 3]);
+        assert!(active.contains(3));
+
+        // Missing from the source
+        assert!(!pending.move_to(&mut active, &3));
+        assert!(!pending.move_to(&mut active, &42));
+        // Already in the destination: both keep theirs
+        assert!(!pending.move_to(&mut active, &5));
+        assert!(pending.contains(5) && active.contains(5));
+        assert_eq!((pending.len(), active.len()), (9, 6));
+    }
+
+    #[test]
+    fn move_to_a_full_set_loses_nothing() {
+        let mut source: BloomSet<String> = core::iter::once("last".to_string()).collect();
+        let mut full: BloomSet<String> = (0..255).map(|i| i.to_string()).collect();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            source.move_to(&mut full, "last")
+        }));
+        assert!(result.is_err());
+        assert_eq!(source.as_slice(), ["last"]);
+        assert_eq!(full.len(), 255);
+        // A duplicate is fine even when full
+        source.insert("7".to_string());
+        assert!(!source.move_to(&mut full, "7"));
+    }
+
+    #[test]
+    fn absorb_straddling_the_limit() {
+        let mut set: BloomSet<u32> = (0..250).collect();
+        // Duplicates don't count towards the limit, so the batch fails at 1005, its sixth new
+        // element, and keeps it and everything after
+        let mut batch: Vec<u32> = (1000..1005).chain([3, 7]).chain(1005..1010).collect();
+        let capacity = batch.capacity();
+        let err = set.absorb(&mut batch).err().unwrap();
+        assert_eq!((err.requested(), err.limit()), (256, 255));
+        assert_eq!(batch, [1005, 1006, 1007, 1008, 1009]);
+        assert_eq!(batch.capacity(), capacity);
+        assert_eq!(set.len(), 255);
+        assert_eq!(set.as_slice()[250..], [1000, 1001, 1002, 1003, 1004]);
+
+        // A full set still takes duplicates
+        let mut dupes = vec![0, 1004, 9];
+        assert_eq!(set.absorb(&mut dupes), Ok(()));
+        assert!(dupes.is_empty() && set.len() == 255);
+        set.retain(|&i| i >= 1000);
+        assert_eq!(set.absorb(&mut batch), Ok(()));
+        assert!(set.as_slice().iter().copied().eq(1000..1010));
+    }
+
+    #[test]
+    fn insert_new() {
+        // Every name lands on the same filter bits, so each lookup gets past the filter and only
+        // the scan tells a duplicate from a new name
+        #[derive(Debug, PartialEq)]
+        struct Name(&'static str);
+        impl Hash for Name {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                0_u8.hash(state);
+            }
+        }
+        let mut names = BloomSet::new();
+        for name in ["alpha", "beta", "gamma"] {
+            assert_eq!(names.insert_new(Name(name)), Ok(()));
+        }
+        assert_eq!(
+            names.filter_bits().count_ones(),
+            u32::from(!cfg!(feature = "no-filter"))
+        );
+        let err = names.insert_new(Name("beta")).unwrap_err();
+        assert_eq!((err.item(), err.index()), (&Name("beta"), 1));
+        assert_eq!(
+            err.to_string(),
+            "the BloomSet already holds an equal element, at index 1"
+        );
+        #[cfg(feature = "std")]
+        {
+            let err: Box<dyn std::error::Error> = Box::new(err);
+            assert!(err.source().is_none());
+        }
+        assert_eq!(names.len(), 3);
+        assert_eq!(names.insert_new(Name("delta")), Ok(()));
+        assert_eq!(names.as_slice()[3], Name("delta"));
+
+        // Without a collision the filter settles it
+        let mut set: BloomSet<u32> = (0..10).collect();
+        assert_eq!(set.insert_new(10), Ok(()));
+        assert_eq!(set.insert_new(4).unwrap_err().into_inner(), 4);
+    }
+
+    /// A key to compare by and a count that isn't.
+    #[derive(Debug)]
+    struct Tally(&'static str, u32);
+
+    impl PartialEq for Tally {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+
+    impl Hash for Tally {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.0.hash(state);
+        }
+    }
+
+    #[test]
+    fn update_or_insert() {
+        let mut tallies = BloomSet::new();
+        let merge = |stored: &mut Tally, new: Tally| stored.1 += new.1;
+        assert!(!tallies.update_or_insert(Tally("a", 1), merge));
+        assert!(!tallies.update_or_insert(Tally("b", 2), merge));
+        assert!(tallies.update_or_insert(Tally("a", 3), merge));
+        assert!(tallies.update_or_insert(Tally("a", 4), merge));
+        let counts: Vec<_> = tallies.as_slice().iter().map(|t| (t.0, t.1)).collect();
+        assert_eq!(counts, [("a", 8), ("b", 2)]);
+
+        // New elements go in as `insert` would put them, and a merge leaves the filter alone
+        let mut set: BloomSet<u32> = (0..10).collect();
+        let bits = set.filter_bits();
+        assert!(set.update_or_insert(3, |stored, new| assert_eq!((*stored, new), (3, 3))));
+        assert_eq!(set.filter_bits(), bits);
+        assert!(!set.update_or_insert(10, |_, _| unreachable!()));
+        assert!(set.contains(10) && set.len() == 11);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "update_or_insert's merge changed the hash of the stored element")]
+    fn update_or_insert_changing_the_hash() {
+        let mut tallies = BloomSet::new();
+        tallies.insert(Tally("a", 1));
+        tallies.update_or_insert(Tally("a", 1), |stored, _| stored.0 = "b");
+    }
+
+    #[test]
+    fn relation_screens() {
+        use std::collections::hash_map::RandomState;
+
+        let mut small: BloomSet<u32> = (0..40).collect();
+        let large: BloomSet<u32> = (20..100).collect();
+        // 0..20 leave their bits behind, which `large` may lack
+        for i in 0..20 {
+            small.remove(i);
+        }
+        assert!(small.maybe_subset_of(&large) && small.is_subset(&large));
+        assert!(!large.is_subset(&small) && !large.maybe_subset_of(&small));
+        assert!(small.maybe_intersects(&large) && !small.is_disjoint(&large));
+
+        let empty = BloomSet::new();
+        assert!(empty.is_subset(&small) && empty.is_disjoint(&small));
+        assert!(!empty.maybe_intersects(&small) && !small.maybe_intersects(&empty));
+
+        // Filters with different seeds, or from hashers with state, mean nothing to each other
+        let mut seeded = BloomSet::with_seed(1);
+        seeded.extend(20..40);
+        assert!(seeded.maybe_subset_of(&large) && seeded.is_subset(&large));
+        let random: BloomSet<u32, RandomState> =
+            BloomSet::try_from_iter_with_hasher(0..5, RandomState::new()).unwrap();
+        let other = BloomSet::try_from_iter_with_hasher(0..50, RandomState::new()).unwrap();
+        assert!(random.maybe_subset_of(&other) && random.is_subset(&other));
+        assert!(random.maybe_intersects(&other) && !random.is_disjoint(&other));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "no-filter", ignore = "checks the filter bits")]
+    fn relation_screens_rule_out() {
+        let (mut a, mut b) = (BloomSet::with_seed(0), BloomSet::with_seed(0));
+        a.extend(0..4_u64);
+        b.extend(1004..1008);
+        assert_eq!(a.filter_bits() & b.filter_bits(), 0);
+        assert!(!a.maybe_intersects(&b) && a.is_disjoint(&b));
+        assert!(!a.maybe_subset_of(&b) && !a.is_subset(&b));
+    }
+
+    #[test]
+    fn overlap_counts() {
+        thread_local!(static COMPARISONS: Cell<usize> = const { Cell::new(0) });
+        struct Counted(u32);
+        impl PartialEq for Counted {
+            fn eq(&self, other: &Self) -> bool {
+                COMPARISONS.with(|c| c.set(c.get() + 1));
+                self.0 == other.0
+            }
+        }
+        impl Hash for Counted {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                self.0.hash(state);
+            }
+        }
+        let a: BloomSet<Counted> = (0..100).map(Counted).collect();
+        let b: BloomSet<Counted> = (0..100).map(Counted).collect();
+        let c: BloomSet<Counted> = (95..105).map(Counted).collect();
+        assert_eq!((a.intersection_len(&c), c.intersection_len(&a)), (5, 5));
+        assert_eq!((a.difference_len(&c), c.difference_len(&a)), (95, 5));
+        assert!(a.intersection_at_least(&c, 0) && a.intersection_at_least(&c, 5));
+        assert!(!a.intersection_at_least(&c, 6) && !c.intersection_at_least(&a, 11));
+
+        // The first three of `b` are all in the first chunk of 16 `a` scans, and stopping after
+        // them means one chunk each, where counting all of them would compare thousands of times
+        COMPARISONS.with(|c| c.set(0));
+        assert!(a.intersection_at_least(&b, 3));
+        // Stats and paranoid lookups compare elements again to check themselves
+        if !cfg!(any(feature = "stats", feature = "paranoid")) {
+            assert!(COMPARISONS.with(Cell::get) <= 3 * 16);
+        }
+    }
+
+    #[test]
+    // The ratios are of small counts, so they come out exact
+    #[allow(clippy::float_cmp)]
+    fn jaccard() {
+        let a: BloomSet<u32> = (0..10).collect();
+        let b: BloomSet<u32> = (5..20).collect();
+        let empty = BloomSet::new();
+        assert_eq!(
+            (a.jaccard_similarity(&b), b.jaccard_similarity(&a)),
+            (0.25, 0.25)
+        );
+        assert_eq!(a.jaccard_similarity(&a.clone()), 1.0);
+        assert_eq!(a.jaccard_similarity(&(10..20).collect()), 0.0);
+        assert_eq!(a.jaccard_similarity(&empty), 0.0);
+        assert_eq!(empty.jaccard_similarity(&BloomSet::new()), 1.0);
+
+        assert_eq!(a.estimated_jaccard(&a.clone()), 1.0);
+        assert_eq!(
+            (a.estimated_jaccard(&empty), empty.estimated_jaccard(&a)),
+            (0.0, 0.0)
+        );
+        assert_eq!(empty.estimated_jaccard(&BloomSet::new()), 1.0);
+        // Filters that can't be compared always pass
+        let mut seeded = BloomSet::with_seed(a.seed().wrapping_add(1));
+        seeded.extend(100..110);
+        assert_eq!(a.estimated_jaccard(&seeded), 1.0);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "no-filter", ignore = "checks the filter bits")]
+    #[allow(clippy::float_cmp)]
+    fn estimated_jaccard_from_the_filters() {
+        let (mut a, mut b) = (BloomSet::with_seed(0), BloomSet::with_seed(0));
+        a.extend(0..4_u64);
+        b.extend(1004..1008);
+        assert_eq!(a.filter_bits() & b.filter_bits(), 0);
+        assert_eq!(a.estimated_jaccard(&b), 0.0);
+        b.extend(0..4);
+        let estimate = a.estimated_jaccard(&b);
+        let (a_bits, b_bits) = (a.filter_bits(), b.filter_bits());
+        let expected = f64::from(a_bits.count_ones()) / f64::from(b_bits.count_ones());
+        assert_eq!(estimate, expected);
+    }
+
+    #[test]
+    fn intersect_all() {
+        thread_local!(static COMPARISONS: Cell<usize> = const { Cell::new(0) });
+        #[derive(Clone)]
+        struct Counted(u32);
+        impl PartialEq for Counted {
+            fn eq(&self, other: &Self) -> bool {
+                COMPARISONS.with(|c| c.set(c.get() + 1));
+                self.0 == other.0
+            }
+        }
+        impl Hash for Counted {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                self.0.hash(state);
+            }
+        }
+        let set =
+            |range: core::ops::Range<u32>| -> BloomSet<Counted> { range.map(Counted).collect() };
+        let values =
+            |set: &BloomSet<Counted>| -> Vec<u32> { set.as_slice().iter().map(|c| c.0).collect() };
+
+        let (a, b, c) = (set(0..100), set(50..250), set(90..95));
+        assert_eq!(
+            values(&BloomSet::intersect_all(&[&a, &b, &c])),
+            [90, 91, 92, 93, 94]
+        );
+        assert_eq!(values(&BloomSet::intersect_all(&[&b])), values(&b));
+        assert!(BloomSet::<Counted>::intersect_all(&[]).is_empty());
+
+        // An empty input means nothing is looked up at all
+        let empty = set(0..0);
+        COMPARISONS.with(|c| c.set(0));
+        assert!(BloomSet::intersect_all(&[&a, &b, &empty]).is_empty());
+        assert_eq!(COMPARISONS.with(Cell::get), 0);
+
+        // Each element of the smallest set stops at the first set that lacks it. `a` holds none
+        // of these, so at worst each scans all 100 of `a`. `b` holds them all 150 elements in,
+        // so reaching it would cost over 1500 more
+        let disjoint = set(200..210);
+        COMPARISONS.with(|c| c.set(0));
+        assert!(BloomSet::intersect_all(&[&disjoint, &a, &b]).is_empty());
+        // Stats and paranoid lookups compare elements again to check themselves
+        if !cfg!(any(feature = "stats", feature = "paranoid")) {
+            assert!(COMPARISONS.with(Cell::get) <= 10 * 100);
+        }
+    }
+
+    #[test]
+    fn replace_with() {
+        #[derive(Debug)]
+        struct Entry {
+            id: u32,
+            payload: &'static str,
+        }
+        impl PartialEq for Entry {
+            fn eq(&self, other: &Self) -> bool {
+                self.id == other.id
+            }
+        }
+        impl Hash for Entry {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                self.id.hash(state);
+            }
+        }
+        impl core::borrow::Borrow<u32> for Entry {
+            fn borrow(&self) -> &u32 {
+                &self.id
+            }
+        }
+        let entry = |id, payload| Entry { id, payload };
+        let mut set: BloomSet<Entry> = (1..=3).map(|id| entry(id, "old")).collect();
+
+        assert!(set.replace_with(&2, |old| entry(old.id, "new")));
         assert_eq!(set.len(), 3);
+        assert_eq!(set.get(entry(2, "")).map(|e| e.payload), Some("new"));
+        assert!(!set.replace_with(&9, |_| panic!("called without a match")));
+
+        // Changing the id rehashes, so the element is found under the new one only
+        assert!(set.replace_with(&1, |old| entry(10, old.payload)));
+        assert!(set.contains(entry(10, "")) && !set.contains(entry(1, "")));
+        let ids: Vec<u32> = set.as_slice().iter().map(|e| e.id).collect();
+        assert_eq!(ids, [3, 2, 10]);
+
+        // Colliding with another element keeps that one and drops the replacement
+        assert!(set.replace_with(&2, |_| entry(3, "clash")));
+        let kept: Vec<(u32, &str)> = set.as_slice().iter().map(|e| (e.id, e.payload)).collect();
+        assert_eq!(kept, [(3, "old"), (10, "old")]);
+
+        // A full set has room for the replacement, since the old element made way for it
+        let mut full: BloomSet<u32> = (0..255).collect();
+        assert!(full.replace_with(&0, |_| 255));
+        assert_eq!(full.len(), 255);
+        assert!(full.contains(255) && !full.contains(0));
+    }
+
+    #[test]
+    fn clone() {
+        let mut set: BloomSet<String> = BloomSet::with_capacity_and_seed(64, 17);
+        set.extend((0..40).map(|i| i.to_string()));
+        set.remove("3".to_string());
+        let clone = set.clone();
+        assert_eq!(clone.as_slice(), set.as_slice());
+        assert_eq!(
+            (clone.filter_bits(), clone.seed(), clone.is_sorted()),
+            (set.filter_bits(), 17, false)
+        );
+        assert_eq!(clone.capacity(), 39);
+        assert!(clone.contains("39".to_string()) && !clone.contains("3".to_string()));
+
+        let mut sorted: BloomSet<u32> = (0..10).rev().collect();
+        sorted.sort();
+        assert!(sorted.clone().is_sorted());
+        let empty = BloomSet::<u32>::with_capacity_and_seed(8, 5);
+        let clone = empty.clone();
+        assert_eq!((clone.capacity(), clone.seed()), (0, 5));
+        assert_eq!(empty.capacity(), 8);
+        // Removing keeps the elements' filter bits
+        let mut emptied: BloomSet<u32> = (0..10).collect();
+        assert!((0..10).all(|i| emptied.remove(i)));
+        let clone = emptied.clone();
+        assert!(clone.is_empty());
+        assert_eq!(clone.filter_bits() == 0, cfg!(feature = "no-filter"));
+        assert_eq!(clone.filter_bits(), emptied.filter_bits());
+        assert_eq!(clone.filter_bits_set(), emptied.filter_bits_set());
+        let units: BloomSet<()> = core::iter::once(()).collect();
+        let clone = units.clone();
+        assert_eq!((clone.len(), units.len()), (1, 1));
+
+        let mut wide: BloomSet16<u32> = (0..300).collect();
+        wide.retain(|&i| i % 7 != 0);
+        let clone = wide.try_clone().unwrap();
+        assert_eq!(clone.as_slice(), wide.as_slice());
+        assert_eq!(clone.filter_bits(), wide.filter_bits());
+    }
+
+    #[test]
+    fn into_shared_slices() {
+        let set: BloomSet<String> = ["b", "a", "c"].iter().map(ToString::to_string).collect();
+        let cloned: Arc<[String]> = Arc::from(&set);
+        let rc_cloned = Rc::<[String]>::from(&set);
+        let moved: Arc<[String]> = set.clone().into();
+        let rc_moved = Rc::<[String]>::from(set);
+        for shared in [&*cloned, &*moved, &*rc_cloned, &*rc_moved] {
+            assert_eq!(shared, ["b", "a", "c"]);
+        }
+
+        let empty: BloomSet<u32> = BloomSet::with_capacity(10);
+        assert!(Arc::<[u32]>::from(&empty).is_empty());
+        assert!(Rc::<[u32]>::from(empty).is_empty());
+    }
+
+    #[test]
+    fn clone_empty() {
+        use std::collections::hash_map::RandomState;
+
+        let mut template: BloomSet<u64> = BloomSet::with_capacity_and_seed(40, 23);
+        template.extend(0..30);
+        let mut empty = template.clone_empty();
+        assert!(empty.is_empty());
+        assert_eq!(
+            (empty.capacity(), empty.seed(), empty.filter_bits()),
+            (40, 23, 0)
+        );
+        // The same keys land on the same bits as in the template
+        let ptr = empty.as_slice().as_ptr();
+        empty.extend(0..30);
+        assert_eq!(empty.filter_bits(), template.filter_bits());
+        assert_eq!(empty.as_slice().as_ptr(), ptr);
+
+        let hashed: BloomSet<u32, RandomState> =
+            BloomSet::try_from_iter_with_hasher(0..10, RandomState::new()).unwrap();
+        let mut like = hashed.clone_empty();
+        like.extend(0..10);
+        assert_eq!(like.filter_bits(), hashed.filter_bits());
+        assert_eq!(
+            BloomSet::<()>::new().clone_empty().capacity(),
+            usize::from(u8::MAX)
+        );
+    }
+
+    #[test]
+    fn clone_panic_drops_clones_made() {
+        struct Bomb<'a>(u32, &'a Cell<u32>);
+        impl Clone for Bomb<'_> {
+            fn clone(&self) -> Self {
+                assert!(self.0 != 3, "clone panicked");
+                Bomb(self.0, self.1)
+            }
+        }
+        impl Drop for Bomb<'_> {
+            fn drop(&mut self) {
+                self.1.set(self.1.get() + 1);
+            }
+        }
+        impl PartialEq for Bomb<'_> {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+        impl Hash for Bomb<'_> {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                self.0.hash(state);
+            }
+        }
+        let drops = Cell::new(0);
+        let set: BloomSet<Bomb> = (0..5).map(|i| Bomb(i, &drops)).collect();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| set.clone()));
+        assert!(result.is_err());
+        assert_eq!(drops.get(), 3);
+        drop(set);
+        assert_eq!(drops.get(), 8);
     }
 }
@@ -0,0 +1,668 @@
+//! [`BloomMap`], along with its entry API and iterators.
+
+use crate::{
+    bloom_mask, grown_capacity, split_mask, unpack_filter, BuildBloomHasher, CapacityError,
+    ElementHasher, Packed8, FILTER_BITS, MAX_CAPACITY,
+};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::iter::FromIterator;
+use core::mem::{self, ManuallyDrop};
+use core::ptr::NonNull;
+use core::{fmt, slice};
+
+/// A map of up to 255 entries stored contiguously in insertion order, with a bloom filter over
+/// the keys packed into the spare bits of its length and capacity, like a
+/// [`BloomSet`](crate::BloomSet).
+///
+/// Lookups check the key's filter bit first, and only scan the entries if it is set, so looking
+/// up an absent key is usually just a hash.
+///
+/// ```
+/// use bloomset::BloomMap;
+///
+/// let mut map = BloomMap::new();
+/// assert_eq!(map.insert("one", 1), None);
+/// assert_eq!(map.insert("one", 2), Some(1));
+/// *map.entry("two").or_insert(0) += 2;
+/// assert_eq!(map.get("one"), Some(&2));
+/// assert_eq!(map.get("two"), Some(&2));
+/// assert_eq!(map.get("three"), None);
+/// ```
+pub struct BloomMap<K, V, S = BuildBloomHasher> {
+    ptr: NonNull<(K, V)>,
+    length: usize,
+    capacity: usize,
+    hash_builder: S,
+}
+
+impl<K, V> Default for BloomMap<K, V> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_hasher(BuildBloomHasher)
+    }
+}
+
+impl<K, V> BloomMap<K, V> {
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self::with_hasher(BuildBloomHasher)
+    }
+}
+
+impl<K, V, S> BloomMap<K, V, S> {
+    /// Creates an empty map which will use `hash_builder` to hash its keys.
+    #[inline]
+    #[must_use]
+    pub const fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            ptr: NonNull::dangling(),
+            length: 0,
+            capacity: 0,
+            hash_builder,
+        }
+    }
+
+    /// Returns a reference to the map's `BuildHasher`.
+    #[inline]
+    #[must_use]
+    pub const fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.length & MAX_CAPACITY
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.capacity & MAX_CAPACITY
+    }
+
+    /// Returns the entries in insertion order.
+    #[inline]
+    #[must_use]
+    pub const fn as_slice(&self) -> &[(K, V)] {
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len()) }
+    }
+
+    /// Iterates over the entries in insertion order.
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            entries: self.as_slice().iter(),
+        }
+    }
+
+    /// Iterates over the entries in insertion order, with mutable references to the values.
+    #[inline]
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            entries: self.as_mut_slice().iter_mut(),
+        }
+    }
+
+    #[inline]
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.as_slice().iter().map(|(key, _)| key)
+    }
+
+    #[inline]
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.as_slice().iter().map(|(_, value)| value)
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.with_vec(Vec::clear);
+        self.capacity &= MAX_CAPACITY;
+        self.length = 0;
+    }
+
+    const fn as_mut_slice(&mut self) -> &mut [(K, V)] {
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len()) }
+    }
+
+    const fn filter(&self) -> u128 {
+        unpack_filter::<Packed8>(self.length, self.capacity)
+    }
+
+    const fn set_bits(&mut self, mask: u128) {
+        let (low, high) = split_mask::<Packed8>(mask);
+        self.capacity |= low;
+        self.length |= high;
+    }
+
+    /// Runs `f` on the entries as a `Vec`, then takes back whatever allocation it leaves,
+    /// keeping the filter.
+    ///
+    /// The length is zeroed while `f` runs, so if it panics the entries are leaked rather than
+    /// dropped twice.
+    fn with_vec<R>(&mut self, f: impl FnOnce(&mut Vec<(K, V)>) -> R) -> R {
+        let length = self.len();
+        self.length &= !MAX_CAPACITY;
+        let mut vec = unsafe {
+            // Use ManuallyDrop to ensure that the Vec is never dropped
+            ManuallyDrop::new(Vec::from_raw_parts(
+                self.ptr.as_ptr(),
+                length,
+                self.capacity(),
+            ))
+        };
+        let result = f(&mut vec);
+        debug_assert!(vec.len() <= MAX_CAPACITY);
+        unsafe { self.ptr = NonNull::new_unchecked(vec.as_mut_ptr()) };
+        self.capacity = vec.capacity().min(MAX_CAPACITY) | (self.capacity & !MAX_CAPACITY);
+        self.length |= vec.len();
+        result
+    }
+
+    /// Appends an entry whose key isn't in the map yet.
+    fn push(&mut self, key: K, value: V, mask: u128) -> Result<usize, CapacityError> {
+        let index = self.len();
+        if index == MAX_CAPACITY {
+            return Err(CapacityError::new(MAX_CAPACITY + 1, MAX_CAPACITY));
+        }
+        self.with_vec(|vec| {
+            if vec.len() == vec.capacity() {
+                let new_capacity = grown_capacity(vec.capacity(), MAX_CAPACITY);
+                vec.reserve_exact(new_capacity - vec.len());
+            }
+            vec.push((key, value));
+        });
+        self.set_bits(mask);
+        Ok(index)
+    }
+
+    /// Removes the entry at `index`, keeping the order of the rest.
+    fn remove_index(&mut self, index: usize) -> (K, V) {
+        self.with_vec(|vec| vec.remove(index))
+    }
+}
+
+impl<K: PartialEq, V, S> BloomMap<K, V, S> {
+    fn key_mask<Q>(&self, key: &Q) -> u128
+    where
+        S: ElementHasher<Q>,
+        Q: ?Sized,
+    {
+        bloom_mask::<1>(self.hash_builder.hash_element(key), FILTER_BITS)
+    }
+
+    fn find<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: PartialEq + ?Sized,
+        S: ElementHasher<Q>,
+    {
+        let mask = self.key_mask(key);
+        if self.filter() & mask != mask {
+            return None;
+        }
+        self.as_slice().iter().position(|(k, _)| k.borrow() == key)
+    }
+
+    #[inline]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: PartialEq + ?Sized,
+        S: ElementHasher<Q>,
+    {
+        self.find(key).is_some()
+    }
+
+    #[inline]
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: PartialEq + ?Sized,
+        S: ElementHasher<Q>,
+    {
+        let index = self.find(key)?;
+        Some(&self.as_slice()[index].1)
+    }
+
+    #[inline]
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: PartialEq + ?Sized,
+        S: ElementHasher<Q>,
+    {
+        let index = self.find(key)?;
+        Some(&mut self.as_mut_slice()[index].1)
+    }
+
+    /// Removes `key` from the map, returning its value if it was present. The remaining entries
+    /// keep their order, and the filter keeps `key`'s bit.
+    #[inline]
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: PartialEq + ?Sized,
+        S: ElementHasher<Q>,
+    {
+        let index = self.find(key)?;
+        Some(self.remove_index(index).1)
+    }
+}
+
+impl<K: PartialEq, V, S: ElementHasher<K>> BloomMap<K, V, S> {
+    /// Inserts `value` under `key`, returning the value it replaced, if any. Replacing a value
+    /// keeps the original key and the entry's position.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CapacityError`] if `key` would be the map's 256th entry.
+    #[inline]
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<Option<V>, CapacityError> {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => Ok(Some(entry.insert(value))),
+            Entry::Vacant(entry) => entry.try_insert(value).map(|_| None),
+        }
+    }
+
+    /// Like [`try_insert`](BloomMap::try_insert), but panics instead of failing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` would be the map's 256th entry.
+    #[inline]
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.try_insert(key, value)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Gets the entry for `key`, for in-place updates.
+    #[inline]
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S> {
+        let mask = self.key_mask(&key);
+        let index = if self.filter() & mask == mask {
+            self.as_slice().iter().position(|(k, _)| *k == key)
+        } else {
+            None
+        };
+        match index {
+            Some(index) => Entry::Occupied(OccupiedEntry { map: self, index }),
+            None => Entry::Vacant(VacantEntry {
+                map: self,
+                key,
+                mask,
+            }),
+        }
+    }
+}
+
+impl<K: PartialEq, V, S: ElementHasher<K> + Default> FromIterator<(K, V)> for BloomMap<K, V, S> {
+    /// Later values for a key replace earlier ones.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the iterator yields more than 255 distinct keys.
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::with_hasher(S::default());
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, S> fmt::Debug for BloomMap<K, V, S> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.as_slice().iter().map(|(k, v)| (k, v)))
+            .finish()
+    }
+}
+
+impl<K, V, S> Drop for BloomMap<K, V, S> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { Vec::from_raw_parts(self.ptr.as_ptr(), self.len(), self.capacity()) };
+    }
+}
+
+// The map owns its entries like a Vec does, so it can cross threads under the same conditions
+unsafe impl<K: Send, V: Send, S: Send> Send for BloomMap<K, V, S> {}
+unsafe impl<K: Sync, V: Sync, S: Sync> Sync for BloomMap<K, V, S> {}
+
+impl<'a, K, V, S> IntoIterator for &'a BloomMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut BloomMap<K, V, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// An iterator over the entries of a [`BloomMap`], in insertion order.
+#[derive(Clone, Debug)]
+pub struct Iter<'a, K, V> {
+    entries: slice::Iter<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next().map(|(k, v)| (k, v))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+/// An iterator over the entries of a [`BloomMap`], in insertion order, with mutable references
+/// to the values.
+#[derive(Debug)]
+pub struct IterMut<'a, K, V> {
+    entries: slice::IterMut<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next().map(|(k, v)| (&*k, v))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+/// A view into one key's entry in a [`BloomMap`], made by [`BloomMap::entry`].
+pub enum Entry<'a, K, V, S> {
+    Occupied(OccupiedEntry<'a, K, V, S>),
+    Vacant(VacantEntry<'a, K, V, S>),
+}
+
+/// An entry for a key that is in the map.
+pub struct OccupiedEntry<'a, K, V, S> {
+    map: &'a mut BloomMap<K, V, S>,
+    index: usize,
+}
+
+/// An entry for a key that isn't in the map yet.
+pub struct VacantEntry<'a, K, V, S> {
+    map: &'a mut BloomMap<K, V, S>,
+    key: K,
+    mask: u128,
+}
+
+impl<'a, K, V, S> Entry<'a, K, V, S> {
+    #[inline]
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the entry is vacant and the map is full.
+    #[inline]
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the entry is vacant and the map is full.
+    #[inline]
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the entry is vacant and the map is full.
+    #[inline]
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, K, V, S> OccupiedEntry<'a, K, V, S> {
+    #[inline]
+    #[must_use]
+    pub fn key(&self) -> &K {
+        &self.map.as_slice()[self.index].0
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn get(&self) -> &V {
+        &self.map.as_slice()[self.index].1
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.map.as_mut_slice()[self.index].1
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.map.as_mut_slice()[self.index].1
+    }
+
+    /// Replaces the entry's value, returning the old one.
+    #[inline]
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    /// Removes the entry from the map, returning its key and value.
+    #[inline]
+    #[must_use]
+    pub fn remove_entry(self) -> (K, V) {
+        self.map.remove_index(self.index)
+    }
+
+    /// Removes the entry from the map, returning its value.
+    #[inline]
+    #[must_use]
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+}
+
+impl<'a, K, V, S> VacantEntry<'a, K, V, S> {
+    #[inline]
+    #[must_use]
+    pub const fn key(&self) -> &K {
+        &self.key
+    }
+
+    #[inline]
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Inserts the entry, returning a reference to its value.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CapacityError`] if the map already has 255 entries.
+    #[inline]
+    pub fn try_insert(self, value: V) -> Result<&'a mut V, CapacityError> {
+        let index = self.map.push(self.key, value, self.mask)?;
+        Ok(&mut self.map.as_mut_slice()[index].1)
+    }
+
+    /// Inserts the entry, returning a reference to its value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the map already has 255 entries.
+    #[inline]
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.try_insert(value)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BloomSet;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+
+    #[test]
+    fn agrees_with_hashmap() {
+        let mut map = BloomMap::new();
+        let mut expected = HashMap::new();
+        for i in 0..600_u32 {
+            let key = i * 7 % 200;
+            assert_eq!(map.insert(key, i), expected.insert(key, i), "{i}");
+        }
+        assert_eq!(map.len(), expected.len());
+        for key in 0..400 {
+            assert_eq!(map.get(&key), expected.get(&key));
+            assert_eq!(map.contains_key(&key), expected.contains_key(&key));
+        }
+        for key in (0..400).step_by(3) {
+            assert_eq!(map.remove(&key), expected.remove(&key));
+        }
+        assert!(map.iter().all(|(k, v)| expected[k] == *v));
+        assert_eq!(map.len(), expected.len());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "random-seed", ignore = "compares filter bits across sets")]
+    fn filter_matches_bloomset() {
+        let keys = ["a", "bb", "ccc", "dddd", "eeeee"];
+        let map: BloomMap<_, _> = keys.iter().map(|k| (*k, k.len())).collect();
+        let set: BloomSet<_> = keys.iter().copied().collect();
+        assert_eq!(map.filter(), set.filter_bits());
+    }
+
+    #[test]
+    fn overwriting_keeps_key_and_position() {
+        let mut map = BloomMap::new();
+        map.insert("x".to_string(), 1);
+        map.insert("y".to_string(), 2);
+        assert_eq!(map.insert("x".to_string(), 3), Some(1));
+        assert_eq!(
+            map.iter()
+                .map(|(k, v)| (k.as_str(), *v))
+                .collect::<Vec<_>>(),
+            [("x", 3), ("y", 2)]
+        );
+        *map.get_mut("y").unwrap() += 10;
+        assert_eq!(map.get("y"), Some(&12));
+        assert_eq!(map.get("z"), None);
+    }
+
+    #[test]
+    fn entry() {
+        let mut map: BloomMap<&str, u32> = BloomMap::new();
+        for word in "the cat and the hat and the bat".split(' ') {
+            *map.entry(word).or_default() += 1;
+        }
+        assert_eq!(map.get("the"), Some(&3));
+        assert_eq!(map.get("and"), Some(&2));
+        assert_eq!(map.get("cat"), Some(&1));
+
+        map.entry("cat").and_modify(|n| *n *= 10).or_insert(0);
+        map.entry("dog").and_modify(|n| *n *= 10).or_insert(7);
+        assert_eq!(map.get("cat"), Some(&10));
+        assert_eq!(map.get("dog"), Some(&7));
+
+        match map.entry("hat") {
+            Entry::Occupied(entry) => assert_eq!(entry.remove_entry(), ("hat", 1)),
+            Entry::Vacant(_) => panic!("hat should be present"),
+        }
+        assert_eq!(map.entry("hat").key(), &"hat");
+        assert!(!map.contains_key("hat"));
+    }
+
+    #[test]
+    fn capacity_limit() {
+        let mut map: BloomMap<u32, ()> = (0..255).map(|i| (i, ())).collect();
+        assert_eq!(map.insert(0, ()), Some(()));
+        let err = map.try_insert(255, ()).unwrap_err();
+        assert_eq!(err.limit(), 255);
+        assert_eq!(map.len(), 255);
+        assert!(map.remove(&0).is_some());
+        assert_eq!(map.try_insert(255, ()), Ok(None));
+    }
+
+    #[test]
+    fn map_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<BloomMap<u32, u32>>();
+        assert_send_sync::<BloomMap<String, Vec<u8>>>();
+    }
+
+    #[test]
+    fn drops_every_entry() {
+        let counted = Rc::new(());
+        let mut map = BloomMap::new();
+        for i in 0..20_u32 {
+            map.insert(i, Rc::clone(&counted));
+        }
+        map.insert(0, Rc::clone(&counted));
+        assert_eq!(Rc::strong_count(&counted), 21);
+        drop(map.remove(&1));
+        assert_eq!(Rc::strong_count(&counted), 20);
+        map.clear();
+        assert_eq!(Rc::strong_count(&counted), 1);
+        assert!(map.is_empty() && map.filter() == 0);
+        map.insert(3, Rc::clone(&counted));
+        drop(map);
+        assert_eq!(Rc::strong_count(&counted), 1);
+    }
+}
@@ -0,0 +1,309 @@
+use crate::{
+    bloom_mask, BuildBloomHasher, FrozenBloomSet, FrozenBloomSetRef, LayoutError, Packing,
+    FILTER_BITS,
+};
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::hash::{BuildHasher, Hash};
+use core::mem;
+use core::slice;
+
+/// Element types whose values can be written out as their bytes and read back by pointing at
+/// them, for [`FrozenBloomSet::write_to`] and [`FrozenBloomSetRef::from_bytes`].
+///
+/// # Safety
+///
+/// The type must have no padding bytes, so that all of a value's bytes are initialized, and
+/// every bit pattern of its size must be a valid value. It must not hold pointers or
+/// references, since those don't mean anything once the bytes are in another process.
+pub unsafe trait Pod: Copy + 'static {}
+
+macro_rules! impl_pod {
+    ($($ty:ty),*) => {
+        $(unsafe impl Pod for $ty {})*
+    };
+}
+
+impl_pod!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+/// The bytes a written set starts with.
+const MAGIC: [u8; 4] = *b"BLMS";
+/// Bumped whenever the header or the filter's bit mapping changes.
+const VERSION: u16 = 1;
+/// The header's length, which keeps the elements after it aligned for any element type up to
+/// 16-byte alignment.
+///
+/// | bytes    | field                                           |
+/// |----------|-------------------------------------------------|
+/// | `0..4`   | `MAGIC`                                         |
+/// | `4..6`   | `VERSION`, little-endian                        |
+/// | `6`      | the elements' byte order, 1 little or 2 big     |
+/// | `8..16`  | the size of an element, little-endian           |
+/// | `16..24` | the number of elements, little-endian           |
+/// | `24..40` | the filter, little-endian                       |
+///
+/// The rest is zero.
+const HEADER_LEN: usize = 48;
+const BYTE_ORDER: u8 = if cfg!(target_endian = "little") { 1 } else { 2 };
+
+impl<T: Pod + Hash, S, const K: usize, P: Packing> FrozenBloomSet<T, S, K, P> {
+    /// Appends the set to `out` as a header followed by the elements' bytes, for
+    /// [`FrozenBloomSetRef::from_bytes`] to read back, say from a file mapped into memory.
+    ///
+    /// The elements are written in the target's byte order, and only a target with the same
+    /// byte order reads them back. The header holds a filter for the view's lookups, which use
+    /// neither this set's hasher nor its seed, so it is built afresh from the elements.
+    ///
+    /// For the elements to be aligned when read back, the bytes must start at a multiple of
+    /// the element type's alignment; if `out` isn't empty, its length counts too.
+    #[inline]
+    pub fn write_to(&self, out: &mut Vec<u8>) {
+        let elements = self.as_slice();
+        let filter = elements.iter().fold(0, |filter, item| {
+            filter | bloom_mask::<1>(BuildBloomHasher.hash_one(item), FILTER_BITS)
+        });
+        out.reserve(HEADER_LEN + mem::size_of_val(elements));
+        out.extend_from_slice(&MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        out.extend_from_slice(&[BYTE_ORDER, 0]);
+        out.extend_from_slice(&(mem::size_of::<T>() as u64).to_le_bytes());
+        out.extend_from_slice(&(elements.len() as u64).to_le_bytes());
+        out.extend_from_slice(&filter.to_le_bytes());
+        out.extend_from_slice(&[0; HEADER_LEN - 40]);
+        // `Pod` elements have no padding, so all their bytes are initialized
+        out.extend_from_slice(unsafe {
+            slice::from_raw_parts(elements.as_ptr().cast::<u8>(), mem::size_of_val(elements))
+        });
+    }
+
+    /// The set as bytes, like [`write_to`](FrozenBloomSet::write_to) into a new `Vec`.
+    #[inline]
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write_to(&mut bytes);
+        bytes
+    }
+}
+
+impl<'a, T: Pod> FrozenBloomSetRef<'a, T> {
+    /// A view over a set written by [`FrozenBloomSet::write_to`], answering lookups from the
+    /// elements in `bytes` without copying them.
+    ///
+    /// Only the header is checked, so this doesn't read the elements, and pages of a mapped
+    /// file that lookups never reach are never loaded. If the elements aren't sorted and unique,
+    /// as they are when written by a `FrozenBloomSet`, lookups may give wrong answers, but
+    /// nothing worse happens.
+    ///
+    /// ```
+    /// use bloomset::{BloomSet, FrozenBloomSetRef};
+    ///
+    /// let ids: BloomSet<u64> = [40, 3, 17].iter().copied().collect();
+    /// let bytes = ids.into_frozen().to_bytes();
+    ///
+    /// // A mapped file starts on a page boundary; here an aligned buffer stands in for one
+    /// #[repr(align(8))]
+    /// struct Page([u8; 128]);
+    /// let mut page = Page([0; 128]);
+    /// page.0[..bytes.len()].copy_from_slice(&bytes);
+    ///
+    /// let view = FrozenBloomSetRef::<u64>::from_bytes(&page.0[..bytes.len()]).unwrap();
+    /// assert_eq!(view.as_slice(), [3, 17, 40]);
+    /// assert!(view.contains(&17) && !view.contains(&18));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`LayoutError`] if the header is missing or isn't one this version writes, if
+    /// it was written on a target with the other byte order or for elements of another size,
+    /// if the number of bytes after it doesn't match the number of elements it gives, or if
+    /// the elements aren't aligned for `T`.
+    #[inline]
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, LayoutError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(LayoutError::TooShort { len: bytes.len() });
+        }
+        let (header, body) = bytes.split_at(HEADER_LEN);
+        if header[..4] != MAGIC {
+            return Err(LayoutError::Magic);
+        }
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version != VERSION {
+            return Err(LayoutError::Version(version));
+        }
+        if header[6] != BYTE_ORDER {
+            return Err(LayoutError::ByteOrder);
+        }
+        let word = |at: usize| {
+            let mut word = [0; 8];
+            word.copy_from_slice(&header[at..at + 8]);
+            u64::from_le_bytes(word)
+        };
+        let size = word(8);
+        if size != mem::size_of::<T>() as u64 {
+            return Err(LayoutError::ElementSize {
+                expected: mem::size_of::<T>(),
+                found: size,
+            });
+        }
+        let count = word(16);
+        let len = usize::try_from(count)
+            .ok()
+            .filter(|&len| len.checked_mul(mem::size_of::<T>()) == Some(body.len()))
+            .ok_or(LayoutError::Length {
+                count,
+                len: body.len(),
+            })?;
+        if body.as_ptr().align_offset(mem::align_of::<T>()) != 0 {
+            return Err(LayoutError::Misaligned {
+                align: mem::align_of::<T>(),
+            });
+        }
+        let filter = u128::from(word(24)) | u128::from(word(32)) << 64;
+        // The bytes are aligned and hold `len` elements, any bit pattern of which is valid
+        let elements = unsafe { slice::from_raw_parts(body.as_ptr().cast::<T>(), len) };
+        Ok(Self::from_parts(elements, filter))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BloomSet, FrozenBloomSet, FrozenBloomSetRef, LayoutError, Pod};
+    use core::hash::Hash;
+
+    /// Room to place bytes at any offset from a 16-byte boundary.
+    #[repr(align(16))]
+    struct Aligned([u8; 4096]);
+
+    fn frozen<T: Pod + Ord + Hash>(items: impl IntoIterator<Item = T>) -> FrozenBloomSet<T> {
+        items.into_iter().collect::<BloomSet<T>>().into_frozen()
+    }
+
+    /// Reads `bytes` back after copying them to `offset` bytes past an aligned address.
+    fn read_at<T: Pod>(
+        bytes: &[u8],
+        offset: usize,
+        buf: &mut Aligned,
+    ) -> Result<Vec<T>, LayoutError> {
+        let placed = &mut buf.0[offset..offset + bytes.len()];
+        placed.copy_from_slice(bytes);
+        FrozenBloomSetRef::<T>::from_bytes(placed).map(|view| view.as_slice().to_vec())
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut buf = Aligned([0; 4096]);
+        let set = frozen((0..200_u32).map(|i| i * 7919 % 1000));
+        let bytes = set.to_bytes();
+        assert_eq!(bytes.len(), 48 + 200 * 4);
+        let placed = &mut buf.0[..bytes.len()];
+        placed.copy_from_slice(&bytes);
+        let view = FrozenBloomSetRef::<u32>::from_bytes(placed).unwrap();
+        assert_eq!(view.as_slice(), set.as_slice());
+        assert!((0..1000).all(|i| view.contains(&i) == set.contains(i)));
+
+        let wide = frozen([u128::MAX, 0, 1 << 100]);
+        assert_eq!(
+            read_at::<u128>(&wide.to_bytes(), 0, &mut buf).unwrap(),
+            wide.as_slice()
+        );
+
+        // Elements with an alignment of 1 can be anywhere
+        let triples = frozen([[1_u8, 2, 3], [0, 0, 9]]);
+        for offset in 0..4 {
+            assert_eq!(
+                read_at::<[u8; 3]>(&triples.to_bytes(), offset, &mut buf).unwrap(),
+                [[0, 0, 9], [1, 2, 3]]
+            );
+        }
+
+        let empty = frozen(Vec::<i64>::new());
+        let view_bytes = empty.to_bytes();
+        let view = FrozenBloomSetRef::<i64>::from_bytes(&view_bytes).unwrap();
+        assert!(view.is_empty() && !view.contains(&0));
+
+        // Appending to a buffer keeps what was there
+        let mut out = vec![7; 16];
+        wide.write_to(&mut out);
+        assert_eq!(out[..16], [7; 16]);
+        assert_eq!(
+            read_at::<u128>(&out[16..], 0, &mut buf).unwrap(),
+            wide.as_slice()
+        );
+    }
+
+    #[test]
+    fn misaligned() {
+        let mut buf = Aligned([0; 4096]);
+        let bytes = frozen([1_u64, 2, 3]).to_bytes();
+        for offset in 1..8 {
+            assert_eq!(
+                read_at::<u64>(&bytes, offset, &mut buf),
+                Err(LayoutError::Misaligned { align: 8 })
+            );
+        }
+        assert_eq!(read_at::<u64>(&bytes, 8, &mut buf).unwrap(), [1, 2, 3]);
+        let bytes = frozen([1_u128]).to_bytes();
+        assert_eq!(
+            read_at::<u128>(&bytes, 8, &mut buf),
+            Err(LayoutError::Misaligned { align: 16 })
+        );
+    }
+
+    #[test]
+    fn rejects_bad_headers() {
+        let mut buf = Aligned([0; 4096]);
+        let bytes = frozen([5_u32, 6]).to_bytes();
+        let read = |bytes: &[u8], buf: &mut Aligned| read_at::<u32>(bytes, 0, buf);
+
+        assert_eq!(
+            read(&bytes[..47], &mut buf),
+            Err(LayoutError::TooShort { len: 47 })
+        );
+        assert_eq!(
+            read(&bytes[..bytes.len() - 1], &mut buf),
+            Err(LayoutError::Length { count: 2, len: 7 })
+        );
+        let mut longer = bytes.clone();
+        longer.extend_from_slice(&[0; 4]);
+        assert_eq!(
+            read(&longer, &mut buf),
+            Err(LayoutError::Length { count: 2, len: 12 })
+        );
+        assert_eq!(
+            read_at::<u64>(&bytes, 0, &mut buf),
+            Err(LayoutError::ElementSize {
+                expected: 8,
+                found: 4
+            })
+        );
+
+        let corrupt = |at: usize, byte: u8| {
+            let mut bytes = bytes.clone();
+            bytes[at] = byte;
+            bytes
+        };
+        assert_eq!(read(&corrupt(0, b'X'), &mut buf), Err(LayoutError::Magic));
+        assert_eq!(read(&corrupt(4, 2), &mut buf), Err(LayoutError::Version(2)));
+        assert_eq!(
+            read(&corrupt(6, 3 - super::BYTE_ORDER), &mut buf),
+            Err(LayoutError::ByteOrder)
+        );
+        // A count so large that the elements' length overflows
+        let mut huge = bytes.clone();
+        huge[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(
+            read(&huge, &mut buf),
+            Err(LayoutError::Length {
+                count: u64::MAX,
+                len: 8
+            })
+        );
+        assert_eq!(
+            LayoutError::Misaligned { align: 4 }.to_string(),
+            "the frozen BloomSet's elements aren't aligned to 4 bytes"
+        );
+    }
+}
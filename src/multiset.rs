@@ -0,0 +1,304 @@
+//! [`BloomMultiSet`] and its iterator.
+
+use crate::map::{self, Entry};
+use crate::{BloomMap, BuildBloomHasher, CapacityError, ElementHasher};
+use core::borrow::Borrow;
+use core::fmt;
+use core::iter::FromIterator;
+
+/// A set that counts how many times each of up to 255 distinct elements has been inserted.
+///
+/// It is a [`BloomMap`] from elements to their counts, so asking for the count of an element
+/// that was never inserted usually only costs a hash. The 255 limit is on distinct elements;
+/// counts saturate at `usize::MAX`.
+///
+/// ```
+/// use bloomset::BloomMultiSet;
+///
+/// let tokens: BloomMultiSet<&str> = "a b a c a".split(' ').collect();
+/// assert_eq!(tokens.count("a"), 3);
+/// assert_eq!(tokens.count("z"), 0);
+/// assert_eq!(tokens.len(), 3);
+/// ```
+pub struct BloomMultiSet<T, S = BuildBloomHasher> {
+    counts: BloomMap<T, usize, S>,
+}
+
+impl<T> Default for BloomMultiSet<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_hasher(BuildBloomHasher)
+    }
+}
+
+impl<T> BloomMultiSet<T> {
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self::with_hasher(BuildBloomHasher)
+    }
+}
+
+impl<T, S> BloomMultiSet<T, S> {
+    /// Creates an empty set which will use `hash_builder` to hash its elements.
+    #[inline]
+    #[must_use]
+    pub const fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            counts: BloomMap::with_hasher(hash_builder),
+        }
+    }
+
+    /// Returns a reference to the set's `BuildHasher`.
+    #[inline]
+    #[must_use]
+    pub const fn hasher(&self) -> &S {
+        self.counts.hasher()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Returns the number of distinct elements.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Returns the sum of all the counts, saturating at `usize::MAX`.
+    #[inline]
+    #[must_use]
+    pub fn total(&self) -> usize {
+        self.counts
+            .values()
+            .fold(0, |sum, n| sum.saturating_add(*n))
+    }
+
+    /// Iterates over the distinct elements and their counts, in order of first insertion.
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            counts: self.counts.iter(),
+        }
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.counts.clear();
+    }
+}
+
+impl<T: PartialEq, S> BloomMultiSet<T, S> {
+    /// Returns how many times `item` is in the set, which is 0 if it was never inserted.
+    #[inline]
+    pub fn count<Q>(&self, item: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: PartialEq + ?Sized,
+        S: ElementHasher<Q>,
+    {
+        self.counts.get(item).copied().unwrap_or(0)
+    }
+
+    #[inline]
+    pub fn contains<Q>(&self, item: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: PartialEq + ?Sized,
+        S: ElementHasher<Q>,
+    {
+        self.counts.contains_key(item)
+    }
+
+    /// Removes one occurrence of `item`, returning whether it was present. The element is erased
+    /// once its count reaches 0.
+    #[inline]
+    pub fn remove<Q>(&mut self, item: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: PartialEq + ?Sized,
+        S: ElementHasher<Q>,
+    {
+        match self.counts.get_mut(item) {
+            Some(1) => {
+                self.counts.remove(item);
+                true
+            }
+            Some(count) => {
+                *count -= 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Erases every occurrence of `item`, returning how many there were.
+    #[inline]
+    pub fn remove_all<Q>(&mut self, item: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: PartialEq + ?Sized,
+        S: ElementHasher<Q>,
+    {
+        self.counts.remove(item).unwrap_or(0)
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T>> BloomMultiSet<T, S> {
+    /// Adds `n` occurrences of `item`, returning its new count. Adding 0 occurrences of an
+    /// element that isn't in the set does nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CapacityError`] if `item` would be the set's 256th distinct element.
+    #[inline]
+    pub fn try_insert_many(&mut self, item: T, n: usize) -> Result<usize, CapacityError> {
+        match self.counts.entry(item) {
+            Entry::Occupied(mut entry) => {
+                let count = entry.get_mut();
+                *count = count.saturating_add(n);
+                Ok(*count)
+            }
+            Entry::Vacant(_) if n == 0 => Ok(0),
+            Entry::Vacant(entry) => entry.try_insert(n).map(|count| *count),
+        }
+    }
+
+    /// Adds `n` occurrences of `item`, returning its new count.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `item` would be the set's 256th distinct element.
+    #[inline]
+    pub fn insert_many(&mut self, item: T, n: usize) -> usize {
+        self.try_insert_many(item, n)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Adds one occurrence of `item`, returning its new count.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `item` would be the set's 256th distinct element.
+    #[inline]
+    pub fn insert(&mut self, item: T) -> usize {
+        self.insert_many(item, 1)
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T> + Default> FromIterator<T> for BloomMultiSet<T, S> {
+    /// # Panics
+    ///
+    /// Panics if the iterator yields more than 255 distinct elements.
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::with_hasher(S::default());
+        for item in iter {
+            set.insert(item);
+        }
+        set
+    }
+}
+
+impl<T: fmt::Debug, S> fmt::Debug for BloomMultiSet<T, S> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, T, S> IntoIterator for &'a BloomMultiSet<T, S> {
+    type Item = (&'a T, usize);
+    type IntoIter = Iter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the distinct elements of a [`BloomMultiSet`] and their counts.
+#[derive(Clone, Debug)]
+pub struct Iter<'a, T> {
+    counts: map::Iter<'a, T, usize>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (&'a T, usize);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.counts.next().map(|(item, count)| (item, *count))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.counts.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_occurrences() {
+        let mut set = BloomMultiSet::new();
+        for i in 0..1000_u32 {
+            set.insert(i % 10);
+        }
+        assert_eq!(set.len(), 10);
+        assert_eq!(set.total(), 1000);
+        assert!((0..10).all(|i| set.count(&i) == 100));
+        assert_eq!(set.count(&10), 0);
+        assert_eq!(
+            set.iter().map(|(i, n)| (*i, n)).collect::<Vec<_>>(),
+            (0..10).map(|i| (i, 100)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn removal_erases_at_zero() {
+        let mut set: BloomMultiSet<&str> = ["x", "y", "x"].iter().copied().collect();
+        assert!(set.remove("x"));
+        assert_eq!(set.count("x"), 1);
+        assert!(set.remove("x"));
+        assert_eq!(set.count("x"), 0);
+        assert!(!set.contains("x") && !set.remove("x"));
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.remove_all("y"), 1);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn counts_saturate() {
+        let mut set = BloomMultiSet::new();
+        assert_eq!(set.insert_many("big", usize::MAX - 1), usize::MAX - 1);
+        assert_eq!(set.insert("big"), usize::MAX);
+        assert_eq!(set.insert("big"), usize::MAX);
+        assert_eq!(set.insert_many("big", usize::MAX), usize::MAX);
+        set.insert("small");
+        assert_eq!(set.total(), usize::MAX);
+        assert!(set.remove("big"));
+        assert_eq!(set.count("big"), usize::MAX - 1);
+    }
+
+    #[test]
+    fn limit_is_on_distinct_elements() {
+        let mut set: BloomMultiSet<u32> = (0..255).collect();
+        for _ in 0..1000 {
+            set.insert(7);
+        }
+        assert_eq!(set.count(&7), 1001);
+        assert_eq!(set.try_insert_many(255, 0), Ok(0));
+        assert_eq!(set.try_insert_many(255, 1).unwrap_err().limit(), 255);
+        assert!(set.remove(&0));
+        assert_eq!(set.try_insert_many(255, 1), Ok(1));
+    }
+}
@@ -0,0 +1,80 @@
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// How a `BloomSet` divides its length and capacity words between the counts and the filter.
+///
+/// The low bits of each word hold the length and capacity, and everything above them is filter,
+/// except that the top bit of the length word is the set's sorted flag (see
+/// [`BloomSet::sort`](crate::BloomSet::sort)), the seven bits below it count the filter bits that
+/// are set, and [`Packed8`] also keeps a seed in the top byte of the capacity word. Wider counts
+/// raise the capacity limit at the cost of filter bits. The number of filter bits follows from
+/// `usize::BITS`, so it is smaller on 32-bit targets. This trait is sealed; the packings are
+/// [`Packed8`], the default, and [`Packed16`].
+pub trait Packing: sealed::Sealed {
+    /// The number of low bits of each word holding the length or capacity.
+    const COUNT_BITS: u32;
+    /// Whether the top byte of the capacity word holds the set's seed.
+    const SEEDED: bool;
+
+    /// The largest number of elements a set with this packing can hold.
+    const MAX_CAPACITY: usize = (1 << Self::COUNT_BITS) - 1;
+    /// The number of bits in the filter.
+    const FILTER_BITS: u64 = (Self::LOW_BITS + Self::HIGH_BITS) as u64;
+
+    #[doc(hidden)]
+    const COUNT_MASK: usize = Self::MAX_CAPACITY;
+    #[doc(hidden)]
+    const SEED_MASK: usize = if Self::SEEDED {
+        0xFF << (usize::BITS - 8)
+    } else {
+        0
+    };
+    /// The number of filter bits in the capacity word; they come first.
+    #[doc(hidden)]
+    const LOW_BITS: u32 = usize::BITS - Self::COUNT_BITS - if Self::SEEDED { 8 } else { 0 };
+    /// The number of filter bits in the length word; they sit between the length and the count
+    /// of set filter bits.
+    #[doc(hidden)]
+    const HIGH_BITS: u32 = usize::BITS - Self::COUNT_BITS - 1 - Self::POPCOUNT_BITS;
+    /// The number of bits of the length word, just below the sorted flag, that count the set
+    /// filter bits, so lookups can check for saturation without counting them. Seven bits count
+    /// up to 127, more than any packing has filter bits.
+    #[doc(hidden)]
+    const POPCOUNT_BITS: u32 = 7;
+    #[doc(hidden)]
+    const POPCOUNT_SHIFT: u32 = usize::BITS - 1 - Self::POPCOUNT_BITS;
+    #[doc(hidden)]
+    const POPCOUNT_MASK: usize = ((1 << Self::POPCOUNT_BITS) - 1) << Self::POPCOUNT_SHIFT;
+    /// The bit of the length word that is set while the elements are known to be sorted.
+    #[doc(hidden)]
+    const SORTED_FLAG: usize = 1 << (usize::BITS - 1);
+    #[doc(hidden)]
+    #[allow(clippy::cast_possible_truncation)]
+    const SATURATION_THRESHOLD: u32 = (Self::FILTER_BITS * 3 / 4) as u32;
+}
+
+/// One byte each for the length and capacity, so at most 255 elements, a one-byte seed and a
+/// 96-bit filter (32 bits on 32-bit targets).
+#[derive(Clone, Copy, Debug)]
+pub enum Packed8 {}
+
+impl sealed::Sealed for Packed8 {}
+
+impl Packing for Packed8 {
+    const COUNT_BITS: u32 = 8;
+    const SEEDED: bool = true;
+}
+
+/// Two bytes each for the length and capacity, so at most 65,535 elements, and an 88-bit filter
+/// (24 bits on 32-bit targets).
+/// Sets with this packing can't be seeded.
+#[derive(Clone, Copy, Debug)]
+pub enum Packed16 {}
+
+impl sealed::Sealed for Packed16 {}
+
+impl Packing for Packed16 {
+    const COUNT_BITS: u32 = 16;
+    const SEEDED: bool = false;
+}
@@ -0,0 +1,131 @@
+use crate::BloomSet;
+use alloc::vec::Vec;
+
+/// Keeps the buffers of finished [`BloomSet`]s around so that building the next set doesn't
+/// have to allocate.
+///
+/// [`put`](BloomSetPool::put) clears a set and keeps it, up to the pool's limit; any set beyond
+/// that is dropped as usual. [`get`](BloomSetPool::get) hands back one of the kept sets, empty
+/// but with its capacity, or a new set if there are none.
+///
+/// ```
+/// use bloomset::BloomSetPool;
+///
+/// let mut pool = BloomSetPool::new(4);
+/// for round in 0..10 {
+///     let mut set = pool.get();
+///     set.insert(round);
+///     pool.put(set);
+/// }
+/// assert_eq!(pool.len(), 1);
+/// assert!(pool.get().capacity() > 0);
+/// ```
+pub struct BloomSetPool<T> {
+    sets: Vec<BloomSet<T>>,
+    limit: usize,
+}
+
+impl<T> BloomSetPool<T> {
+    /// Creates an empty pool that keeps at most `limit` sets.
+    #[inline]
+    #[must_use]
+    pub const fn new(limit: usize) -> Self {
+        Self {
+            sets: Vec::new(),
+            limit,
+        }
+    }
+
+    /// The most sets the pool keeps.
+    #[inline]
+    #[must_use]
+    pub const fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// The number of sets the pool is keeping for reuse.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.sets.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.sets.is_empty()
+    }
+
+    /// Returns an empty set, reusing the buffer of one handed to [`put`](BloomSetPool::put)
+    /// if there is one.
+    #[inline]
+    #[must_use]
+    pub fn get(&mut self) -> BloomSet<T> {
+        self.sets.pop().unwrap_or_default()
+    }
+
+    /// Drops the set's elements and keeps its buffer for a later [`get`](BloomSetPool::get),
+    /// unless the pool is full or the set never allocated. The set's seed is replaced the way
+    /// [`BloomSet::new`] picks one, so every set the pool hands out behaves like a new one:
+    /// unseeded, or with the `random-seed` feature, freshly seeded.
+    #[inline]
+    pub fn put(&mut self, mut set: BloomSet<T>) {
+        if self.sets.len() < self.limit && set.capacity() > 0 {
+            set.clear();
+            set.capacity &= crate::MAX_CAPACITY;
+            self.sets.push(set.with_random_seed());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn reuses_buffers() {
+        let mut pool = BloomSetPool::new(2);
+        let mut set = pool.get();
+        for i in 0..50_u32 {
+            set.insert(i);
+        }
+        let ptr = set.as_mut_ptr();
+        let capacity = set.capacity();
+        pool.put(set);
+
+        let mut set = pool.get();
+        assert!(set.is_empty() && set.filter_bits() == 0);
+        assert_eq!((set.as_mut_ptr(), set.capacity()), (ptr, capacity));
+        assert!(!set.contains(1));
+        set.insert(1);
+        assert!(set.contains(1));
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn bounded_and_drops_elements() {
+        let counted = Rc::new(());
+        let mut pool = BloomSetPool::new(2);
+        let sets: Vec<BloomSet<(u32, Rc<()>)>> = (0..4)
+            .map(|i| {
+                let mut set = BloomSet::with_seed(9);
+                set.insert((i, Rc::clone(&counted)));
+                set
+            })
+            .collect();
+        for set in sets {
+            pool.put(set);
+        }
+        assert_eq!(pool.len(), pool.limit());
+        assert_eq!(Rc::strong_count(&counted), 1);
+        let recycled = pool.get();
+        if !cfg!(feature = "random-seed") {
+            assert_eq!(recycled.seed(), 0);
+        }
+
+        // A set without a buffer isn't worth keeping
+        pool.put(BloomSet::new());
+        assert_eq!(pool.len(), 1);
+    }
+}
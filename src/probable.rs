@@ -0,0 +1,145 @@
+use crate::{BloomSet, ElementHasher, Packing};
+use core::borrow::Borrow;
+use core::fmt;
+use core::iter::FusedIterator;
+
+impl<T: PartialEq, S: ElementHasher<T>, const K: usize, P: Packing> BloomSet<T, S, K, P> {
+    /// Passes on the items of `iter` that get past the set's filter, dropping the ones it
+    /// proves absent. Nothing is compared against the elements, so what comes out may still
+    /// include false positives, but never leaves out an item the set holds.
+    ///
+    /// This is for thinning out a stream of mostly absent keys before an exact check that
+    /// costs more than a filter lookup.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let wanted: BloomSet<u64> = [3, 500, 70_000].iter().copied().collect();
+    /// let survivors: Vec<u64> = wanted.filter_probable(0..100_000).collect();
+    /// # #[cfg(not(feature = "no-filter"))]
+    /// assert!(survivors.len() < 10_000);
+    /// let found: Vec<u64> = survivors.into_iter().filter(|&key| wanted.contains(key)).collect();
+    /// assert_eq!(found, [3, 500, 70_000]);
+    /// ```
+    #[inline]
+    pub fn filter_probable<I>(&self, iter: I) -> FilterProbable<'_, I::IntoIter, T, S, K, P>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<T>,
+    {
+        FilterProbable {
+            set: self,
+            iter: iter.into_iter(),
+        }
+    }
+}
+
+/// The iterator returned by [`BloomSet::filter_probable`].
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct FilterProbable<'a, I, T, S, const K: usize, P: Packing> {
+    set: &'a BloomSet<T, S, K, P>,
+    iter: I,
+}
+
+impl<I, T, S, const K: usize, P> Iterator for FilterProbable<'_, I, T, S, K, P>
+where
+    I: Iterator,
+    I::Item: Borrow<T>,
+    T: PartialEq,
+    S: ElementHasher<T>,
+    P: Packing,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let set = self.set;
+        self.iter
+            .find(|item| set.bloom_contains(set.bloom_mask_of(item.borrow())))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+impl<I, T, S, const K: usize, P> FusedIterator for FilterProbable<'_, I, T, S, K, P>
+where
+    I: FusedIterator,
+    I::Item: Borrow<T>,
+    T: PartialEq,
+    S: ElementHasher<T>,
+    P: Packing,
+{
+}
+
+impl<I: Clone, T, S, const K: usize, P: Packing> Clone for FilterProbable<'_, I, T, S, K, P> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            set: self.set,
+            iter: self.iter.clone(),
+        }
+    }
+}
+
+impl<I: fmt::Debug, T, S, const K: usize, P: Packing> fmt::Debug
+    for FilterProbable<'_, I, T, S, K, P>
+{
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FilterProbable")
+            .field("iter", &self.iter)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BloomSet, BloomSet16};
+
+    fn xorshift(mut x: u64) -> impl Iterator<Item = u64> {
+        core::iter::repeat_with(move || {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x
+        })
+    }
+
+    #[test]
+    fn keeps_every_member() {
+        let members: Vec<u64> = xorshift(1).take(255).collect();
+        for n in [1, 10, 100, 255] {
+            let set: BloomSet<u64> = members[..n].iter().copied().collect();
+            assert_eq!(set.filter_probable(&members[..n]).count(), n);
+        }
+        let wide: BloomSet16<u64> = members.iter().copied().collect();
+        assert_eq!(wide.filter_probable(members.iter()).count(), 255);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "no-filter", ignore = "checks the filter bits")]
+    fn drops_most_absent_keys() {
+        let set: BloomSet<u64> = xorshift(2).take(10).collect();
+        // Ten bits out of 96 let through about a tenth of absent keys
+        let kept = set.filter_probable(xorshift(3).take(10_000)).count();
+        assert!(kept < 1_500, "{}", kept);
+
+        // Composes like any other adapter, and never scans
+        let empty: BloomSet<u64> = BloomSet::new();
+        assert_eq!(
+            empty.filter_probable(0..1000).map(|i| i * 2).sum::<u64>(),
+            0
+        );
+    }
+
+    #[test]
+    fn borrowed_items() {
+        let set: BloomSet<String> = ["a", "b"].iter().map(ToString::to_string).collect();
+        let words = vec!["a".to_string(), "c".to_string(), "b".to_string()];
+        let probable: Vec<&String> = set.filter_probable(&words).collect();
+        assert!(probable.contains(&&words[0]) && probable.contains(&&words[2]));
+    }
+}
@@ -0,0 +1,146 @@
+//! Kani proof harnesses for the unsafe core.
+//!
+//! They only exist under `cfg(kani)`, which `cargo kani` sets, so normal builds and tests never
+//! see them. Run them all from the crate root with `cargo kani`, or one with e.g.
+//! `cargo kani --harness insert_stays_in_bounds`.
+//!
+//! Kani checks every pointer dereference, `copy` and `dealloc` on its own, so the harnesses
+//! that drive a set only need to reach its states; their assertions add what isn't a memory
+//! error. Elements are `u8` or `u16` and sets hold at most [`STEPS`] of them, so the proofs
+//! converge in seconds; every loop in them is bounded by that, hence the `unwind` of one more.
+
+use crate::{
+    bloom_mask, raw, seeded, split_mask, unpack_filter, BloomSet, ElementHasher, Packed16, Packed8,
+    Packing, MAX_CAPACITY, SEED_SHIFT,
+};
+use alloc::alloc::Layout;
+use core::ptr::NonNull;
+
+/// How many operations a harness applies, and so the most elements a set in one holds.
+const STEPS: usize = 4;
+
+/// Passes `u64` elements through as their own hash, so a harness can range over every hash.
+struct Identity;
+
+impl ElementHasher<u64> for Identity {
+    fn hash_element(&self, item: &u64) -> u64 {
+        *item
+    }
+}
+
+/// Inserting into a set of any small capacity only writes inside its allocation, growing it
+/// when needed.
+#[kani::proof]
+#[kani::unwind(5)]
+fn insert_stays_in_bounds() {
+    let capacity: usize = kani::any_where(|&capacity| capacity <= STEPS);
+    let mut set = BloomSet::<u8>::with_capacity(capacity);
+    for _ in 0..STEPS {
+        set.insert(kani::any());
+        assert!(set.len() <= set.capacity());
+        assert!(set.as_slice().len() == set.len());
+    }
+}
+
+/// Every public mutation keeps `len() <= capacity() <= MAX_CAPACITY`, whatever order they come
+/// in.
+#[kani::proof]
+#[kani::unwind(5)]
+fn mutations_keep_len_within_capacity() {
+    let mut set = BloomSet::<u16>::new();
+    for _ in 0..STEPS {
+        let value: u16 = kani::any();
+        match kani::any::<u8>() % 10 {
+            0 => set.insert(value),
+            1 => {
+                set.remove(value);
+            }
+            2 => set.clear(),
+            3 => set.retain(|&it| it != value),
+            4 => set.retain_unordered(|&it| it < value),
+            5 => {
+                set.promote(value);
+            }
+            6 => {
+                set.contains_promoting(value);
+            }
+            7 => set.sort(),
+            8 => set.shrink_to(usize::from(value) % STEPS),
+            _ => {
+                let _ = set.try_reserve(usize::from(value) % STEPS);
+            }
+        }
+        assert!(set.len() <= set.capacity() && set.capacity() <= MAX_CAPACITY);
+    }
+}
+
+/// For every hash and seed, the filter bit `insert` sets survives being split across the
+/// capacity and length words, without touching the counts, the seed, the sorted flag or the
+/// count of set filter bits, and is the bit `contains` then tests.
+#[kani::proof]
+fn filter_bits_agree() {
+    fn check<P: Packing>(hash: u64, seed: u8) {
+        let mask = bloom_mask::<1>(seeded(hash, seed), P::FILTER_BITS);
+        assert!(mask.count_ones() == 1 && mask >> P::FILTER_BITS == 0);
+        let (low, high) = split_mask::<P>(mask);
+        assert!(low & (P::COUNT_MASK | P::SEED_MASK) == 0);
+        assert!(high & (P::COUNT_MASK | P::POPCOUNT_MASK | P::SORTED_FLAG) == 0);
+        assert!(unpack_filter::<P>(high, low) == mask);
+    }
+    let hash: u64 = kani::any();
+    let seed: u8 = kani::any();
+    check::<Packed8>(hash, seed);
+    check::<Packed16>(hash, seed);
+
+    let mut set = BloomSet::<u64, _>::with_hasher(Identity);
+    set.capacity |= usize::from(seed) << SEED_SHIFT;
+    set.insert(hash);
+    assert!(set.contains(hash));
+    assert!(set.seed() == seed);
+    assert!(set.filter_bits_set() == 1);
+}
+
+/// Dropping a set frees the block it allocated, whatever capacity it grew or shrank to on the
+/// way, and nothing else; Kani rejects a `dealloc` of anything but a live allocation's start.
+#[kani::proof]
+#[kani::unwind(5)]
+fn drop_frees_the_allocation() {
+    let capacity: usize = kani::any_where(|&capacity| capacity <= STEPS);
+    let inserts: usize = kani::any_where(|&inserts| inserts <= STEPS);
+    let mut set = BloomSet::<u16>::with_capacity(capacity);
+    for _ in 0..inserts {
+        set.insert(kani::any());
+    }
+    if kani::any() {
+        set.shrink_to_fit();
+    }
+    drop(set);
+}
+
+/// The type-independent buffer code hands out blocks big enough for the capacity asked for,
+/// and frees them with the layout they were allocated with, for any element layout.
+#[kani::proof]
+fn reallocate_then_deallocate() {
+    let align = 1 << kani::any_where(|&shift: &u32| shift < 4);
+    let size = align * kani::any_where(|&count: &usize| count <= 2);
+    let element = Layout::from_size_align(size, align).unwrap();
+    let first: usize = kani::any_where(|&capacity| capacity <= MAX_CAPACITY);
+    let second: usize = kani::any_where(|&capacity| capacity <= MAX_CAPACITY);
+    unsafe {
+        let Ok(ptr) = raw::reallocate(NonNull::dangling(), element, 0, first) else {
+            return;
+        };
+        if size * first != 0 {
+            ptr.as_ptr().add(size * first - 1).write(0);
+        }
+        // On failure the first block is still ours
+        let Ok(moved) = raw::reallocate(ptr, element, first, second) else {
+            raw::deallocate(ptr, element, first);
+            return;
+        };
+        if size * second != 0 {
+            moved.as_ptr().add(size * second - 1).write(0);
+        }
+        raw::deallocate(moved, element, second);
+    }
+}
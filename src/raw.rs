@@ -0,0 +1,129 @@
+//! The parts of [`BloomSet`](crate::BloomSet) that don't depend on the element type, taking the
+//! element's `Layout` or indices instead of a `T`, so that they are compiled once rather than
+//! for every element type.
+
+use crate::TryReserveError;
+use alloc::alloc::{self as heap, Layout};
+use alloc::vec::Vec;
+use core::ptr::{self, NonNull};
+
+/// The layout of an array of `capacity` elements laid out like `element`, if it fits in an
+/// `isize`. This is `Layout::array` without the type parameter.
+fn array(element: Layout, capacity: usize) -> Option<Layout> {
+    let size = element.size().checked_mul(capacity)?;
+    Layout::from_size_align(size, element.align()).ok()
+}
+
+/// Moves a buffer of `old_capacity` elements laid out like `element` into one of `new_capacity`,
+/// returning the new buffer. A size of 0 means no allocation, on either side; the returned
+/// pointer is then dangling, but aligned for `element`.
+///
+/// On failure the old buffer is untouched and still owned by the caller.
+///
+/// # Safety
+///
+/// `ptr` must be dangling if the old buffer's size is 0, and otherwise be an allocation with
+/// exactly the old buffer's layout. `element.size()` must be a multiple of its alignment.
+#[inline(never)]
+pub unsafe fn reallocate(
+    ptr: NonNull<u8>,
+    element: Layout,
+    old_capacity: usize,
+    new_capacity: usize,
+) -> Result<NonNull<u8>, TryReserveError> {
+    let new_layout =
+        array(element, new_capacity).ok_or(TryReserveError::AllocFailed { layout: None })?;
+    // The old buffer was allocated with this layout, so it can't overflow
+    let old_layout =
+        Layout::from_size_align_unchecked(element.size() * old_capacity, element.align());
+    if new_layout.size() == 0 {
+        // Only shrinking gets here, and an empty buffer is no buffer at all
+        deallocate(ptr, element, old_capacity);
+        return Ok(dangling(element));
+    }
+    let new = if old_layout.size() == 0 {
+        heap::alloc(new_layout)
+    } else {
+        heap::realloc(ptr.as_ptr(), old_layout, new_layout.size())
+    };
+    NonNull::new(new).ok_or(TryReserveError::AllocFailed {
+        layout: Some(new_layout),
+    })
+}
+
+/// Frees a buffer of `capacity` elements laid out like `element`, if it is an allocation at all.
+///
+/// # Safety
+///
+/// As for [`reallocate`], `ptr` and `capacity` must describe the buffer exactly.
+#[inline(never)]
+pub unsafe fn deallocate(ptr: NonNull<u8>, element: Layout, capacity: usize) {
+    let size = element.size() * capacity;
+    if size != 0 {
+        heap::dealloc(
+            ptr.as_ptr(),
+            Layout::from_size_align_unchecked(size, element.align()),
+        );
+    }
+}
+
+/// A dangling pointer aligned for `element`, like `NonNull::<T>::dangling`.
+const fn dangling(element: Layout) -> NonNull<u8> {
+    // Alignments are never 0. Offsetting from null rather than casting the address keeps this
+    // a pointer without provenance, which strict provenance allows
+    unsafe { NonNull::new_unchecked(core::ptr::null_mut::<u8>().wrapping_add(element.align())) }
+}
+
+/// Finds which of the elements from index `len` on are duplicates, of an element before `len` or
+/// of an earlier one after it that isn't a duplicate itself, given the hashes of all of them.
+///
+/// Only elements with equal hashes are compared, through `eq`, which is called with two indices
+/// and is the only part that knows the element type.
+#[inline(never)]
+pub fn mark_duplicates(
+    hashes: &[u64],
+    len: usize,
+    eq: &mut dyn FnMut(usize, usize) -> bool,
+) -> Vec<bool> {
+    let mut duplicate = alloc::vec![false; hashes.len() - len];
+    let mut order: Vec<(u64, usize)> = hashes.iter().copied().zip(0..).collect();
+    order.sort_unstable();
+    for group in order
+        .chunk_by(|a, b| a.0 == b.0)
+        .filter(|group| group.len() > 1)
+    {
+        for (k, &(_, j)) in group.iter().enumerate().skip(1) {
+            if j >= len
+                && group[..k]
+                    .iter()
+                    .any(|&(_, i)| (i < len || !duplicate[i - len]) && eq(i, j))
+            {
+                duplicate[j - len] = true;
+            }
+        }
+    }
+    duplicate
+}
+
+/// Moves the elements of `size` bytes at `base` that `duplicate` doesn't mark to the front,
+/// keeping their order, and their hashes along with them, and returns how many there are. The
+/// duplicates end up after them, in some order.
+///
+/// # Safety
+///
+/// `base` must point to `duplicate.len()` initialized elements of `size` bytes, and `hashes`
+/// must be at least that long.
+#[inline(never)]
+pub unsafe fn compact(base: *mut u8, size: usize, hashes: &mut [u64], duplicate: &[bool]) -> usize {
+    let mut kept = 0;
+    for (j, &is_duplicate) in duplicate.iter().enumerate() {
+        if !is_duplicate {
+            if kept != j {
+                ptr::swap_nonoverlapping(base.add(kept * size), base.add(j * size), size);
+            }
+            hashes[kept] = hashes[j];
+            kept += 1;
+        }
+    }
+    kept
+}
@@ -0,0 +1,283 @@
+use crate::{bloom_mask, seeded, BloomSet, ElementHasher, Packing, TryReserveError};
+
+impl<T: PartialEq, S: ElementHasher<T>, const K: usize, P: Packing> BloomSet<T, S, K, P> {
+    /// Looks up an element by a hash computed beforehand, for inserting it afterwards without
+    /// hashing it again or scanning the set a second time.
+    ///
+    /// `hash` must be what the set's hasher gives the element, `hasher().hash_element(item)`;
+    /// for the default hasher that is `BuildBloomHasher.hash_one(item)`, the same hash
+    /// [`BloomFilter::insert_hash`](crate::BloomFilter::insert_hash) takes. The set's seed is
+    /// applied to it here, so one hash can be kept and used with any number of sets that share
+    /// a hasher. `is_match` has to agree with `PartialEq`: the element it picks out is the one
+    /// `contains` would find. A wrong hash or a `is_match` that matches an element of a
+    /// different hash isn't unsafe, but it leaves the set in a state where lookups miss
+    /// elements it holds.
+    ///
+    /// ```
+    /// use bloomset::{BloomSet, BuildBloomHasher, RawEntry};
+    /// use std::hash::BuildHasher;
+    ///
+    /// let mut seen: BloomSet<String> = BloomSet::new();
+    /// let hash = BuildBloomHasher.hash_one("lib.rs");
+    /// match seen.raw_entry(hash, |name| name == "lib.rs") {
+    ///     RawEntry::Occupied(_) => unreachable!(),
+    ///     RawEntry::Vacant(entry) => assert_eq!(entry.insert("lib.rs".to_string()), "lib.rs"),
+    /// }
+    /// assert!(seen.contains("lib.rs".to_string()));
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn raw_entry(
+        &mut self,
+        hash: u64,
+        is_match: impl Fn(&T) -> bool,
+    ) -> RawEntry<'_, T, S, K, P> {
+        let mask = bloom_mask::<K>(seeded(hash, self.seed()), P::FILTER_BITS);
+        let passed = self.filter_bits_set() >= P::SATURATION_THRESHOLD || self.bloom_contains(mask);
+        let index = if passed {
+            self.as_slice().iter().position(&is_match)
+        } else {
+            None
+        };
+        #[cfg(feature = "stats")]
+        crate::stats::record(self.len(), passed, index);
+        #[cfg(feature = "paranoid")]
+        self.check_lookup_by(&is_match, index.is_some());
+        match index {
+            Some(index) => RawEntry::Occupied(RawOccupiedEntry { set: self, index }),
+            None => RawEntry::Vacant(RawVacantEntry {
+                set: self,
+                hash,
+                mask,
+            }),
+        }
+    }
+}
+
+/// A view into the place of one hash in a [`BloomSet`], made by [`BloomSet::raw_entry`].
+pub enum RawEntry<'a, T, S, const K: usize, P: Packing> {
+    Occupied(RawOccupiedEntry<'a, T, S, K, P>),
+    Vacant(RawVacantEntry<'a, T, S, K, P>),
+}
+
+/// A raw entry for an element that is in the set.
+pub struct RawOccupiedEntry<'a, T, S, const K: usize, P: Packing> {
+    set: &'a mut BloomSet<T, S, K, P>,
+    index: usize,
+}
+
+/// A raw entry for an element that isn't in the set yet, holding its hash and filter bits.
+pub struct RawVacantEntry<'a, T, S, const K: usize, P: Packing> {
+    set: &'a mut BloomSet<T, S, K, P>,
+    hash: u64,
+    mask: u128,
+}
+
+impl<'a, T: PartialEq, S: ElementHasher<T>, const K: usize, P: Packing> RawEntry<'a, T, S, K, P> {
+    /// The element, if the entry is occupied.
+    #[inline]
+    #[must_use]
+    pub fn get(&self) -> Option<&T> {
+        match self {
+            RawEntry::Occupied(entry) => Some(entry.get()),
+            RawEntry::Vacant(_) => None,
+        }
+    }
+
+    /// The element already there, or else `item`, inserted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the entry is vacant and the set already holds its packing's
+    /// [`MAX_CAPACITY`](Packing::MAX_CAPACITY) elements.
+    #[inline]
+    pub fn or_insert(self, item: T) -> &'a T {
+        match self {
+            RawEntry::Occupied(entry) => entry.into_ref(),
+            RawEntry::Vacant(entry) => entry.insert(item),
+        }
+    }
+}
+
+impl<'a, T: PartialEq, S: ElementHasher<T>, const K: usize, P: Packing>
+    RawOccupiedEntry<'a, T, S, K, P>
+{
+    /// Where the element is in [`as_slice`](BloomSet::as_slice).
+    #[inline]
+    #[must_use]
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn get(&self) -> &T {
+        &self.set.as_slice()[self.index]
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn into_ref(self) -> &'a T {
+        &self.set.as_slice()[self.index]
+    }
+
+    /// Removes the element from the set, like [`remove`](BloomSet::remove): the elements after
+    /// it move forward, and its filter bits stay set.
+    #[inline]
+    #[must_use]
+    pub fn remove(self) -> T {
+        self.set.take(self.index)
+    }
+}
+
+impl<'a, T: PartialEq, S: ElementHasher<T>, const K: usize, P: Packing>
+    RawVacantEntry<'a, T, S, K, P>
+{
+    /// The hash the entry was looked up by.
+    #[inline]
+    #[must_use]
+    pub const fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Appends `item`, setting the filter bits of the entry's hash, and returns a reference to
+    /// it. `item` isn't hashed; in debug builds it is, to check it has the entry's hash.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TryReserveError::Capacity`] if the set already holds its packing's
+    /// [`MAX_CAPACITY`](Packing::MAX_CAPACITY) elements, and [`TryReserveError::AllocFailed`]
+    /// if the allocator can't provide a bigger buffer. Either way `item` is dropped and the
+    /// set is left as it was.
+    #[inline]
+    pub fn try_insert(self, item: T) -> Result<&'a T, TryReserveError> {
+        debug_assert_eq!(
+            self.set.hash_builder.hash_element(&item),
+            self.hash,
+            "the element inserted through a raw entry has a different hash"
+        );
+        self.set.push_new(item, self.mask)?;
+        Ok(&self.set.as_slice()[self.set.len() - 1])
+    }
+
+    /// Like [`try_insert`](RawVacantEntry::try_insert), but panics instead of returning an
+    /// error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the set already holds its packing's [`MAX_CAPACITY`](Packing::MAX_CAPACITY)
+    /// elements.
+    #[inline]
+    pub fn insert(self, item: T) -> &'a T {
+        self.try_insert(item)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BloomSet, BloomSet16, BuildBloomHasher, ElementHasher, RawEntry};
+    use core::hash::BuildHasher;
+
+    /// Inserts `item` through a raw entry, the way a caller that hashed it already would.
+    fn raw_insert<T: core::hash::Hash + PartialEq>(set: &mut BloomSet<T>, item: T) -> bool {
+        let hash = BuildBloomHasher.hash_one(&item);
+        match set.raw_entry(hash, |it| *it == item) {
+            RawEntry::Occupied(entry) => {
+                assert!(*entry.get() == item);
+                false
+            }
+            RawEntry::Vacant(entry) => {
+                entry.insert(item);
+                true
+            }
+        }
+    }
+
+    #[test]
+    fn same_as_insert() {
+        let mut rng = 0x2545_F491_4F6C_DD1D_u64;
+        let mut plain: BloomSet<u64> = BloomSet::new();
+        let mut raw = BloomSet::with_seed(plain.seed());
+        for _ in 0..1000 {
+            rng ^= rng << 13;
+            rng ^= rng >> 7;
+            rng ^= rng << 17;
+            if plain.len() == 255 {
+                plain.clear();
+                raw.clear();
+            }
+            let item = rng % 400;
+            let new = !plain.contains(item);
+            plain.insert(item);
+            assert_eq!(raw_insert(&mut raw, item), new);
+            assert_eq!(raw.as_slice(), plain.as_slice());
+            assert_eq!(raw.filter_bits(), plain.filter_bits());
+            assert_eq!(raw.is_sorted(), plain.is_sorted());
+        }
+        assert!((0..400).all(|i| raw.contains(i) == plain.contains(i)));
+
+        let mut plain: BloomSet<String> = BloomSet::new();
+        let mut raw = BloomSet::with_seed(plain.seed());
+        for word in ["a", "bb", "a", "ccc", "bb", "dddd"] {
+            plain.insert(word.to_string());
+            raw_insert(&mut raw, word.to_string());
+        }
+        assert_eq!(raw.as_slice(), plain.as_slice());
+        assert_eq!(raw.filter_bits(), plain.filter_bits());
+    }
+
+    #[test]
+    fn one_hash_for_many_sets() {
+        // Sets with different seeds map the same hash onto different bits, and each finds it
+        let mut sets: Vec<BloomSet<&str>> = (0..8).map(BloomSet::with_seed).collect();
+        let hash = BuildBloomHasher.hash_one("shared");
+        for set in &mut sets {
+            assert_eq!(
+                set.raw_entry(hash, |it| *it == "shared")
+                    .or_insert("shared"),
+                &"shared"
+            );
+            assert!(set.contains("shared"));
+            assert!(set.raw_entry(hash, |it| *it == "shared").get().is_some());
+        }
+        // And past 255 elements, where the filter bits sit elsewhere in the words
+        let mut wide: BloomSet16<u16> = BloomSet16::with_hasher(BuildBloomHasher);
+        wide.extend(0..300);
+        let hash = wide.hasher().hash_element(&300);
+        let RawEntry::Vacant(entry) = wide.raw_entry(hash, |&i| i == 300) else {
+            panic!("300 isn't in the set yet");
+        };
+        assert_eq!(entry.hash(), hash);
+        entry.insert(300);
+        assert!(wide.contains(300) && wide.len() == 301);
+    }
+
+    #[test]
+    fn occupied() {
+        let mut set: BloomSet<u32> = (0..5).collect();
+        let RawEntry::Occupied(entry) =
+            set.raw_entry(BuildBloomHasher.hash_one(3_u32), |&i| i == 3)
+        else {
+            panic!("3 is in the set");
+        };
+        assert_eq!((entry.index(), *entry.get()), (3, 3));
+        assert_eq!(entry.remove(), 3);
+        assert_eq!(set.as_slice(), [0, 1, 2, 4]);
+        assert!(set
+            .raw_entry(BuildBloomHasher.hash_one(3_u32), |&i| i == 3)
+            .get()
+            .is_none());
+    }
+
+    #[test]
+    fn full() {
+        let mut set: BloomSet<u32> = (0..255).collect();
+        let hash = BuildBloomHasher.hash_one(255_u32);
+        let RawEntry::Vacant(entry) = set.raw_entry(hash, |&i| i == 255) else {
+            panic!("255 isn't in the set");
+        };
+        assert!(entry.try_insert(255).is_err());
+        assert_eq!(set.len(), 255);
+        assert!(!set.contains(255));
+    }
+}
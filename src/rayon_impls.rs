@@ -0,0 +1,136 @@
+use crate::{BloomSet, ElementHasher, Packing};
+use rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelExtend, ParallelIterator};
+use rayon::slice::Iter;
+
+impl<T: Sync, S, const K: usize, P: Packing> BloomSet<T, S, K, P> {
+    /// Returns a parallel iterator over the elements, in the same order as
+    /// [`as_slice`](BloomSet::as_slice).
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    /// use rayon::iter::ParallelIterator;
+    ///
+    /// let set: BloomSet<u32> = (1..=100).collect();
+    /// assert_eq!(set.par_iter().sum::<u32>(), 5050);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn par_iter(&self) -> Iter<'_, T> {
+        self.as_slice().into_par_iter()
+    }
+}
+
+/// Merges `b` into `a`, or `a` into `b` if it's bigger, so the fewest elements are inserted.
+fn merge<T: PartialEq, S: ElementHasher<T>, const K: usize, P: Packing>(
+    a: BloomSet<T, S, K, P>,
+    b: BloomSet<T, S, K, P>,
+) -> BloomSet<T, S, K, P> {
+    let (mut into, from) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    into.extend(from.into_vec());
+    into
+}
+
+/// Each thread collects into a set of its own, and those are merged pairwise, each time into the
+/// bigger of the two. So the elements end up in no particular order, unlike with
+/// [`FromIterator`].
+///
+/// # Panics
+///
+/// Panics if the iterator yields more unique elements than the set can hold (255 by default),
+/// which may be noticed on any thread.
+impl<T, S, const K: usize, P> FromParallelIterator<T> for BloomSet<T, S, K, P>
+where
+    T: PartialEq + Send,
+    S: ElementHasher<T> + Default + Send,
+    P: Packing,
+{
+    #[inline]
+    fn from_par_iter<I: IntoParallelIterator<Item = T>>(iter: I) -> Self {
+        iter.into_par_iter()
+            .fold(
+                || Self::with_hasher(S::default()),
+                |mut set, item| {
+                    set.insert(item);
+                    set
+                },
+            )
+            .reduce(|| Self::with_hasher(S::default()), merge)
+    }
+}
+
+/// Collects the new elements in parallel like [`FromParallelIterator`], then inserts them into
+/// this set, which keeps its elements where they are and its hasher.
+///
+/// # Panics
+///
+/// Panics if the set would need to hold more unique elements than it can.
+impl<T, S, const K: usize, P> ParallelExtend<T> for BloomSet<T, S, K, P>
+where
+    T: PartialEq + Send,
+    S: ElementHasher<T> + Default + Send,
+    P: Packing,
+{
+    #[inline]
+    fn par_extend<I: IntoParallelIterator<Item = T>>(&mut self, iter: I) {
+        let new: Self = iter.into_par_iter().collect();
+        self.extend(new.into_vec());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BloomSet, BloomSet16};
+    use rayon::iter::{IntoParallelIterator, ParallelExtend, ParallelIterator};
+
+    fn sorted<T: Clone + Ord>(elements: &[T]) -> Vec<T> {
+        let mut elements = elements.to_vec();
+        elements.sort_unstable();
+        elements
+    }
+
+    #[test]
+    fn collect_matches_serial() {
+        let input: Vec<u32> = (0..10_000).map(|i| i * 7_919 % 251).collect();
+        let serial: BloomSet<u32> = input.iter().copied().collect();
+        let parallel: BloomSet<u32> = input.into_par_iter().collect();
+        assert_eq!(sorted(parallel.as_slice()), sorted(serial.as_slice()));
+        assert!((0..300).all(|i| parallel.contains(i) == serial.contains(i)));
+
+        let strings: BloomSet<String> = (0..5_000)
+            .into_par_iter()
+            .map(|i| (i % 97).to_string())
+            .collect();
+        assert_eq!(strings.len(), 97);
+        assert!(strings.contains("96".to_string()));
+
+        let wide: BloomSet16<u32> = (0..2_000).into_par_iter().map(|i| i % 1_500).collect();
+        assert_eq!(sorted(wide.as_slice()), (0..1_500).collect::<Vec<_>>());
+
+        let empty: BloomSet<u32> = Vec::new().into_par_iter().collect();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn par_extend_matches_serial() {
+        let mut serial: BloomSet<u32> = (0..50).collect();
+        let mut parallel: BloomSet<u32> = (0..50).collect();
+        serial.extend((25..200).map(|i| i % 150));
+        parallel.par_extend((25..200).into_par_iter().map(|i| i % 150));
+        // The elements already there stay at the front
+        assert_eq!(parallel.as_slice()[..50], serial.as_slice()[..50]);
+        assert_eq!(sorted(parallel.as_slice()), sorted(serial.as_slice()));
+    }
+
+    #[test]
+    fn par_iter() {
+        let set: BloomSet<u64> = (0..255).collect();
+        let doubled: Vec<u64> = set.par_iter().map(|i| i * 2).collect();
+        assert!(doubled.iter().copied().eq((0..255).map(|i| i * 2)));
+    }
+
+    #[test]
+    #[should_panic = "cannot hold more than 255"]
+    fn collect_past_capacity() {
+        let _: BloomSet<u32> = (0..1_000).into_par_iter().collect();
+    }
+}
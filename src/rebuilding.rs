@@ -0,0 +1,196 @@
+use crate::{BloomSet, BuildBloomHasher, ElementHasher};
+use core::borrow::Borrow;
+use core::ops::Deref;
+
+/// A [`BloomSet`] that keeps its filter from saturating under churn by
+/// [rebuilding it](BloomSet::rebuild_filter) by itself.
+///
+/// A rebuild happens once more elements have been removed since the last one than the set
+/// holds, so a long-lived set whose contents keep changing keeps its fast misses.
+///
+/// The rebuild happens inside the removal that crosses that line; reads go straight to the set,
+/// through `Deref`, and never rebuild. A rebuild hashes each element left, and there have been
+/// more removals than that since the last one, so it costs less than a hash per removal on
+/// average. Between rebuilds the filter keeps the bits of up to `len() + 1` removed elements,
+/// where a [`CountingBloomSet`](crate::CountingBloomSet) keeps none but needs an allocation for
+/// its counts.
+///
+/// ```
+/// use bloomset::RebuildingBloomSet;
+///
+/// let mut recent = RebuildingBloomSet::new();
+/// for i in 0..1000_u32 {
+///     recent.insert(i);
+///     if i >= 10 {
+///         recent.remove(i - 10);
+///     }
+/// }
+/// assert_eq!(recent.len(), 10);
+/// assert!(recent.saturation() < 0.5);
+/// ```
+pub struct RebuildingBloomSet<T, S = BuildBloomHasher, const K: usize = 1> {
+    set: BloomSet<T, S, K>,
+    /// Elements removed since the filter was last rebuilt.
+    removals: usize,
+}
+
+impl<T> Default for RebuildingBloomSet<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_hasher(BuildBloomHasher)
+    }
+}
+
+impl<T> RebuildingBloomSet<T> {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T, S, const K: usize> RebuildingBloomSet<T, S, K> {
+    /// Creates an empty set which will use `hash_builder` to hash its elements.
+    #[inline]
+    #[must_use]
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self::from(BloomSet::with_hasher(hash_builder))
+    }
+
+    /// The set, with whatever stale bits its filter has.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> BloomSet<T, S, K> {
+        self.set
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.set.clear();
+        self.removals = 0;
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T>, const K: usize> RebuildingBloomSet<T, S, K> {
+    /// # Panics
+    ///
+    /// Panics if the set would need to grow beyond 255 elements.
+    #[inline]
+    pub fn insert(&mut self, item: T) {
+        self.set.insert(item);
+    }
+
+    /// Removes `item` from the set, returning whether it was present, and rebuilds the filter
+    /// if that makes the removals since the last rebuild outnumber the elements left.
+    #[inline]
+    pub fn remove<B: Borrow<T>>(&mut self, item: B) -> bool {
+        if !self.set.remove(item) {
+            return false;
+        }
+        self.removals += 1;
+        if self.removals > self.set.len() {
+            self.rebuild_filter();
+        }
+        true
+    }
+
+    /// Keeps only the elements for which `f` returns `true`, like [`BloomSet::retain`], which
+    /// rebuilds the filter already.
+    #[inline]
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, f: F) {
+        self.set.retain(f);
+        self.removals = 0;
+    }
+
+    /// Rebuilds the filter now, like [`BloomSet::rebuild_filter`], and starts counting removals
+    /// again.
+    #[inline]
+    pub fn rebuild_filter(&mut self) {
+        self.set.rebuild_filter();
+        self.removals = 0;
+    }
+}
+
+impl<T, S, const K: usize> Deref for RebuildingBloomSet<T, S, K> {
+    type Target = BloomSet<T, S, K>;
+
+    #[inline]
+    fn deref(&self) -> &BloomSet<T, S, K> {
+        &self.set
+    }
+}
+
+impl<T, S, const K: usize> From<BloomSet<T, S, K>> for RebuildingBloomSet<T, S, K> {
+    /// Starts counting removals from `set`, leaving any stale bits its filter already has until
+    /// the first rebuild.
+    #[inline]
+    fn from(set: BloomSet<T, S, K>) -> Self {
+        Self { set, removals: 0 }
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T> + Default, const K: usize> core::iter::FromIterator<T>
+    for RebuildingBloomSet<T, S, K>
+{
+    /// # Panics
+    ///
+    /// Panics if the iterator yields more than 255 unique elements.
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<BloomSet<T, S, K>>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg_attr(feature = "no-filter", ignore = "checks the filter bits")]
+    fn churn_keeps_saturation_bounded() {
+        const WINDOW: u32 = 30;
+        let mut rebuilding = RebuildingBloomSet::new();
+        let mut plain = BloomSet::with_seed(rebuilding.seed());
+        let mut worst: f64 = 0.0;
+        for i in 0..5000_u32 {
+            rebuilding.insert(i);
+            plain.insert(i);
+            if i >= WINDOW {
+                assert!(rebuilding.remove(i - WINDOW));
+                assert!(plain.remove(i - WINDOW));
+            }
+            assert_eq!(rebuilding.as_slice(), plain.as_slice());
+            worst = worst.max(rebuilding.saturation());
+        }
+        // At most 61 elements' worth of bits are ever set, about 45% of the filter
+        assert!(worst < 0.6, "{}", worst);
+        assert!(plain.saturation() > 0.99, "{}", plain.saturation());
+        assert!((4970..5000).all(|i| rebuilding.contains(i)));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "no-filter", ignore = "checks the filter bits")]
+    fn rebuilds_in_the_removal_that_crosses_the_line() {
+        let mut set: RebuildingBloomSet<u32> = (0..8).collect();
+        let fresh = |set: &RebuildingBloomSet<u32>| {
+            let mut fresh = BloomSet::with_seed(set.seed());
+            fresh.extend(set.as_slice().iter().copied());
+            fresh.filter_bits()
+        };
+        // Removing 0..4 leaves 4 elements, which the 4 removals don't outnumber yet
+        for i in 0..4 {
+            assert!(set.remove(i) && set.removals == i as usize + 1);
+        }
+        assert!(!set.remove(0));
+        assert_eq!(set.removals, 4);
+        assert!(set.remove(4));
+        assert_eq!(set.removals, 0);
+        assert_eq!(set.filter_bits(), fresh(&set));
+
+        set.insert(4);
+        set.retain(|&i| i != 5);
+        assert_eq!(set.removals, 0);
+        let set = set.into_inner();
+        assert_eq!(set.as_slice(), [6, 7, 4]);
+    }
+}
@@ -0,0 +1,483 @@
+//! A `BloomSet` without unsafe code, for builds whose policy forbids it, and a reference model
+//! to test the real one against.
+//!
+//! [`safe::BloomSet`](BloomSet) keeps its elements in a `Vec` and its filter in a `u128` field
+//! of its own, so it is five words rather than three, but it maps elements onto filter bits
+//! through the same functions, with the same seeds, and behaves the same: the same elements in
+//! the same order, the same filter bits, the same answers and the same capacity limit. This
+//! module is `#![forbid(unsafe_code)]`.
+//!
+//! It covers the core of `BloomSet`'s API: building, inserting, looking up and removing. The
+//! raw-parts constructors and spare-capacity access have no safe equivalent, and the other set
+//! types in the crate are built on raw pointers and aren't here. A program that only names
+//! `bloomset::safe::BloomSet` links none of the crate's unsafe code.
+
+#![forbid(unsafe_code)]
+
+use crate::{
+    bloom_mask, seeded, BuildBloomHasher, CapacityError, ElementHasher, Packed8, Packing,
+    TryReserveError,
+};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::iter::FromIterator;
+use core::slice;
+
+/// A set with the behavior of [`crate::BloomSet`], stored as a `Vec` and a separate filter.
+pub struct BloomSet<T, S = BuildBloomHasher, const K: usize = 1, P: Packing = Packed8> {
+    elements: Vec<T>,
+    filter: u128,
+    seed: u8,
+    sorted: bool,
+    hash_builder: S,
+    packing: core::marker::PhantomData<P>,
+}
+
+impl<T> Default for BloomSet<T> {
+    #[inline]
+    fn default() -> Self {
+        let mut set = Self::with_hasher(BuildBloomHasher);
+        set.seed = random_seed::<Packed8>();
+        set
+    }
+}
+
+impl<T> BloomSet<T> {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty set whose elements are spread over the filter according to `seed`,
+    /// like [`crate::BloomSet::with_seed`].
+    #[inline]
+    #[must_use]
+    pub const fn with_seed(seed: u8) -> Self {
+        let mut set = Self::with_hasher(BuildBloomHasher);
+        set.seed = if Packed8::SEEDED { seed } else { 0 };
+        set
+    }
+}
+
+/// A seed for a new set, drawn the way `BloomSet::new` draws one.
+#[cfg(feature = "random-seed")]
+#[allow(clippy::cast_possible_truncation)]
+fn random_seed<P: Packing>() -> u8 {
+    use std::hash::{BuildHasher, Hasher};
+    if !P::SEEDED {
+        return 0;
+    }
+    let random = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+    ((random as usize & P::SEED_MASK) >> crate::SEED_SHIFT) as u8
+}
+
+#[cfg(not(feature = "random-seed"))]
+#[allow(clippy::extra_unused_type_parameters)]
+const fn random_seed<P: Packing>() -> u8 {
+    0
+}
+
+impl<T, S, const K: usize, P: Packing> BloomSet<T, S, K, P> {
+    #[inline]
+    #[must_use]
+    pub const fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            elements: Vec::new(),
+            filter: 0,
+            seed: 0,
+            sorted: false,
+            hash_builder,
+            packing: core::marker::PhantomData,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn seed(&self) -> u8 {
+        self.seed
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        &self.elements
+    }
+
+    #[inline]
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.elements.iter()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_sorted(&self) -> bool {
+        self.sorted
+    }
+
+    /// The filter bits, with bit `i` standing for filter bit `i`.
+    #[inline]
+    #[must_use]
+    const fn filter_bits(&self) -> u128 {
+        self.filter
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn filter_bits_set(&self) -> u32 {
+        self.filter_bits().count_ones()
+    }
+
+    #[inline]
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub const fn saturation(&self) -> f64 {
+        self.filter_bits_set() as f64 / P::FILTER_BITS as f64
+    }
+
+    /// Drops the elements and clears the filter, keeping the seed.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.elements.clear();
+        self.filter = 0;
+        self.sorted = false;
+    }
+
+    /// Removes the element at `index` and returns it, moving the last element into its place.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    #[inline]
+    #[track_caller]
+    pub fn swap_remove_index(&mut self, index: usize) -> T {
+        let len = self.len();
+        assert!(
+            index < len,
+            "swap_remove_index index (is {}) should be < len (is {})",
+            index,
+            len
+        );
+        if index + 1 < len {
+            self.sorted = false;
+        }
+        self.elements.swap_remove(index)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn into_vec(self) -> Vec<T> {
+        self.elements
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T>, const K: usize, P: Packing> BloomSet<T, S, K, P> {
+    fn bloom_mask_of(&self, item: &T) -> u128 {
+        let hash = seeded(self.hash_builder.hash_element(item), self.seed);
+        bloom_mask::<K>(hash, P::FILTER_BITS)
+    }
+
+    fn position(&self, item: &T) -> Option<usize> {
+        let mask = self.bloom_mask_of(item);
+        if self.filter & mask == mask {
+            self.elements.iter().position(|it| it == item)
+        } else {
+            None
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the set would need to hold more than its packing's
+    /// [`MAX_CAPACITY`](Packing::MAX_CAPACITY) elements.
+    #[inline]
+    pub fn insert(&mut self, item: T) {
+        if let Err(err) = self.try_insert(item) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Like [`insert`](BloomSet::insert), but returns an error instead of panicking. On
+    /// failure the set is left as it was and `item` is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TryReserveError::Capacity`] if `item` is new and the set is full, and
+    /// [`TryReserveError::AllocFailed`] if the `Vec` can't grow.
+    #[inline]
+    pub fn try_insert(&mut self, item: T) -> Result<(), TryReserveError> {
+        let mask = self.bloom_mask_of(&item);
+        if self.filter & mask == mask && self.elements.contains(&item) {
+            return Ok(());
+        }
+        if self.len() == P::MAX_CAPACITY {
+            return Err(CapacityError::new(P::MAX_CAPACITY + 1, P::MAX_CAPACITY).into());
+        }
+        self.elements
+            .try_reserve(1)
+            .map_err(|_| TryReserveError::AllocFailed { layout: None })?;
+        self.elements.push(item);
+        self.filter |= mask;
+        self.sorted = false;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn contains<B: Borrow<T>>(&self, item: B) -> bool {
+        self.position(item.borrow()).is_some()
+    }
+
+    #[inline]
+    pub fn get<B: Borrow<T>>(&self, item: B) -> Option<&T> {
+        self.position(item.borrow())
+            .map(|index| &self.elements[index])
+    }
+
+    /// Removes `item`, returning whether it was present. The remaining elements keep their
+    /// order, and the filter keeps `item`'s bits.
+    #[inline]
+    pub fn remove<B: Borrow<T>>(&mut self, item: B) -> bool {
+        match self.position(item.borrow()) {
+            Some(index) => {
+                drop(self.elements.remove(index));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Keeps only the elements for which `f` returns `true`, in order, and rebuilds the filter
+    /// from them.
+    #[inline]
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, f: F) {
+        self.elements.retain(f);
+        self.rebuild_filter();
+    }
+
+    /// Recomputes the filter from the elements, clearing bits only removed elements had set.
+    #[inline]
+    pub fn rebuild_filter(&mut self) {
+        let filter = self
+            .elements
+            .iter()
+            .fold(0, |filter, item| filter | self.bloom_mask_of(item));
+        self.filter = filter;
+    }
+
+    /// Builds a set from `iter`, failing if it yields more unique elements than the set holds.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CapacityError`] once `iter` yields one unique element too many.
+    #[inline]
+    pub fn try_from_iter_with_hasher<I: IntoIterator<Item = T>>(
+        iter: I,
+        hash_builder: S,
+    ) -> Result<Self, CapacityError> {
+        let mut set = Self::with_hasher(hash_builder);
+        for item in iter {
+            match set.try_insert(item) {
+                Ok(()) => {}
+                Err(TryReserveError::Capacity(err)) => return Err(err),
+                Err(TryReserveError::AllocFailed { .. }) => {
+                    alloc::alloc::handle_alloc_error(core::alloc::Layout::new::<T>())
+                }
+            }
+        }
+        Ok(set)
+    }
+}
+
+impl<T: Ord, S, const K: usize, P: Packing> BloomSet<T, S, K, P> {
+    /// Sorts the elements and marks the set as sorted until the next insert.
+    #[inline]
+    pub fn sort(&mut self) {
+        self.elements.sort_unstable();
+        self.sorted = true;
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T>, const K: usize, P: Packing> Extend<T>
+    for BloomSet<T, S, K, P>
+{
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(item);
+        }
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T> + Default, const K: usize, P: Packing> FromIterator<T>
+    for BloomSet<T, S, K, P>
+{
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::with_hasher(S::default());
+        set.seed = random_seed::<P>();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<'a, T, S, const K: usize, P: Packing> IntoIterator for &'a BloomSet<T, S, K, P> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> slice::Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T, S, const K: usize, P: Packing> IntoIterator for BloomSet<T, S, K, P> {
+    type Item = T;
+    type IntoIter = alloc::vec::IntoIter<T>;
+
+    #[inline]
+    fn into_iter(self) -> alloc::vec::IntoIter<T> {
+        self.elements.into_iter()
+    }
+}
+
+impl<T: Clone, S: Clone, const K: usize, P: Packing> Clone for BloomSet<T, S, K, P> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            elements: self.elements.clone(),
+            filter: self.filter,
+            seed: self.seed,
+            sorted: self.sorted,
+            hash_builder: self.hash_builder.clone(),
+            packing: core::marker::PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BloomSet as SafeSet;
+    use crate::{BloomSet, BuildBloomHasher, Packed16, Packed8, Packing};
+    use alloc::string::{String, ToString};
+    use core::convert::TryFrom;
+
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    /// Drives a `BloomSet` and a `safe::BloomSet` through the same random operations, checking
+    /// after each that their elements, filter bits and answers agree.
+    fn differential<T, P>(seed: u64, make: fn(u64) -> T)
+    where
+        T: Clone + Ord + core::hash::Hash + core::fmt::Debug,
+        P: Packing,
+    {
+        let mut rng = Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1);
+        let mut real: BloomSet<T, BuildBloomHasher, 1, P> = BloomSet::with_hasher(BuildBloomHasher);
+        let mut safe: SafeSet<T, BuildBloomHasher, 1, P> = SafeSet::with_hasher(BuildBloomHasher);
+        let keys = 2 * P::MAX_CAPACITY as u64;
+        for step in 0..3000 {
+            let item = make(rng.next() % keys);
+            match rng.next() % 16 {
+                0..=8 => assert_eq!(
+                    real.try_insert(item.clone()).is_ok(),
+                    safe.try_insert(item).is_ok()
+                ),
+                9..=11 => assert_eq!(real.remove(&item), safe.remove(&item)),
+                12 if !safe.is_empty() => {
+                    let index = usize::try_from(rng.next()).unwrap() % safe.len();
+                    assert_eq!(real.swap_remove_index(index), safe.swap_remove_index(index));
+                }
+                13 => {
+                    let modulus = rng.next() % 5 + 2;
+                    let mut i = 0_u64;
+                    real.retain(|_| {
+                        i += 1;
+                        !i.is_multiple_of(modulus)
+                    });
+                    let mut i = 0_u64;
+                    safe.retain(|_| {
+                        i += 1;
+                        !i.is_multiple_of(modulus)
+                    });
+                }
+                14 => {
+                    real.sort();
+                    safe.sort();
+                }
+                _ if rng.next().is_multiple_of(8) => {
+                    real.clear();
+                    safe.clear();
+                }
+                _ => {}
+            }
+            assert_eq!(real.as_slice(), safe.as_slice(), "seed {seed}, step {step}");
+            assert_eq!(
+                real.filter_bits(),
+                safe.filter_bits(),
+                "seed {seed}, step {step}"
+            );
+            assert_eq!(real.is_sorted(), safe.is_sorted());
+            let probe = make(rng.next() % keys);
+            assert_eq!(real.contains(&probe), safe.contains(&probe));
+            assert_eq!(real.get(&probe), safe.get(&probe));
+        }
+    }
+
+    #[test]
+    fn agrees_with_bloomset() {
+        for seed in 0..8 {
+            differential::<u64, Packed8>(seed, |key| key);
+            differential::<String, Packed8>(seed, |key| key.to_string());
+            differential::<u32, Packed16>(seed, |key| u32::try_from(key).unwrap());
+        }
+    }
+
+    #[test]
+    fn same_seeds_same_bits() {
+        for seed in [0, 1, 200] {
+            let real: BloomSet<u32> = {
+                let mut set = BloomSet::with_seed(seed);
+                set.extend(0..40);
+                set
+            };
+            let mut safe = SafeSet::with_seed(seed);
+            safe.extend(0..40);
+            assert_eq!(safe.seed(), real.seed());
+            assert_eq!(safe.filter_bits(), real.filter_bits());
+            #[allow(clippy::float_cmp)] // the same popcount over the same width
+            {
+                assert_eq!(safe.saturation(), real.saturation());
+            }
+        }
+        let full = SafeSet::<u32>::try_from_iter_with_hasher(0..256, BuildBloomHasher);
+        assert_eq!(full.err().map(|err| err.requested()), Some(256));
+    }
+}
@@ -0,0 +1,192 @@
+use crate::{handle_reserve_error, BloomSet, ElementHasher, Packing};
+use core::ops::{Deref, DerefMut};
+
+impl<T: Clone + PartialEq, S: ElementHasher<T>, const K: usize, P: Packing> BloomSet<T, S, K, P> {
+    /// Inserts `item` for as long as the returned guard lives, or returns `None` if it is
+    /// already in the set, for marking what is being expanded in a recursive walk and catching
+    /// cycles.
+    ///
+    /// The guard removes the item again when it is dropped, whether the scope is left normally,
+    /// by an early return or by a panic. It keeps a clone of the item to find it by, so take
+    /// cheap keys like `&str` for a deep walk. It derefs mutably to the set, so a recursive call can take `&mut guard` as the set and
+    /// enter the next item, whose guard is then dropped first. Its filter bits stay set, as
+    /// with [`remove`](BloomSet::remove), until the last guard leaves the set empty, which
+    /// clears the filter.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// fn resolve(name: &'static str, expanding: &mut BloomSet<&'static str>) -> Result<(), String> {
+    ///     let mut guard = expanding.enter(name).ok_or(format!("{} imports itself", name))?;
+    ///     let imports: &[&str] = match name {
+    ///         "app" => &["net", "log"],
+    ///         "net" => &["log"],
+    ///         "log" => &["app"],
+    ///         _ => &[],
+    ///     };
+    ///     for &import in imports {
+    ///         resolve(import, &mut guard)?;
+    ///     }
+    ///     Ok(())
+    /// }
+    ///
+    /// let mut expanding = BloomSet::new();
+    /// assert_eq!(resolve("app", &mut expanding), Err("app imports itself".to_string()));
+    /// assert!(expanding.is_empty());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `item` is new and the set already holds its packing's
+    /// [`MAX_CAPACITY`](Packing::MAX_CAPACITY) elements, like [`insert`](BloomSet::insert).
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn enter(&mut self, item: T) -> Option<EnterGuard<'_, T, S, K, P>> {
+        let (index, mask) = self.position_and_mask(&item);
+        if index.is_some() {
+            return None;
+        }
+        if let Err(err) = self.push_new(item.clone(), mask) {
+            handle_reserve_error(err);
+        }
+        Some(EnterGuard { set: self, item })
+    }
+}
+
+/// The guard returned by [`BloomSet::enter`], which removes the entered item when dropped.
+///
+/// Only the entered item is removed; anything else inserted or removed through the guard stays
+/// that way. If the item itself was removed through the guard, dropping it leaves the set alone.
+pub struct EnterGuard<'a, T: PartialEq, S, const K: usize, P: Packing> {
+    set: &'a mut BloomSet<T, S, K, P>,
+    /// A copy of the entered item, to find it by however the elements moved.
+    item: T,
+}
+
+impl<T: PartialEq, S, const K: usize, P: Packing> Deref for EnterGuard<'_, T, S, K, P> {
+    type Target = BloomSet<T, S, K, P>;
+
+    #[inline]
+    fn deref(&self) -> &BloomSet<T, S, K, P> {
+        self.set
+    }
+}
+
+impl<T: PartialEq, S, const K: usize, P: Packing> DerefMut for EnterGuard<'_, T, S, K, P> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut BloomSet<T, S, K, P> {
+        self.set
+    }
+}
+
+impl<T: PartialEq, S, const K: usize, P: Packing> Drop for EnterGuard<'_, T, S, K, P> {
+    #[inline]
+    fn drop(&mut self) {
+        // Searched from the back, since the entered item is usually still the last element
+        let item = &self.item;
+        if let Some(index) = self.set.as_slice().iter().rposition(|it| it == item) {
+            drop(self.set.take(index));
+        }
+        if self.set.is_empty() {
+            // Nothing is left to keep bits for, so forget the ones entered items left behind
+            self.set.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BloomSet;
+
+    #[test]
+    fn nested_guards() {
+        let mut set: BloomSet<u32> = BloomSet::new();
+        let mut outer = set.enter(1).unwrap();
+        {
+            let mut inner = outer.enter(2).unwrap();
+            assert!(inner.enter(1).is_none());
+            assert!(inner.enter(2).is_none());
+            let innermost = inner.enter(3).unwrap();
+            assert_eq!(innermost.as_slice(), [1, 2, 3]);
+        }
+        assert_eq!(outer.as_slice(), [1]);
+        assert!(!outer.contains(2));
+        // Siblings enter one after the other
+        drop(outer.enter(2).unwrap());
+        drop(outer.enter(3).unwrap());
+        drop(outer);
+        assert!(set.is_empty());
+        assert_eq!(set.filter_bits(), 0);
+    }
+
+    #[test]
+    fn early_returns() {
+        fn depth(n: u32, limit: u32, seen: &mut BloomSet<u32>) -> Result<u32, u32> {
+            let mut guard = seen.enter(n % limit).ok_or(n)?;
+            assert_eq!(guard.as_slice().last(), Some(&(n % limit)));
+            Ok(depth(n + 1, limit, &mut guard)? + 1)
+        }
+        let mut seen = BloomSet::new();
+        assert_eq!(depth(0, 7, &mut seen), Err(7));
+        assert!(seen.is_empty());
+
+        // Entering something already there leaves everything as it was
+        seen.insert(3);
+        assert_eq!(depth(3, 10, &mut seen), Err(3));
+        assert_eq!(depth(0, 10, &mut seen), Err(3));
+        assert_eq!(seen.as_slice(), [3]);
+    }
+
+    #[test]
+    fn panics_unwind_the_guards() {
+        fn walk(n: u32, seen: &mut BloomSet<String>) {
+            let mut guard = seen.enter(n.to_string()).unwrap();
+            assert!(n < 5, "too deep");
+            walk(n + 1, &mut guard);
+        }
+        let mut seen = BloomSet::new();
+        seen.insert("root".to_string());
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| walk(0, &mut seen)));
+        assert!(result.is_err());
+        assert_eq!(seen.as_slice(), ["root"]);
+        assert!(seen.enter("0".to_string()).is_some());
+    }
+
+    #[test]
+    fn removes_only_the_entered_item() {
+        let mut set: BloomSet<u32> = (0..3).collect();
+        {
+            let mut guard = set.enter(10).unwrap();
+            guard.insert(11);
+            assert!(guard.contains(11));
+        }
+        assert_eq!(set.as_slice(), [0, 1, 2, 11]);
+        {
+            // Removing what came before moves the entered item forward
+            let mut guard = set.enter(10).unwrap();
+            assert!(guard.remove(0) && guard.remove(1));
+            guard.insert(12);
+        }
+        assert_eq!(set.as_slice(), [2, 11, 12]);
+        {
+            let mut guard = set.enter(10).unwrap();
+            guard.swap_remove_index(0);
+            assert_eq!(guard.as_slice(), [10, 11, 12]);
+        }
+        assert_eq!(set.as_slice(), [11, 12]);
+        {
+            let mut guard = set.enter(10).unwrap();
+            guard.retain(|&i| i > 11);
+        }
+        assert_eq!(set.as_slice(), [12]);
+        {
+            let mut guard = set.enter(10).unwrap();
+            assert!(guard.remove(10));
+            guard.insert(13);
+        }
+        assert_eq!(set.as_slice(), [12, 13]);
+        set.clear();
+        let guard = set.enter(4).unwrap();
+        drop(guard);
+        assert_eq!(set.filter_bits(), 0);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::{BloomSet, ElementHasher, Packing};
+use core::fmt;
+use core::marker::PhantomData;
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+/// Serializes the elements as a sequence, in the order [`as_slice`](BloomSet::as_slice) has
+/// them. The filter and seed aren't part of it; they are rebuilt on the way back in.
+impl<T: Serialize, S, const K: usize, P: Packing> Serialize for BloomSet<T, S, K, P> {
+    #[inline]
+    fn serialize<Z: Serializer>(&self, serializer: Z) -> Result<Z::Ok, Z::Error> {
+        serializer.collect_seq(self.as_slice())
+    }
+}
+
+/// Inserts the elements of a sequence one at a time, so duplicates are dropped and the filter is
+/// rebuilt by the same code as any other insert. A sequence with more unique elements than the
+/// set can hold is an error, not a panic.
+///
+/// Elements that borrow from the input, like `&'de str`, are deserialized in place, so a
+/// `BloomSet<&str>` read from a buffer points into it and allocates nothing but its own storage.
+impl<'de, T, S, const K: usize, P> Deserialize<'de> for BloomSet<T, S, K, P>
+where
+    T: Deserialize<'de> + PartialEq,
+    S: ElementHasher<T> + Default,
+    P: Packing,
+{
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(SetVisitor(PhantomData))
+    }
+}
+
+struct SetVisitor<T, S, const K: usize, P: Packing>(PhantomData<BloomSet<T, S, K, P>>);
+
+impl<'de, T, S, const K: usize, P> Visitor<'de> for SetVisitor<T, S, K, P>
+where
+    T: Deserialize<'de> + PartialEq,
+    S: ElementHasher<T> + Default,
+    P: Packing,
+{
+    type Value = BloomSet<T, S, K, P>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "a sequence of at most {} unique elements",
+            P::MAX_CAPACITY
+        )
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        // The hint comes from the input, so it only picks an initial capacity within the limit
+        let capacity = seq.size_hint().unwrap_or(0).min(P::MAX_CAPACITY);
+        let mut set = BloomSet::with_capacity_and_hasher(capacity, S::default());
+        while let Some(item) = seq.next_element()? {
+            set.try_insert(item).map_err(de::Error::custom)?;
+        }
+        Ok(set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BloomSet, BloomSet16};
+
+    #[test]
+    fn json_round_trip() {
+        let tags: BloomSet<String> = ["beta", "alpha", "gamma"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let json = serde_json::to_string(&tags).unwrap();
+        assert_eq!(json, r#"["beta","alpha","gamma"]"#);
+
+        let back: BloomSet<String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.as_slice(), tags.as_slice());
+        // Under `random-seed` the two sets have seeds of their own
+        let mut expected = BloomSet::with_seed(back.seed());
+        expected.extend(tags.iter().cloned());
+        assert_eq!(back.filter_bits(), expected.filter_bits());
+        assert!(back.contains("gamma".to_string()));
+        assert!(!back.contains("delta".to_string()));
+
+        let empty: BloomSet<String> = serde_json::from_str("[]").unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let set: BloomSet<u64> = (0..255).map(|i| i * 7919).collect();
+        let bytes = serde_cbor::to_vec(&set).unwrap();
+        let back: BloomSet<u64> = serde_cbor::from_slice(&bytes).unwrap();
+        assert_eq!(back.as_slice(), set.as_slice());
+        let mut expected = BloomSet::with_seed(back.seed());
+        expected.extend(set.iter().copied());
+        assert_eq!(back.filter_bits(), expected.filter_bits());
+
+        let wide: BloomSet16<u32> = (0..1000).collect();
+        let bytes = serde_cbor::to_vec(&wide).unwrap();
+        let back: BloomSet16<u32> = serde_cbor::from_slice(&bytes).unwrap();
+        assert_eq!(back.as_slice(), wide.as_slice());
+    }
+
+    /// Whether `element` lies within `source`.
+    fn points_into(source: &[u8], element: &str) -> bool {
+        source.as_ptr_range().contains(&element.as_ptr())
+            && element.as_bytes().as_ptr_range().end <= source.as_ptr_range().end
+    }
+
+    #[test]
+    fn borrowed_from_the_input() {
+        let json = String::from(r#"["read", "write", "admin", "read"]"#);
+        let set: BloomSet<&str> = serde_json::from_str(&json).unwrap();
+        assert_eq!(set.as_slice(), ["read", "write", "admin"]);
+        assert!(set
+            .as_slice()
+            .iter()
+            .all(|tag| points_into(json.as_bytes(), tag)));
+        assert!(set.contains("admin"));
+
+        let bytes = serde_cbor::to_vec(&set).unwrap();
+        let back: BloomSet<&str> = serde_cbor::from_slice(&bytes).unwrap();
+        assert_eq!(back.as_slice(), set.as_slice());
+        assert!(back.as_slice().iter().all(|tag| points_into(&bytes, tag)));
+
+        // A string with an escape can't be borrowed as it is, so it is an error, not a copy
+        assert!(serde_json::from_str::<BloomSet<&str>>(r#"["tab\t"]"#).is_err());
+    }
+
+    #[test]
+    fn duplicates_are_dropped() {
+        let set: BloomSet<u32> = serde_json::from_str("[3, 1, 3, 2, 1]").unwrap();
+        assert_eq!(set.as_slice(), [3, 1, 2]);
+        // Duplicates don't count towards the limit
+        let json = serde_json::to_string(&(0..1000).map(|i| i % 255).collect::<Vec<u32>>());
+        let set: BloomSet<u32> = serde_json::from_str(&json.unwrap()).unwrap();
+        assert_eq!(set.len(), 255);
+    }
+
+    #[test]
+    fn too_many_elements_is_an_error() {
+        let json = serde_json::to_string(&(0..256).collect::<Vec<u32>>()).unwrap();
+        let err = serde_json::from_str::<BloomSet<u32>>(&json).err().unwrap();
+        assert!(err.to_string().contains("more than 255"));
+
+        let bytes = serde_cbor::to_vec(&(0..300).collect::<Vec<u32>>()).unwrap();
+        assert!(serde_cbor::from_slice::<BloomSet<u32>>(&bytes).is_err());
+        let slack: BloomSet16<u32> = serde_cbor::from_slice(&bytes).unwrap();
+        assert_eq!(slack.len(), 300);
+
+        // An element that doesn't parse is the format's error, as for any sequence
+        assert!(serde_json::from_str::<BloomSet<u32>>("[1, \"two\"]").is_err());
+    }
+}
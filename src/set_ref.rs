@@ -0,0 +1,155 @@
+use crate::{bloom_mask, BuildBloomHasher, ElementHasher, FILTER_BITS, SATURATION_THRESHOLD};
+use core::borrow::Borrow;
+use core::slice;
+
+/// A read-only view over a slice owned elsewhere, with a bloom filter computed once when the view
+/// is created.
+///
+/// Lookups work like [`BloomSet::contains`](crate::BloomSet::contains): check the filter, then
+/// scan. The slice may contain duplicates; they just set the same filter bits. The view is `Copy`,
+/// so it can be passed around by value as cheaply as the slice itself.
+///
+/// ```
+/// use bloomset::BloomSetRef;
+///
+/// let words = ["alpha", "beta", "gamma"];
+/// let view = BloomSetRef::new(&words);
+/// assert!(view.contains("beta"));
+/// assert!(!view.contains("delta"));
+/// ```
+#[derive(Debug)]
+pub struct BloomSetRef<'a, T, S = BuildBloomHasher> {
+    elements: &'a [T],
+    filter: u128,
+    hash_builder: S,
+}
+
+impl<T, S: Clone> Clone for BloomSetRef<'_, T, S> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            elements: self.elements,
+            filter: self.filter,
+            hash_builder: self.hash_builder.clone(),
+        }
+    }
+}
+
+impl<T, S: Copy> Copy for BloomSetRef<'_, T, S> {}
+
+impl<'a, T> BloomSetRef<'a, T>
+where
+    BuildBloomHasher: ElementHasher<T>,
+{
+    #[inline]
+    #[must_use]
+    pub fn new(elements: &'a [T]) -> Self {
+        Self::with_hasher(elements, BuildBloomHasher)
+    }
+}
+
+impl<'a, T, S: ElementHasher<T>> BloomSetRef<'a, T, S> {
+    /// Creates a view over `elements` which will use `hash_builder` to hash them and any lookups.
+    #[inline]
+    #[must_use]
+    pub fn with_hasher(elements: &'a [T], hash_builder: S) -> Self {
+        let filter = elements.iter().fold(0, |filter, item| {
+            filter | bloom_mask::<1>(hash_builder.hash_element(item), FILTER_BITS)
+        });
+        Self {
+            elements,
+            filter,
+            hash_builder,
+        }
+    }
+}
+
+impl<'a, T, S> BloomSetRef<'a, T, S> {
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn as_slice(&self) -> &'a [T] {
+        self.elements
+    }
+
+    #[inline]
+    pub fn iter(&self) -> slice::Iter<'a, T> {
+        self.elements.iter()
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T>> BloomSetRef<'_, T, S> {
+    #[inline]
+    pub fn contains<B: Borrow<T>>(&self, item: B) -> bool {
+        let item = item.borrow();
+        let maybe_in_set = self.filter.count_ones() >= SATURATION_THRESHOLD || {
+            let mask = bloom_mask::<1>(self.hash_builder.hash_element(item), FILTER_BITS);
+            self.filter & mask == mask
+        };
+        maybe_in_set && self.elements.iter().any(|it| it == item)
+    }
+}
+
+impl<'a, T, S> IntoIterator for BloomSetRef<'a, T, S> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.elements.iter()
+    }
+}
+
+impl<'a, T, S> IntoIterator for &BloomSetRef<'a, T, S> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.elements.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BloomSet;
+
+    #[test]
+    #[cfg_attr(feature = "random-seed", ignore = "compares filter bits across sets")]
+    fn agrees_with_bloomset() {
+        let arena: Vec<u32> = (0..200).map(|i| i * 37 % 1000).collect();
+        let view = BloomSetRef::new(&arena);
+        let owned: BloomSet<u32> = arena.iter().copied().collect();
+        assert_eq!(view.filter, owned.filter_bits());
+        assert!((0..1000).all(|i| view.contains(i) == owned.contains(i)));
+
+        for (i, elements) in [&arena[..0], &arena[..1], &arena[..30]].iter().enumerate() {
+            let view = BloomSetRef::new(elements);
+            let owned: BloomSet<u32> = elements.iter().copied().collect();
+            assert_eq!(view.filter, owned.filter_bits(), "{i}");
+            assert!((0..1000).all(|i| view.contains(i) == owned.contains(i)));
+        }
+    }
+
+    #[test]
+    fn copy_and_iterate() {
+        let arena = ["a", "b", "a"];
+        let view = BloomSetRef::new(&arena);
+        let copy = view;
+        assert_eq!(view.len(), 3);
+        assert_eq!(copy.into_iter().copied().collect::<Vec<_>>(), arena);
+        assert!(view.contains("a") && !view.contains("c"));
+    }
+}
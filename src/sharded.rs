@@ -0,0 +1,213 @@
+use crate::{BloomSet, BuildBloomHasher, ElementHasher};
+use alloc::vec::{self, Vec};
+use core::borrow::Borrow;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// A set that many threads can insert into at once, made of several [`BloomSet`]s behind their
+/// own locks.
+///
+/// Each element belongs to the shard its hash picks, so threads only contend when they touch the
+/// same shard. Every shard holds up to 255 elements, so the whole set holds up to 255 times the
+/// number of shards, provided the hasher spreads elements evenly. By default there is one shard
+/// per available thread, rounded up to a power of two.
+///
+/// ```
+/// use bloomset::ShardedBloomSet;
+///
+/// let set = ShardedBloomSet::with_shards(4);
+/// std::thread::scope(|scope| {
+///     for thread in 0..4 {
+///         let set = &set;
+///         scope.spawn(move || set.insert(thread));
+///     }
+/// });
+/// assert_eq!(set.len(), 4);
+/// assert!(set.contains(3));
+/// ```
+pub struct ShardedBloomSet<T, S = BuildBloomHasher> {
+    shards: Vec<Mutex<BloomSet<T, S>>>,
+    hash_builder: S,
+}
+
+impl<T> Default for ShardedBloomSet<T> {
+    #[inline]
+    fn default() -> Self {
+        let threads = std::thread::available_parallelism().map_or(1, core::num::NonZeroUsize::get);
+        Self::with_shards(threads.next_power_of_two())
+    }
+}
+
+impl<T> ShardedBloomSet<T> {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `shards` is 0.
+    #[inline]
+    #[must_use]
+    pub fn with_shards(shards: usize) -> Self {
+        Self::with_shards_and_hasher(shards, BuildBloomHasher)
+    }
+}
+
+/// Locks a shard. Its lock is only poisoned if hashing or comparing an element panicked, which
+/// leaves the shard itself intact.
+fn lock<T>(shard: &Mutex<T>) -> MutexGuard<'_, T> {
+    shard.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl<T, S: Clone> ShardedBloomSet<T, S> {
+    /// Creates an empty set with `shards` shards, which will all use a clone of `hash_builder`
+    /// to hash their elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is 0.
+    #[inline]
+    #[must_use]
+    pub fn with_shards_and_hasher(shards: usize, hash_builder: S) -> Self {
+        assert!(shards > 0, "a ShardedBloomSet needs at least one shard");
+        Self {
+            shards: (0..shards)
+                .map(|_| Mutex::new(BloomSet::with_hasher(hash_builder.clone())))
+                .collect(),
+            hash_builder,
+        }
+    }
+}
+
+impl<T, S> ShardedBloomSet<T, S> {
+    #[inline]
+    #[must_use]
+    pub const fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// The number of elements across all shards. Other threads may change it at any time, so
+    /// it is only exact while nothing is inserting.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| lock(shard).len()).sum()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|shard| lock(shard).is_empty())
+    }
+
+    /// Copies out the elements, a shard at a time, and iterates over the copies. Elements
+    /// inserted while the snapshot is taken may or may not be included.
+    #[inline]
+    pub fn iter(&self) -> vec::IntoIter<T>
+    where
+        T: Clone,
+    {
+        let mut elements = Vec::new();
+        for shard in &self.shards {
+            elements.extend_from_slice(lock(shard).as_slice());
+        }
+        elements.into_iter()
+    }
+
+    #[inline]
+    pub fn clear(&self) {
+        for shard in &self.shards {
+            lock(shard).clear();
+        }
+    }
+}
+
+impl<T: Clone, S> IntoIterator for &ShardedBloomSet<T, S> {
+    type Item = T;
+    type IntoIter = vec::IntoIter<T>;
+
+    /// Takes a snapshot; see [`ShardedBloomSet::iter`].
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T>> ShardedBloomSet<T, S> {
+    fn shard(&self, item: &T) -> &Mutex<BloomSet<T, S>> {
+        // Take the shard from the top bits of a remixed hash, which the filter bit within the
+        // shard doesn't depend on as directly
+        let hash = self
+            .hash_builder
+            .hash_element(item)
+            .wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        #[allow(clippy::cast_possible_truncation)]
+        let index = ((u128::from(hash) * self.shards.len() as u128) >> 64) as usize;
+        &self.shards[index]
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `item`'s shard already holds 255 elements and doesn't contain it.
+    #[inline]
+    pub fn insert(&self, item: T) {
+        lock(self.shard(&item)).insert(item);
+    }
+
+    /// Removes `item` from the set, returning whether it was present.
+    #[inline]
+    pub fn remove<B: Borrow<T>>(&self, item: B) -> bool {
+        let item = item.borrow();
+        lock(self.shard(item)).remove(item)
+    }
+
+    #[inline]
+    pub fn contains<B: Borrow<T>>(&self, item: B) -> bool {
+        let item = item.borrow();
+        lock(self.shard(item)).contains(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn matches_single_threaded_run() {
+        const THREADS: u32 = 8;
+        // Each thread's items overlap with the next thread's
+        let items = |thread: u32| (thread * 100..thread * 100 + 150).map(|i| i * 7);
+
+        let set = ShardedBloomSet::with_shards(8);
+        std::thread::scope(|scope| {
+            for thread in 0..THREADS {
+                let set = &set;
+                scope.spawn(move || {
+                    for item in items(thread) {
+                        set.insert(item);
+                        assert!(set.contains(item));
+                        assert!(!set.contains(item + 1));
+                    }
+                });
+            }
+        });
+
+        let reference: BTreeSet<u32> = (0..THREADS).flat_map(items).collect();
+        assert_eq!(set.len(), reference.len());
+        assert_eq!(set.iter().collect::<BTreeSet<_>>(), reference);
+        assert!(reference.iter().all(|&item| set.contains(item)));
+
+        assert!(set.remove(0) && !set.contains(0));
+        set.clear();
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn default_shards() {
+        let set = ShardedBloomSet::<u32>::new();
+        assert!(set.shard_count().is_power_of_two());
+        assert!(set.is_empty());
+    }
+}
@@ -0,0 +1,99 @@
+//! Explicit `core::simd` scans for the unsigned integers, behind the `nightly-simd` feature.
+
+use crate::scalar_scan;
+use core::simd::cmp::SimdPartialEq;
+use core::simd::Simd;
+
+/// Whether `elements` contains `item`: with vector compares for the unsigned integers, and
+/// [`scalar_scan`] for everything else.
+#[inline]
+pub fn scan<T: PartialEq>(elements: &[T], item: &T) -> bool {
+    T::simd_scan(elements, item)
+}
+
+trait SimdScan: PartialEq + Sized {
+    fn simd_scan(elements: &[Self], item: &Self) -> bool;
+}
+
+impl<T: PartialEq> SimdScan for T {
+    #[inline]
+    default fn simd_scan(elements: &[Self], item: &Self) -> bool {
+        scalar_scan(elements, item)
+    }
+}
+
+macro_rules! simd_scan {
+    ($($int:ty => $lanes:literal),*) => {
+        $(
+            impl SimdScan for $int {
+                /// Compares a whole vector of elements against a splat of `item` per iteration,
+                /// and only then checks whether any lane matched.
+                #[inline]
+                fn simd_scan(elements: &[Self], item: &Self) -> bool {
+                    let needle = Simd::<$int, $lanes>::splat(*item);
+                    let (chunks, rest) = elements.as_chunks::<$lanes>();
+                    chunks
+                        .iter()
+                        .any(|chunk| Simd::from_array(*chunk).simd_eq(needle).any())
+                        || rest.contains(item)
+                }
+            }
+        )*
+    };
+}
+
+// 32 bytes a compare for the narrow types, 64 for the wide ones
+simd_scan!(u8 => 32, u16 => 32, u32 => 16, u64 => 8);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xorshift(mut x: u64) -> impl Iterator<Item = u64> {
+        core::iter::repeat_with(move || {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x
+        })
+    }
+
+    /// Checks `scan` against a plain scan for every length up to a few vectors, with the needle
+    /// at the start, at the end, somewhere random, and nowhere.
+    fn agrees_with_scalar<T: PartialEq + Copy>(random: impl Fn(u64) -> T) {
+        let mut bits = xorshift(0x9E37_79B9_7F4A_7C15);
+        for len in 0..=100 {
+            let mut elements: Vec<T> = bits.by_ref().take(len).map(&random).collect();
+            let absent = bits
+                .by_ref()
+                .map(&random)
+                .find(|value| !elements.contains(value))
+                .unwrap();
+            assert!(!scan(&elements, &absent));
+            for &item in &elements {
+                assert!(scan(&elements, &item));
+            }
+            if len > 0 {
+                let needle = random(bits.next().unwrap());
+                elements[0] = needle;
+                assert_eq!(scan(&elements, &needle), elements.contains(&needle));
+                elements[0] = absent;
+                elements[len - 1] = needle;
+                assert_eq!(scan(&elements, &needle), elements.contains(&needle));
+                elements[len - 1] = absent;
+                assert_eq!(scan(&elements, &needle), elements.contains(&needle));
+            }
+        }
+    }
+
+    #[test]
+    #[allow(clippy::cast_possible_truncation)]
+    fn unsigned_integers() {
+        agrees_with_scalar(|bits| bits as u8);
+        agrees_with_scalar(|bits| bits as u16);
+        agrees_with_scalar(|bits| bits as u32);
+        agrees_with_scalar(|bits| bits);
+        // Other types take the scalar path
+        agrees_with_scalar(|bits| bits as i32);
+    }
+}
@@ -0,0 +1,444 @@
+use crate::{
+    bloom_mask, grown_capacity, BuildBloomHasher, CapacityError, ElementHasher, MAX_CAPACITY,
+};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::iter::FromIterator;
+use core::mem::{self, ManuallyDrop, MaybeUninit};
+use core::ptr::{self, NonNull};
+use core::slice;
+
+/// The number of filter bits, which all live in the length word between the length byte and the
+/// top byte: 48 on 64-bit targets and 16 on 32-bit ones.
+const FILTER_BITS: u64 = usize::BITS as u64 - 16;
+
+/// Set in the length word once the elements have moved to the heap.
+const SPILLED: usize = 1 << (usize::BITS - 1);
+
+/// Where the elements live: in the two words themselves, or in a heap allocation.
+union Data<T> {
+    inline: MaybeUninit<[usize; 2]>,
+    heap: Heap<T>,
+}
+
+struct Heap<T> {
+    ptr: NonNull<T>,
+    capacity: usize,
+}
+
+impl<T> Clone for Heap<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Heap<T> {}
+
+/// A set that stores its first few elements inline, in the space a `BloomSet` uses for its
+/// pointer and capacity, and only allocates once it outgrows them.
+///
+/// Two words fit 16 bytes of elements aligned to at most 8, e.g. two `u64`s or four `u32`s (half
+/// that on 32-bit targets); element types with a larger alignment than `usize` always live on the
+/// heap. The filter has 48 bits (16 on 32-bit targets), which
+/// share the length word with the length and a flag recording whether the set has spilled to
+/// the heap, so the set is still three words. Like a `BloomSet` it holds at most 255 elements.
+///
+/// ```
+/// use bloomset::SmallBloomSet;
+///
+/// let mut set = SmallBloomSet::new();
+/// set.insert(1_u32);
+/// set.insert(2);
+/// assert!(!set.spilled());
+/// for i in 3..10 {
+///     set.insert(i);
+/// }
+/// assert!(set.spilled());
+/// assert!(set.contains(9));
+/// ```
+pub struct SmallBloomSet<T, S = BuildBloomHasher> {
+    data: Data<T>,
+    /// The length in the low byte, the filter above it, and the `SPILLED` flag in the top bit.
+    length: usize,
+    hash_builder: S,
+}
+
+impl<T> Default for SmallBloomSet<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_hasher(BuildBloomHasher)
+    }
+}
+
+impl<T> SmallBloomSet<T> {
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self::with_hasher(BuildBloomHasher)
+    }
+}
+
+impl<T, S> SmallBloomSet<T, S> {
+    /// The number of elements that fit without allocating.
+    pub const INLINE_CAPACITY: usize = if mem::size_of::<T>() == 0 {
+        MAX_CAPACITY
+    } else if mem::align_of::<T>() > mem::align_of::<usize>() {
+        0
+    } else {
+        mem::size_of::<[usize; 2]>() / mem::size_of::<T>()
+    };
+
+    /// Creates an empty set which will use `hash_builder` to hash its elements.
+    #[inline]
+    #[must_use]
+    pub const fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            data: Data {
+                inline: MaybeUninit::uninit(),
+            },
+            length: 0,
+            hash_builder,
+        }
+    }
+
+    /// Returns a reference to the set's `BuildHasher`.
+    #[inline]
+    #[must_use]
+    pub const fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    /// Returns whether the elements have moved to a heap allocation.
+    #[inline]
+    #[must_use]
+    pub const fn spilled(&self) -> bool {
+        self.length & SPILLED != 0
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.length & 0xFF
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        if self.spilled() {
+            unsafe { self.data.heap.capacity }
+        } else {
+            Self::INLINE_CAPACITY
+        }
+    }
+
+    /// Whether the elements can't use the inline slots, which are only aligned to a `usize`:
+    /// zero-sized types don't need them, and overaligned ones go straight to the heap. Until
+    /// the set spills, their pointer is a dangling one aligned for `T`.
+    const NOT_INLINE: bool =
+        mem::size_of::<T>() == 0 || mem::align_of::<T>() > mem::align_of::<usize>();
+
+    const fn as_ptr(&self) -> *const T {
+        if self.spilled() {
+            unsafe { self.data.heap.ptr.as_ptr() }
+        } else if Self::NOT_INLINE {
+            NonNull::dangling().as_ptr()
+        } else {
+            ptr::addr_of!(self.data.inline).cast()
+        }
+    }
+
+    const fn as_mut_ptr(&mut self) -> *mut T {
+        if self.spilled() {
+            unsafe { self.data.heap.ptr.as_ptr() }
+        } else if Self::NOT_INLINE {
+            NonNull::dangling().as_ptr()
+        } else {
+            ptr::addr_of_mut!(self.data.inline).cast()
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.as_ptr(), self.len()) }
+    }
+
+    #[inline]
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    /// Makes room for at least one more element, moving the elements to the heap if they are
+    /// still inline.
+    #[inline(never)]
+    fn grow(&mut self) {
+        let len = self.len();
+        let new_capacity = grown_capacity(self.capacity(), MAX_CAPACITY);
+        let mut vec = if self.spilled() {
+            unsafe {
+                let heap = self.data.heap;
+                Vec::from_raw_parts(heap.ptr.as_ptr(), len, heap.capacity)
+            }
+        } else {
+            let mut vec = Vec::with_capacity(new_capacity);
+            unsafe {
+                ptr::copy_nonoverlapping(self.as_ptr(), vec.as_mut_ptr(), len);
+                vec.set_len(len);
+            }
+            vec
+        };
+        vec.reserve_exact(new_capacity - len);
+        // Use ManuallyDrop to ensure that the Vec is never dropped
+        let mut vec = ManuallyDrop::new(vec);
+        self.data = Data {
+            heap: Heap {
+                ptr: unsafe { NonNull::new_unchecked(vec.as_mut_ptr()) },
+                capacity: vec.capacity(),
+            },
+        };
+        self.length |= SPILLED;
+    }
+
+    /// Removes every element. A set that had spilled keeps its allocation.
+    #[inline]
+    pub fn clear(&mut self) {
+        let elements = ptr::slice_from_raw_parts_mut(self.as_mut_ptr(), self.len());
+        // Reset the length first, so a panicking Drop impl can't lead to a double drop
+        self.length &= SPILLED;
+        unsafe { ptr::drop_in_place(elements) };
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T>> SmallBloomSet<T, S> {
+    #[allow(clippy::cast_possible_truncation)]
+    fn bloom_mask_of(&self, item: &T) -> usize {
+        let mask = bloom_mask::<1>(self.hash_builder.hash_element(item), FILTER_BITS);
+        (mask as usize) << 8
+    }
+
+    /// # Errors
+    ///
+    /// Returns a [`CapacityError`] if `item` would be the set's 256th element.
+    #[inline]
+    pub fn try_insert(&mut self, item: T) -> Result<(), CapacityError> {
+        let mask = self.bloom_mask_of(&item);
+        if self.length & mask == mask && self.as_slice().contains(&item) {
+            return Ok(());
+        }
+        if self.len() == MAX_CAPACITY {
+            return Err(CapacityError::new(MAX_CAPACITY + 1, MAX_CAPACITY));
+        }
+        if self.len() == self.capacity() {
+            self.grow();
+        }
+        unsafe { self.as_mut_ptr().add(self.len()).write(item) };
+        self.length = (self.length | mask) + 1;
+        Ok(())
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the set would need to grow beyond 255 elements.
+    #[inline]
+    pub fn insert(&mut self, item: T) {
+        if let Err(err) = self.try_insert(item) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Removes `item` from the set, returning whether it was present. The remaining elements
+    /// keep their order, and the filter keeps `item`'s bit.
+    #[inline]
+    pub fn remove<B: Borrow<T>>(&mut self, item: B) -> bool {
+        let item = item.borrow();
+        let mask = self.bloom_mask_of(item);
+        if self.length & mask != mask {
+            return false;
+        }
+        let Some(index) = self.iter().position(|it| it == item) else {
+            return false;
+        };
+        let len = self.len();
+        let removed = unsafe {
+            let base = self.as_mut_ptr();
+            let removed = base.add(index).read();
+            ptr::copy(base.add(index + 1), base.add(index), len - index - 1);
+            removed
+        };
+        self.length -= 1;
+        drop(removed);
+        true
+    }
+
+    #[inline]
+    pub fn contains<B: Borrow<T>>(&self, item: B) -> bool {
+        let item = item.borrow();
+        let mask = self.bloom_mask_of(item);
+        self.length & mask == mask && self.iter().any(|it| it == item)
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T> + Default> FromIterator<T> for SmallBloomSet<T, S> {
+    /// # Panics
+    ///
+    /// Panics if the iterator yields more than 255 unique elements.
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::with_hasher(S::default());
+        for item in iter {
+            set.insert(item);
+        }
+        set
+    }
+}
+
+impl<'a, T, S> IntoIterator for &'a SmallBloomSet<T, S> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T, S> Drop for SmallBloomSet<T, S> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe {
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.as_mut_ptr(), self.len()));
+            if self.spilled() {
+                let heap = self.data.heap;
+                drop(Vec::from_raw_parts(heap.ptr.as_ptr(), 0, heap.capacity));
+            }
+        }
+    }
+}
+
+// The set owns its elements, inline or on the heap, so it can cross threads under the same
+// conditions as a Vec
+unsafe impl<T: Send, S: Send> Send for SmallBloomSet<T, S> {}
+unsafe impl<T: Sync, S: Sync> Sync for SmallBloomSet<T, S> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BloomSet;
+    use std::rc::Rc;
+
+    #[test]
+    fn three_words() {
+        assert_eq!(
+            mem::size_of::<SmallBloomSet<u8>>(),
+            mem::size_of::<Vec<u8>>()
+        );
+        assert_eq!(
+            mem::size_of::<SmallBloomSet<String>>(),
+            mem::size_of::<Vec<u8>>()
+        );
+        assert_eq!(SmallBloomSet::<usize>::INLINE_CAPACITY, 2);
+        assert_eq!(SmallBloomSet::<u128>::INLINE_CAPACITY, 0);
+        assert_eq!(SmallBloomSet::<()>::INLINE_CAPACITY, 255);
+        if cfg!(target_pointer_width = "64") {
+            assert_eq!(SmallBloomSet::<u64>::INLINE_CAPACITY, 2);
+            assert_eq!(SmallBloomSet::<u32>::INLINE_CAPACITY, 4);
+            assert_eq!(SmallBloomSet::<[u8; 5]>::INLINE_CAPACITY, 3);
+        }
+    }
+
+    #[test]
+    fn spills_when_inline_space_runs_out() {
+        let inline = SmallBloomSet::<usize>::INLINE_CAPACITY;
+        let mut set = SmallBloomSet::new();
+        for i in 0..inline {
+            set.insert(i);
+            set.insert(i);
+            assert!(!set.spilled());
+        }
+        assert!(set.as_slice().iter().copied().eq(0..inline));
+        set.insert(inline);
+        assert!(set.spilled());
+        assert_eq!(set.capacity(), (inline * 2).max(4));
+        assert!(set.iter().copied().eq(0..=inline));
+        assert!((0..=inline).all(|i| set.contains(i)) && !set.contains(inline + 1));
+    }
+
+    #[test]
+    fn agrees_with_bloomset() {
+        let mut small = SmallBloomSet::new();
+        let mut plain = BloomSet::new();
+        for i in 0..255_u64 {
+            let elem = i * 7 % 300;
+            small.insert(elem);
+            plain.insert(elem);
+            assert_eq!(small.as_slice(), plain.as_slice());
+        }
+        assert!(small.try_insert(1000).is_err());
+        assert!((0..400).all(|i| small.contains(i) == plain.contains(i)));
+        assert!(small.remove(7) && plain.remove(7));
+        assert_eq!(small.as_slice(), plain.as_slice());
+    }
+
+    #[test]
+    fn small_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SmallBloomSet<u32>>();
+        assert_send_sync::<SmallBloomSet<String>>();
+    }
+
+    #[test]
+    fn drops_inline_and_spilled_elements() {
+        let counted = Rc::new(());
+        let mut set = SmallBloomSet::new();
+        set.insert((0_u8, Rc::clone(&counted)));
+        assert!(!set.spilled());
+        set.clear();
+        assert_eq!(Rc::strong_count(&counted), 1);
+        assert!(set.is_empty() && !set.contains((0, Rc::clone(&counted))));
+
+        set.insert((1, Rc::clone(&counted)));
+        drop(set);
+        assert_eq!(Rc::strong_count(&counted), 1);
+
+        let mut set = SmallBloomSet::new();
+        for i in 0..20_u8 {
+            set.insert((i, Rc::clone(&counted)));
+        }
+        assert!(set.spilled());
+        assert!(set.remove((3, Rc::clone(&counted))));
+        assert_eq!(Rc::strong_count(&counted), 20);
+        set.clear();
+        assert!(set.spilled() && set.capacity() >= 20);
+        assert_eq!(Rc::strong_count(&counted), 1);
+        set.insert((0, Rc::clone(&counted)));
+        drop(set);
+        assert_eq!(Rc::strong_count(&counted), 1);
+    }
+
+    #[test]
+    fn overaligned_zero_sized_elements() {
+        #[derive(Hash, PartialEq)]
+        #[repr(align(64))]
+        struct Marker;
+        let mut set = SmallBloomSet::new();
+        set.insert(Marker);
+        set.insert(Marker);
+        assert_eq!(set.len(), 1);
+        assert!(!set.spilled() && set.contains(Marker));
+        assert_eq!(set.as_slice().as_ptr() as usize % 64, 0);
+    }
+
+    #[test]
+    fn overaligned_elements_start_on_the_heap() {
+        let mut set = SmallBloomSet::new();
+        set.insert(1_u128);
+        assert!(set.spilled());
+        assert!(set.contains(1));
+    }
+}
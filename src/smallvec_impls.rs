@@ -0,0 +1,48 @@
+use crate::{BloomSet, ElementHasher, Packing};
+use smallvec::{Array, SmallVec};
+
+impl<T, S, const K: usize, P: Packing> BloomSet<T, S, K, P> {
+    /// Moves the elements out into a `SmallVec`, in the set's order. If they fit inline they are
+    /// moved there and the set's buffer is freed; otherwise the `SmallVec` takes the buffer over,
+    /// so neither way allocates.
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    /// use smallvec::SmallVec;
+    ///
+    /// let mut set: BloomSet<u32> = BloomSet::with_capacity(16);
+    /// set.extend([3, 1, 3]);
+    /// let elements: SmallVec<[u32; 4]> = set.into_smallvec();
+    /// assert_eq!(elements.as_slice(), [3, 1]);
+    /// assert!(!elements.spilled());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn into_smallvec<A: Array<Item = T>>(self) -> SmallVec<A> {
+        let vec = self.into_vec();
+        if vec.len() <= A::size() {
+            vec.into_iter().collect()
+        } else {
+            SmallVec::from_vec(vec)
+        }
+    }
+}
+
+/// Inserts the elements in order, dropping duplicates like collecting does, straight into a
+/// buffer sized for all of them.
+///
+/// # Panics
+///
+/// Panics if there are more unique elements than the set can hold (255 by default).
+impl<A, S, const K: usize, P> From<SmallVec<A>> for BloomSet<A::Item, S, K, P>
+where
+    A: Array,
+    A::Item: PartialEq,
+    S: ElementHasher<A::Item> + Default,
+    P: Packing,
+{
+    #[inline]
+    fn from(elements: SmallVec<A>) -> Self {
+        elements.into_iter().collect()
+    }
+}
@@ -0,0 +1,107 @@
+//! Serializes a [`BloomSet`] with its elements in sorted order.
+//!
+//! The output then only depends on which elements the set holds and not the order they were
+//! inserted in. Use it through serde's `with` attribute:
+//!
+//! ```
+//! use bloomset::BloomSet;
+//! use serde_derive::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     #[serde(with = "bloomset::sorted")]
+//!     tags: BloomSet<String>,
+//! }
+//!
+//! let config = Config {
+//!     tags: ["web", "api", "db"].iter().map(|tag| tag.to_string()).collect(),
+//! };
+//! let json = serde_json::to_string(&config).unwrap();
+//! assert_eq!(json, r#"{"tags":["api","db","web"]}"#);
+//! let back: Config = serde_json::from_str(&json).unwrap();
+//! assert_eq!(back.tags.len(), 3);
+//! ```
+//!
+//! Deserializing is the same as without the attribute, so the set holds its elements in the
+//! sorted order they were written in.
+
+use crate::{BloomSet, ElementHasher, Packing};
+use alloc::vec::Vec;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Serializes the elements of `set` as a sequence, in ascending order.
+///
+/// # Errors
+///
+/// Returns whatever error `serializer` does.
+#[inline]
+pub fn serialize<T, S, const K: usize, P, Z>(
+    set: &BloomSet<T, S, K, P>,
+    serializer: Z,
+) -> Result<Z::Ok, Z::Error>
+where
+    T: Ord + Serialize,
+    P: Packing,
+    Z: Serializer,
+{
+    let mut elements: Vec<&T> = set.as_slice().iter().collect();
+    elements.sort_unstable();
+    serializer.collect_seq(elements)
+}
+
+/// Deserializes a set exactly as its [`Deserialize`] impl does.
+///
+/// # Errors
+///
+/// Returns an error if the input isn't a sequence of elements, or holds more unique ones than
+/// the set can.
+#[inline]
+pub fn deserialize<'de, T, S, const K: usize, P, D>(
+    deserializer: D,
+) -> Result<BloomSet<T, S, K, P>, D::Error>
+where
+    T: Deserialize<'de> + PartialEq,
+    S: ElementHasher<T> + Default,
+    P: Packing,
+    D: Deserializer<'de>,
+{
+    BloomSet::deserialize(deserializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BloomSet;
+
+    fn sorted_json(set: &BloomSet<u32>) -> Vec<u8> {
+        let mut out = Vec::new();
+        super::serialize(set, &mut serde_json::Serializer::new(&mut out)).unwrap();
+        out
+    }
+
+    #[test]
+    fn independent_of_insertion_order() {
+        let forwards: BloomSet<u32> = (0..100).map(|i| i * 37 % 101).collect();
+        let backwards: BloomSet<u32> = (0..100).rev().map(|i| i * 37 % 101).collect();
+        assert_ne!(
+            serde_json::to_vec(&forwards).unwrap(),
+            serde_json::to_vec(&backwards).unwrap()
+        );
+        assert_eq!(sorted_json(&forwards), sorted_json(&backwards));
+
+        let back: BloomSet<u32> = super::deserialize(&mut serde_json::Deserializer::from_slice(
+            &sorted_json(&forwards),
+        ))
+        .unwrap();
+        let mut expected = forwards.as_slice().to_vec();
+        expected.sort_unstable();
+        assert_eq!(back.as_slice(), expected);
+    }
+
+    #[test]
+    fn leaves_the_set_alone() {
+        let set: BloomSet<u32> = [3, 1, 2].iter().copied().collect();
+        assert_eq!(sorted_json(&set), b"[1,2,3]");
+        assert_eq!(set.as_slice(), [3, 1, 2]);
+        assert!(!set.is_sorted());
+    }
+}
@@ -0,0 +1,140 @@
+//! Borrow and lifetime checks that only hold if some code *fails* to compile.
+//!
+//! References into a set's buffer are invalidated when it reallocates, so every API that hands
+//! one out has to keep the set borrowed for as long as it lives. That is a property of the
+//! signatures rather than the implementation, and the only way to test it is with code the
+//! compiler rejects. Each case below is a `compile_fail` doctest, so one that starts compiling
+//! fails `cargo test --doc`. They are tagged with the error they must fail with, which a nightly
+//! toolchain also checks, so a case that breaks for some other reason is caught there. The cases
+//! that must compile pin the variance over `T`.
+//!
+//! This module only exists under `cfg(doctest)`, so it adds nothing to the crate.
+//!
+//! A slice from [`as_slice`](crate::BloomSet::as_slice) can't be held across an `insert`, which
+//! may move the buffer it points into:
+//!
+//! ```compile_fail,E0502
+//! use bloomset::BloomSet;
+//!
+//! let mut set: BloomSet<u32> = (0..4).collect();
+//! let elements = set.as_slice();
+//! set.insert(4);
+//! assert_eq!(elements[0], 0);
+//! ```
+//!
+//! Nor can the `&T` from [`get`](crate::BloomSet::get):
+//!
+//! ```compile_fail,E0502
+//! use bloomset::BloomSet;
+//!
+//! let mut set: BloomSet<String> = ["a", "b"].iter().map(|s| s.to_string()).collect();
+//! let found = set.get("a".to_string()).unwrap();
+//! set.insert("c".to_string());
+//! assert_eq!(found, "a");
+//! ```
+//!
+//! Or the `&mut V` that inserting through an entry hands back, once the map is changed again:
+//!
+//! ```compile_fail,E0499
+//! use bloomset::BloomMap;
+//!
+//! let mut map = BloomMap::new();
+//! let count = map.entry("a").or_insert(0);
+//! map.insert("b", 1);
+//! *count += 1;
+//! ```
+//!
+//! An iterator borrowed from a set can't outlive it:
+//!
+//! ```compile_fail,E0597
+//! use bloomset::BloomSet;
+//!
+//! let mut elements = None;
+//! {
+//!     let set: BloomSet<u32> = (0..4).collect();
+//!     elements = Some(set.as_slice().iter());
+//! }
+//! assert_eq!(elements.unwrap().count(), 4);
+//! ```
+//!
+//! ```compile_fail,E0505
+//! use bloomset::BloomSet;
+//!
+//! let set: BloomSet<u32> = (0..4).collect();
+//! let frozen = set.into_frozen();
+//! let elements = frozen.iter();
+//! drop(frozen);
+//! assert_eq!(elements.count(), 4);
+//! ```
+//!
+//! A set of references keeps what they point to borrowed, so it can't be dropped or changed:
+//!
+//! ```compile_fail,E0505
+//! use bloomset::BloomSet;
+//!
+//! let word = String::from("borrowed");
+//! let mut set = BloomSet::new();
+//! set.insert(word.as_str());
+//! drop(word);
+//! assert!(!set.is_empty());
+//! ```
+//!
+//! ```compile_fail,E0502
+//! use bloomset::BloomSetRef;
+//!
+//! let mut words = vec!["a", "b"];
+//! let view = BloomSetRef::new(&words);
+//! words.push("c");
+//! assert!(view.contains("a"));
+//! ```
+//!
+//! And a set of `'static` references only takes `'static` ones, even though it's covariant:
+//!
+//! ```compile_fail,E0597
+//! use bloomset::BloomSet;
+//!
+//! fn fill(set: &mut BloomSet<&'static str>) {
+//!     let word = String::from("short-lived");
+//!     set.insert(&word);
+//! }
+//! ```
+//!
+//! Like `Vec<T>`, the sets are covariant in `T`, and views in their lifetime, so a set of longer
+//! lived references can be used where one of shorter lived references is expected:
+//!
+//! ```
+//! use bloomset::{BloomSet, BloomSet16, BloomSetRef, FrozenBloomSet};
+//!
+//! fn shorten<'a>(set: BloomSet<&'static str>) -> BloomSet<&'a str> {
+//!     set
+//! }
+//! fn shorten_ref<'a, 'b>(set: &'b BloomSet16<&'static str>) -> &'b BloomSet16<&'a str> {
+//!     set
+//! }
+//! fn shorten_frozen<'a>(set: FrozenBloomSet<&'static str>) -> FrozenBloomSet<&'a str> {
+//!     set
+//! }
+//! fn shorten_view<'a>(view: BloomSetRef<'static, &'static str>) -> BloomSetRef<'a, &'a str> {
+//!     view
+//! }
+//!
+//! let local = String::from("local");
+//! let mut set = shorten(["static"].iter().copied().collect());
+//! set.insert(&local);
+//! assert_eq!(set.len(), 2);
+//! # let _ = (shorten_ref, shorten_frozen, shorten_view);
+//! ```
+//!
+//! A set that owns its elements isn't tied to any borrow, so it can be returned from where it was
+//! built and outlive everything it was built from:
+//!
+//! ```
+//! use bloomset::BloomSet;
+//!
+//! fn build() -> BloomSet<String> {
+//!     let words = vec![String::from("a"), String::from("b")];
+//!     words.into_iter().collect()
+//! }
+//! let set: BloomSet<String> = build();
+//! assert!(set.contains("a".to_string()));
+//! ```
@@ -0,0 +1,256 @@
+use crate::{BloomSet, BuildBloomHasher, ElementHasher, WideBloomSet, MAX_CAPACITY};
+use core::borrow::Borrow;
+use core::iter::FromIterator;
+use core::mem;
+
+enum Repr<T, S> {
+    Inline(BloomSet<T, S>),
+    Spilled(WideBloomSet<T, S>),
+}
+
+/// A [`BloomSet`] that keeps working past 255 elements instead of panicking.
+///
+/// Up to 255 elements it is a `BloomSet`. Inserting a 256th unique element moves everything into
+/// a [`WideBloomSet`], whose 16-bit filter is saturated at that size, so lookups become plain
+/// scans: correct, but no faster than a `Vec`. Removing elements doesn't move them back, but
+/// [`clear`](SpillingBloomSet::clear) does.
+///
+/// This is one word larger than a `BloomSet`, since it has to record which of the two it is.
+///
+/// ```
+/// use bloomset::SpillingBloomSet;
+///
+/// let mut set = SpillingBloomSet::new();
+/// for i in 0..1000 {
+///     set.insert(i);
+/// }
+/// assert!(set.is_spilled());
+/// assert!(set.contains(999));
+///
+/// set.clear();
+/// assert!(!set.is_spilled());
+/// ```
+pub struct SpillingBloomSet<T, S = BuildBloomHasher> {
+    repr: Repr<T, S>,
+}
+
+impl<T> Default for SpillingBloomSet<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_hasher(BuildBloomHasher)
+    }
+}
+
+impl<T> SpillingBloomSet<T> {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T, S> SpillingBloomSet<T, S> {
+    /// Creates an empty set which will use `hash_builder` to hash its elements.
+    #[inline]
+    #[must_use]
+    pub const fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            repr: Repr::Inline(BloomSet::with_hasher(hash_builder)),
+        }
+    }
+
+    /// Returns whether the set has outgrown a `BloomSet`.
+    #[inline]
+    #[must_use]
+    pub const fn is_spilled(&self) -> bool {
+        matches!(self.repr, Repr::Spilled(_))
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        match &self.repr {
+            Repr::Inline(set) => set.len(),
+            Repr::Spilled(set) => set.len(),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        match &self.repr {
+            Repr::Inline(set) => set.as_slice(),
+            Repr::Spilled(set) => set.as_slice(),
+        }
+    }
+
+    /// Returns a reference to the set's `BuildHasher`.
+    #[inline]
+    #[must_use]
+    pub const fn hasher(&self) -> &S {
+        match &self.repr {
+            Repr::Inline(set) => set.hasher(),
+            Repr::Spilled(set) => set.hasher(),
+        }
+    }
+}
+
+impl<T, S: Clone> SpillingBloomSet<T, S> {
+    /// Removes every element, going back to a `BloomSet` if the set had spilled.
+    #[inline]
+    pub fn clear(&mut self) {
+        match &mut self.repr {
+            Repr::Inline(set) => set.clear(),
+            Repr::Spilled(set) => {
+                self.repr = Repr::Inline(BloomSet::with_hasher(set.hasher().clone()));
+            }
+        }
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T> + Clone> SpillingBloomSet<T, S> {
+    #[inline]
+    pub fn insert(&mut self, item: T) {
+        match &mut self.repr {
+            Repr::Inline(set) if set.len() < MAX_CAPACITY || set.contains(&item) => {
+                set.insert(item);
+            }
+            Repr::Inline(set) => {
+                let hash_builder = set.hasher().clone();
+                let inline = mem::replace(set, BloomSet::with_hasher(hash_builder.clone()));
+                let mut spilled =
+                    WideBloomSet::with_capacity_and_hasher(MAX_CAPACITY * 2, hash_builder);
+                for elem in inline.into_vec() {
+                    spilled.insert(elem);
+                }
+                spilled.insert(item);
+                self.repr = Repr::Spilled(spilled);
+            }
+            Repr::Spilled(set) => set.insert(item),
+        }
+    }
+
+    /// Removes `item` from the set, returning whether it was present.
+    #[inline]
+    pub fn remove<B: Borrow<T>>(&mut self, item: B) -> bool {
+        match &mut self.repr {
+            Repr::Inline(set) => set.remove(item),
+            Repr::Spilled(set) => set.remove(item),
+        }
+    }
+
+    #[inline]
+    pub fn contains<B: Borrow<T>>(&self, item: B) -> bool {
+        match &self.repr {
+            Repr::Inline(set) => set.contains(item),
+            Repr::Spilled(set) => set.contains(item),
+        }
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T> + Clone + Default> FromIterator<T>
+    for SpillingBloomSet<T, S>
+{
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::with_hasher(S::default());
+        for item in iter {
+            set.insert(item);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spills_on_the_256th_unique_element() {
+        let mut set = SpillingBloomSet::new();
+        for i in 0..255_u32 {
+            set.insert(i);
+        }
+        // Duplicates don't need room
+        set.insert(0);
+        set.insert(254);
+        assert!(!set.is_spilled());
+        assert_eq!(set.len(), 255);
+
+        set.insert(255);
+        assert!(set.is_spilled());
+        assert_eq!(set.len(), 256);
+        assert_eq!(set.as_slice(), (0..256).collect::<Vec<_>>().as_slice());
+        assert!((0..256).all(|i| set.contains(i)));
+        assert!(!set.contains(256));
+
+        set.insert(255);
+        assert_eq!(set.len(), 256);
+    }
+
+    #[test]
+    fn removing_does_not_unspill() {
+        let mut set: SpillingBloomSet<u32> = (0..300).collect();
+        for i in 0..250 {
+            assert!(set.remove(i));
+        }
+        assert!(!set.remove(0));
+        assert!(set.is_spilled());
+        assert_eq!(set.as_slice(), (250..300).collect::<Vec<_>>().as_slice());
+        assert!(!set.contains(0) && set.contains(299));
+    }
+
+    #[test]
+    fn clear_goes_back_inline() {
+        let mut set = SpillingBloomSet::new();
+        for round in 0..3_u32 {
+            for i in 0..400 {
+                set.insert(i * (round + 1));
+            }
+            assert!(set.is_spilled());
+            assert_eq!(set.len(), 400);
+            set.clear();
+            assert!(!set.is_spilled() && set.is_empty());
+            assert!(!set.contains(0));
+
+            // Back inline the set hits the limit at the same place
+            for i in 0..255 {
+                set.insert(i);
+            }
+            assert!(!set.is_spilled());
+            set.insert(1000);
+            assert!(set.is_spilled());
+            set.clear();
+        }
+    }
+
+    #[test]
+    fn inline_clear_keeps_the_set_usable() {
+        let mut set: SpillingBloomSet<&str> = ["a", "b"].iter().copied().collect();
+        set.clear();
+        assert!(set.is_empty() && !set.is_spilled());
+        set.insert("c");
+        assert!(set.contains("c") && !set.contains("a"));
+    }
+
+    #[test]
+    fn spilled_set_crosses_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SpillingBloomSet<u32>>();
+
+        let set: SpillingBloomSet<u32> = (0..300).collect();
+        let set = std::thread::spawn(move || {
+            assert!(set.is_spilled() && set.contains(299));
+            set
+        })
+        .join()
+        .unwrap();
+        assert_eq!(set.len(), 300);
+    }
+}
@@ -0,0 +1,201 @@
+//! Lookup counters for the `stats` feature.
+//!
+//! A set has no room for counters of its own without growing past the size of a `Vec`, so
+//! they are kept per thread instead, summed over every [`BloomSet`](crate::BloomSet) the thread
+//! looks things up in. Sets used from several threads are counted on each of them separately.
+
+use std::cell::Cell;
+
+/// Counts of what the filter did for the lookups a thread has made since it started, or since
+/// it last called [`reset_stats`].
+///
+/// Every operation that looks an element up counts: `contains`, `get`, `promote`,
+/// `contains_promoting`, `remove`, and the duplicate check in `insert`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct BloomStats {
+    /// Lookups made.
+    pub lookups: u64,
+    /// Lookups the filter answered on its own, without scanning.
+    pub filter_rejects: u64,
+    /// Lookups that got past the filter but didn't find the element.
+    pub false_positives: u64,
+    /// Elements compared by the lookups that got past the filter: up to and including the match,
+    /// or all of them for a false positive.
+    pub scanned_elements: u64,
+}
+
+impl BloomStats {
+    /// The fraction of lookups that had to scan and found nothing, out of those that didn't
+    /// find anything.
+    #[inline]
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn false_positive_rate(&self) -> f64 {
+        let misses = self.filter_rejects + self.false_positives;
+        if misses == 0 {
+            0.0
+        } else {
+            self.false_positives as f64 / misses as f64
+        }
+    }
+}
+
+std::thread_local! {
+    static STATS: Cell<BloomStats> = Cell::new(BloomStats::default());
+}
+
+/// The counters for the lookups this thread has made.
+///
+/// ```
+/// use bloomset::BloomSet;
+///
+/// let set: BloomSet<u32> = (0..10).collect();
+/// bloomset::reset_stats();
+/// assert!(set.contains(5));
+/// let stats = bloomset::stats();
+/// assert_eq!((stats.lookups, stats.scanned_elements), (1, 6));
+/// ```
+#[inline]
+#[must_use]
+pub fn stats() -> BloomStats {
+    STATS.with(Cell::get)
+}
+
+/// Sets this thread's counters back to zero.
+#[inline]
+pub fn reset_stats() {
+    STATS.with(|stats| stats.set(BloomStats::default()));
+}
+
+/// Counts a lookup in a set of `len` elements, which either the filter rejected or got past it
+/// and found the element at `index`, if at all.
+pub fn record(len: usize, passed_filter: bool, index: Option<usize>) {
+    STATS.with(|cell| {
+        let mut stats = cell.get();
+        stats.lookups += 1;
+        if passed_filter {
+            stats.scanned_elements += index.map_or(len, |index| index + 1) as u64;
+            stats.false_positives += u64::from(index.is_none());
+        } else {
+            stats.filter_rejects += 1;
+        }
+        cell.set(stats);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BloomSet;
+
+    #[test]
+    fn scripted_lookups() {
+        let mut set: BloomSet<u64> = (0..8).collect();
+        // An absent element the filter rejects, and one it lets through
+        let rejected = (100..10_000)
+            .find(|i| !set.bloom_contains(set.bloom_mask_of(i)))
+            .unwrap();
+        let passes = (100..10_000)
+            .find(|i| set.bloom_contains(set.bloom_mask_of(i)))
+            .unwrap();
+        reset_stats();
+
+        assert!(set.contains(0));
+        assert!(set.contains(7));
+        assert!(!set.contains(rejected));
+        assert!(!set.contains(passes));
+        assert_eq!(
+            stats(),
+            BloomStats {
+                lookups: 4,
+                filter_rejects: 1,
+                false_positives: 1,
+                scanned_elements: 1 + 8 + 8,
+            }
+        );
+        assert!((stats().false_positive_rate() - 0.5).abs() < f64::EPSILON);
+
+        reset_stats();
+        assert_eq!(set.get(3), Some(&3));
+        assert!(set.promote(5));
+        set.insert(2);
+        assert!(!set.remove(rejected));
+        assert!(set.remove(0));
+        assert_eq!(
+            stats(),
+            BloomStats {
+                lookups: 5,
+                filter_rejects: 1,
+                false_positives: 0,
+                // 2 and 0 are a slot further back once 5 moved to the front
+                scanned_elements: 4 + 6 + 4 + 2,
+            }
+        );
+    }
+
+    #[test]
+    fn lookups_by_key() {
+        let mut set: BloomSet<u64> = (0..8).collect();
+        let mut other = BloomSet::new();
+        reset_stats();
+        assert_eq!(set.get_or_try_insert_with(&5, |_| Err(())), Ok(&5));
+        assert!(set.move_to(&mut other, &7));
+        assert!(set.replace_with(&2, |it| it));
+        assert_eq!(
+            stats(),
+            BloomStats {
+                // `move_to` also looks for 7 in the empty `other`, which the filter rejects, and
+                // `replace_with` looks for the new 2 among the six others, past 2's stale bit
+                lookups: 5,
+                filter_rejects: 1,
+                false_positives: 1,
+                scanned_elements: 6 + 8 + 3 + 6,
+            }
+        );
+    }
+
+    #[test]
+    fn batched_lookups() {
+        let set: BloomSet<u64> = (0..8).collect();
+        let rejected = (100..10_000)
+            .find(|i| !set.bloom_contains(set.bloom_mask_of(i)))
+            .unwrap();
+        reset_stats();
+        assert_eq!(
+            set.get_many([&6, &rejected, &1, &6]),
+            [Some(&6), None, Some(&1), Some(&6)]
+        );
+        assert_eq!(
+            stats(),
+            BloomStats {
+                lookups: 4,
+                filter_rejects: 1,
+                false_positives: 0,
+                scanned_elements: 7 + 2 + 7,
+            }
+        );
+    }
+
+    #[test]
+    fn sorted_lookups() {
+        let mut set: BloomSet<u64> = (0..8).rev().collect();
+        set.sort();
+        reset_stats();
+        assert!(set.contains_sorted(2));
+        assert!(!set.contains_sorted(8));
+        assert_eq!(stats().lookups, 2);
+    }
+
+    #[test]
+    fn counted_per_thread() {
+        let set: BloomSet<u32> = (0..4).collect();
+        reset_stats();
+        std::thread::scope(|scope| {
+            scope.spawn(|| assert!(set.contains(1)));
+        });
+        assert_eq!(stats(), BloomStats::default());
+        assert!(set.contains(1));
+        assert_eq!(stats().lookups, 1);
+    }
+}
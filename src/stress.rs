@@ -0,0 +1,270 @@
+//! Long seeded runs of random operations on sets of elements with drop glue, checked after
+//! every step against a `Vec` doing the same.
+//!
+//! The other tests mostly build a set once and look at it. These keep one set alive through
+//! hundreds of operations, so it grows through several reallocations, is cleared and grows
+//! again in the same buffer, shrinks, is cloned and moved through `into_iter` and a drain, with
+//! `String` and `Box<u32>` elements that Miri tracks each allocation of. That is where a wrong
+//! offset or a stale pointer into an old buffer shows up.
+//!
+//! The suite is meant to pass under Miri with strict provenance:
+//!
+//! ```text
+//! MIRIFLAGS=-Zmiri-strict-provenance cargo +nightly miri test --lib stress
+//! ```
+//!
+//! Under Miri the runs are shorter, so that takes a few minutes rather than hours.
+
+use crate::{BloomSet, BuildBloomHasher, Packed16, Packed8, Packing};
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::hash::Hash;
+
+/// Operations per run, and runs per element type and packing.
+const STEPS: usize = if cfg!(miri) { 300 } else { 5000 };
+const SEEDS: u64 = if cfg!(miri) { 2 } else { 16 };
+
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number below `n`, which must not be 0.
+    fn below(&mut self, n: usize) -> usize {
+        usize::try_from(self.next() % u64::try_from(n).unwrap()).unwrap()
+    }
+}
+
+/// A set, the `Vec` it is checked against, and where the operations on them come from.
+struct Run<T, P: Packing> {
+    rng: Rng,
+    set: BloomSet<T, BuildBloomHasher, 1, P>,
+    model: Vec<T>,
+    keys: usize,
+    make: fn(usize) -> T,
+}
+
+impl<T, P> Run<T, P>
+where
+    T: Clone + Hash + Ord + core::fmt::Debug,
+    P: Packing,
+{
+    /// An element from one of the keys below `keys`.
+    fn item(&mut self) -> T {
+        (self.make)(self.rng.below(self.keys))
+    }
+
+    /// Inserts one element or a batch of them.
+    fn grow(&mut self, batch: bool) {
+        if !batch {
+            let item = self.item();
+            let new = !self.model.contains(&item);
+            let result = self.set.try_insert(item.clone());
+            if new && self.model.len() == P::MAX_CAPACITY {
+                assert!(result.is_err());
+            } else {
+                assert!(result.is_ok());
+                if new {
+                    self.model.push(item);
+                }
+            }
+            return;
+        }
+        // Duplicates within the batch and of what is there are dropped, and nothing new is
+        // passed once the set is full
+        let mut batch = Vec::new();
+        for _ in 0..self.rng.below(40) {
+            let item = self.item();
+            if self.model.contains(&item) {
+                batch.push(item);
+            } else if self.model.len() < P::MAX_CAPACITY {
+                self.model.push(item.clone());
+                batch.push(item);
+            }
+        }
+        self.set.extend(batch);
+    }
+
+    /// Removes elements in one of the ways there are to.
+    fn shrink(&mut self, how: usize) {
+        let (set, model) = (&mut self.set, &mut self.model);
+        match how {
+            0 => {
+                let item = (self.make)(self.rng.below(self.keys));
+                let index = model.iter().position(|it| *it == item);
+                assert_eq!(set.remove(&item), index.is_some());
+                if let Some(index) = index {
+                    model.remove(index);
+                }
+            }
+            1 if !model.is_empty() => {
+                let index = self.rng.below(model.len());
+                assert_eq!(set.swap_remove_index(index), model.swap_remove(index));
+            }
+            2 => {
+                let modulus = self.rng.below(5) + 2;
+                let mut key = 0;
+                set.retain(|_| {
+                    key += 1;
+                    key % modulus != 0
+                });
+                let mut key = 0;
+                model.retain(|_| {
+                    key += 1;
+                    key % modulus != 0
+                });
+            }
+            3 if !model.is_empty() => {
+                let start = self.rng.below(model.len() + 1);
+                let end = start + self.rng.below(model.len() - start + 1);
+                let taken = self.rng.below(end - start + 1);
+                let mut drain = set.drain(start..end);
+                let got: Vec<T> = drain.by_ref().take(taken).collect();
+                assert_eq!(got, model[start..start + taken]);
+                if self.rng.below(2) == 0 {
+                    drain.keep_rest();
+                    model.drain(start..start + taken);
+                } else {
+                    drop(drain);
+                    model.drain(start..end);
+                }
+            }
+            4 => {
+                let odd = |it: &T| format!("{it:?}").len() % 2 == 1;
+                let index = model.iter().position(odd);
+                assert_eq!(set.take_if(odd), index.map(|i| model.swap_remove(i)));
+            }
+            _ => {
+                set.clear();
+                model.clear();
+            }
+        }
+    }
+
+    /// Moves the elements to a new buffer, or changes the one they are in.
+    fn reallocate(&mut self, how: usize) {
+        match how {
+            0 => self.set.shrink_to_fit(),
+            1 => {
+                let room = P::MAX_CAPACITY - self.set.len();
+                self.set.reserve(self.rng.below(room + 1));
+            }
+            2 => {
+                let copy = self.set.clone();
+                assert_eq!(copy.as_slice(), self.set.as_slice());
+                // Drop the original and carry on with the copy, in its own buffer
+                self.set = copy;
+            }
+            3 => {
+                let empty = BloomSet::with_hasher(BuildBloomHasher);
+                let set = core::mem::replace(&mut self.set, empty);
+                let mut iter = set.into_iter();
+                let first = iter.next();
+                let rest: Vec<T> = iter.collect();
+                self.set = BloomSet::with_capacity_and_hasher(self.model.len(), BuildBloomHasher);
+                self.set.extend(first.into_iter().chain(rest));
+            }
+            _ => {
+                self.set.sort();
+                self.model.sort();
+            }
+        }
+    }
+}
+
+/// Drives one set and its model through `STEPS` random operations, making elements from keys
+/// below `keys` with `make`.
+fn run<T, P>(seed: u64, keys: usize, make: fn(usize) -> T)
+where
+    T: Clone + Hash + Ord + core::fmt::Debug,
+    P: Packing,
+{
+    let mut rng = Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1);
+    let set = BloomSet::with_capacity_and_hasher(rng.below(8), BuildBloomHasher);
+    let mut run = Run::<T, P> {
+        rng,
+        set,
+        model: Vec::new(),
+        keys,
+        make,
+    };
+    for step in 0..STEPS {
+        // Inserting is most of it, so the set keeps growing between the operations that
+        // shrink it
+        match run.rng.below(16) {
+            op @ 0..=5 => run.grow(op == 5),
+            op @ 6..=11 => run.shrink(op - 6),
+            op => run.reallocate(op - 12),
+        }
+        assert_eq!(
+            run.set.as_slice(),
+            run.model.as_slice(),
+            "seed {seed}, step {step}"
+        );
+        assert!(run.set.len() <= run.set.capacity());
+        let item = run.item();
+        assert_eq!(run.set.contains(&item), run.model.contains(&item));
+    }
+    // Everything left is still found, after all of that
+    assert!(run.model.iter().all(|item| run.set.contains(item)));
+}
+
+fn string(key: usize) -> String {
+    // Never empty, so each one is a heap allocation of its own
+    key.to_string().repeat(3)
+}
+
+fn boxed(key: usize) -> Box<u32> {
+    Box::new(u32::try_from(key).unwrap())
+}
+
+#[test]
+fn strings() {
+    for seed in 0..SEEDS {
+        run::<String, Packed8>(seed, 300, string);
+    }
+}
+
+#[test]
+fn boxes() {
+    for seed in 0..SEEDS {
+        run::<Box<u32>, Packed8>(seed, 300, boxed);
+    }
+}
+
+#[test]
+fn past_255_elements() {
+    for seed in 0..SEEDS {
+        run::<String, Packed16>(seed, 2000, string);
+        run::<Box<u32>, Packed16>(seed, 2000, boxed);
+    }
+}
+
+#[test]
+fn grow_clear_grow() {
+    let rounds = if cfg!(miri) { 3 } else { 50 };
+    let mut set: BloomSet<Box<u32>> = BloomSet::with_capacity(1);
+    for round in 0..rounds {
+        // Each round grows from empty through several reallocations, then clears and reuses
+        // the buffer it ended up with
+        for i in 0..255 {
+            set.insert(Box::new(i * round));
+            assert_eq!(*set.as_slice()[set.len() - 1], i * round);
+        }
+        let capacity = set.capacity();
+        set.clear();
+        assert_eq!(set.capacity(), capacity);
+        assert!(set.is_empty() && !set.contains(Box::new(0)));
+        if round % 2 == 0 {
+            set.shrink_to_fit();
+            assert_eq!(set.capacity(), 0);
+        }
+    }
+}
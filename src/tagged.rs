@@ -0,0 +1,356 @@
+use crate::{
+    bloom_mask, grown_capacity, split_mask, unpack_filter, BuildBloomHasher, CapacityError,
+    ElementHasher, Packed8, FILTER_BITS, MAX_CAPACITY,
+};
+use ::alloc::alloc::{self, Layout};
+use core::iter::FromIterator;
+use core::ptr::{self, NonNull};
+use core::slice;
+
+/// A [`BloomSet`](crate::BloomSet) that also keeps a one-byte fingerprint of each element's
+/// hash, so that a lookup the filter can't rule out compares fingerprints before it compares
+/// elements.
+///
+/// The fingerprints live in the same allocation, directly after the elements, so this is still
+/// the size of a `Vec<T>`. Scanning them is cheap, so this pays off when `PartialEq` is
+/// expensive (e.g. `String` keys) and many lookups miss in a well-filled set.
+///
+/// ```
+/// use bloomset::TaggedBloomSet;
+///
+/// let set: TaggedBloomSet<String> = ["a", "b"].iter().map(|s| s.to_string()).collect();
+/// assert!(set.contains(&"a".to_string()));
+/// assert!(!set.contains(&"c".to_string()));
+/// ```
+pub struct TaggedBloomSet<T, S = BuildBloomHasher> {
+    ptr: NonNull<T>,
+    length: usize,
+    capacity: usize,
+    hash_builder: S,
+}
+
+impl<T> Default for TaggedBloomSet<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_hasher(BuildBloomHasher)
+    }
+}
+
+impl<T> TaggedBloomSet<T> {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_capacity(cap: usize) -> Self {
+        Self::with_capacity_and_hasher(cap, BuildBloomHasher)
+    }
+}
+
+impl<T, S> TaggedBloomSet<T, S> {
+    /// Creates an empty set which will use `hash_builder` to hash its elements.
+    #[inline]
+    #[must_use]
+    pub const fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            ptr: NonNull::dangling(),
+            length: 0,
+            capacity: 0,
+            hash_builder,
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `cap` is more than 255.
+    #[inline]
+    #[must_use]
+    pub fn with_capacity_and_hasher(cap: usize, hash_builder: S) -> Self {
+        assert!(
+            cap <= MAX_CAPACITY,
+            "{}",
+            CapacityError::new(cap, MAX_CAPACITY)
+        );
+        let mut set = Self::with_hasher(hash_builder);
+        if cap > 0 {
+            set.ptr = Self::allocate(cap);
+            set.capacity = cap;
+        }
+        set
+    }
+
+    /// Returns a reference to the set's `BuildHasher`.
+    #[inline]
+    #[must_use]
+    pub const fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.length & MAX_CAPACITY
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.capacity & MAX_CAPACITY
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len()) }
+    }
+
+    /// The layout of an allocation holding `cap` elements followed by `cap` tags, and the offset
+    /// of the tags within it.
+    fn layout(cap: usize) -> (Layout, usize) {
+        // Can't overflow: cap is at most 255 and an array of 255 T's is itself a valid layout
+        Layout::array::<T>(cap)
+            .and_then(|elements| elements.extend(Layout::array::<u8>(cap)?))
+            .unwrap()
+    }
+
+    fn allocate(cap: usize) -> NonNull<T> {
+        let (layout, _) = Self::layout(cap);
+        // cap > 0, so there's always at least one tag and the layout isn't zero-sized
+        let ptr = unsafe { alloc::alloc(layout) };
+        NonNull::new(ptr.cast()).unwrap_or_else(|| alloc::handle_alloc_error(layout))
+    }
+
+    fn tags_ptr(&self) -> *mut u8 {
+        let (_, offset) = Self::layout(self.capacity());
+        unsafe { self.ptr.as_ptr().cast::<u8>().add(offset) }
+    }
+
+    fn tags(&self) -> &[u8] {
+        if self.capacity() == 0 {
+            return &[];
+        }
+        unsafe { slice::from_raw_parts(self.tags_ptr(), self.len()) }
+    }
+
+    #[inline(never)]
+    fn grow(&mut self) -> Result<(), CapacityError> {
+        if self.len() == MAX_CAPACITY {
+            return Err(CapacityError::new(MAX_CAPACITY + 1, MAX_CAPACITY));
+        }
+        let new_capacity = grown_capacity(self.capacity(), MAX_CAPACITY);
+        let new_ptr = Self::allocate(new_capacity);
+        let (_, new_offset) = Self::layout(new_capacity);
+        unsafe {
+            ptr::copy_nonoverlapping(self.ptr.as_ptr(), new_ptr.as_ptr(), self.len());
+            let new_tags = new_ptr.as_ptr().cast::<u8>().add(new_offset);
+            ptr::copy_nonoverlapping(self.tags().as_ptr(), new_tags, self.len());
+            self.deallocate();
+        }
+        self.ptr = new_ptr;
+        self.capacity = new_capacity | (self.capacity & !MAX_CAPACITY);
+        Ok(())
+    }
+
+    /// Frees the allocation without dropping any elements.
+    unsafe fn deallocate(&mut self) {
+        if self.capacity() > 0 {
+            let (layout, _) = Self::layout(self.capacity());
+            alloc::dealloc(self.ptr.as_ptr().cast(), layout);
+        }
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        let elements = ptr::slice_from_raw_parts_mut(self.ptr.as_ptr(), self.len());
+        // Reset the length first, so a panicking Drop impl can't lead to a double drop
+        self.length = 0;
+        self.capacity &= MAX_CAPACITY;
+        unsafe { ptr::drop_in_place(elements) };
+    }
+
+    #[inline]
+    const fn filter(&self) -> u128 {
+        unpack_filter::<Packed8>(self.length, self.capacity)
+    }
+}
+
+/// The fingerprint stored for an element with this hash. The top byte is used because the
+/// filter bit is mostly determined by the low bytes.
+#[allow(clippy::cast_possible_truncation)]
+const fn tag(hash: u64) -> u8 {
+    (hash >> 56) as u8
+}
+
+impl<T: PartialEq, S: ElementHasher<T>> TaggedBloomSet<T, S> {
+    /// # Errors
+    ///
+    /// Returns a [`CapacityError`] if the iterator yields a 256th unique element.
+    #[inline]
+    pub fn try_from_iter_with_hasher<I: IntoIterator<Item = T>>(
+        iter: I,
+        hash_builder: S,
+    ) -> Result<Self, CapacityError> {
+        let iter = iter.into_iter();
+        let mut set =
+            Self::with_capacity_and_hasher(iter.size_hint().0.min(MAX_CAPACITY), hash_builder);
+        for item in iter {
+            set.try_insert(item)?;
+        }
+        Ok(set)
+    }
+
+    /// Returns whether `item` is in the set, comparing only elements whose tag matches.
+    fn find(&self, item: &T, tag: u8) -> bool {
+        self.tags()
+            .iter()
+            .zip(self.as_slice())
+            .any(|(&t, it)| t == tag && it == item)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the set would need to grow beyond 255 elements.
+    #[inline]
+    pub fn insert(&mut self, item: T) {
+        if let Err(err) = self.try_insert(item) {
+            panic!("{}", err);
+        }
+    }
+
+    /// # Errors
+    ///
+    /// Returns a [`CapacityError`] if `item` would be the set's 256th element.
+    #[inline]
+    pub fn try_insert(&mut self, item: T) -> Result<(), CapacityError> {
+        let hash = self.hash_builder.hash_element(&item);
+        let mask = bloom_mask::<1>(hash, FILTER_BITS);
+        if self.filter() & mask == mask && self.find(&item, tag(hash)) {
+            return Ok(());
+        }
+        if self.len() == self.capacity() {
+            self.grow()?;
+        }
+        unsafe {
+            self.ptr.as_ptr().add(self.len()).write(item);
+            self.tags_ptr().add(self.len()).write(tag(hash));
+        }
+        let (low, high) = split_mask::<Packed8>(mask);
+        self.capacity |= low;
+        self.length = (self.length | high) + 1;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn contains(&self, item: &T) -> bool {
+        let hash = self.hash_builder.hash_element(item);
+        let mask = bloom_mask::<1>(hash, FILTER_BITS);
+        self.filter() & mask == mask && self.find(item, tag(hash))
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T> + Default> FromIterator<T> for TaggedBloomSet<T, S> {
+    /// # Panics
+    ///
+    /// Panics if the iterator yields more than 255 unique elements.
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        match Self::try_from_iter_with_hasher(iter, S::default()) {
+            Ok(set) => set,
+            Err(err) => panic!("{}", err),
+        }
+    }
+}
+
+impl<T, S> Drop for TaggedBloomSet<T, S> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe {
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.ptr.as_ptr(), self.len()));
+            self.deallocate();
+        }
+    }
+}
+
+// The set owns its elements like a Vec does, so it can cross threads under the same conditions
+unsafe impl<T: Send, S: Send> Send for TaggedBloomSet<T, S> {}
+unsafe impl<T: Sync, S: Sync> Sync for TaggedBloomSet<T, S> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BloomSet;
+    use std::rc::Rc;
+
+    #[test]
+    fn agrees_with_bloomset() {
+        let words: Vec<String> = (0..255_u32).map(|i| format!("word{}", i * 31)).collect();
+        let mut tagged = TaggedBloomSet::new();
+        let mut plain = BloomSet::new();
+        for word in &words {
+            tagged.insert(word.as_str());
+            plain.insert(word.as_str());
+            assert_eq!(tagged.as_slice(), plain.as_slice());
+        }
+        assert_eq!(tagged.capacity(), 255);
+        let misses: Vec<String> = (0..500).map(|i| format!("word{i}")).collect();
+        for probe in words.iter().chain(&misses) {
+            let probe = probe.as_str();
+            assert_eq!(tagged.contains(&probe), plain.contains(probe), "{probe}");
+        }
+
+        let err = tagged.try_insert("one too many").err().unwrap();
+        assert_eq!(err.requested(), 256);
+    }
+
+    #[test]
+    fn tags_follow_elements() {
+        let set: TaggedBloomSet<u64> = (0..100).collect();
+        for (elem, &t) in set.as_slice().iter().zip(set.tags()) {
+            assert_eq!(t, tag(BuildBloomHasher.hash_element(elem)));
+        }
+    }
+
+    #[test]
+    fn clear_and_drop_release_elements() {
+        let counted = Rc::new(());
+        let mut set = TaggedBloomSet::new();
+        for i in 0..10_u8 {
+            set.insert((i, Rc::clone(&counted)));
+        }
+        assert_eq!(Rc::strong_count(&counted), 11);
+        set.clear();
+        assert_eq!(Rc::strong_count(&counted), 1);
+        assert!(set.is_empty() && set.filter() == 0);
+        assert_eq!(set.capacity(), 16);
+
+        set.insert((0, Rc::clone(&counted)));
+        assert!(set.contains(&(0, Rc::clone(&counted))));
+        drop(set);
+        assert_eq!(Rc::strong_count(&counted), 1);
+    }
+
+    #[test]
+    fn tagged_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<TaggedBloomSet<u32>>();
+        assert_send_sync::<TaggedBloomSet<String>>();
+    }
+
+    #[test]
+    fn zero_sized_elements() {
+        let mut set = TaggedBloomSet::new();
+        set.insert(());
+        set.insert(());
+        assert_eq!(set.len(), 1);
+        assert!(set.contains(&()));
+    }
+}
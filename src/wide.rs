@@ -0,0 +1,458 @@
+use crate::{bloom_mask, BuildBloomHasher, ElementHasher};
+use alloc::vec::Vec;
+use core::iter::FromIterator;
+use core::mem::ManuallyDrop;
+use core::ptr::NonNull;
+use core::slice;
+
+/// The filter bit for a hash, as a mask over the 16 filter bits. It comes from `bloom_mask`
+/// like every other set's, so with the `no-filter` feature it is empty and every lookup scans.
+#[inline]
+#[allow(clippy::cast_possible_truncation)]
+const fn filter_mask(hash: u64) -> u16 {
+    bloom_mask::<1>(hash, 16) as u16
+}
+
+/// On `x86_64` user-space addresses fit in 48 bits, so the filter goes in the top 16 bits of the
+/// pointer and both length and capacity are full-width. `aarch64` isn't included: with
+/// top-byte-ignore, MTE or 52-bit addresses its heap pointers can use the high bits.
+#[cfg(all(target_arch = "x86_64", not(bloomset_no_ptr_tags)))]
+mod repr {
+    const TAG_SHIFT: u32 = 48;
+    const ADDR_MASK: usize = (1 << TAG_SHIFT) - 1;
+
+    pub struct Repr<T> {
+        /// The allocation's address in the low 48 bits, and the filter in the high 16. All
+        /// changes go through `map_addr`, so the pointer keeps its provenance.
+        ptr: *mut T,
+        length: usize,
+        capacity: usize,
+    }
+
+    impl<T> Repr<T> {
+        pub const fn new(ptr: *mut T, length: usize, capacity: usize) -> Self {
+            Self {
+                ptr,
+                length,
+                capacity,
+            }
+        }
+
+        pub fn ptr(&self) -> *mut T {
+            self.ptr.map_addr(|addr| addr & ADDR_MASK)
+        }
+
+        /// # Panics
+        ///
+        /// Panics if `ptr` uses the high 16 bits, e.g. under 5-level paging. Only pointers
+        /// handed out by the allocator come through here, and Linux never gives those out above
+        /// 2^47 unless a program asks for it.
+        pub fn set_ptr(&mut self, ptr: *mut T) {
+            assert!(
+                ptr.addr() & !ADDR_MASK == 0,
+                "allocation at {:p} does not fit in 48 bits",
+                ptr
+            );
+            let filter = self.ptr.addr() & !ADDR_MASK;
+            self.ptr = ptr.map_addr(|addr| addr | filter);
+        }
+
+        pub const fn len(&self) -> usize {
+            self.length
+        }
+
+        pub const fn set_len(&mut self, length: usize) {
+            self.length = length;
+        }
+
+        pub const fn capacity(&self) -> usize {
+            self.capacity
+        }
+
+        pub const fn set_capacity(&mut self, capacity: usize) {
+            self.capacity = capacity;
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        pub fn filter(&self) -> u16 {
+            (self.ptr.addr() >> TAG_SHIFT) as u16
+        }
+
+        pub fn set_filter(&mut self, filter: u16) {
+            self.ptr = self
+                .ptr
+                .map_addr(|addr| (addr & ADDR_MASK) | usize::from(filter) << TAG_SHIFT);
+        }
+    }
+}
+
+#[cfg(not(all(target_arch = "x86_64", not(bloomset_no_ptr_tags))))]
+mod repr {
+    use core::mem::size_of;
+
+    const TAG_SHIFT: u32 = usize::BITS - 16;
+    const CAPACITY_MASK: usize = (1 << TAG_SHIFT) - 1;
+
+    pub struct Repr<T> {
+        ptr: *mut T,
+        length: usize,
+        /// The capacity in the low bits, and the filter in the high 16.
+        capacity: usize,
+    }
+
+    impl<T> Repr<T> {
+        pub const fn new(ptr: *mut T, length: usize, capacity: usize) -> Self {
+            Self {
+                ptr,
+                length,
+                capacity,
+            }
+        }
+
+        pub const fn ptr(&self) -> *mut T {
+            self.ptr
+        }
+
+        pub const fn set_ptr(&mut self, ptr: *mut T) {
+            self.ptr = ptr;
+        }
+
+        pub const fn len(&self) -> usize {
+            self.length
+        }
+
+        pub const fn set_len(&mut self, length: usize) {
+            self.length = length;
+        }
+
+        /// A `Vec` of zero-sized elements always has a capacity of `usize::MAX`, which doesn't
+        /// fit below the filter, so none is stored for them and it is restored here.
+        pub const fn capacity(&self) -> usize {
+            if size_of::<T>() == 0 {
+                usize::MAX
+            } else {
+                self.capacity & CAPACITY_MASK
+            }
+        }
+
+        /// # Panics
+        ///
+        /// Panics if `capacity` needs any of the bits the filter lives in, unless `T` is
+        /// zero-sized.
+        pub fn set_capacity(&mut self, capacity: usize) {
+            if size_of::<T>() == 0 {
+                return;
+            }
+            assert!(
+                capacity <= CAPACITY_MASK,
+                "capacity {} needs more than {} bits",
+                capacity,
+                TAG_SHIFT
+            );
+            self.capacity = (self.capacity & !CAPACITY_MASK) | capacity;
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        pub const fn filter(&self) -> u16 {
+            (self.capacity >> TAG_SHIFT) as u16
+        }
+
+        pub fn set_filter(&mut self, filter: u16) {
+            self.capacity = (self.capacity & CAPACITY_MASK) | usize::from(filter) << TAG_SHIFT;
+        }
+    }
+}
+
+/// A set with no limit on its length, which keeps a 16-bit bloom filter in the high bits of its
+/// pointer rather than in its length and capacity.
+///
+/// This is the layout the crate's name promises: `x86_64` only uses 48 bits of address space,
+/// leaving 16 free above the pointer. The filter is much smaller than a
+/// [`BloomSet`](crate::BloomSet)'s 96 bits, so it saturates after a few dozen elements, but
+/// the set keeps working (as a plain scan) however large it grows.
+///
+/// On other targets, or when built with `--cfg bloomset_no_ptr_tags`, the filter goes in the top
+/// 16 bits of the capacity instead, which limits the capacity to 2^48 elements on 64-bit targets
+/// and 65,535 on 32-bit ones.
+///
+/// ```
+/// use bloomset::WideBloomSet;
+///
+/// let set: WideBloomSet<u32> = (0..1000).collect();
+/// assert_eq!(set.len(), 1000);
+/// assert!(set.contains(999));
+/// assert!(!set.contains(1000));
+/// ```
+pub struct WideBloomSet<T, S = BuildBloomHasher> {
+    repr: repr::Repr<T>,
+    hash_builder: S,
+}
+
+impl<T> Default for WideBloomSet<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_hasher(BuildBloomHasher)
+    }
+}
+
+impl<T> WideBloomSet<T> {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_capacity(cap: usize) -> Self {
+        Self::with_capacity_and_hasher(cap, BuildBloomHasher)
+    }
+}
+
+impl<T, S> WideBloomSet<T, S> {
+    /// Creates an empty set which will use `hash_builder` to hash its elements.
+    #[inline]
+    #[must_use]
+    pub const fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            repr: repr::Repr::new(NonNull::dangling().as_ptr(), 0, 0),
+            hash_builder,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_capacity_and_hasher(cap: usize, hash_builder: S) -> Self {
+        let mut set = Self::with_hasher(hash_builder);
+        set.with_vec(|vec| vec.reserve_exact(cap));
+        set
+    }
+
+    /// Returns a reference to the set's `BuildHasher`.
+    #[inline]
+    #[must_use]
+    pub const fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.repr.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.repr.capacity()
+    }
+
+    #[inline]
+    #[must_use]
+    // Untagging the pointer can't be done in a const fn
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.repr.ptr(), self.len()) }
+    }
+
+    /// Runs `f` on the elements as a `Vec`, then takes back whatever allocation it leaves.
+    ///
+    /// The length is zeroed while `f` runs, so if it panics the elements are leaked rather than
+    /// dropped twice.
+    fn with_vec<R>(&mut self, f: impl FnOnce(&mut Vec<T>) -> R) -> R {
+        let length = self.repr.len();
+        self.repr.set_len(0);
+        let mut vec = unsafe {
+            // Use ManuallyDrop to ensure that the Vec is never dropped
+            ManuallyDrop::new(Vec::from_raw_parts(
+                self.repr.ptr(),
+                length,
+                self.repr.capacity(),
+            ))
+        };
+        let result = f(&mut vec);
+        self.repr.set_ptr(vec.as_mut_ptr());
+        self.repr.set_capacity(vec.capacity());
+        self.repr.set_len(vec.len());
+        result
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.with_vec(Vec::clear);
+        self.repr.set_filter(0);
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T>> WideBloomSet<T, S> {
+    #[inline]
+    pub fn insert(&mut self, item: T) {
+        let mask = filter_mask(self.hash_builder.hash_element(&item));
+        let filter = self.repr.filter();
+        if filter & mask == mask && self.as_slice().contains(&item) {
+            return;
+        }
+        self.with_vec(|vec| vec.push(item));
+        self.repr.set_filter(filter | mask);
+    }
+
+    /// Removes `item` from the set, returning whether it was present. The remaining elements
+    /// keep their order, and the filter keeps `item`'s bit.
+    #[inline]
+    pub fn remove<B: core::borrow::Borrow<T>>(&mut self, item: B) -> bool {
+        let item = item.borrow();
+        if !self.maybe_contains(item) {
+            return false;
+        }
+        let Some(index) = self.as_slice().iter().position(|it| it == item) else {
+            return false;
+        };
+        drop(self.with_vec(|vec| vec.remove(index)));
+        true
+    }
+
+    fn maybe_contains(&self, item: &T) -> bool {
+        let mask = filter_mask(self.hash_builder.hash_element(item));
+        self.repr.filter() & mask == mask
+    }
+
+    #[inline]
+    pub fn contains<B: core::borrow::Borrow<T>>(&self, item: B) -> bool {
+        let item = item.borrow();
+        self.maybe_contains(item) && self.as_slice().iter().any(|it| it == item)
+    }
+}
+
+impl<T: PartialEq, S: ElementHasher<T> + Default> FromIterator<T> for WideBloomSet<T, S> {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::with_hasher(S::default());
+        for item in iter {
+            set.insert(item);
+        }
+        set
+    }
+}
+
+impl<T, S> Drop for WideBloomSet<T, S> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { Vec::from_raw_parts(self.repr.ptr(), self.len(), self.capacity()) };
+    }
+}
+
+// The set owns its elements like a Vec does, so it can cross threads under the same conditions
+unsafe impl<T: Send, S: Send> Send for WideBloomSet<T, S> {}
+unsafe impl<T: Sync, S: Sync> Sync for WideBloomSet<T, S> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BloomSet;
+    use std::rc::Rc;
+
+    // Keep the tests quick under Miri
+    const N: u32 = if cfg!(miri) { 40 } else { 255 };
+
+    #[test]
+    fn same_size_as_vec() {
+        assert_eq!(
+            std::mem::size_of::<WideBloomSet<u8>>(),
+            std::mem::size_of::<Vec<u8>>()
+        );
+    }
+
+    #[test]
+    fn agrees_with_bloomset() {
+        let mut wide = WideBloomSet::new();
+        let mut narrow = BloomSet::new();
+        for i in 0..N {
+            let elem = i.wrapping_mul(0x9E37_79B9) % 300;
+            wide.insert(elem);
+            narrow.insert(elem);
+            assert_eq!(wide.as_slice(), narrow.as_slice());
+            if i % 3 == 0 {
+                let elem = i.wrapping_mul(7) % 300;
+                assert_eq!(wide.remove(elem), narrow.remove(elem));
+            }
+        }
+        for probe in 0..400 {
+            assert_eq!(wide.contains(probe), narrow.contains(probe), "{probe}");
+        }
+        assert_eq!(wide.repr.filter() == 0, cfg!(feature = "no-filter"));
+        wide.clear();
+        assert!(wide.is_empty() && wide.repr.filter() == 0);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "no-filter", ignore = "checks the filter bits")]
+    fn past_255_elements() {
+        let n = if cfg!(miri) { 300 } else { 5000 };
+        let mut set = WideBloomSet::with_capacity(10);
+        for i in 0..n {
+            set.insert(i);
+        }
+        set.insert(0);
+        assert_eq!(set.len(), n);
+        assert!(set.capacity() >= n);
+        assert!((0..n).all(|i| set.contains(i)));
+        assert!(!set.contains(n));
+        assert_eq!(set.repr.filter(), u16::MAX);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "no-filter", ignore = "checks the filter bits")]
+    fn filter_survives_reallocation() {
+        let mut set = WideBloomSet::new();
+        set.insert("a".to_string());
+        let filter = set.repr.filter();
+        assert_eq!(filter.count_ones(), 1);
+        for i in 0..100 {
+            set.insert(i.to_string());
+            assert_eq!(set.repr.filter() & filter, filter);
+        }
+        assert!(set.contains("a".to_string()));
+        assert_eq!(set.as_slice()[0], "a");
+    }
+
+    #[test]
+    fn zero_sized_elements() {
+        let mut set = WideBloomSet::new();
+        set.insert(());
+        set.insert(());
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.capacity(), usize::MAX);
+        assert!(set.contains(()));
+        assert!(set.remove(()));
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn wide_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<WideBloomSet<u32>>();
+        assert_send_sync::<WideBloomSet<String>>();
+    }
+
+    #[test]
+    fn drops_every_element() {
+        let counted = Rc::new(());
+        let mut set = WideBloomSet::new();
+        for i in 0..N {
+            set.insert((i, Rc::clone(&counted)));
+        }
+        assert!(set.remove((3, Rc::clone(&counted))));
+        assert_eq!(Rc::strong_count(&counted), N as usize);
+        set.clear();
+        assert_eq!(Rc::strong_count(&counted), 1);
+        set.insert((0, Rc::clone(&counted)));
+        drop(set);
+        assert_eq!(Rc::strong_count(&counted), 1);
+    }
+}
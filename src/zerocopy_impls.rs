@@ -0,0 +1,140 @@
+use crate::{BloomSet, CapacityError, ElementHasher, Packing, PodCastError};
+use zerocopy::{ConvertError, FromBytes, Immutable, IntoBytes, KnownLayout};
+
+impl<T: IntoBytes + Immutable, S, const K: usize, P: Packing> BloomSet<T, S, K, P> {
+    /// The elements as their bytes, in the set's order: exactly the `len()` initialized ones,
+    /// none of the spare capacity. The filter and seed aren't part of it, and are rebuilt by
+    /// [`try_from_bytes`](BloomSet::try_from_bytes).
+    ///
+    /// ```
+    /// use bloomset::BloomSet;
+    ///
+    /// let set: BloomSet<u32> = [7, 1, 7].iter().copied().collect();
+    /// assert_eq!(set.as_bytes(), [7_u32.to_ne_bytes(), 1_u32.to_ne_bytes()].concat());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        self.as_slice().as_bytes()
+    }
+}
+
+impl<T, S, const K: usize, P> BloomSet<T, S, K, P>
+where
+    T: FromBytes + Immutable + KnownLayout + Copy + PartialEq,
+    S: ElementHasher<T> + Default,
+    P: Packing,
+{
+    /// Builds a set from a copy of `elements`, dropping duplicates and rebuilding the filter
+    /// like collecting does.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CapacityError`] if there are more unique elements than the set can hold.
+    #[inline]
+    pub fn from_pod_slice(elements: &[T]) -> Result<Self, CapacityError> {
+        Self::try_from_iter_with_hasher(elements.iter().copied(), S::default())
+    }
+
+    /// Builds a set from bytes written by [`as_bytes`](BloomSet::as_bytes), or any other run of
+    /// elements' bytes, checking that they can be viewed as elements first. The elements are
+    /// copied out, so the bytes can be reused as soon as this returns.
+    ///
+    /// ```
+    /// use bloomset::{BloomSet, PodCastError};
+    ///
+    /// let set: BloomSet<[u8; 8]> = [*b"bloomset", *b"zerocopy"].iter().copied().collect();
+    /// let back: BloomSet<[u8; 8]> = BloomSet::try_from_bytes(set.as_bytes()).unwrap();
+    /// assert_eq!(back.as_slice(), set.as_slice());
+    /// assert_eq!(
+    ///     BloomSet::<[u8; 8]>::try_from_bytes(&set.as_bytes()[1..]).err(),
+    ///     Some(PodCastError::Length { len: 15, size: 8 })
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PodCastError::Misaligned`] if `bytes` doesn't start at a multiple of `T`'s
+    /// alignment, [`PodCastError::Length`] if it isn't a whole number of elements, and
+    /// [`PodCastError::Capacity`] if it holds more unique elements than the set can.
+    #[inline]
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, PodCastError> {
+        let elements = <[T]>::ref_from_bytes(bytes).map_err(|err| match err {
+            ConvertError::Alignment(_) => PodCastError::Misaligned {
+                align: core::mem::align_of::<T>(),
+            },
+            ConvertError::Size(_) => PodCastError::Length {
+                len: bytes.len(),
+                size: core::mem::size_of::<T>(),
+            },
+            ConvertError::Validity(never) => match never {},
+        })?;
+        Self::from_pod_slice(elements).map_err(PodCastError::Capacity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BloomSet, BloomSet16, PodCastError};
+    use zerocopy::IntoBytes;
+
+    #[test]
+    fn round_trip() {
+        let set: BloomSet<u32> = (0..255).map(|i| i * 7919).collect();
+        assert_eq!(set.as_bytes().len(), 255 * 4);
+        let back: BloomSet<u32> = BloomSet::try_from_bytes(set.as_bytes()).unwrap();
+        assert_eq!(back.as_slice(), set.as_slice());
+        let mut rebuilt = BloomSet::with_seed(back.seed());
+        rebuilt.extend(set.as_slice().iter().copied());
+        assert_eq!(back.filter_bits(), rebuilt.filter_bits());
+        assert!(back.contains(7919));
+
+        let words: BloomSet<[u8; 8]> = [*b"abcdefgh", *b"01234567"].iter().copied().collect();
+        let back: BloomSet<[u8; 8]> = BloomSet::try_from_bytes(words.as_bytes()).unwrap();
+        assert_eq!(back.as_slice(), words.as_slice());
+        assert!(back.contains(*b"01234567"));
+
+        let empty: BloomSet<u32> = BloomSet::try_from_bytes([0_u32; 0].as_bytes()).unwrap();
+        assert!(empty.is_empty() && BloomSet::<u32>::new().as_bytes().is_empty());
+        let wide: BloomSet16<u16> =
+            BloomSet16::try_from_bytes((0..1000).collect::<Vec<u16>>().as_bytes()).unwrap();
+        assert_eq!(wide.len(), 1000);
+    }
+
+    #[test]
+    fn only_the_initialized_elements() {
+        let mut set: BloomSet<u32> = BloomSet::with_capacity(100);
+        set.extend([5, 6]);
+        assert_eq!(set.as_bytes(), [5_u32, 6].as_bytes());
+    }
+
+    #[test]
+    fn dedups_and_checks_the_bytes() {
+        let elements = [3_u32, 9, 3, 3, 1];
+        let set: BloomSet<u32> = BloomSet::from_pod_slice(&elements).unwrap();
+        assert_eq!(set.as_slice(), [3, 9, 1]);
+        assert_eq!(
+            BloomSet::<u32>::try_from_bytes(elements.as_bytes())
+                .unwrap()
+                .as_slice(),
+            [3, 9, 1]
+        );
+
+        // A u32 buffer is aligned for u32s, so one byte in isn't
+        let bytes = &elements.as_bytes()[1..9];
+        assert_eq!(
+            BloomSet::<u32>::try_from_bytes(bytes).err(),
+            Some(PodCastError::Misaligned { align: 4 })
+        );
+        assert_eq!(
+            BloomSet::<u32>::try_from_bytes(&elements.as_bytes()[..6]).err(),
+            Some(PodCastError::Length { len: 6, size: 4 })
+        );
+        let too_many: Vec<u32> = (0..256).collect();
+        let err = BloomSet::<u32>::try_from_bytes(too_many.as_bytes()).err();
+        assert_eq!(
+            err.unwrap().to_string(),
+            "a BloomSet cannot hold more than 255 elements (256 requested)"
+        );
+    }
+}
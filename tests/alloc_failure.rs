@@ -0,0 +1,78 @@
+//! Allocation failures, from an allocator that can be told to refuse the next allocations the
+//! current thread makes.
+
+use bloomset::{BloomSet, TryReserveError};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+struct Refusing;
+
+thread_local! {
+    static REFUSE: Cell<bool> = const { Cell::new(false) };
+}
+
+unsafe impl GlobalAlloc for Refusing {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if REFUSE.with(Cell::get) {
+            return std::ptr::null_mut();
+        }
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Refusing = Refusing;
+
+/// Runs `f` with every allocation on this thread failing.
+fn refusing<R>(f: impl FnOnce() -> R) -> R {
+    REFUSE.with(|refuse| refuse.set(true));
+    let result = f();
+    REFUSE.with(|refuse| refuse.set(false));
+    result
+}
+
+#[test]
+fn try_clone() {
+    let set: BloomSet<u64> = (0..50).collect();
+    let clone = set.try_clone().unwrap();
+    assert_eq!(clone.as_slice(), set.as_slice());
+    assert_eq!(clone.seed(), set.seed());
+
+    let err = refusing(|| set.try_clone()).err().unwrap();
+    assert_eq!(
+        err,
+        TryReserveError::AllocFailed {
+            layout: Some(Layout::array::<u64>(50).unwrap())
+        }
+    );
+    // Nothing to allocate for an empty set
+    let empty = BloomSet::<u64>::with_capacity_and_seed(8, 3);
+    assert_eq!(refusing(|| empty.try_clone()).unwrap().seed(), 3);
+}
+
+#[test]
+fn try_reserve() {
+    let mut set: BloomSet<u64> = (0..4).collect();
+    set.shrink_to_fit();
+    assert!(matches!(
+        refusing(|| set.try_reserve_exact(10)),
+        Err(TryReserveError::AllocFailed { layout: Some(_) })
+    ));
+    assert_eq!((set.len(), set.capacity()), (4, 4));
+    assert!(set.contains(3));
+}
+
+#[test]
+fn empty_sets_share_without_allocating() {
+    let empty: BloomSet<String> = BloomSet::new();
+    assert!(refusing(|| Arc::<[String]>::from(&empty)).is_empty());
+    assert!(refusing(|| Arc::<[String]>::from(BloomSet::new())).is_empty());
+    // An `Rc<[T]>` allocates its counts even when empty
+    assert!(Rc::<[String]>::from(empty).is_empty());
+}
@@ -0,0 +1,124 @@
+//! Allocation budgets, counted by a global allocator: how often building, clearing and growing
+//! a set allocates, and how much it holds at once. Changes to the growth policy or to the
+//! bulk paths that add allocations fail here rather than going unnoticed.
+
+mod common;
+
+use bloomset::{BloomSet, BloomSet16, BuildBloomHasher};
+use common::{counting, Allocations, Counting};
+use std::convert::TryFrom;
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// `⌈log₂ n⌉`.
+fn log2_ceil(n: usize) -> usize {
+    usize::try_from(usize::BITS - n.saturating_sub(1).leading_zeros()).unwrap()
+}
+
+#[test]
+fn with_capacity_allocates_once() {
+    for &n in &[1, 4, 50, 255] {
+        let keys: Vec<u64> = (0..n as u64).map(|i| i * 7919).collect();
+        let (set, counts) = counting(|| {
+            let mut set = BloomSet::<u64>::with_capacity(n);
+            for &key in &keys {
+                set.insert(key);
+            }
+            set
+        });
+        let one = Allocations {
+            allocations: 1,
+            reallocations: 0,
+            peak_bytes: n * 8,
+        };
+        assert_eq!(counts, one, "{n} inserts");
+        assert_eq!(set.len(), n);
+
+        let (set, counts) = counting(|| {
+            let mut set = BloomSet::<u64>::with_capacity(n);
+            set.extend(keys.iter().copied());
+            set
+        });
+        assert_eq!(counts, one, "extending by {n}");
+        assert_eq!(set.len(), n);
+    }
+
+    // Past the bulk threshold, extending hashes the whole batch and sorts the hashes to find
+    // duplicates, which takes three scratch buffers, each allocated once. The elements, which
+    // own allocations of their own, are moved in and not copied
+    let names: Vec<String> = (0..100).map(|i| i.to_string()).collect();
+    let (set, counts) = counting(|| {
+        let mut set = BloomSet::<String>::with_capacity(names.len());
+        set.extend(names);
+        set
+    });
+    assert_eq!((counts.allocations, counts.reallocations), (1 + 3, 0));
+    assert_eq!(set.len(), 100);
+}
+
+#[test]
+fn clear_keeps_the_buffer() {
+    let mut set: BloomSet<u64> = (0..200).collect();
+    let capacity = set.capacity();
+    let ((), counts) = counting(|| set.clear());
+    assert_eq!(counts, Allocations::default());
+    assert_eq!(set.capacity(), capacity);
+
+    // Filling it up again reuses the buffer
+    let ((), counts) = counting(|| set.extend(1000..1200));
+    assert_eq!(counts, Allocations::default());
+    assert_eq!(set.len(), 200);
+
+    let mut names: BloomSet<String> = (0..50).map(|i| i.to_string()).collect();
+    let ((), counts) = counting(|| names.clear());
+    assert_eq!((counts.allocations, counts.reallocations), (0, 0));
+}
+
+#[test]
+fn growth_reallocates_logarithmically() {
+    for &n in &[1, 2, 5, 100, 128, 129, 255] {
+        let (set, counts) = counting(|| {
+            let mut set = BloomSet::<u64>::new();
+            for i in 0..n as u64 {
+                set.insert(i);
+            }
+            set
+        });
+        assert_eq!(counts.allocations, 1, "{n}");
+        assert!(counts.reallocations <= log2_ceil(n), "{}: {:?}", n, counts);
+        assert_eq!(counts.peak_bytes, set.capacity() * 8);
+    }
+    // 4, 8, 16, 32, 64, 128 and 255
+    let (_, counts) = counting(|| (0..255).collect::<BloomSet<u64>>());
+    assert!(
+        counts.allocations + counts.reallocations <= 7,
+        "{:?}",
+        counts
+    );
+
+    for &n in &[256, 1000, 5000] {
+        let (set, counts) = counting(|| {
+            let mut set = BloomSet16::<u32>::with_hasher(BuildBloomHasher);
+            for i in 0..n as u32 {
+                set.insert(i);
+            }
+            set
+        });
+        assert_eq!(counts.allocations, 1, "{n}");
+        assert!(counts.reallocations <= log2_ceil(n), "{}: {:?}", n, counts);
+        assert_eq!(set.len(), n);
+    }
+}
+
+#[test]
+fn nothing_for_empty_sets() {
+    let (set, counts) = counting(|| {
+        let mut set = BloomSet::<u64>::new();
+        set.extend(std::iter::empty());
+        set.shrink_to_fit();
+        set
+    });
+    assert_eq!(counts, Allocations::default());
+    assert_eq!(set.capacity(), 0);
+}
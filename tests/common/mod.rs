@@ -0,0 +1,176 @@
+//! Helpers shared by the integration tests.
+
+// Each test file uses only some of these
+#![allow(dead_code)]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::{Cell, RefCell};
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+/// Hands out [`DropCounter`]s and records every drop of them, so a test can check that each
+/// one was dropped exactly once by the end, never twice, and which are still alive.
+#[derive(Clone, Default)]
+pub struct Tracker {
+    /// How many times each counter made so far has been dropped, by id.
+    drops: Rc<RefCell<Vec<u32>>>,
+}
+
+impl Tracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A new counter for `value`. Counters compare and hash by value alone, so two counters
+    /// for the same value are duplicates in a set, but are dropped and counted separately.
+    pub fn make(&self, value: u32) -> DropCounter {
+        let mut drops = self.drops.borrow_mut();
+        drops.push(0);
+        DropCounter {
+            value,
+            id: drops.len() - 1,
+            drops: Rc::clone(&self.drops),
+        }
+    }
+
+    /// How many counters have been made and not yet dropped.
+    pub fn live(&self) -> usize {
+        self.drops
+            .borrow()
+            .iter()
+            .filter(|&&drops| drops == 0)
+            .count()
+    }
+
+    /// How many times the counter with `id` has been dropped.
+    pub fn drops(&self, id: usize) -> u32 {
+        self.drops.borrow()[id]
+    }
+
+    /// Panics unless every counter has been dropped exactly once.
+    pub fn assert_all_dropped(&self) {
+        let drops = self.drops.borrow();
+        let alive: Vec<usize> = (0..drops.len()).filter(|&id| drops[id] == 0).collect();
+        assert!(alive.is_empty(), "never dropped: {:?}", alive);
+    }
+}
+
+/// An element with drop glue, which reports its drops to the [`Tracker`] that made it and
+/// panics if it is dropped twice.
+#[derive(Debug)]
+pub struct DropCounter {
+    pub value: u32,
+    pub id: usize,
+    drops: Rc<RefCell<Vec<u32>>>,
+}
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        let mut drops = self.drops.borrow_mut();
+        drops[self.id] += 1;
+        assert_eq!(drops[self.id], 1, "counter {} dropped twice", self.id);
+    }
+}
+
+impl PartialEq for DropCounter {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl Eq for DropCounter {}
+
+impl PartialOrd for DropCounter {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DropCounter {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
+impl Hash for DropCounter {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.hash(state);
+    }
+}
+
+/// A global allocator that counts what each thread allocates, for tests that hold the set to an
+/// allocation budget. A test file installs it with
+/// `#[global_allocator] static ALLOCATOR: Counting = Counting;` and measures with [`counting`].
+pub struct Counting;
+
+/// What the current thread allocated while [`counting`] ran a closure.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Allocations {
+    /// Fresh allocations, not counting reallocations.
+    pub allocations: usize,
+    pub reallocations: usize,
+    /// The most bytes the closure had allocated and not yet freed at any one point. A
+    /// reallocation counts as its new size, whether or not it moved.
+    pub peak_bytes: usize,
+}
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    static REALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    /// Bytes allocated on this thread and not freed, and the most there have been since
+    /// `counting` last reset it.
+    static LIVE_BYTES: Cell<usize> = const { Cell::new(0) };
+    static PEAK_BYTES: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Adds `added` to the live bytes and takes away `freed`, keeping track of the peak. Memory
+/// freed on another thread than it was allocated on can take the count below 0, which is
+/// clamped, so peaks are only meaningful for closures that stay on one thread.
+fn track(added: usize, freed: usize) {
+    let live = LIVE_BYTES.with(|live| {
+        live.set((live.get() + added).saturating_sub(freed));
+        live.get()
+    });
+    PEAK_BYTES.with(|peak| peak.set(peak.get().max(live)));
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            ALLOCATIONS.with(|n| n.set(n.get() + 1));
+            track(layout.size(), 0);
+        }
+        ptr
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = System.realloc(ptr, layout, new_size);
+        if !new.is_null() {
+            REALLOCATIONS.with(|n| n.set(n.get() + 1));
+            track(new_size, layout.size());
+        }
+        new
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        track(0, layout.size());
+    }
+}
+
+/// Runs `f`, returning what it did and what it allocated on this thread. Only meaningful with
+/// [`Counting`] installed as the global allocator.
+pub fn counting<R>(f: impl FnOnce() -> R) -> (R, Allocations) {
+    let allocations = ALLOCATIONS.with(Cell::get);
+    let reallocations = REALLOCATIONS.with(Cell::get);
+    let live = LIVE_BYTES.with(Cell::get);
+    PEAK_BYTES.with(|peak| peak.set(live));
+    let result = f();
+    let counts = Allocations {
+        allocations: ALLOCATIONS.with(Cell::get) - allocations,
+        reallocations: REALLOCATIONS.with(Cell::get) - reallocations,
+        peak_bytes: PEAK_BYTES.with(Cell::get) - live,
+    };
+    (result, counts)
+}
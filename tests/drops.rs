@@ -0,0 +1,189 @@
+//! Sets of elements with drop glue, checking that every element is dropped exactly once: none
+//! leak, none are dropped twice, and the ones a set rejects are dropped right away.
+//!
+//! These are cheap enough to run under Miri, which also checks the raw-pointer code they go
+//! through for undefined behavior: `cargo +nightly miri test --test drops`.
+
+mod common;
+
+use bloomset::{BloomSet, SmallBloomSet};
+use common::Tracker;
+
+#[test]
+fn building_mutating_and_dropping() {
+    let tracker = Tracker::new();
+    let mut set = BloomSet::new();
+    for value in 0..40 {
+        set.insert(tracker.make(value));
+    }
+    assert!(set.remove(tracker.make(7)));
+    assert!(!set.remove(tracker.make(100)));
+    set.retain(|counter| counter.value % 3 != 0);
+    set.retain_unordered(|counter| counter.value % 5 != 0);
+    assert!(set.promote(tracker.make(22)));
+    assert!(set.contains_promoting(tracker.make(38)));
+    set.sort();
+    set.shrink_to_fit();
+    // The removed elements and every probe are gone already
+    assert_eq!(tracker.live(), set.len());
+
+    set.clear();
+    assert_eq!(tracker.live(), 0);
+    for value in 0..10 {
+        set.insert(tracker.make(value));
+    }
+    assert_eq!(tracker.live(), 10);
+    drop(set);
+    tracker.assert_all_dropped();
+}
+
+#[test]
+fn duplicates_drop_the_incoming_value_once() {
+    let tracker = Tracker::new();
+    let mut set = BloomSet::new();
+    let original = tracker.make(1);
+    let original_id = original.id;
+    set.insert(original);
+    let duplicate = tracker.make(1);
+    let duplicate_id = duplicate.id;
+    set.insert(duplicate);
+    assert_eq!(
+        (tracker.drops(original_id), tracker.drops(duplicate_id)),
+        (0, 1)
+    );
+    assert_eq!(set.as_slice()[0].id, original_id);
+
+    // Duplicates in a bulk insert, both of present elements and of each other
+    set.extend((0..6).map(|value| tracker.make(value % 3)));
+    assert_eq!(set.len(), 3);
+    assert_eq!(tracker.live(), 3);
+
+    drop(set);
+    tracker.assert_all_dropped();
+}
+
+#[test]
+fn growth_moves_without_dropping() {
+    let tracker = Tracker::new();
+    let mut set = BloomSet::with_capacity(1);
+    for value in 0..200 {
+        set.insert(tracker.make(value));
+        // Every reallocation so far moved the elements, and none of them was dropped
+        assert_eq!(tracker.live(), set.len());
+    }
+    set.shrink_to(50);
+    set.reserve_exact(55);
+    assert_eq!(tracker.live(), 200);
+    assert!(set
+        .as_slice()
+        .iter()
+        .map(|counter| counter.value)
+        .eq(0..200));
+    drop(set);
+    tracker.assert_all_dropped();
+}
+
+#[test]
+fn failed_collect_drops_everything() {
+    let tracker = Tracker::new();
+    let result: Result<BloomSet<_>, _> =
+        BloomSet::try_from_iter((0..300).map(|value| tracker.make(value)));
+    assert_eq!(result.err().map(|err| err.limit()), Some(255));
+    tracker.assert_all_dropped();
+}
+
+#[test]
+fn raw_parts_round_trip() {
+    let tracker = Tracker::new();
+    let set: BloomSet<_> = (0..20).map(|value| tracker.make(value)).collect();
+    let (ptr, length, capacity) = set.into_raw_parts();
+    assert_eq!(tracker.live(), 20);
+    let set: BloomSet<_> = unsafe { BloomSet::from_raw_parts(ptr, length, capacity) };
+    assert_eq!(set.len(), 20);
+    drop(set);
+    tracker.assert_all_dropped();
+}
+
+#[test]
+fn small_set() {
+    let tracker = Tracker::new();
+    let mut set = SmallBloomSet::new();
+    // A `DropCounter` is too big to be stored inline, so this spills straight away
+    for value in 0..30 {
+        set.insert(tracker.make(value));
+    }
+    set.insert(tracker.make(3));
+    assert!(set.remove(tracker.make(4)));
+    assert_eq!(tracker.live(), 29);
+    set.clear();
+    assert_eq!(tracker.live(), 0);
+    set.insert(tracker.make(0));
+    drop(set);
+    tracker.assert_all_dropped();
+}
+
+#[test]
+fn drain_ranges() {
+    let tracker = Tracker::new();
+    let mut set: BloomSet<_> = (0..20).map(|value| tracker.make(value)).collect();
+    let prefix: Vec<_> = set.drain(..5).collect();
+    assert_eq!((prefix.len(), tracker.live()), (5, 20));
+    drop(prefix);
+    assert_eq!(tracker.live(), 15);
+
+    // Suffix and middle, dropped without yielding everything
+    let mut suffix = set.drain(10..);
+    assert_eq!(suffix.next().map(|counter| counter.value), Some(15));
+    drop(suffix);
+    assert_eq!((set.len(), tracker.live()), (10, 10));
+    set.drain(3..7);
+    assert_eq!((set.len(), tracker.live()), (6, 6));
+    assert!(set
+        .as_slice()
+        .iter()
+        .map(|counter| counter.value)
+        .eq([5, 6, 7, 12, 13, 14]));
+
+    set.drain(2..2);
+    assert_eq!(tracker.live(), 6);
+    assert!(set.contains(tracker.make(12)) && !set.contains(tracker.make(9)));
+    drop(set);
+    tracker.assert_all_dropped();
+}
+
+#[test]
+fn absorb_moves_new_elements_and_drops_duplicates() {
+    let tracker = Tracker::new();
+    let mut set: BloomSet<_> = (0..10).map(|value| tracker.make(value)).collect();
+    let mut batch: Vec<_> = (5..15).map(|value| tracker.make(value)).collect();
+    let ids: Vec<usize> = batch.iter().map(|counter| counter.id).collect();
+    set.absorb(&mut batch).unwrap();
+    assert!(batch.is_empty());
+    // The five duplicates went, and the five new ones moved into the set
+    assert_eq!(tracker.live(), 15);
+    assert!(ids[..5].iter().all(|&id| tracker.drops(id) == 1));
+    assert!(ids[5..].iter().all(|&id| tracker.drops(id) == 0));
+    assert!(set.as_slice()[10..]
+        .iter()
+        .map(|c| c.id)
+        .eq(ids[5..].iter().copied()));
+
+    drop(set);
+    assert_eq!(tracker.live(), 0);
+
+    // Failing past the limit leaves the rest in the batch, each still alive once
+    let mut set: BloomSet<_> = (0..254).map(|value| tracker.make(value)).collect();
+    let mut batch: Vec<_> = [0, 500, 1, 501, 502]
+        .iter()
+        .map(|&value| tracker.make(value))
+        .collect();
+    assert!(set.absorb(&mut batch).is_err());
+    assert_eq!(
+        batch.iter().map(|c| c.value).collect::<Vec<_>>(),
+        [501, 502]
+    );
+    assert_eq!(tracker.live(), 255 + 2);
+    drop(batch);
+    drop(set);
+    tracker.assert_all_dropped();
+}
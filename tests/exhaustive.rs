@@ -0,0 +1,223 @@
+//! Exhaustive model tests: every sequence of operations up to some length, over a universe of
+//! four values, run on a `BloomSet` and on a `Vec`-based model, checking after every step that
+//! they answer the same and that the set's invariants hold.
+//!
+//! Operations are declared once, in `operations!`, which also lists them for enumeration, so a
+//! new one only needs a line there and arms in `Model::apply` and `apply`, which the compiler
+//! asks for. The length of the sequences shrinks as operations are added, to keep the number of
+//! cases fixed.
+
+use bloomset::{BloomSet, BuildBloomHasher, Packed16, Packed8, Packing};
+
+/// The values every operation that takes one is applied to.
+const UNIVERSE: [u8; 4] = [0, 1, 2, 3];
+
+/// About how many sequences a test may run.
+const BUDGET: usize = 1_000_000;
+
+macro_rules! operations {
+    (with a value: $($unary:ident),*; without: $($nullary:ident),*) => {
+        #[derive(Clone, Copy, Debug)]
+        enum Op {
+            $($unary(u8),)*
+            $($nullary,)*
+        }
+
+        /// Every operation, once for each value it can take.
+        fn all_operations() -> Vec<Op> {
+            let mut ops = Vec::new();
+            for &value in &UNIVERSE {
+                ops.extend([$(Op::$unary(value)),*].iter().copied());
+            }
+            ops.extend([$(Op::$nullary),*].iter().copied());
+            ops
+        }
+    };
+}
+
+operations! {
+    with a value: Insert, Contains, Remove, Retain, RetainUnordered, Promote, ContainsPromoting;
+    without: Clear, Sort, ShrinkToFit
+}
+
+/// The operations the first version of this test covered, which still run to the full length.
+fn core_operations() -> Vec<Op> {
+    let mut ops = Vec::new();
+    for &value in &UNIVERSE {
+        ops.push(Op::Insert(value));
+        ops.push(Op::Contains(value));
+    }
+    ops.push(Op::Clear);
+    ops
+}
+
+/// What a set should hold, and whether it should be marked sorted.
+#[derive(Default)]
+struct Model {
+    elements: Vec<u8>,
+    sorted: bool,
+}
+
+impl Model {
+    /// Applies `op`, returning its answer if it has one.
+    fn apply(&mut self, op: Op) -> Option<bool> {
+        let position = |elements: &[u8], value| elements.iter().position(|&it| it == value);
+        match op {
+            Op::Insert(value) => {
+                if position(&self.elements, value).is_none() {
+                    self.elements.push(value);
+                    self.sorted = false;
+                }
+                None
+            }
+            Op::Contains(value) => Some(self.elements.contains(&value)),
+            Op::Remove(value) => Some(
+                position(&self.elements, value)
+                    .map(|index| self.elements.remove(index))
+                    .is_some(),
+            ),
+            Op::Retain(value) => {
+                self.elements.retain(|&it| it != value);
+                None
+            }
+            Op::RetainUnordered(value) => {
+                // The last element kept fills the hole the removed one leaves
+                if let Some(index) = position(&self.elements, value) {
+                    self.elements.swap_remove(index);
+                    self.sorted = false;
+                }
+                None
+            }
+            Op::Promote(value) => Some(position(&self.elements, value).is_some_and(|index| {
+                if index > 0 {
+                    self.elements[..=index].rotate_right(1);
+                    self.sorted = false;
+                }
+                true
+            })),
+            Op::ContainsPromoting(value) => {
+                Some(position(&self.elements, value).is_some_and(|index| {
+                    if index > 0 {
+                        self.elements.swap(index - 1, index);
+                        self.sorted = false;
+                    }
+                    true
+                }))
+            }
+            Op::Clear => {
+                self.elements.clear();
+                self.sorted = false;
+                None
+            }
+            Op::Sort => {
+                self.elements.sort_unstable();
+                self.sorted = true;
+                None
+            }
+            Op::ShrinkToFit => None,
+        }
+    }
+}
+
+fn apply<P: Packing>(set: &mut BloomSet<u8, BuildBloomHasher, 1, P>, op: Op) -> Option<bool> {
+    match op {
+        Op::Insert(value) => {
+            set.insert(value);
+            None
+        }
+        Op::Contains(value) => Some(set.contains(value)),
+        Op::Remove(value) => Some(set.remove(value)),
+        Op::Retain(value) => {
+            set.retain(|&it| it != value);
+            None
+        }
+        Op::RetainUnordered(value) => {
+            set.retain_unordered(|&it| it != value);
+            None
+        }
+        Op::Promote(value) => Some(set.promote(value)),
+        Op::ContainsPromoting(value) => Some(set.contains_promoting(value)),
+        Op::Clear => {
+            set.clear();
+            None
+        }
+        Op::Sort => {
+            set.sort();
+            None
+        }
+        Op::ShrinkToFit => {
+            set.shrink_to_fit();
+            None
+        }
+    }
+}
+
+/// The longest sequences of `ops` that fit in the budget, but no longer than `max`.
+fn depth(ops: usize, max: u32) -> u32 {
+    (1..=max)
+        .take_while(|&depth| ops.pow(depth) <= BUDGET)
+        .last()
+        .unwrap_or(1)
+}
+
+/// Runs every sequence of `depth` operations from `ops`, checking the set against the model
+/// after every step. Shorter sequences are the prefixes of these, so they are covered too.
+fn exhaustive<P: Packing>(ops: &[Op], depth: u32) {
+    let mut indices = vec![0; depth as usize];
+    loop {
+        let mut set = BloomSet::<u8, _, 1, P>::with_hasher(BuildBloomHasher);
+        let mut model = Model::default();
+        for (step, &index) in indices.iter().enumerate() {
+            let op = ops[index];
+            // Only built for a failure message
+            let sequence = || -> Vec<Op> { indices[..=step].iter().map(|&i| ops[i]).collect() };
+            assert_eq!(
+                apply(&mut set, op),
+                model.apply(op),
+                "after {:?}",
+                sequence()
+            );
+            assert_eq!(set.as_slice(), model.elements, "after {:?}", sequence());
+            assert_eq!(set.is_sorted(), model.sorted, "after {:?}", sequence());
+            assert_eq!(set.len(), model.elements.len());
+            assert_eq!(set.is_empty(), model.elements.is_empty());
+            assert!(set.len() <= set.capacity() && set.capacity() <= P::MAX_CAPACITY);
+            for &value in &UNIVERSE {
+                assert_eq!(
+                    set.contains(value),
+                    model.elements.contains(&value),
+                    "contains({}) after {:?}",
+                    value,
+                    sequence()
+                );
+            }
+        }
+        // Step to the next sequence, like an odometer
+        let Some(last) = indices.iter().rposition(|&index| index + 1 < ops.len()) else {
+            return;
+        };
+        indices[last] += 1;
+        for index in &mut indices[last + 1..] {
+            *index = 0;
+        }
+    }
+}
+
+#[test]
+fn core_operations_to_depth_6() {
+    let ops = core_operations();
+    assert_eq!(depth(ops.len(), 6), 6);
+    exhaustive::<Packed8>(&ops, 6);
+}
+
+#[test]
+fn every_operation() {
+    let ops = all_operations();
+    exhaustive::<Packed8>(&ops, depth(ops.len(), 6));
+}
+
+#[test]
+fn every_operation_packed16() {
+    let ops = all_operations();
+    exhaustive::<Packed16>(&ops, depth(ops.len(), 6));
+}
@@ -0,0 +1,102 @@
+//! Compile-time checks of the layout and auto traits the crate promises. These are `const`
+//! assertions, so a change that breaks one fails to build this test rather than failing at run
+//! time.
+//!
+//! The sizes don't hold with the `debug-checks` feature, which adds a word to every set, so
+//! nothing here is built with it.
+
+#![cfg(not(feature = "debug-checks"))]
+
+use bloomset::{BloomHasher, BloomSet, BloomSet16, BuildBloomHasher};
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::hash::BuildHasherDefault;
+use std::marker::PhantomPinned;
+use std::mem::{align_of, size_of};
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::rc::Rc;
+
+/// Whether `$ty` implements `$trait`, as a `const bool`.
+///
+/// The inherent constant only exists when the bound holds, and takes precedence over the trait's
+/// when it does; otherwise lookup falls back to the trait's, which is `false`.
+macro_rules! implements {
+    ($ty:ty: $($trait:tt)+) => {{
+        struct Probe<T: ?Sized>(std::marker::PhantomData<T>);
+        #[allow(dead_code)]
+        trait Fallback {
+            const IMPLEMENTS: bool = false;
+        }
+        impl<T: ?Sized> Fallback for Probe<T> {}
+        #[allow(dead_code)]
+        impl<T: ?Sized + $($trait)+> Probe<T> {
+            const IMPLEMENTS: bool = true;
+        }
+        <Probe<$ty>>::IMPLEMENTS
+    }};
+}
+
+/// A `BloomSet` of each of `$ty` is the size of a `Vec` of it, and so is a `BloomSet16`; both
+/// are word-aligned whatever the element's alignment; and `Option` of either uses the
+/// pointer's niche rather than growing.
+macro_rules! same_size_as_vec {
+    ($($ty:ty),*) => {$(
+        const _: () = {
+            assert!(size_of::<BloomSet<$ty>>() == size_of::<Vec<$ty>>());
+            assert!(size_of::<BloomSet16<$ty>>() == size_of::<Vec<$ty>>());
+            assert!(align_of::<BloomSet<$ty>>() == align_of::<usize>());
+            assert!(align_of::<BloomSet16<$ty>>() == align_of::<usize>());
+            assert!(size_of::<Option<BloomSet<$ty>>>() == size_of::<BloomSet<$ty>>());
+            assert!(size_of::<Option<BloomSet16<$ty>>>() == size_of::<BloomSet16<$ty>>());
+        };
+    )*};
+}
+
+same_size_as_vec!(
+    (),
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    [u8; 3],
+    String,
+    &'static str,
+    Box<dyn Fn()>
+);
+
+// Three words, the same as the `Vec` it replaces, and smaller than any `HashSet`
+const _: () = assert!(size_of::<BloomSet<u64>>() == 3 * size_of::<usize>());
+const _: () = assert!(size_of::<BloomSet<u64>>() < size_of::<HashSet<u64>>());
+
+// A hasher with state adds exactly its own size, and none for a stateless one
+const _: () = assert!(
+    size_of::<BloomSet<u64, (u64, u64)>>() == size_of::<Vec<u64>>() + size_of::<(u64, u64)>()
+);
+const _: () =
+    assert!(size_of::<BloomSet<u64, BuildHasherDefault<BloomHasher>>>() == size_of::<Vec<u64>>());
+
+// The set owns its elements like a `Vec` does, so it is `Send` and `Sync` under the same
+// conditions on them and on the hasher, and unwind safe like a `Vec` of them
+const _: () = {
+    assert!(implements!(BloomSet<u32>: Send));
+    assert!(implements!(BloomSet<u32>: Sync));
+    assert!(implements!(BloomSet<String>: Send + Sync + Unpin));
+    assert!(implements!(BloomSet<u32>: UnwindSafe + RefUnwindSafe));
+    assert!(!implements!(BloomSet<Rc<u32>>: Send));
+    assert!(!implements!(BloomSet<Rc<u32>>: Sync));
+    assert!(implements!(BloomSet<Cell<u32>>: Send));
+    assert!(!implements!(BloomSet<Cell<u32>>: Sync));
+    assert!(!implements!(BloomSet<u32, Rc<BuildBloomHasher>>: Send));
+    assert!(implements!(BloomSet16<u32>: Send + Sync));
+    // The elements live behind a pointer, so moving the set never moves them and it is `Unpin`
+    // whatever they are; `Vec` is more conservative here
+    assert!(implements!(BloomSet<PhantomPinned>: Unpin));
+    assert!(!implements!(Vec<PhantomPinned>: Unpin));
+};
+
+#[test]
+fn layout_is_checked_at_compile_time() {
+    // Everything here is a `const` assertion; this test only exists so the file is built
+    assert_eq!(size_of::<BloomSet<u8>>(), size_of::<Vec<u8>>());
+}
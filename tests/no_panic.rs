@@ -0,0 +1,25 @@
+//! Links `examples/no_panic.rs` in release with LTO, which fails if the fallible API it calls
+//! can still panic. That is a full optimized build of the crate and its dev-dependencies, so it
+//! only runs when asked for: `cargo test --test no_panic -- --ignored`.
+
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+#[ignore = "builds the crate again in release, which takes a minute or more"]
+fn fallible_api_links_without_panics() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // A target directory of its own, so the build doesn't wait on the lock this test run holds
+    let output = Command::new(env!("CARGO"))
+        .args(["build", "--release", "--example", "no_panic"])
+        .env("CARGO_PROFILE_RELEASE_LTO", "fat")
+        .env("CARGO_TARGET_DIR", root.join("target").join("no-panic"))
+        .current_dir(root)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,37 @@
+//! Uses the crate the way a `no_std` crate would, with nothing but `core` and `alloc` in scope.
+//! Run with `--no-default-features` to check the library itself doesn't need `std`.
+
+#![no_std]
+
+extern crate alloc;
+// The test harness itself needs std
+extern crate std;
+
+use alloc::vec::Vec;
+use bloomset::{BloomMap, BloomSet};
+
+#[test]
+fn insert_contains_clear() {
+    let mut set = BloomSet::new();
+    for i in 0..100_u32 {
+        set.insert(i * 3);
+    }
+    assert_eq!(set.len(), 100);
+    assert!(set.contains(297) && !set.contains(298));
+
+    set.clear();
+    assert!(set.is_empty() && !set.contains(0));
+    set.insert(1);
+    assert_eq!(set.as_slice(), [1]);
+}
+
+#[test]
+fn collections_need_only_alloc() {
+    let set: BloomSet<&str> = ["a", "b", "a"].iter().copied().collect();
+    let frozen = set.into_frozen();
+    assert_eq!(frozen.iter().copied().collect::<Vec<_>>(), ["a", "b"]);
+
+    let mut map = BloomMap::new();
+    map.insert(1_u8, "one");
+    assert_eq!(map.get(&1), Some(&"one"));
+}
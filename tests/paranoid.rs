@@ -0,0 +1,75 @@
+//! Soak test for the `paranoid` feature: runs random operations on sets of several shapes, so
+//! every lookup is checked against a scan that ignores the filter. Run with
+//! `cargo test --features paranoid`.
+
+#![cfg(feature = "paranoid")]
+
+use bloomset::{BloomSet, BloomSet16, BuildBloomHasher, Packing};
+use std::hash::BuildHasherDefault;
+
+fn xorshift(mut x: u64) -> impl Iterator<Item = u64> {
+    core::iter::repeat_with(move || {
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        x
+    })
+}
+
+/// Applies random operations to `set` and to a plain `Vec`, checking they agree. Values are
+/// drawn from a small range so that inserts hit duplicates and lookups hit present elements.
+fn soak<S: core::hash::BuildHasher, const K: usize, P: Packing>(
+    mut set: BloomSet<u32, S, K, P>,
+    seed: u64,
+) {
+    let mut model = Vec::new();
+    let limit = P::MAX_CAPACITY.min(200);
+    #[allow(clippy::cast_possible_truncation)]
+    for random in xorshift(seed).take(5000) {
+        let value = (random >> 8) as u32 % 400;
+        match random % 8 {
+            0..=3 if model.len() < limit => {
+                set.insert(value);
+                if !model.contains(&value) {
+                    model.push(value);
+                }
+            }
+            4 => {
+                let present = model.iter().position(|&it| it == value);
+                assert_eq!(set.remove(value), present.is_some());
+                if let Some(index) = present {
+                    model.remove(index);
+                }
+            }
+            5 if random % 97 == 0 => {
+                set.clear();
+                model.clear();
+            }
+            6 => {
+                let present = model.iter().position(|&it| it == value);
+                assert_eq!(set.contains_promoting(value), present.is_some());
+                if let Some(index) = present.filter(|&index| index > 0) {
+                    model.swap(index - 1, index);
+                }
+            }
+            7 if random % 31 == 0 => {
+                set.retain(|&it| it % 5 != value % 5);
+                model.retain(|&it| it % 5 != value % 5);
+            }
+            _ => assert_eq!(set.contains(value), model.contains(&value)),
+        }
+        assert_eq!(set.as_slice(), model);
+    }
+}
+
+#[test]
+fn random_operations() {
+    soak(BloomSet::new(), 1);
+    soak(BloomSet::with_seed(7), 2);
+    soak(BloomSet::<u32, _, 3>::with_hasher(BuildBloomHasher), 3);
+    soak(BloomSet16::<u32>::with_hasher(BuildBloomHasher), 4);
+    soak(
+        BloomSet::<u32, _>::with_hasher(BuildHasherDefault::<fnv::FnvHasher>::default()),
+        5,
+    );
+}
@@ -0,0 +1,447 @@
+//! Property tests: each property is checked on many random inputs, and an input it fails on is
+//! shrunk to a minimal one before the test panics, so the failure names the shortest sequence
+//! of values or operations that still breaks it.
+//!
+//! Every run uses the same inputs. Set `BLOOMSET_PROPERTY_CASES` to check more of them than
+//! the default 64 per property, e.g. `BLOOMSET_PROPERTY_CASES=100000 cargo test --release
+//! --test properties` for a soak run.
+
+use bloomset::{BloomSet, BucketedBloomSet};
+use std::collections::HashSet;
+use std::fmt::Debug;
+
+fn xorshift(mut x: u64) -> impl Iterator<Item = u64> {
+    core::iter::repeat_with(move || {
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        x
+    })
+}
+
+fn cases() -> usize {
+    std::env::var("BLOOMSET_PROPERTY_CASES").map_or(64, |cases| {
+        cases
+            .parse()
+            .expect("BLOOMSET_PROPERTY_CASES must be a number")
+    })
+}
+
+/// Checks that `holds` is true for `cases()` inputs made by `generate`, panicking with a
+/// shrunk input if it isn't.
+fn check<T: Clone + Debug>(
+    name: &str,
+    mut generate: impl FnMut(&mut dyn Iterator<Item = u64>) -> Vec<T>,
+    holds: impl Fn(&[T]) -> bool,
+) {
+    let mut random = xorshift(0x2545_F491_4F6C_DD1D);
+    for case in 0..cases() {
+        let input = generate(&mut random);
+        if !holds(&input) {
+            let minimal = shrink(input, &holds);
+            panic!("{} fails on case {}, shrunk to {:?}", name, case, minimal);
+        }
+    }
+}
+
+/// Removes as much of `input` as it can while `holds` stays false: runs of half the input,
+/// then a quarter, down to single items, until no single item can go.
+fn shrink<T: Clone>(mut input: Vec<T>, holds: impl Fn(&[T]) -> bool) -> Vec<T> {
+    let mut run = input.len() / 2;
+    while run > 0 {
+        let mut start = 0;
+        let mut removed = false;
+        while start + run <= input.len() {
+            let mut smaller = input.clone();
+            smaller.drain(start..start + run);
+            if holds(&smaller) {
+                start += run;
+            } else {
+                input = smaller;
+                removed = true;
+            }
+        }
+        if !removed {
+            run /= 2;
+        }
+    }
+    input
+}
+
+/// Up to 255 values, so a set can always hold them, drawn from a range that is sometimes small
+/// enough for duplicates to be common.
+#[allow(clippy::cast_possible_truncation)]
+fn values(random: &mut dyn Iterator<Item = u64>) -> Vec<u32> {
+    let len = random.next().unwrap() % 256;
+    let range = 1 + random.next().unwrap() % 1000;
+    random
+        .take(len as usize)
+        .map(|bits| (bits % range) as u32)
+        .collect()
+}
+
+fn distinct<T: std::hash::Hash + Eq>(values: &[T]) -> usize {
+    values.iter().collect::<HashSet<_>>().len()
+}
+
+#[test]
+fn inserted_values_are_found() {
+    check("inserted_values_are_found", values, |values| {
+        let set: BloomSet<u32> = values.iter().copied().collect();
+        values.iter().all(|value| set.contains(value))
+    });
+    check(
+        "inserted_strings_are_found",
+        |random| values(random).iter().map(u32::to_string).collect(),
+        |strings: &[String]| {
+            let set: BloomSet<String> = strings.iter().cloned().collect();
+            strings.iter().all(|string| set.contains(string))
+        },
+    );
+}
+
+#[test]
+fn len_counts_distinct_values() {
+    check("len_counts_distinct_values", values, |values| {
+        let mut set = BloomSet::new();
+        for &value in values {
+            set.insert(value);
+        }
+        set.len() == distinct(values) && set.len() <= set.capacity()
+    });
+}
+
+#[test]
+fn duplicate_inserts_keep_len() {
+    check("duplicate_inserts_keep_len", values, |values| {
+        let mut set: BloomSet<u32> = values.iter().copied().collect();
+        let len = set.len();
+        let order = set.as_slice().to_vec();
+        for &value in values.iter().rev() {
+            set.insert(value);
+        }
+        set.len() == len && set.as_slice() == order
+    });
+}
+
+#[test]
+fn clear_is_like_new() {
+    check("clear_is_like_new", values, |values| {
+        let mut set: BloomSet<u32> = values.iter().copied().collect();
+        set.clear();
+        let empty = set.is_empty()
+            && set.as_slice().is_empty()
+            && set.saturation() == 0.0
+            && values.iter().all(|value| !set.contains(value));
+        // Refilling a cleared set gives the same set as filling a new one
+        let mut fresh = BloomSet::with_seed(set.seed());
+        fresh.extend(values.iter().rev().copied());
+        set.extend(values.iter().rev().copied());
+        empty && set.as_slice() == fresh.as_slice() && set.saturation() == fresh.saturation()
+    });
+}
+
+#[test]
+fn insertion_order_doesnt_change_membership() {
+    check(
+        "insertion_order_doesnt_change_membership",
+        values,
+        |values| {
+            let forward: BloomSet<u32> = values.iter().copied().collect();
+            let mut backward = BloomSet::with_seed(forward.seed());
+            backward.extend(values.iter().rev().copied());
+            let mut sorted = backward.as_slice().to_vec();
+            sorted.sort_unstable();
+            let mut other = forward.as_slice().to_vec();
+            other.sort_unstable();
+            sorted == other
+                && forward.saturation() == backward.saturation()
+                && (0..1000).all(|probe| forward.contains(probe) == backward.contains(probe))
+        },
+    );
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Op {
+    Insert(u32),
+    Remove(u32),
+    Contains(u32),
+    Clear,
+}
+
+/// Applies `ops` to a set and to a `Vec`, returning whether they agreed throughout.
+fn agrees_with_vec(ops: &[Op]) -> bool {
+    let mut set = BloomSet::new();
+    let mut model = Vec::new();
+    ops.iter().all(|&op| {
+        let agreed = match op {
+            Op::Insert(value) => {
+                set.insert(value);
+                if !model.contains(&value) {
+                    model.push(value);
+                }
+                true
+            }
+            Op::Remove(value) => {
+                let present = model.iter().position(|&it| it == value);
+                if let Some(index) = present {
+                    model.remove(index);
+                }
+                set.remove(value) == present.is_some()
+            }
+            Op::Contains(value) => set.contains(value) == model.contains(&value),
+            Op::Clear => {
+                set.clear();
+                model.clear();
+                true
+            }
+        };
+        agreed && set.as_slice() == model
+    })
+}
+
+#[test]
+fn operations_agree_with_vec() {
+    check(
+        "operations_agree_with_vec",
+        |random| {
+            let ops = values(random);
+            ops.iter()
+                .zip(&mut *random)
+                .map(|(&value, bits)| match bits % 16 {
+                    0..=7 => Op::Insert(value),
+                    8..=11 => Op::Remove(value),
+                    12 => Op::Clear,
+                    _ => Op::Contains(value),
+                })
+                .collect()
+        },
+        agrees_with_vec,
+    );
+}
+
+/// Applies `ops` to a bucketed set and a plain one, returning whether every lookup agreed and
+/// they ended up with the same elements.
+fn bucketed_agrees_with_plain(ops: &[Op]) -> bool {
+    let mut bucketed = BucketedBloomSet::new();
+    let mut plain = BloomSet::new();
+    let agreed = ops.iter().all(|&op| match op {
+        Op::Insert(value) => {
+            bucketed.insert(value);
+            plain.insert(value);
+            bucketed.len() == plain.len()
+        }
+        Op::Remove(value) => bucketed.remove(value) == plain.remove(value),
+        Op::Contains(value) => bucketed.contains(value) == plain.contains(value),
+        Op::Clear => {
+            bucketed.clear();
+            plain.clear();
+            true
+        }
+    });
+    let mut elements = bucketed.as_slice().to_vec();
+    elements.sort_unstable();
+    let mut expected = plain.as_slice().to_vec();
+    expected.sort_unstable();
+    agreed && elements == expected && expected.iter().all(|value| bucketed.contains(value))
+}
+
+#[test]
+fn bucketed_agrees_with_plain_layout() {
+    check(
+        "bucketed_agrees_with_plain_layout",
+        |random| {
+            let ops = values(random);
+            ops.iter()
+                .zip(&mut *random)
+                .map(|(&value, bits)| match bits % 16 {
+                    0..=9 => Op::Insert(value),
+                    10..=11 => Op::Remove(value),
+                    12 => Op::Clear,
+                    _ => Op::Contains(value),
+                })
+                .collect()
+        },
+        bucketed_agrees_with_plain,
+    );
+    // Collecting groups the elements in one go rather than one insert at a time
+    check(
+        "collected_bucketed_sets_find_everything",
+        |random| values(random).iter().map(u32::to_string).collect(),
+        |strings: &[String]| {
+            let set: BucketedBloomSet<String> = strings.iter().cloned().collect();
+            set.len() == distinct(strings) && strings.iter().all(|s| set.contains(s))
+        },
+    );
+}
+
+#[test]
+fn shrinking_finds_a_minimal_input() {
+    // Fails whenever a 3 comes before a 7
+    let holds = |values: &[u32]| {
+        values
+            .iter()
+            .position(|&it| it == 3)
+            .is_none_or(|three| !values[three..].contains(&7))
+    };
+    let input = vec![1, 3, 5, 3, 9, 2, 7, 7, 4, 3];
+    assert_eq!(shrink(input, holds), [3, 7]);
+}
+
+#[test]
+fn overlap_counts_match_hash_sets() {
+    // Each value goes to the first set, the second, or both
+    let tagged = |random: &mut dyn Iterator<Item = u64>| {
+        values(random)
+            .into_iter()
+            .zip(random)
+            .map(|(value, bits)| (value, (bits % 3) as u8))
+            .collect()
+    };
+    check("overlap_counts_match_hash_sets", tagged, |tagged| {
+        let a: BloomSet<u32> = tagged.iter().filter(|t| t.1 != 1).map(|t| t.0).collect();
+        let b: BloomSet<u32> = tagged.iter().filter(|t| t.1 != 0).map(|t| t.0).collect();
+        let a_hash: HashSet<u32> = a.as_slice().iter().copied().collect();
+        let b_hash: HashSet<u32> = b.as_slice().iter().copied().collect();
+        let common = a_hash.intersection(&b_hash).count();
+        a.intersection_len(&b) == common
+            && b.intersection_len(&a) == common
+            && a.difference_len(&b) == a_hash.difference(&b_hash).count()
+            && b.difference_len(&a) == b_hash.difference(&a_hash).count()
+            && (0..=common + 1).all(|k| a.intersection_at_least(&b, k) == (k <= common))
+    });
+}
+
+#[test]
+fn relation_screens_never_rule_out_a_relation() {
+    // Each value goes to the first set, the second, or both, and some are removed again
+    let tagged = |random: &mut dyn Iterator<Item = u64>| {
+        values(random)
+            .into_iter()
+            .zip(random)
+            .map(|(value, bits)| (value, (bits % 5) as u8))
+            .collect()
+    };
+    check(
+        "relation_screens_never_rule_out_a_relation",
+        tagged,
+        |tagged| {
+            let mut a: BloomSet<u32> = tagged.iter().filter(|t| t.1 != 1).map(|t| t.0).collect();
+            let mut b: BloomSet<u32> = tagged.iter().filter(|t| t.1 != 0).map(|t| t.0).collect();
+            for &(value, tag) in tagged {
+                match tag {
+                    3 => a.remove(value),
+                    4 => b.remove(value),
+                    _ => false,
+                };
+            }
+            let a_hash: HashSet<u32> = a.as_slice().iter().copied().collect();
+            let b_hash: HashSet<u32> = b.as_slice().iter().copied().collect();
+            let subset = a_hash.is_subset(&b_hash);
+            let disjoint = a_hash.is_disjoint(&b_hash);
+            (!subset || a.maybe_subset_of(&b))
+                && (disjoint || a.maybe_intersects(&b))
+                && a.is_subset(&b) == subset
+                && a.is_disjoint(&b) == disjoint
+                && b.is_subset(&a) == b_hash.is_subset(&a_hash)
+        },
+    );
+}
+
+#[test]
+fn dedup_in_place_matches_a_hash_set() {
+    let reference = |items: &[u64]| {
+        let mut seen = HashSet::new();
+        items
+            .iter()
+            .copied()
+            .filter(|&item| seen.insert(item))
+            .collect::<Vec<_>>()
+    };
+    check(
+        "dedup_in_place keeps the first of each element, in order",
+        |random| {
+            let len = random.next().unwrap() as usize % 600;
+            let range = random.next().unwrap() % 400 + 1;
+            random.take(len).map(|x| x % range).collect()
+        },
+        |items| {
+            let mut deduped = items.to_vec();
+            bloomset::dedup_in_place(&mut deduped);
+            let mut by_key = items.to_vec();
+            bloomset::dedup_in_place_by_key(&mut by_key, |&item| item % 50);
+            let mut seen = HashSet::new();
+            let by_key_reference: Vec<u64> = items
+                .iter()
+                .copied()
+                .filter(|&item| seen.insert(item % 50))
+                .collect();
+            deduped == reference(items) && by_key == by_key_reference
+        },
+    );
+}
+
+#[test]
+// Both sides divide the same two counts
+#[allow(clippy::float_cmp)]
+fn jaccard_matches_hash_sets() {
+    let tagged = |random: &mut dyn Iterator<Item = u64>| {
+        values(random)
+            .into_iter()
+            .zip(random)
+            .map(|(value, bits)| (value, (bits % 3) as u8))
+            .collect()
+    };
+    check("jaccard_matches_hash_sets", tagged, |tagged| {
+        let a: BloomSet<u32> = tagged.iter().filter(|t| t.1 != 1).map(|t| t.0).collect();
+        let b: BloomSet<u32> = tagged.iter().filter(|t| t.1 != 0).map(|t| t.0).collect();
+        let a_hash: HashSet<u32> = a.as_slice().iter().copied().collect();
+        let b_hash: HashSet<u32> = b.as_slice().iter().copied().collect();
+        let union = a_hash.union(&b_hash).count();
+        #[allow(clippy::cast_precision_loss)]
+        let expected = if union == 0 {
+            1.0
+        } else {
+            a_hash.intersection(&b_hash).count() as f64 / union as f64
+        };
+        a.jaccard_similarity(&b) == expected && b.jaccard_similarity(&a) == expected
+    });
+}
+
+/// Tag sets of up to ten tags out of 30, where the estimate should be a usable screen.
+#[test]
+#[cfg_attr(feature = "no-filter", ignore = "checks the filter bits")]
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+fn estimated_jaccard_error() {
+    let mut random = xorshift(0x9E37_79B9_7F4A_7C15);
+    let mut total = 0.0;
+    let mut far_off = 0;
+    let pairs = 10 * cases();
+    for _ in 0..pairs {
+        let a_len = 1 + random.next().unwrap() as usize % 10;
+        let b_len = 1 + random.next().unwrap() as usize % 10;
+        let a: BloomSet<u32> = random
+            .by_ref()
+            .take(a_len)
+            .map(|bits| (bits % 30) as u32)
+            .collect();
+        // The same seed, so the filters can be compared even with `random-seed`
+        let mut b = BloomSet::with_seed(a.seed());
+        b.extend(random.by_ref().take(b_len).map(|bits| (bits % 30) as u32));
+        let error = a.estimated_jaccard(&b) - a.jaccard_similarity(&b);
+        if error.abs() > 0.25 {
+            far_off += 1;
+        }
+        total += error;
+    }
+    // A single shared bit can make two tiny sets look alike, but that is rare. Mostly the
+    // errors are bits shared by different elements, so it runs high on the whole
+    assert!(
+        far_off * 20 <= pairs,
+        "{} of {} off by more than 0.25",
+        far_off,
+        pairs
+    );
+    let mean = total / pairs as f64;
+    assert!((0.0..0.05).contains(&mean), "off by {} on average", mean);
+}
@@ -0,0 +1,69 @@
+//! Converting to and from `SmallVec`, counting the allocations each way.
+
+#![cfg(feature = "smallvec")]
+
+mod common;
+
+use bloomset::BloomSet;
+use common::{counting, Counting};
+use smallvec::{smallvec, SmallVec};
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Runs `f`, returning what it did and how many times it allocated or reallocated on this
+/// thread.
+fn allocating<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let (result, counts) = counting(f);
+    (result, counts.allocations + counts.reallocations)
+}
+
+#[test]
+fn inline_round_trip() {
+    let elements: SmallVec<[u32; 8]> = smallvec![3, 1, 3, 2, 1];
+    // The set's buffer is the only allocation, with no `Vec` in between
+    let (set, allocations) = allocating(|| BloomSet::<u32>::from(elements));
+    assert_eq!(allocations, 1);
+    assert_eq!(set.as_slice(), [3, 1, 2]);
+
+    let (back, allocations) = allocating(|| set.into_smallvec::<[u32; 8]>());
+    assert_eq!(allocations, 0);
+    assert!(!back.spilled());
+    assert_eq!(back.as_slice(), [3, 1, 2]);
+
+    let (empty, allocations) = allocating(|| {
+        BloomSet::<u32>::from(SmallVec::<[u32; 8]>::new()).into_smallvec::<[u32; 8]>()
+    });
+    assert_eq!((empty.len(), allocations), (0, 0));
+}
+
+#[test]
+fn spilled_round_trip() {
+    let elements: SmallVec<[String; 2]> = (0..10).map(|i| (i % 6).to_string()).collect();
+    assert!(elements.spilled());
+    let set = BloomSet::<String>::from(elements);
+    assert_eq!(set.len(), 6);
+    assert!(set.contains("5".to_string()));
+
+    // Too many to go inline, so the `SmallVec` takes over the set's buffer
+    let buffer = set.as_slice().as_ptr();
+    let (back, allocations) = allocating(|| set.into_smallvec::<[String; 2]>());
+    assert_eq!(allocations, 0);
+    assert!(back.spilled());
+    assert_eq!(back.as_ptr(), buffer);
+    assert!(back
+        .iter()
+        .map(String::as_str)
+        .eq(["0", "1", "2", "3", "4", "5"]));
+}
+
+#[test]
+fn back_inline_when_they_fit() {
+    let mut set: BloomSet<u64> = (0..40).collect();
+    set.retain(|&i| i % 10 == 0);
+    assert!(set.capacity() > 4);
+    let (back, allocations) = allocating(|| set.into_smallvec::<[u64; 4]>());
+    assert_eq!(allocations, 0);
+    assert!(!back.spilled());
+    assert_eq!(back.as_slice(), [0, 10, 20, 30]);
+}